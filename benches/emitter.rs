@@ -0,0 +1,60 @@
+//! Measures the cost of emitting a progress event to a few listeners.
+//!
+//! `serde_round_trip` emulates the former emitter, which serialized the data
+//! of every event and deserialized it again for every listener.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use lyceris::minecraft::emitter::{Emitter, Event};
+
+const LISTENERS: usize = 4;
+
+/// A listener of the former emitter, which received the data by value.
+type Listener = Box<dyn Fn((String, u64, u64))>;
+
+fn emit(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let path = "assets/objects/00/0057a4b2d5f1f1b7b1e7c39a1b3d1f0c8c6f7a1b".to_string();
+
+    let emitter = Emitter::default();
+    runtime.block_on(async {
+        for _ in 0..LISTENERS {
+            emitter
+                .on_payload(Event::SingleDownloadProgress, |payload| {
+                    black_box(payload);
+                })
+                .await;
+        }
+    });
+    c.bench_function("emit", |b| {
+        b.to_async(&runtime).iter(|| {
+            emitter.emit(
+                Event::SingleDownloadProgress,
+                (black_box(path.clone()), 512, 1024),
+            )
+        })
+    });
+
+    let listeners: Vec<Listener> = (0..LISTENERS)
+        .map(|_| {
+            Box::new(|data: (String, u64, u64)| {
+                black_box(data);
+            }) as Listener
+        })
+        .collect();
+    c.bench_function("serde_round_trip", |b| {
+        b.iter(|| {
+            let data = (black_box(path.clone()), 512u64, 1024u64);
+            let serialized = serde_json::to_vec(&data).unwrap();
+            for listener in &listeners {
+                listener(serde_json::from_slice(&serialized).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, emit);
+criterion_main!(benches);
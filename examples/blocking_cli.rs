@@ -0,0 +1,26 @@
+use std::env;
+
+use lyceris::{
+    blocking::{install, launch},
+    minecraft::{config::ConfigBuilder, emitter::EventPayload},
+};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ConfigBuilder::offline(
+        env::current_dir()?.join("game"),
+        "1.21.4".into(),
+        "Lyceris".into(),
+    )
+    .build();
+
+    // No async runtime needed, progress is delivered through a plain callback.
+    install(&config, Some(|payload: &EventPayload| {
+        if let EventPayload::MultipleDownloadProgress(progress) = payload {
+            println!("Downloading {}/{}", progress.current, progress.total);
+        }
+    }))?;
+
+    launch(&config)?.wait()?;
+
+    Ok(())
+}
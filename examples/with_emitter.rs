@@ -13,8 +13,8 @@ use lyceris::minecraft::{
 /// progress events and launch the Minecraft game with a specified configuration.
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Emitter uses `EventEmitter` inside of it
-    // and it uses tokio::Mutex for locking.
+    // Emitter keeps its listeners behind
+    // a tokio::RwLock for locking.
     // That causes emitter methods to be async.
     let emitter = Emitter::default();
 
@@ -51,16 +51,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .await;
 
     let current_dir = env::current_dir()?;
-    let config = ConfigBuilder::new(
-        current_dir.join("game"),
-        "1.21.4".into(),
-        lyceris::auth::AuthMethod::Offline {
-            username: "Lyceris".into(),
-            // If none given, it will be generated.
-            uuid: None,
-        },
-    )
-    .build();
+    let config =
+        ConfigBuilder::offline(current_dir.join("game"), "1.21.4".into(), "Lyceris".into()).build();
 
     // Install method also checks for broken files
     // and downloads them again if they are broken.
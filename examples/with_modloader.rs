@@ -8,18 +8,11 @@ use lyceris::minecraft::{
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let current_dir = env::current_dir()?;
-    let config = ConfigBuilder::new(
-        current_dir.join("game"),
-        "1.21.4".into(),
-        lyceris::auth::AuthMethod::Offline {
-            username: "Lyceris".into(),
-            // If none given, it will be generated.
-            uuid: None,
-        },
-    )
-    // You can use Fabric, Quilt or Forge here.
-    .loader(Fabric("0.16.9".to_string()).into())
-    .build();
+    let config =
+        ConfigBuilder::offline(current_dir.join("game"), "1.21.4".into(), "Lyceris".into())
+            // You can use Fabric, Legacy Fabric, Quilt or Forge here.
+            .loader(Fabric("0.16.9".to_string()).into())
+            .build();
 
     // Install method also checks for broken files
     // and downloads them again if they are broken.
@@ -0,0 +1,123 @@
+//! Derive macros of lyceris, re-exported by lyceris with the `macros`
+//! feature.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Derives `EventEnum` for an enum of events, so that it can be emitted
+/// through a `TypedEmitter`.
+///
+/// Every variant gets a marker type named after the enum and the variant,
+/// e.g. `AppEventStarted` for `AppEvent::Started`, which implements
+/// `EventVariant` with the data of the variant as its payload:
+///
+/// - a unit variant carries `()`,
+/// - a variant with one field carries that field,
+/// - a variant with several fields carries a tuple of them.
+///
+/// Variants with named fields are not supported, the fields are expected to
+/// implement `Clone`.
+#[proc_macro_derive(EventEnum)]
+pub fn derive_event_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Expands the derive of an enum.
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "EventEnum can only be derived for enums",
+        ));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "EventEnum can't be derived for generic enums",
+        ));
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let mut markers = Vec::new();
+    let mut variant_arms = Vec::new();
+
+    for variant in &data.variants {
+        let variant_name = &variant.ident;
+        let marker = format_ident!("{}{}", name, variant_name);
+        let doc = format!("The marker of `{}::{}`.", name, variant_name);
+
+        let (pattern, payload_type, payload) = match &variant.fields {
+            Fields::Unit => (quote!(#name::#variant_name), quote!(()), quote!(())),
+            Fields::Unnamed(fields) => {
+                let bindings: Vec<_> = (0..fields.unnamed.len())
+                    .map(|index| format_ident!("field_{}", index))
+                    .collect();
+                let types: Vec<_> = fields.unnamed.iter().map(|field| &field.ty).collect();
+                let pattern = quote!(#name::#variant_name(#(#bindings),*));
+                if let ([binding], [ty]) = (bindings.as_slice(), types.as_slice()) {
+                    (
+                        pattern,
+                        quote!(#ty),
+                        quote!(::std::clone::Clone::clone(#binding)),
+                    )
+                } else {
+                    (
+                        pattern,
+                        quote!((#(#types),*)),
+                        quote!((#(::std::clone::Clone::clone(#bindings)),*)),
+                    )
+                }
+            }
+            Fields::Named(_) => {
+                return Err(Error::new_spanned(
+                    variant,
+                    "EventEnum doesn't support variants with named fields, \
+                     wrap the fields in a struct instead",
+                ));
+            }
+        };
+
+        let any_fields = match &variant.fields {
+            Fields::Unit => quote!(),
+            _ => quote!((..)),
+        };
+        variant_arms.push(quote! {
+            #name::#variant_name #any_fields => ::std::any::TypeId::of::<#marker>()
+        });
+        markers.push(quote! {
+            #[doc = #doc]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            #vis struct #marker;
+
+            impl ::lyceris::minecraft::emitter::EventVariant for #marker {
+                type Event = #name;
+                type Payload = #payload_type;
+
+                fn payload(event: &#name) -> ::std::option::Option<Self::Payload> {
+                    #[allow(unreachable_patterns)]
+                    match event {
+                        #pattern => ::std::option::Option::Some(#payload),
+                        _ => ::std::option::Option::None,
+                    }
+                }
+            }
+        });
+    }
+
+    Ok(quote! {
+        #(#markers)*
+
+        impl ::lyceris::minecraft::emitter::EventEnum for #name {
+            fn variant(&self) -> ::std::any::TypeId {
+                match *self {
+                    #(#variant_arms,)*
+                }
+            }
+        }
+    })
+}
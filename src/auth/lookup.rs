@@ -0,0 +1,102 @@
+use reqwest::{Client, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// The endpoint for resolving a username to its profile.
+const USERNAME_ENDPOINT: &str = "https://api.mojang.com/users/profiles/minecraft/";
+/// The endpoint for fetching the public profile of a UUID.
+const PROFILE_ENDPOINT: &str = "https://sessionserver.mojang.com/session/minecraft/profile/";
+
+/// Represents the UUID and name of a player.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct NameLookup {
+    id: String,
+}
+
+/// Represents the public profile of a player.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublicProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<ProfileProperty>,
+}
+
+/// Represents a property of a public profile, such as its textures.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+/// Resolves a username to the UUID of the player.
+///
+/// This doesn't require authentication.
+///
+/// # Parameters
+/// - `name`: The username of the player.
+/// - `client`: The HTTP client used for making requests.
+///
+/// # Returns
+/// A result containing the UUID without dashes, or `None` if no player has
+/// the given username.
+pub async fn username_to_uuid(name: &str, client: &Client) -> crate::Result<Option<String>> {
+    let lookup: Option<NameLookup> =
+        fetch_optional(&format!("{}{}", USERNAME_ENDPOINT, name), client).await?;
+    Ok(lookup.map(|lookup| lookup.id))
+}
+
+/// Fetches the public profile of a player.
+///
+/// This doesn't require authentication.
+///
+/// # Parameters
+/// - `uuid`: The UUID of the player, with or without dashes.
+/// - `client`: The HTTP client used for making requests.
+///
+/// # Returns
+/// A result containing the public profile, or `None` if no player has the
+/// given UUID.
+pub async fn uuid_to_profile(uuid: &str, client: &Client) -> crate::Result<Option<PublicProfile>> {
+    fetch_optional(
+        &format!("{}{}", PROFILE_ENDPOINT, uuid.replace('-', "")),
+        client,
+    )
+    .await
+}
+
+/// Fetches a JSON response that may be missing.
+///
+/// # Parameters
+/// - `url`: The URL to fetch.
+/// - `client`: The HTTP client used for making requests.
+///
+/// # Returns
+/// A result containing the deserialized response, or `None` for a 204, a 404
+/// or an empty body.
+async fn fetch_optional<T: DeserializeOwned>(
+    url: &str,
+    client: &Client,
+) -> crate::Result<Option<T>> {
+    let response = client.get(url).send().await?;
+    let status = response.status();
+
+    if status == StatusCode::NO_CONTENT || status == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(Error::Fail(format!(
+            "Profile lookup failed with status code: {}",
+            status
+        )));
+    }
+
+    let body = response.bytes().await?;
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_slice(&body)?))
+}
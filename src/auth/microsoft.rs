@@ -1,12 +1,22 @@
-use oauth2::{AuthUrl, ClientId, CsrfToken, RedirectUrl, Scope, TokenUrl};
-use reqwest::{Client, Method};
+use oauth2::{url::Url, AuthUrl, ClientId, CsrfToken, RedirectUrl, Scope, TokenUrl};
+use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
+    sync::OnceLock,
     time::{SystemTime, UNIX_EPOCH},
 };
+use tokio::sync::Mutex;
 
-use crate::{error::Error, http::fetch::fetch_with_options, util::base64::decode_base64};
+use crate::{
+    error::Error,
+    http::{
+        check_status,
+        fetch::{fetch_with_options, FetchOptions},
+        network_error,
+    },
+    util::{base64::decode_base64, time::parse_iso8601},
+};
 
 /// The client ID for Microsoft authentication.
 pub static CLIENT_ID: &str = "00000000402b5328";
@@ -17,6 +27,28 @@ pub static AUTH_URL: &str = "https://login.live.com/oauth20_authorize.srf";
 /// The token URL for Microsoft authentication.
 pub static TOKEN_URL: &str = "https://login.live.com/oauth20_token.srf";
 
+/// The endpoints of the services taking part in the authentication chain.
+struct Endpoints {
+    token: String,
+    xbox: String,
+    xsts: String,
+    minecraft: String,
+    profile: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            token: TOKEN_URL.to_string(),
+            xbox: "https://user.auth.xboxlive.com/user/authenticate".to_string(),
+            xsts: "https://xsts.auth.xboxlive.com/xsts/authorize".to_string(),
+            minecraft: "https://api.minecraftservices.com/authentication/login_with_xbox"
+                .to_string(),
+            profile: "https://api.minecraftservices.com/minecraft/profile".to_string(),
+        }
+    }
+}
+
 /// Represents the token received from Microsoft after authentication.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct MSToken {
@@ -24,6 +56,14 @@ struct MSToken {
     refresh_token: String,
 }
 
+/// Represents the error body returned by the OAuth token endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct OAuthError {
+    error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_description: Option<String>,
+}
+
 /// Represents the token received from Xbox Live after authentication.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "PascalCase")]
@@ -45,6 +85,7 @@ pub struct MinecraftResponse {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "PascalCase")]
 struct XstsToken {
+    not_after: String,
     token: String,
     display_claims: DisplayClaims,
 }
@@ -63,23 +104,23 @@ struct Xui {
 
 /// Represents a user's skin in Minecraft.
 #[derive(Serialize, Deserialize, Debug, Clone)]
-struct Skin {
-    id: String,
-    state: String,
-    url: String,
-    variant: String,
+pub struct Skin {
+    pub id: String,
+    pub state: String,
+    pub url: String,
+    pub variant: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    alias: Option<String>,
+    pub alias: Option<String>,
 }
 
 /// Represents a user's cape in Minecraft.
 #[derive(Serialize, Deserialize, Debug, Clone)]
-struct Cape {
-    id: String,
-    state: String,
-    url: String,
+pub struct Cape {
+    pub id: String,
+    pub state: String,
+    pub url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    alias: Option<String>,
+    pub alias: Option<String>,
 }
 
 /// Represents a user's profile in Minecraft, including skins and capes.
@@ -87,14 +128,40 @@ struct Cape {
 pub struct UserProfile {
     pub id: Option<String>,
     pub name: Option<String>,
-    skins: Option<Vec<Skin>>,
-    capes: Option<Vec<Cape>>,
+    pub skins: Option<Vec<Skin>>,
+    pub capes: Option<Vec<Cape>>,
     path: Option<String>,
     error: Option<String>,
     #[serde(rename = "errorMessage")]
     error_message: Option<String>,
 }
 
+/// Represents the authorization code extracted from the redirect URL.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuthCode {
+    pub code: String,
+    pub state: Option<String>,
+}
+
+impl AuthCode {
+    /// Validates the state against the one returned by `create_link_with_state`.
+    ///
+    /// # Parameters
+    /// - `state`: The expected state.
+    ///
+    /// # Returns
+    /// A result indicating whether the state matches.
+    pub fn verify_state(&self, state: &str) -> crate::Result<()> {
+        if self.state.as_deref() == Some(state) {
+            Ok(())
+        } else {
+            Err(Error::Authentication(
+                "State of the redirect does not match.".to_string(),
+            ))
+        }
+    }
+}
+
 /// Represents the decoded JWT from Minecraft authentication.
 #[derive(Debug, Deserialize, Clone)]
 pub struct MCJWTDecoded {
@@ -102,6 +169,38 @@ pub struct MCJWTDecoded {
     exp: u64,
 }
 
+/// Represents an intermediate Xbox Live token along with its expiration time.
+#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+pub struct CachedToken {
+    pub token: String,
+    pub exp: u64,
+}
+
+impl CachedToken {
+    /// Creates a cached token from a token and its ISO-8601 expiration time.
+    ///
+    /// # Parameters
+    /// - `token`: The token.
+    /// - `not_after`: The expiration time of the token.
+    ///
+    /// # Returns
+    /// A result containing the `CachedToken`.
+    fn new(token: String, not_after: &str) -> crate::Result<Self> {
+        Ok(Self {
+            token,
+            exp: parse_iso8601(not_after)?,
+        })
+    }
+
+    /// Checks whether the token has not expired yet.
+    ///
+    /// # Returns
+    /// A boolean indicating whether the token is still valid.
+    pub fn is_valid(&self) -> bool {
+        validate(self.exp)
+    }
+}
+
 /// Represents a Minecraft account with authentication details.
 #[derive(Deserialize, Serialize, Default, Debug, Clone)]
 pub struct MinecraftAccount {
@@ -112,6 +211,12 @@ pub struct MinecraftAccount {
     pub access_token: String,
     pub refresh_token: String,
     pub client_id: String,
+    #[serde(default)]
+    pub userhash: String,
+    #[serde(default)]
+    pub xbox_token: Option<CachedToken>,
+    #[serde(default)]
+    pub xsts_token: Option<CachedToken>,
 }
 
 /// Creates the authorization link for Microsoft authentication.
@@ -119,6 +224,15 @@ pub struct MinecraftAccount {
 /// # Returns
 /// A result containing the authorization URL as a string.
 pub fn create_link() -> crate::Result<String> {
+    create_link_with_state().map(|(url, _)| url)
+}
+
+/// Creates the authorization link for Microsoft authentication along with
+/// the CSRF state embedded into it.
+///
+/// # Returns
+/// A result containing the authorization URL and the state.
+pub fn create_link_with_state() -> crate::Result<(String, String)> {
     let auth_url = AuthUrl::new(AUTH_URL.to_string())?;
     let token_url = TokenUrl::new(TOKEN_URL.to_string())?;
 
@@ -130,7 +244,7 @@ pub fn create_link() -> crate::Result<String> {
     )
     .set_redirect_uri(RedirectUrl::new(REDIRECT_URI.to_string())?);
 
-    let (authorize_url, _) = client
+    let (authorize_url, state) = client
         .authorize_url(CsrfToken::new_random)
         .add_scope(Scope::new(
             "service::user.auth.xboxlive.com::MBI_SSL".to_string(),
@@ -138,13 +252,42 @@ pub fn create_link() -> crate::Result<String> {
         .add_extra_param("prompt", "select_account")
         .url();
 
-    Ok(authorize_url.to_string())
+    Ok((authorize_url.to_string(), state.secret().to_owned()))
+}
+
+/// Parses the redirect URL of the Microsoft authentication process.
+///
+/// # Parameters
+/// - `url`: The URL the user got redirected to after signing in.
+///
+/// # Returns
+/// A result containing the `AuthCode`.
+pub fn parse_redirect(url: &str) -> crate::Result<AuthCode> {
+    let url = Url::parse(url)?;
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+    if let Some(error) = query.get("error") {
+        return Err(Error::Authentication(match error.as_str() {
+            "access_denied" => "User cancelled sign-in.".to_string(),
+            _ => query.get("error_description").unwrap_or(error).clone(),
+        }));
+    }
+
+    let code = query
+        .get("code")
+        .ok_or_else(|| Error::NotFound("Authorization code in the redirect URL".to_string()))?;
+
+    Ok(AuthCode {
+        code: code.clone(),
+        state: query.get("state").cloned(),
+    })
 }
 
 /// Authenticates the user using the provided authorization code.
 ///
 /// # Parameters
-/// - `code`: The authorization code received from the Microsoft authentication process.
+/// - `code`: The authorization code received from the Microsoft authentication process,
+///   or the full redirect URL containing it.
 /// - `client`: The HTTP client used for making requests.
 ///
 /// # Returns
@@ -153,18 +296,16 @@ pub async fn authenticate(
     code: String,
     client: &Client,
 ) -> crate::Result<MinecraftAccount> {
-    let ms_token = get_ms_token(&code, client).await?;
-    let xbox_token = get_xbox_token(&ms_token.access_token, client).await?;
-    let xsts_token = get_xsts_token(&xbox_token.token, client).await?;
-    let userhash = xsts_token
-        .display_claims
-        .xui
-        .first()
-        .ok_or(Error::Authentication("No XUI claims found.".to_string()))?
-        .uhs
-        .clone();
+    let code = if code.starts_with("http://") || code.starts_with("https://") {
+        parse_redirect(&code)?.code
+    } else {
+        code
+    };
+    let endpoints = Endpoints::default();
+    let ms_token = get_ms_token(&code, client, &endpoints).await?;
+    let xbox_token = get_xbox_token(&ms_token.access_token, client, &endpoints).await?;
 
-    obtain_minecraft_account(&xsts_token.token, &userhash, ms_token.refresh_token, client).await
+    authenticate_with_xbox(xbox_token, ms_token.refresh_token, client, &endpoints).await
 }
 
 /// Refreshes the access token using the provided refresh token.
@@ -178,9 +319,26 @@ pub async fn authenticate(
 pub async fn refresh(
     refresh_token: String,
     client: &Client,
+) -> crate::Result<MinecraftAccount> {
+    refresh_with(refresh_token, client, &Endpoints::default()).await
+}
+
+/// Refreshes the access token against the given endpoints.
+///
+/// # Parameters
+/// - `refresh_token`: The refresh token used to obtain a new access token.
+/// - `client`: The HTTP client used for making requests.
+/// - `endpoints`: The endpoints of the authentication chain.
+///
+/// # Returns
+/// A result containing the refreshed `MinecraftAccount`.
+async fn refresh_with(
+    refresh_token: String,
+    client: &Client,
+    endpoints: &Endpoints,
 ) -> crate::Result<MinecraftAccount> {
     let token_response = client
-        .post(TOKEN_URL)
+        .post(&endpoints.token)
         .form(&[ 
             ("client_id", CLIENT_ID),
             ("scope", "service::user.auth.xboxlive.com::MBI_SSL"),
@@ -191,9 +349,112 @@ pub async fn refresh(
         .send()
         .await?;
 
-    let ms_token: MSToken = token_response.json().await?;
-    let xbox_token = get_xbox_token(&ms_token.access_token, client).await?;
-    let xsts_token = get_xsts_token(&xbox_token.token, client).await?;
+    let ms_token = parse_ms_token(token_response).await?;
+    let xbox_token = get_xbox_token(&ms_token.access_token, client, endpoints).await?;
+
+    authenticate_with_xbox(xbox_token, ms_token.refresh_token, client, endpoints).await
+}
+
+/// Refreshes the account while reusing the cached Xbox and XSTS tokens when
+/// they are still valid.
+///
+/// When the XSTS token is still valid only the Minecraft token is requested
+/// again, when only the Xbox token is valid the XSTS token is requested as
+/// well. Otherwise it falls back to the full `refresh` flow.
+///
+/// # Parameters
+/// - `account`: The account to refresh.
+/// - `client`: The HTTP client used for making requests.
+///
+/// # Returns
+/// A result containing the refreshed `MinecraftAccount`.
+pub async fn refresh_account(
+    account: &MinecraftAccount,
+    client: &Client,
+) -> crate::Result<MinecraftAccount> {
+    refresh_account_with(account, client, &Endpoints::default()).await
+}
+
+/// Refreshes the account against the given endpoints.
+///
+/// # Parameters
+/// - `account`: The account to refresh.
+/// - `client`: The HTTP client used for making requests.
+/// - `endpoints`: The endpoints of the authentication chain.
+///
+/// # Returns
+/// A result containing the refreshed `MinecraftAccount`.
+async fn refresh_account_with(
+    account: &MinecraftAccount,
+    client: &Client,
+    endpoints: &Endpoints,
+) -> crate::Result<MinecraftAccount> {
+    if let Some(xsts_token) = &account.xsts_token {
+        if xsts_token.is_valid() && !account.userhash.is_empty() {
+            return obtain_minecraft_account(
+                account.xbox_token.clone(),
+                xsts_token.clone(),
+                account.userhash.clone(),
+                account.refresh_token.clone(),
+                client,
+                endpoints,
+            )
+            .await;
+        }
+    }
+
+    if let Some(xbox_token) = &account.xbox_token {
+        if xbox_token.is_valid() {
+            return authorize_xsts(
+                xbox_token.clone(),
+                account.refresh_token.clone(),
+                client,
+                endpoints,
+            )
+            .await;
+        }
+    }
+
+    refresh_with(account.refresh_token.clone(), client, endpoints).await
+}
+
+/// Continues the authentication chain from a freshly obtained Xbox token.
+///
+/// # Parameters
+/// - `xbox_token`: The Xbox token.
+/// - `refresh_token`: The refresh token for obtaining new access tokens.
+/// - `client`: The HTTP client used for making requests.
+/// - `endpoints`: The endpoints of the authentication chain.
+///
+/// # Returns
+/// A result containing the authenticated `MinecraftAccount`.
+async fn authenticate_with_xbox(
+    xbox_token: XboxToken,
+    refresh_token: String,
+    client: &Client,
+    endpoints: &Endpoints,
+) -> crate::Result<MinecraftAccount> {
+    let xbox_token = CachedToken::new(xbox_token.token, &xbox_token.not_after)?;
+    authorize_xsts(xbox_token, refresh_token, client, endpoints).await
+}
+
+/// Obtains the XSTS token and continues the authentication chain.
+///
+/// # Parameters
+/// - `xbox_token`: The cached Xbox token.
+/// - `refresh_token`: The refresh token for obtaining new access tokens.
+/// - `client`: The HTTP client used for making requests.
+/// - `endpoints`: The endpoints of the authentication chain.
+///
+/// # Returns
+/// A result containing the authenticated `MinecraftAccount`.
+async fn authorize_xsts(
+    xbox_token: CachedToken,
+    refresh_token: String,
+    client: &Client,
+    endpoints: &Endpoints,
+) -> crate::Result<MinecraftAccount> {
+    let xsts_token = get_xsts_token(&xbox_token.token, client, endpoints).await?;
     let userhash = xsts_token
         .display_claims
         .xui
@@ -202,27 +463,39 @@ pub async fn refresh(
         .uhs
         .clone();
 
-    obtain_minecraft_account(&xsts_token.token, &userhash, ms_token.refresh_token, client).await
+    obtain_minecraft_account(
+        Some(xbox_token),
+        CachedToken::new(xsts_token.token, &xsts_token.not_after)?,
+        userhash,
+        refresh_token,
+        client,
+        endpoints,
+    )
+    .await
 }
 
 /// Obtains the Minecraft account details using the provided tokens.
 ///
 /// # Parameters
+/// - `xbox_token`: The Xbox token, kept for later refreshes.
 /// - `xsts_token`: The XSTS token for authentication.
 /// - `userhash`: The user hash obtained from the XSTS token.
 /// - `refresh_token`: The refresh token for obtaining new access tokens.
 /// - `client`: The HTTP client used for making requests.
+/// - `endpoints`: The endpoints of the authentication chain.
 ///
 /// # Returns
 /// A result containing the authenticated `MinecraftAccount`.
 async fn obtain_minecraft_account(
-    xsts_token: &str,
-    userhash: &str,
+    xbox_token: Option<CachedToken>,
+    xsts_token: CachedToken,
+    userhash: String,
     refresh_token: String,
     client: &Client,
+    endpoints: &Endpoints,
 ) -> crate::Result<MinecraftAccount> {
-    let token = get_minecraft_token(xsts_token, userhash, client).await?;
-    let profile = get_profile(token.access_token.clone()).await?;
+    let token = get_minecraft_token(&xsts_token.token, &userhash, client, endpoints).await?;
+    let profile = fetch_profile(&token.access_token, client, &endpoints.profile).await?;
     let jwt = parse_login_token(&token.access_token)?;
 
     Ok(MinecraftAccount {
@@ -233,6 +506,9 @@ async fn obtain_minecraft_account(
         access_token: token.access_token,
         refresh_token,
         client_id: CLIENT_ID.to_string(),
+        userhash,
+        xbox_token,
+        xsts_token: Some(xsts_token),
     })
 }
 
@@ -241,12 +517,17 @@ async fn obtain_minecraft_account(
 /// # Parameters
 /// - `code`: The authorization code received from the Microsoft authentication process.
 /// - `client`: The HTTP client used for making requests.
+/// - `endpoints`: The endpoints of the authentication chain.
 ///
 /// # Returns
 /// A result containing the `MSToken`.
-async fn get_ms_token(code: &str, client: &Client) -> crate::Result<MSToken> {
+async fn get_ms_token(
+    code: &str,
+    client: &Client,
+    endpoints: &Endpoints,
+) -> crate::Result<MSToken> {
     let token_response = client
-        .post(TOKEN_URL)
+        .post(&endpoints.token)
         .form(&[
             ("client_id", CLIENT_ID),
             ("scope", "service::user.auth.xboxlive.com::MBI_SSL"),
@@ -257,8 +538,43 @@ async fn get_ms_token(code: &str, client: &Client) -> crate::Result<MSToken> {
         .send()
         .await?;
 
-    let ms_token: MSToken = token_response.json().await?;
-    Ok(ms_token)
+    parse_ms_token(token_response).await
+}
+
+/// Parses the response of the Microsoft token endpoint.
+///
+/// Revoked or expired grants are reported as `Error::ReauthenticationRequired`
+/// so the interactive flow can be started again, other OAuth errors are
+/// reported as `Error::Authentication`.
+///
+/// # Parameters
+/// - `response`: The response returned by the token endpoint.
+///
+/// # Returns
+/// A result containing the `MSToken`.
+async fn parse_ms_token(response: reqwest::Response) -> crate::Result<MSToken> {
+    parse_ms_token_body(&response.text().await?)
+}
+
+/// Parses the body of a response of the Microsoft token endpoint.
+///
+/// # Parameters
+/// - `body`: The body returned by the token endpoint.
+///
+/// # Returns
+/// A result containing the `MSToken`.
+fn parse_ms_token_body(body: &str) -> crate::Result<MSToken> {
+    if let Ok(error) = serde_json::from_str::<OAuthError>(body) {
+        let description = error.error_description.unwrap_or(error.error.clone());
+        return match error.error.as_str() {
+            "invalid_grant" | "unauthorized_client" => {
+                Err(Error::ReauthenticationRequired(description))
+            }
+            _ => Err(Error::Authentication(description)),
+        };
+    }
+
+    Ok(serde_json::from_str(body)?)
 }
 
 /// Retrieves the Xbox token using the provided Microsoft token.
@@ -266,10 +582,15 @@ async fn get_ms_token(code: &str, client: &Client) -> crate::Result<MSToken> {
 /// # Parameters
 /// - `ms_token`: The Microsoft access token.
 /// - `client`: The HTTP client used for making requests.
+/// - `endpoints`: The endpoints of the authentication chain.
 ///
 /// # Returns
 /// A result containing the `XboxToken`.
-async fn get_xbox_token(ms_token: &str, client: &Client) -> crate::Result<XboxToken> {
+async fn get_xbox_token(
+    ms_token: &str,
+    client: &Client,
+    endpoints: &Endpoints,
+) -> crate::Result<XboxToken> {
     let body = serde_json::json!( {
         "Properties": {
             "AuthMethod": "RPS",
@@ -280,12 +601,7 @@ async fn get_xbox_token(ms_token: &str, client: &Client) -> crate::Result<XboxTo
         "TokenType": "JWT"
     });
 
-    fetch_token(
-        "https://user.auth.xboxlive.com/user/authenticate",
-        body,
-        client,
-    )
-    .await
+    fetch_token(&endpoints.xbox, body, client).await
 }
 
 /// Retrieves the XSTS token using the provided Xbox token.
@@ -293,10 +609,15 @@ async fn get_xbox_token(ms_token: &str, client: &Client) -> crate::Result<XboxTo
 /// # Parameters
 /// - `xbox_token`: The Xbox token for authentication.
 /// - `client`: The HTTP client used for making requests.
+/// - `endpoints`: The endpoints of the authentication chain.
 ///
 /// # Returns
 /// A result containing the `XstsToken`.
-async fn get_xsts_token(xbox_token: &str, client: &Client) -> crate::Result<XstsToken> {
+async fn get_xsts_token(
+    xbox_token: &str,
+    client: &Client,
+    endpoints: &Endpoints,
+) -> crate::Result<XstsToken> {
     let body = serde_json::json!( {
         "Properties": {
             "SandboxId": "RETAIL",
@@ -306,12 +627,7 @@ async fn get_xsts_token(xbox_token: &str, client: &Client) -> crate::Result<Xsts
         "TokenType": "JWT"
     });
 
-    fetch_token(
-        "https://xsts.auth.xboxlive.com/xsts/authorize",
-        body,
-        client,
-    )
-    .await
+    fetch_token(&endpoints.xsts, body, client).await
 }
 
 /// Fetches a token from the specified URL using the provided body.
@@ -328,19 +644,7 @@ async fn fetch_token<T: for<'de> Deserialize<'de>>(
     body: serde_json::Value,
     client: &Client,
 ) -> crate::Result<T> {
-    let token_response: T = fetch_with_options(
-        url,
-        Some(crate::http::fetch::FetchOptions {
-            method: Method::POST,
-            headers: HashMap::default(),
-            query_params: HashMap::default(),
-            body: Some(body),
-        }),
-        client,
-    )
-    .await?;
-
-    Ok(token_response)
+    fetch_with_options(url, Some(FetchOptions::post(body)), client).await
 }
 
 /// Returns player's Minecraft data.
@@ -349,6 +653,7 @@ async fn fetch_token<T: for<'de> Deserialize<'de>>(
 /// - `xsts_token`: Xbox token.
 /// - `userhash`: Hash value.
 /// - `client`: Reqwest client.
+/// - `endpoints`: The endpoints of the authentication chain.
 /// 
 /// # Returns
 /// A result containing the `MinecraftResponse`.
@@ -356,22 +661,13 @@ async fn get_minecraft_token(
     xsts_token: &str,
     userhash: &str,
     client: &Client,
+    endpoints: &Endpoints,
 ) -> crate::Result<MinecraftResponse> {
     let body = serde_json::json!({
         "identityToken": format!("XBL3.0 x={};{}", userhash, xsts_token)
     });
 
-    fetch_with_options(
-        "https://api.minecraftservices.com/authentication/login_with_xbox",
-        Some(crate::http::fetch::FetchOptions {
-            method: Method::POST,
-            headers: HashMap::default(),
-            query_params: HashMap::default(),
-            body: Some(body),
-        }),
-        client,
-    )
-    .await
+    fetch_token(&endpoints.minecraft, body, client).await
 }
 
 /// Parses login token.
@@ -402,10 +698,29 @@ fn parse_login_token(mc_token: &str) -> crate::Result<MCJWTDecoded> {
 ///
 /// # Returns
 /// A result containing the `UserProfile`.
-async fn get_profile(access_token: String) -> crate::Result<UserProfile> {
-    let api_url = "https://api.minecraftservices.com/minecraft/profile";
-    let client = Client::new();
+pub async fn get_profile(access_token: String) -> crate::Result<UserProfile> {
+    fetch_profile(
+        &access_token,
+        &Client::new(),
+        &Endpoints::default().profile,
+    )
+    .await
+}
 
+/// Retrieves the Minecraft profile from the given endpoint.
+///
+/// # Parameters
+/// - `access_token`: The access token for authentication.
+/// - `client`: The HTTP client used for making requests.
+/// - `api_url`: The profile endpoint.
+///
+/// # Returns
+/// A result containing the `UserProfile`.
+async fn fetch_profile(
+    access_token: &str,
+    client: &Client,
+    api_url: &str,
+) -> crate::Result<UserProfile> {
     let response = client
         .get(api_url)
         .header("Authorization", format!("Bearer {}", access_token))
@@ -426,6 +741,74 @@ async fn get_profile(access_token: String) -> crate::Result<UserProfile> {
     }
 }
 
+/// Downloads the active skin texture of the profile as PNG.
+///
+/// # Parameters
+/// - `profile`: The profile of the player.
+/// - `client`: The HTTP client used for making requests.
+///
+/// # Returns
+/// A result containing the PNG bytes of the skin.
+pub async fn fetch_skin_png(profile: &UserProfile, client: &Client) -> crate::Result<Vec<u8>> {
+    let skin = profile
+        .skins
+        .iter()
+        .flatten()
+        .find(|skin| skin.state == "ACTIVE")
+        .ok_or_else(|| Error::NotFound("Active skin".to_string()))?;
+
+    fetch_texture(&skin.url, client).await
+}
+
+/// Downloads the active cape texture of the profile as PNG.
+///
+/// # Parameters
+/// - `profile`: The profile of the player.
+/// - `client`: The HTTP client used for making requests.
+///
+/// # Returns
+/// A result containing the PNG bytes of the cape.
+pub async fn fetch_cape_png(profile: &UserProfile, client: &Client) -> crate::Result<Vec<u8>> {
+    let cape = profile
+        .capes
+        .iter()
+        .flatten()
+        .find(|cape| cape.state == "ACTIVE")
+        .ok_or_else(|| Error::NotFound("Active cape".to_string()))?;
+
+    fetch_texture(&cape.url, client).await
+}
+
+/// Downloads a texture, caching it in memory by the content hash in its URL.
+///
+/// # Parameters
+/// - `url`: The URL of the texture.
+/// - `client`: The HTTP client used for making requests.
+///
+/// # Returns
+/// A result containing the bytes of the texture.
+async fn fetch_texture(url: &str, client: &Client) -> crate::Result<Vec<u8>> {
+    static TEXTURES: OnceLock<Mutex<HashMap<String, Vec<u8>>>> = OnceLock::new();
+    let textures = TEXTURES.get_or_init(Mutex::default);
+
+    // Texture URLs end with the hash of their content.
+    let hash = url.rsplit('/').next().unwrap_or(url).to_string();
+    if let Some(texture) = textures.lock().await.get(&hash) {
+        return Ok(texture.clone());
+    }
+
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| network_error(url, e))?;
+    check_status(&response)?;
+    let texture = response.bytes().await?.to_vec();
+
+    textures.lock().await.insert(hash, texture.clone());
+    Ok(texture)
+}
+
 /// Validates the expiration time of the token.
 ///
 /// # Parameters
@@ -439,4 +822,240 @@ pub fn validate(exp: u64) -> bool {
         .map_err(|_| "System time error")
         .unwrap()
         .as_secs()
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revoked_grant_requires_reauthentication() {
+        let body = r#"{"error":"invalid_grant","error_description":"The refresh token has expired."}"#;
+        match parse_ms_token_body(body) {
+            Err(Error::ReauthenticationRequired(description)) => {
+                assert_eq!(description, "The refresh token has expired.")
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+
+        let body = r#"{"error":"unauthorized_client"}"#;
+        match parse_ms_token_body(body) {
+            Err(Error::ReauthenticationRequired(description)) => {
+                assert_eq!(description, "unauthorized_client")
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn other_oauth_errors_are_authentication_errors() {
+        let body = r#"{"error":"invalid_request","error_description":"Missing code."}"#;
+        match parse_ms_token_body(body) {
+            Err(Error::Authentication(description)) => assert_eq!(description, "Missing code."),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn token_body_is_parsed() {
+        let token =
+            parse_ms_token_body(r#"{"access_token":"access","refresh_token":"refresh"}"#).unwrap();
+        assert_eq!(token.access_token, "access");
+        assert_eq!(token.refresh_token, "refresh");
+    }
+
+    #[test]
+    fn redirect_with_code_and_state() {
+        let code = parse_redirect(
+            "https://login.live.com/oauth20_desktop.srf?code=M.C507_BAY.2.U.abc&state=xyz",
+        )
+        .unwrap();
+        assert_eq!(code.code, "M.C507_BAY.2.U.abc");
+        assert_eq!(code.state.as_deref(), Some("xyz"));
+        assert!(code.verify_state("xyz").is_ok());
+        assert!(code.verify_state("other").is_err());
+    }
+
+    #[test]
+    fn redirect_with_encoded_code_and_no_state() {
+        let code =
+            parse_redirect("https://login.live.com/oauth20_desktop.srf?lc=1033&code=a%2Bb%3Dc")
+                .unwrap();
+        assert_eq!(code.code, "a+b=c");
+        assert!(code.state.is_none());
+        assert!(code.verify_state("xyz").is_err());
+    }
+
+    #[test]
+    fn redirect_with_error() {
+        let cancelled = parse_redirect(
+            "https://login.live.com/oauth20_desktop.srf?error=access_denied&error_description=The+user+has+denied+access",
+        );
+        match cancelled {
+            Err(Error::Authentication(message)) => assert_eq!(message, "User cancelled sign-in."),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let failed = parse_redirect(
+            "https://login.live.com/oauth20_desktop.srf?error=server_error&error_description=Try+again",
+        );
+        match failed {
+            Err(Error::Authentication(message)) => assert_eq!(message, "Try again"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn redirect_without_code() {
+        assert!(matches!(
+            parse_redirect("https://login.live.com/oauth20_desktop.srf?state=xyz"),
+            Err(Error::NotFound(_))
+        ));
+        assert!(parse_redirect("not a url").is_err());
+    }
+
+    mod chain {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        use super::*;
+
+        /// A Minecraft token whose payload is `{"xuid":"2535428","exp":4102444800}`.
+        const MINECRAFT_TOKEN: &str = "header.eyJ4dWlkIjoiMjUzNTQyOCIsImV4cCI6NDEwMjQ0NDgwMH0.signature";
+        const NOT_AFTER: &str = "2100-01-01T00:00:00.0000000Z";
+
+        fn endpoints(server: &MockServer) -> Endpoints {
+            Endpoints {
+                token: format!("{}/token", server.uri()),
+                xbox: format!("{}/xbox", server.uri()),
+                xsts: format!("{}/xsts", server.uri()),
+                minecraft: format!("{}/minecraft", server.uri()),
+                profile: format!("{}/profile", server.uri()),
+            }
+        }
+
+        async fn mount(server: &MockServer, route: &str, body: serde_json::Value, calls: u64) {
+            Mock::given(method(if route == "/profile" { "GET" } else { "POST" }))
+                .and(path(route))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .expect(calls)
+                .mount(server)
+                .await;
+        }
+
+        async fn mount_chain(server: &MockServer, token: u64, xbox: u64, xsts: u64) {
+            mount(
+                server,
+                "/token",
+                serde_json::json!({ "access_token": "ms", "refresh_token": "new-refresh" }),
+                token,
+            )
+            .await;
+            mount(
+                server,
+                "/xbox",
+                serde_json::json!({
+                    "IssueInstant": "2024-01-01T00:00:00.0000000Z",
+                    "NotAfter": NOT_AFTER,
+                    "Token": "xbox"
+                }),
+                xbox,
+            )
+            .await;
+            mount(
+                server,
+                "/xsts",
+                serde_json::json!({
+                    "NotAfter": NOT_AFTER,
+                    "Token": "xsts",
+                    "DisplayClaims": { "xui": [{ "uhs": "hash" }] }
+                }),
+                xsts,
+            )
+            .await;
+            mount(
+                server,
+                "/minecraft",
+                serde_json::json!({
+                    "username": "player",
+                    "access_token": MINECRAFT_TOKEN,
+                    "expires_in": 86400
+                }),
+                1,
+            )
+            .await;
+            mount(
+                server,
+                "/profile",
+                serde_json::json!({ "id": "uuid", "name": "Player" }),
+                1,
+            )
+            .await;
+        }
+
+        fn account(xbox_exp: u64, xsts_exp: u64) -> MinecraftAccount {
+            MinecraftAccount {
+                refresh_token: "refresh".to_string(),
+                userhash: "hash".to_string(),
+                xbox_token: Some(CachedToken {
+                    token: "xbox".to_string(),
+                    exp: xbox_exp,
+                }),
+                xsts_token: Some(CachedToken {
+                    token: "xsts".to_string(),
+                    exp: xsts_exp,
+                }),
+                ..Default::default()
+            }
+        }
+
+        #[tokio::test]
+        async fn valid_xsts_token_only_requests_minecraft_token() {
+            let server = MockServer::start().await;
+            mount_chain(&server, 0, 0, 0).await;
+
+            let account = refresh_account_with(
+                &account(u64::MAX, u64::MAX),
+                &Client::new(),
+                &endpoints(&server),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(account.access_token, MINECRAFT_TOKEN);
+            assert_eq!(account.xuid, "2535428");
+            assert_eq!(account.uuid, "uuid");
+            assert_eq!(account.username, "Player");
+            assert_eq!(account.refresh_token, "refresh");
+        }
+
+        #[tokio::test]
+        async fn valid_xbox_token_skips_microsoft_and_xbox() {
+            let server = MockServer::start().await;
+            mount_chain(&server, 0, 0, 1).await;
+
+            let account =
+                refresh_account_with(&account(u64::MAX, 0), &Client::new(), &endpoints(&server))
+                    .await
+                    .unwrap();
+
+            assert_eq!(account.userhash, "hash");
+            assert!(account.xsts_token.unwrap().is_valid());
+        }
+
+        #[tokio::test]
+        async fn expired_tokens_run_the_full_chain() {
+            let server = MockServer::start().await;
+            mount_chain(&server, 1, 1, 1).await;
+
+            let account = refresh_account_with(&account(0, 0), &Client::new(), &endpoints(&server))
+                .await
+                .unwrap();
+
+            assert_eq!(account.refresh_token, "new-refresh");
+            assert!(account.xbox_token.unwrap().is_valid());
+            assert!(account.xsts_token.unwrap().is_valid());
+        }
+    }
+}
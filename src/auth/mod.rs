@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
+pub mod lookup;
 pub mod microsoft;
 
 /// Represents the authentication method used for logging into Minecraft.
@@ -15,4 +17,174 @@ pub enum AuthMethod {
         access_token: String,
         refresh_token: String
     },
-}
\ No newline at end of file
+}
+
+impl From<microsoft::MinecraftAccount> for AuthMethod {
+    /// Creates a Microsoft authentication method from an account.
+    fn from(account: microsoft::MinecraftAccount) -> Self {
+        AuthMethod::Microsoft {
+            username: account.username,
+            xuid: account.xuid,
+            uuid: account.uuid,
+            access_token: account.access_token,
+            refresh_token: account.refresh_token,
+        }
+    }
+}
+
+impl AuthMethod {
+    /// Creates an offline authentication method.
+    ///
+    /// # Parameters
+    /// - `username`: The username of the player.
+    /// - `uuid`: An optional UUID of the player.
+    /// - `strict`: Whether the username has to follow the rules enforced by
+    ///   vanilla servers, see [`validate_username`].
+    ///
+    /// # Returns
+    /// A result containing the authentication method.
+    pub fn offline(username: String, uuid: Option<String>, strict: bool) -> crate::Result<Self> {
+        if strict {
+            check_username(&username)?;
+        }
+        Ok(AuthMethod::Offline { username, uuid })
+    }
+}
+
+/// Represents the reasons a username can be rejected.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum UsernameError {
+    #[error("Username must be at least 3 characters long, got {0}")]
+    TooShort(usize),
+    #[error("Username must be at most 16 characters long, got {0}")]
+    TooLong(usize),
+    #[error("Username contains an invalid character: {0:?}")]
+    InvalidCharacter(char),
+}
+
+/// Checks whether a username would be accepted by vanilla servers.
+///
+/// A valid username is 3 to 16 characters long and only contains ASCII
+/// letters, digits and underscores.
+///
+/// # Parameters
+/// - `name`: The username to validate.
+///
+/// # Returns
+/// A result indicating whether the username is valid.
+pub fn validate_username(name: &str) -> Result<(), UsernameError> {
+    if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '_')
+    {
+        return Err(UsernameError::InvalidCharacter(c));
+    }
+    match name.len() {
+        len if len < 3 => Err(UsernameError::TooShort(len)),
+        len if len > 16 => Err(UsernameError::TooLong(len)),
+        _ => Ok(()),
+    }
+}
+
+/// Checks whether a username would be accepted by vanilla servers, see
+/// [`validate_username`].
+///
+/// # Parameters
+/// - `name`: The username to validate.
+///
+/// # Returns
+/// A result that is `Error::InvalidUsername` if the username is invalid.
+pub fn check_username(name: &str) -> crate::Result<()> {
+    validate_username(name).map_err(|reason| crate::Error::InvalidUsername {
+        name: name.to_string(),
+        reason,
+    })
+}
+
+/// Turns any string into a username accepted by vanilla servers.
+///
+/// Invalid characters are replaced with underscores, the name is truncated
+/// to 16 characters and padded with underscores up to 3 characters.
+///
+/// # Parameters
+/// - `name`: The username to sanitize.
+///
+/// # Returns
+/// The sanitized username.
+pub fn sanitize_username(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(16)
+        .collect();
+    while sanitized.len() < 3 {
+        sanitized.push('_');
+    }
+    sanitized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boundary_lengths() {
+        assert_eq!(validate_username("ab"), Err(UsernameError::TooShort(2)));
+        assert_eq!(validate_username("abc"), Ok(()));
+        assert_eq!(validate_username("a_234567890123_6"), Ok(()));
+        assert_eq!(
+            validate_username("a_234567890123_67"),
+            Err(UsernameError::TooLong(17))
+        );
+        assert_eq!(validate_username(""), Err(UsernameError::TooShort(0)));
+    }
+
+    #[test]
+    fn unicode_is_invalid() {
+        assert_eq!(
+            validate_username("Jürgen"),
+            Err(UsernameError::InvalidCharacter('ü'))
+        );
+        assert_eq!(
+            validate_username("名前名前"),
+            Err(UsernameError::InvalidCharacter('名'))
+        );
+        assert_eq!(
+            validate_username("a b"),
+            Err(UsernameError::InvalidCharacter(' '))
+        );
+    }
+
+    #[test]
+    fn sanitized_usernames_are_valid() {
+        assert_eq!(sanitize_username("Jürgen"), "J_rgen");
+        assert_eq!(sanitize_username("名"), "___");
+        assert_eq!(sanitize_username(""), "___");
+        assert_eq!(sanitize_username("ab"), "ab_");
+        assert_eq!(sanitize_username("abcdefghijklmnopqrs"), "abcdefghijklmnop");
+        // Characters are counted, not the bytes of their UTF-8 encoding.
+        assert_eq!(sanitize_username(&"é".repeat(20)), "_".repeat(16));
+        assert_eq!(sanitize_username("Valid_Name"), "Valid_Name");
+        for name in ["Jürgen", "名", "", "ab", "🎮 gamer 🎮"] {
+            assert_eq!(validate_username(&sanitize_username(name)), Ok(()));
+        }
+    }
+
+    #[test]
+    fn offline_is_only_validated_when_strict() {
+        assert!(AuthMethod::offline("名".to_string(), None, false).is_ok());
+        assert!(matches!(
+            AuthMethod::offline("名".to_string(), None, true),
+            Err(crate::Error::InvalidUsername {
+                reason: UsernameError::InvalidCharacter('名'),
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,105 @@
+/// Blocking wrappers around the asynchronous API for consumers that don't
+/// want to run an async runtime themselves.
+///
+/// Every call owns a private tokio runtime, progress is delivered through
+/// plain callbacks instead of the asynchronous `Emitter`.
+use std::{process::ExitStatus, sync::Arc};
+
+use tokio::runtime::Runtime;
+
+use crate::minecraft::{
+    config::Config,
+    emitter::{Emitter, Event, EventPayload},
+    install::InstallReport,
+    launch::{launch_with_options, GameInstance, LaunchOptions},
+    loader::Loader,
+};
+
+/// Installs the specified version of Minecraft, blocking until it finishes.
+///
+/// # Parameters
+/// - `config`: The configuration for the installation process.
+/// - `progress`: An optional callback receiving the download progress events.
+///
+/// # Returns
+/// A report of the installation.
+pub fn install<T, F>(config: &Config<T>, progress: Option<F>) -> crate::Result<InstallReport>
+where
+    T: Loader,
+    F: Fn(&EventPayload) + Send + Sync + 'static,
+{
+    let runtime = Runtime::new()?;
+    runtime.block_on(async {
+        let emitter = match progress {
+            Some(progress) => {
+                let emitter = Emitter::default();
+                let progress = Arc::new(progress);
+                for event in [Event::SingleDownloadProgress, Event::MultipleDownloadProgress] {
+                    let progress = progress.clone();
+                    emitter
+                        .on_payload(event, move |payload| progress(payload))
+                        .await;
+                }
+                Some(emitter)
+            }
+            None => None,
+        };
+
+        crate::minecraft::install::install(config, emitter.as_ref()).await
+    })
+}
+
+/// Launches the game, returning a handle to the spawned process.
+///
+/// # Parameters
+/// - `config`: The configuration for the Minecraft launch.
+///
+/// # Returns
+/// A result containing the handle of the game process.
+pub fn launch<T: Loader>(config: &Config<T>) -> crate::Result<Child> {
+    let runtime = Runtime::new()?;
+    let instance =
+        runtime.block_on(launch_with_options(config, &LaunchOptions::default(), None))?;
+    Ok(Child { runtime, instance })
+}
+
+/// A handle to a game process launched through the blocking API.
+pub struct Child {
+    runtime: Runtime,
+    instance: GameInstance,
+}
+
+impl Child {
+    /// Returns the OS-assigned process identifier of the game.
+    ///
+    /// # Returns
+    /// The process id, or `None` if the process has already exited.
+    pub fn id(&self) -> Option<u32> {
+        self.instance.id()
+    }
+
+    /// Waits for the game to exit.
+    ///
+    /// # Returns
+    /// A result containing the exit status of the game.
+    pub fn wait(&mut self) -> crate::Result<ExitStatus> {
+        Ok(self.runtime.block_on(self.instance.child().wait())?)
+    }
+
+    /// Forces the game to exit.
+    ///
+    /// # Returns
+    /// A result indicating whether the process could be killed.
+    pub fn kill(&mut self) -> crate::Result<()> {
+        Ok(self.runtime.block_on(self.instance.child().kill())?)
+    }
+
+    /// Forces the game and the processes it spawned to exit, see
+    /// [`GameInstance::kill_tree`].
+    ///
+    /// # Returns
+    /// A result indicating whether the processes could be killed.
+    pub fn kill_tree(&mut self) -> crate::Result<()> {
+        self.runtime.block_on(self.instance.kill_tree())
+    }
+}
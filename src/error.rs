@@ -1,9 +1,14 @@
+use std::time::Duration;
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum Error {
-    #[error("Unknown {0} version")]
-    UnknownVersion(String),
+    #[error("Unknown {loader_type} version: '{version}'")]
+    UnknownVersion {
+        loader_type: String,
+        version: String,
+    },
     #[error("{0} not found")]
     NotFound(String),
     #[error("Could not parse: {0}")]
@@ -12,30 +17,116 @@ pub enum Error {
     Take(String),
     #[error("Download failed with status code: {0}")]
     Download(String),
+    #[error("Request to {url} failed with client error {status}")]
+    HttpClientError { status: u16, url: String },
+    #[error("Request to {url} failed with server error {status}")]
+    HttpServerError { status: u16, url: String },
+    #[error("Could not reach {url}: {message}")]
+    NetworkError { url: String, message: String },
+    #[error("File is too large: {size} bytes exceeds the limit of {max_size} bytes")]
+    FileTooLarge { size: u64, max_size: u64 },
+    #[error("Hash mismatch for {path}: expected {expected}, got {actual}")]
+    HashMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Rate limited by {url}, retry after {retry_after:?}")]
+    RateLimited { url: String, retry_after: Duration },
+    #[error("Too many failed requests to {0}, not trying again yet")]
+    CircuitOpen(String),
+    #[error("Network unavailable and no cached version manifest")]
+    OfflineNoCache,
     #[error("Timeout error")]
     Timeout(#[from] tokio::time::error::Elapsed),
     #[error("{0}")]
     Authentication(String),
+    #[error("Reauthentication required: {0}")]
+    ReauthenticationRequired(String),
+    #[error("Invalid username {name:?}: {reason}")]
+    InvalidUsername {
+        name: String,
+        #[source]
+        reason: crate::auth::UsernameError,
+    },
     #[error("Malformed token: {0}")]
     MalformedToken(String),
+    #[error("Version mismatch: expected {expected}, got {actual}")]
+    VersionMismatch { expected: String, actual: String },
+    #[error("{0} is not installed")]
+    NotInstalled(String),
+    #[error("Java runtime {0} is damaged, install the version again to repair it")]
+    BrokenJavaRuntime(String),
+    #[error("Java runtime {component} is not available for {platform}")]
+    UnavailableJavaRuntime { component: String, platform: String },
+    #[error("Instance is already running with process id {0}")]
+    InstanceRunning(u32),
+    #[error("Incompatible loader: {0}")]
+    IncompatibleLoader(String),
     #[error("Operation failed: {0}")]
     Fail(String),
     #[error("Unsupported architecture")]
     UnsupportedArchitecture,
-    #[error(transparent)]
+    #[error("Unsupported operating system: {0}")]
+    UnsupportedOperatingSystem(String),
+    #[error("{0}")]
     IO(#[from] tokio::io::Error),
-    #[error(transparent)]
+    #[error("{0}")]
     StripPrefix(#[from] std::path::StripPrefixError),
-    #[error(transparent)]
+    #[error("{0}")]
     Serde(#[from] serde_json::Error),
-    #[error(transparent)]
+    #[error("{0}")]
     Zip(#[from] zip::result::ZipError),
-    #[error(transparent)]
+    #[error("{0}")]
     Base64(#[from] base64::DecodeError),
-    #[error(transparent)]
+    #[error("{0}")]
     Reqwest(#[from] reqwest::Error),
-    #[error(transparent)]
+    #[error("{0}")]
     FromUTF8(#[from] std::string::FromUtf8Error),
-    #[error(transparent)]
+    #[error("{0}")]
     OAuthUrlParse(#[from] oauth2::url::ParseError),
+    #[error("{message}")]
+    Context {
+        message: String,
+        #[source]
+        source: Box<Error>,
+    },
+}
+
+/// Extension trait for attaching context to errors.
+pub trait ErrorExt {
+    /// Wraps the error with a message describing what was being done.
+    ///
+    /// # Parameters
+    /// - `message`: The context message.
+    ///
+    /// # Returns
+    /// The wrapped error.
+    fn context(self, message: impl Into<String>) -> Error;
+}
+
+impl ErrorExt for Error {
+    fn context(self, message: impl Into<String>) -> Error {
+        Error::Context {
+            message: message.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn context_displays_only_the_message() {
+        let error = Error::NotFound("Version JSON".to_string()).context("Reading the version");
+        assert_eq!(error.to_string(), "Reading the version");
+        assert_eq!(
+            error.source().map(ToString::to_string).as_deref(),
+            Some("Version JSON not found")
+        );
+    }
 }
@@ -1,26 +1,51 @@
+use bytes::{Bytes, BytesMut};
 use futures::{stream, StreamExt};
-use reqwest::{Client, IntoUrl};
+use reqwest::{Client, IntoUrl, Url};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
 use std::{
-    path::Path,
+    collections::HashMap,
+    future::Future,
+    path::{Path, PathBuf},
     sync::Arc,
     time::{Duration, Instant},
 };
 use tokio::{
-    fs::{create_dir_all, File},
-    io::AsyncWriteExt,
+    fs::{create_dir_all, remove_file, File},
+    io::{AsyncWriteExt, BufWriter},
     sync::Mutex,
     time::timeout,
 };
 
+use super::{check_status, network_error};
 use crate::{
     error::Error,
     minecraft::{
-        emitter::{Emit, Emitter, Event},
+        emitter::{DownloadProgressPayload, Emit, Emitter, Event, EventPayload},
         install::FileType,
     },
-    util::retry::retry,
+    util::retry::{retry, retry_rate_limited, CircuitBreaker, CircuitState, RateLimit},
 };
 
+/// The default size of the buffer downloads are written through, 64 KiB.
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Options of how downloaded files are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DownloadOptions {
+    /// The size of the buffer the received chunks are collected in before
+    /// they are written to the file.
+    pub buffer_size: usize,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            buffer_size: DEFAULT_BUFFER_SIZE,
+        }
+    }
+}
+
 /// Downloads a file from the specified URL and saves it to the given destination.
 ///
 /// This function performs an asynchronous HTTP GET request to the provided URL,
@@ -54,14 +79,289 @@ pub async fn download<P: AsRef<Path>>(
     emitter: Option<&Emitter>,
     client: Option<&Client>,
 ) -> crate::Result<u64> {
-    // Send a get request to the given url.
+    download_with_options(
+        url,
+        destination,
+        &DownloadOptions::default(),
+        emitter,
+        client,
+    )
+    .await
+}
+
+/// Downloads a file like `download`, with options of how it is written.
+///
+/// # Parameters
+///
+/// - `url`: The URL of the file to download.
+/// - `destination`: The path where the downloaded file will be saved.
+/// - `options`: The options of how the file is written.
+/// - `emitter`: An optional emitter for logging progress.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+///
+/// A result containing the total size of the downloaded file.
+pub async fn download_with_options<P: AsRef<Path>>(
+    url: impl IntoUrl,
+    destination: P,
+    options: &DownloadOptions,
+    emitter: Option<&Emitter>,
+    client: Option<&Client>,
+) -> crate::Result<u64> {
+    stream_to_file(url, destination, None, options.buffer_size, emitter, client).await
+}
+
+/// Downloads a file into memory without touching the filesystem.
+///
+/// Meant for small files such as metadata, the download is aborted as soon as
+/// the size exceeds `max_size`.
+///
+/// # Parameters
+///
+/// - `url`: The URL of the file to download.
+/// - `max_size`: The maximum accepted size of the file in bytes.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+///
+/// A result containing the downloaded bytes.
+///
+/// # Errors
+///
+/// Besides network errors and non-success status codes, returns
+/// `Error::FileTooLarge` if the file is larger than `max_size`.
+pub async fn download_to_memory(
+    url: impl IntoUrl,
+    max_size: u64,
+    client: Option<&Client>,
+) -> crate::Result<Bytes> {
     let default_client = Client::default();
     let client = client.unwrap_or(&default_client);
-    let response = client.get(url).send().await?;
+    let url = url.into_url()?;
+    let response = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| network_error(&url, e))?;
+    check_status(&response)?;
+
+    if let Some(size) = response.content_length() {
+        if size > max_size {
+            return Err(Error::FileTooLarge { size, max_size });
+        }
+    }
+
+    let mut buffer = BytesMut::new();
+    let mut stream = response.bytes_stream();
+
+    while let Some(chunk) = timeout(Duration::from_secs(10), stream.next())
+        .await
+        .map_err(|e| network_error(&url, e))?
+    {
+        let chunk = chunk.map_err(|e| network_error(&url, e))?;
+        let size = (buffer.len() + chunk.len()) as u64;
+        if size > max_size {
+            return Err(Error::FileTooLarge { size, max_size });
+        }
+        buffer.extend_from_slice(&chunk);
+    }
+
+    Ok(buffer.freeze())
+}
+
+/// Represents the outcome of `download_and_verify`.
+#[derive(Debug, Clone)]
+pub enum DownloadVerifyResult {
+    /// The file matched the expected hash.
+    Ok(String),
+    /// No hash was expected, the calculated hash is returned as is.
+    Unverified(String),
+}
+
+/// How many times `download_and_verify` downloads a file again after a hash
+/// mismatch.
+pub const DEFAULT_MAX_HASH_RETRIES: u32 = 2;
+
+/// How long to wait before downloading a file again after a hash mismatch.
+const HASH_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Downloads a file and calculates its SHA-1 hash while it is being written.
+///
+/// This avoids reading the file back from the disk to verify it. If the hash
+/// does not match the expected one, the file is removed and downloaded again
+/// up to `DEFAULT_MAX_HASH_RETRIES` times.
+///
+/// # Parameters
+///
+/// - `url`: The URL of the file to download.
+/// - `destination`: The path where the downloaded file will be saved.
+/// - `expected_sha1`: The expected SHA-1 hash of the file, if known.
+/// - `emitter`: An optional emitter for logging progress.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+///
+/// A result containing the `DownloadVerifyResult` with the calculated hash.
+///
+/// # Errors
+///
+/// Besides the errors of `download`, returns `Error::HashMismatch` if the
+/// calculated hash differs from the expected one.
+pub async fn download_and_verify<P: AsRef<Path>>(
+    url: impl IntoUrl,
+    destination: P,
+    expected_sha1: Option<&str>,
+    emitter: Option<&Emitter>,
+    client: Option<&Client>,
+) -> crate::Result<DownloadVerifyResult> {
+    download_and_verify_with_retries(
+        url,
+        None,
+        destination,
+        expected_sha1,
+        DEFAULT_MAX_HASH_RETRIES,
+        emitter,
+        client,
+    )
+    .await
+}
 
-    if !response.status().is_success() {
-        return Err(Error::Download(response.status().to_string()));
+/// Downloads a file and calculates its SHA-1 hash while it is being written,
+/// downloading it again after a hash mismatch.
+///
+/// A corrupted download is usually a transient issue of the server, so the
+/// retries are downloaded from `fallback_url` when one is provided.
+///
+/// # Parameters
+///
+/// - `url`: The URL of the file to download.
+/// - `fallback_url`: An optional alternative URL, e.g. of a mirror, used by
+///   the retries.
+/// - `destination`: The path where the downloaded file will be saved.
+/// - `expected_sha1`: The expected SHA-1 hash of the file, if known.
+/// - `max_hash_retries`: How many times the file is downloaded again after a
+///   hash mismatch.
+/// - `emitter`: An optional emitter for logging progress and retries.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+///
+/// A result containing the `DownloadVerifyResult` with the calculated hash.
+///
+/// # Errors
+///
+/// Besides the errors of `download`, returns `Error::HashMismatch` if the
+/// hash still differs from the expected one after every retry.
+pub async fn download_and_verify_with_retries<P: AsRef<Path>>(
+    url: impl IntoUrl,
+    fallback_url: Option<&str>,
+    destination: P,
+    expected_sha1: Option<&str>,
+    max_hash_retries: u32,
+    emitter: Option<&Emitter>,
+    client: Option<&Client>,
+) -> crate::Result<DownloadVerifyResult> {
+    stream_verified(
+        url,
+        fallback_url,
+        destination,
+        expected_sha1,
+        max_hash_retries,
+        DEFAULT_BUFFER_SIZE,
+        emitter,
+        client,
+    )
+    .await
+    .map(|(_, result)| result)
+}
+
+/// Downloads a file like `download_and_verify_with_retries`, through a
+/// buffer of `buffer_size` bytes.
+///
+/// # Returns
+/// A result containing the size of the file and the `DownloadVerifyResult`.
+#[allow(clippy::too_many_arguments)]
+async fn stream_verified<P: AsRef<Path>>(
+    url: impl IntoUrl,
+    fallback_url: Option<&str>,
+    destination: P,
+    expected_sha1: Option<&str>,
+    max_hash_retries: u32,
+    buffer_size: usize,
+    emitter: Option<&Emitter>,
+    client: Option<&Client>,
+) -> crate::Result<(u64, DownloadVerifyResult)> {
+    let url = url.into_url()?;
+    let path = destination.as_ref().to_string_lossy().into_owned();
+    let mut attempt = 0;
+    loop {
+        let source = match fallback_url {
+            Some(fallback_url) if attempt > 0 => Url::parse(fallback_url)?,
+            _ => url.clone(),
+        };
+        let mut hasher = Sha1::new();
+        let size = stream_to_file(
+            source,
+            &destination,
+            Some(&mut hasher),
+            buffer_size,
+            emitter,
+            client,
+        )
+        .await?;
+        let hash = format!("{:x}", hasher.finalize());
+
+        match expected_sha1 {
+            Some(expected) if !expected.eq_ignore_ascii_case(&hash) => {
+                remove_file(&destination).await?;
+                if attempt >= max_hash_retries {
+                    return Err(Error::HashMismatch {
+                        path,
+                        expected: expected.to_string(),
+                        actual: hash,
+                    });
+                }
+                attempt += 1;
+                emitter
+                    .emit(
+                        Event::HashRetrying,
+                        EventPayload::HashRetrying {
+                            path: path.clone(),
+                            attempt,
+                            max: max_hash_retries,
+                        },
+                    )
+                    .await;
+                tokio::time::sleep(HASH_RETRY_DELAY).await;
+            }
+            Some(_) => return Ok((size, DownloadVerifyResult::Ok(hash))),
+            None => return Ok((size, DownloadVerifyResult::Unverified(hash))),
+        }
     }
+}
+
+/// Streams the response body of the given URL into a file through a buffer
+/// of `buffer_size` bytes, optionally feeding every chunk into a SHA-1
+/// hasher.
+async fn stream_to_file<P: AsRef<Path>>(
+    url: impl IntoUrl,
+    destination: P,
+    mut hasher: Option<&mut Sha1>,
+    buffer_size: usize,
+    emitter: Option<&Emitter>,
+    client: Option<&Client>,
+) -> crate::Result<u64> {
+    // Send a get request to the given url.
+    let default_client = Client::default();
+    let client = client.unwrap_or(&default_client);
+    let url = url.into_url()?;
+    let response = client
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| network_error(&url, e))?;
+    check_status(&response)?;
 
     // Get the total size of the file to use at progression
     let total_size = response.content_length().unwrap_or(0);
@@ -74,14 +374,17 @@ pub async fn download<P: AsRef<Path>>(
     }
 
     // Create a file to write the downloaded content
-    let mut file = File::create(&destination).await?;
+    let mut file = BufWriter::with_capacity(buffer_size, File::create(&destination).await?);
 
     // Stream the response body
     let mut stream = response.bytes_stream();
 
     let mut last_data_received;
 
-    while let Some(chunk_result) = timeout(Duration::from_secs(10), stream.next()).await? {
+    while let Some(chunk_result) = timeout(Duration::from_secs(10), stream.next())
+        .await
+        .map_err(|e| network_error(&url, e))?
+    {
         match chunk_result {
             Ok(chunk) => {
                 // Reset the timer when data is received
@@ -91,6 +394,10 @@ pub async fn download<P: AsRef<Path>>(
                 // Write chunk to the file
                 file.write_all(&chunk).await?;
 
+                if let Some(hasher) = hasher.as_mut() {
+                    hasher.update(&chunk);
+                }
+
                 // Emit progress event
                 emitter
                     .emit(
@@ -103,88 +410,289 @@ pub async fn download<P: AsRef<Path>>(
                     )
                     .await;
             }
-            Err(_) => {
-                // Timeout occurred (no chunk received in 3 seconds)
-                return Err(Error::Download(
-                    "Connection dead, no data for 3 seconds.".to_string(),
-                ));
+            Err(e) => {
+                return Err(network_error(&url, e));
             }
         }
 
         // Check if no data has been received in the last 3 seconds
         if last_data_received.elapsed() > Duration::from_secs(10) {
-            return Err(Error::Download(
-                "Connection dead, no data for 3 seconds.".to_string(),
+            return Err(network_error(
+                &url,
+                "Connection dead, no data for 10 seconds.",
             ));
         }
     }
+    file.flush().await?;
 
     Ok(total_size)
 }
 
-/// Downloads multiple files from the specified URLs and saves them to the given destinations.
+/// A file to download with `download_many`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadItem {
+    /// The URL of the file.
+    pub url: String,
+    /// The path the file is saved to.
+    pub dest: PathBuf,
+    /// The expected SHA-1 hash of the file, a download with another hash is
+    /// removed and retried.
+    pub expected_hash: Option<String>,
+    /// An alternative URL of the file, e.g. of another mirror, that the
+    /// retries after a hash mismatch download from.
+    pub fallback_url: Option<String>,
+    /// The size of the file in bytes, used for the byte progress.
+    pub size: Option<u64>,
+    /// A label grouping the files in the progress, e.g. `Asset`.
+    pub tag: String,
+}
+
+impl DownloadItem {
+    /// Creates an item without a hash or size.
+    ///
+    /// # Parameters
+    /// - `url`: The URL of the file.
+    /// - `dest`: The path the file is saved to.
+    /// - `tag`: A label grouping the files in the progress.
+    pub fn new(url: impl Into<String>, dest: impl Into<PathBuf>, tag: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            dest: dest.into(),
+            expected_hash: None,
+            fallback_url: None,
+            size: None,
+            tag: tag.into(),
+        }
+    }
+
+    /// Sets the expected SHA-1 hash of the file.
+    pub fn expected_hash(mut self, expected_hash: impl Into<String>) -> Self {
+        self.expected_hash = Some(expected_hash.into());
+        self
+    }
+
+    /// Sets the URL the retries after a hash mismatch download from.
+    pub fn fallback_url(mut self, fallback_url: impl Into<String>) -> Self {
+        self.fallback_url = Some(fallback_url.into());
+        self
+    }
+
+    /// Sets the size of the file in bytes.
+    pub fn size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+}
+
+/// Options of `download_many`.
+#[derive(Clone, Default)]
+pub struct DownloadManyOptions {
+    /// An optional HTTP client, a default one is used if not provided.
+    pub client: Option<Client>,
+    /// An optional circuit breaker, failing the downloads from hosts that
+    /// failed too often immediately instead of retrying them.
+    pub circuit_breaker: Option<Arc<Mutex<CircuitBreaker>>>,
+    /// How long downloads wait for servers that ask to retry later, these
+    /// waits don't count as retries.
+    pub rate_limit: RateLimit,
+    /// The options of how the files are written.
+    pub download: DownloadOptions,
+}
+
+/// Downloads multiple files and saves them to their destinations.
 ///
-/// This function takes a vector of tuples, where each tuple contains a URL and a destination path.
-/// It downloads all files in parallel and provides progress updates through a callback function.
+/// The files are downloaded in parallel. Server and network errors are
+/// retried, hash mismatches are downloaded again from the fallback URL of the
+/// item, other errors fail the whole batch.
+///
+/// # Parameters
+///
+/// - `items`: The files to download.
+/// - `options`: The options of the downloads.
+/// - `emitter`: An optional emitter, notified with a
+///   `DownloadProgressPayload` whenever a file is finished.
+///
+/// # Returns
+///
+/// A result indicating success, the error of the first file that could not
+/// be downloaded otherwise.
+pub async fn download_many(
+    items: Vec<DownloadItem>,
+    options: &DownloadManyOptions,
+    emitter: Option<&Emitter>,
+) -> crate::Result<()> {
+    download_many_with(items, options, emitter, |_| async { Ok(true) }, |_, _| {}).await
+}
+
+/// Downloads multiple files like `download_many`, asking `before_start`
+/// whether an item still has to be downloaded and calling `on_complete`
+/// with every item and its size once it is finished.
 ///
 /// # Parameters
 ///
-/// - `downloads`: A vector of tuples containing the URLs and their corresponding destination paths.
+/// - `items`: The files to download.
+/// - `options`: The options of the downloads.
 /// - `emitter`: An optional emitter for logging progress.
+/// - `before_start`: Called before an item is downloaded, an item it returns
+///   `false` for counts as finished without being downloaded.
+/// - `on_complete`: Called whenever a file is finished.
 ///
 /// # Returns
 ///
-/// This function returns a `Result<(), Error>`. On success, it returns `Ok(())`. If an error occurs
-/// during the download process, it returns an `Err` containing an `Error` that describes the failure.
-pub async fn download_multiple<U, P>(
-    downloads: Vec<(U, P, FileType)>,
+/// A result indicating success, the error of the first file that could not
+/// be downloaded otherwise.
+pub(crate) async fn download_many_with<F>(
+    items: Vec<DownloadItem>,
+    options: &DownloadManyOptions,
     emitter: Option<&Emitter>,
-    client: Option<&Client>,
+    before_start: impl Fn(&DownloadItem) -> F + Sync,
+    on_complete: impl Fn(&DownloadItem, u64) + Sync,
 ) -> crate::Result<()>
 where
-    U: IntoUrl + Send,               // URL type that implements IntoUrl
-    P: AsRef<Path> + Send + 'static, // Path type
+    F: Future<Output = crate::Result<bool>>,
 {
-    let total_files = downloads.len();
-    let total_downloaded = Arc::new(Mutex::new(0));
-    let tasks = downloads.into_iter().map(|(url, destination, file_type)| {
-        let total_downloaded = Arc::clone(&total_downloaded);
+    let total_files = items.len() as u64;
+    // The byte total is only known if the size of every file is
+    let bytes_total = items.iter().map(|item| item.size).sum::<Option<u64>>();
+    let progress = Arc::new(Mutex::new(BatchProgress::default()));
+
+    // The downloaded and total files of every tag, so that the progress of
+    // each of them can be shown separately.
+    for item in &items {
+        progress
+            .lock()
+            .await
+            .tags
+            .entry(item.tag.clone())
+            .or_default()
+            .1 += 1;
+    }
+
+    let client = options.client.as_ref();
+    let before_start = &before_start;
+    let on_complete = &on_complete;
+    let tasks = items.into_iter().map(|item| {
+        let progress = Arc::clone(&progress);
         async move {
-            // Retry download logic
-            let result = retry(
-                || async { download(url.as_str(), destination.as_ref(), emitter, client).await },
-                Result::is_ok,
-                3,
-                Duration::from_secs(5),
-            )
-            .await;
+            let host = Url::parse(&item.url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string));
+            let circuit = options.circuit_breaker.as_deref().zip(host.as_deref());
 
-            // Check if the download was successful
-            match result {
-                Ok(_) => {
-                    // Update the progress counter
-                    let mut downloaded = total_downloaded.lock().await;
-                    *downloaded += 1;
+            let result = if before_start(&item).await? {
+                // Retry download logic
+                retry(
+                    || async {
+                        if let Some((circuit_breaker, host)) = circuit {
+                            match circuit_breaker.lock().await.check(host) {
+                                CircuitState::Open => {
+                                    return Err(Error::CircuitOpen(host.to_string()));
+                                }
+                                CircuitState::Reset => {
+                                    emitter
+                                        .emit(
+                                            Event::CircuitBreakerReset,
+                                            EventPayload::CircuitBreakerReset {
+                                                host: host.to_string(),
+                                            },
+                                        )
+                                        .await;
+                                }
+                                CircuitState::Closed => {}
+                            }
+                        }
 
+                        let result = retry_rate_limited(
+                            || download_item(&item, &options.download, emitter, client),
+                            options.rate_limit,
+                            emitter,
+                        )
+                        .await;
+
+                        if let Some((circuit_breaker, host)) = circuit {
+                            let mut circuit_breaker = circuit_breaker.lock().await;
+                            if result.is_ok() {
+                                circuit_breaker.record_success(host);
+                            } else if circuit_breaker.record_failure(host) {
+                                drop(circuit_breaker);
+                                emitter
+                                    .emit(
+                                        Event::CircuitBreakerOpen,
+                                        EventPayload::CircuitBreakerOpen {
+                                            host: host.to_string(),
+                                        },
+                                    )
+                                    .await;
+                            }
+                        }
+
+                        // Every failed attempt is reported, even if a retry succeeds
+                        if let Err(e) = &result {
+                            emitter
+                                .emit(
+                                    Event::DownloadError,
+                                    EventPayload::DownloadError {
+                                        url: item.url.clone(),
+                                        error: e.to_string(),
+                                    },
+                                )
+                                .await;
+                        }
+                        result
+                    },
+                    |result| !result.as_ref().is_err_and(is_transient),
+                    3,
+                    Duration::from_secs(5),
+                )
+                .await
+            } else {
+                Ok(0)
+            };
+
+            let downloaded = result?;
+            on_complete(&item, downloaded);
+            let payload = {
+                let mut progress = progress.lock().await;
+                progress.files += 1;
+                progress.bytes += item.size.unwrap_or(downloaded);
+                let (tag_current, tag_total) = {
+                    let tag = progress.tags.entry(item.tag.clone()).or_default();
+                    tag.0 += 1;
+                    *tag
+                };
+                DownloadProgressPayload {
+                    path: item.dest.to_string_lossy().into_owned(),
+                    current: progress.files,
+                    total: total_files,
+                    tag: item.tag.clone(),
+                    type_current: tag_current,
+                    type_total: tag_total,
+                    bytes_current: progress.bytes,
+                    bytes_total,
+                }
+            };
+
+            if let Some(emitter) = emitter {
+                if emitter
+                    .has_listeners(Event::LegacyMultipleDownloadProgress)
+                    .await
+                {
                     emitter
                         .emit(
-                            Event::MultipleDownloadProgress,
+                            Event::LegacyMultipleDownloadProgress,
                             (
-                                destination.as_ref().to_string_lossy().into_owned(),
-                                *downloaded as u64,
-                                total_files as u64,
-                                file_type.to_string(),
+                                payload.path.clone(),
+                                payload.current,
+                                payload.total,
+                                payload.tag.clone(),
                             ),
                         )
                         .await;
-
-                    Ok::<(), Error>(())
-                }
-                Err(e) => {
-                    // Return the error immediately
-                    Err(e)
                 }
+                emitter.emit(Event::MultipleDownloadProgress, payload).await;
             }
+
+            Ok::<(), Error>(())
         }
     });
 
@@ -198,3 +706,394 @@ where
 
     Ok(())
 }
+
+/// The progress of a `download_many` batch.
+#[derive(Default)]
+struct BatchProgress {
+    /// The number of finished files.
+    files: u64,
+    /// The number of downloaded bytes of the finished files.
+    bytes: u64,
+    /// The finished and total files of every tag.
+    tags: HashMap<String, (u64, u64)>,
+}
+
+/// Downloads a single item of `download_many`, verifying its hash if one is
+/// expected.
+///
+/// A hash mismatch is retried up to `DEFAULT_MAX_HASH_RETRIES` times from
+/// the fallback URL of the item, see `download_and_verify_with_retries`.
+///
+/// # Returns
+/// A result containing the size of the downloaded file.
+async fn download_item(
+    item: &DownloadItem,
+    options: &DownloadOptions,
+    emitter: Option<&Emitter>,
+    client: Option<&Client>,
+) -> crate::Result<u64> {
+    stream_verified(
+        item.url.as_str(),
+        item.fallback_url.as_deref(),
+        &item.dest,
+        item.expected_hash.as_deref(),
+        DEFAULT_MAX_HASH_RETRIES,
+        options.buffer_size,
+        emitter,
+        client,
+    )
+    .await
+    .map(|(size, _)| size)
+}
+
+/// Downloads multiple files from the specified URLs and saves them to the given destinations.
+///
+/// This function takes a vector of tuples, where each tuple contains a URL and a destination path.
+/// It downloads all files in parallel and provides progress updates through a callback function.
+///
+/// # Parameters
+///
+/// - `downloads`: A vector of tuples containing the URLs, their corresponding destination paths
+///   and their file types, which become the tags of the progress.
+/// - `emitter`: An optional emitter for logging progress.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+///
+/// This function returns a `Result<(), Error>`. On success, it returns `Ok(())`. If an error occurs
+/// during the download process, it returns an `Err` containing an `Error` that describes the failure.
+#[deprecated(note = "use `download_many`, whose `DownloadManyOptions` take the other settings")]
+pub async fn download_multiple<U, P>(
+    downloads: Vec<(U, P, FileType)>,
+    emitter: Option<&Emitter>,
+    client: Option<&Client>,
+) -> crate::Result<()>
+where
+    U: IntoUrl + Send,               // URL type that implements IntoUrl
+    P: AsRef<Path> + Send + 'static, // Path type
+{
+    let items = downloads
+        .into_iter()
+        .map(|(url, destination, file_type)| {
+            DownloadItem::new(url.as_str(), destination.as_ref(), file_type.to_string())
+        })
+        .collect();
+    download_many(
+        items,
+        &DownloadManyOptions {
+            client: client.cloned(),
+            ..Default::default()
+        },
+        emitter,
+    )
+    .await
+}
+
+/// Checks whether a failed download may succeed when it is retried.
+///
+/// Server and network errors may go away by themselves. A missing file or an
+/// open circuit would fail again right away, and a hash mismatch was already
+/// retried by `download_item`.
+///
+/// # Parameters
+/// - `error`: The error of the download.
+///
+/// # Returns
+/// Whether the download is worth retrying.
+fn is_transient(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::HttpServerError { .. } | Error::NetworkError { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex as StdMutex;
+
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    /// Starts a server answering every request with `data`.
+    async fn server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("data"))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[test]
+    fn server_and_network_errors_are_retried() {
+        let url = || "https://example.com/a".to_string();
+        // Hash mismatches are retried by `download_item` instead.
+        assert!(!is_transient(&Error::HashMismatch {
+            path: "a".to_string(),
+            expected: "da39".to_string(),
+            actual: "a9993".to_string(),
+        }));
+        assert!(is_transient(&Error::HttpServerError {
+            status: 503,
+            url: url()
+        }));
+        assert!(is_transient(&Error::NetworkError {
+            url: url(),
+            message: "connection reset".to_string(),
+        }));
+        assert!(!is_transient(&Error::HttpClientError {
+            status: 404,
+            url: url()
+        }));
+        assert!(!is_transient(&Error::CircuitOpen(
+            "example.com".to_string()
+        )));
+    }
+
+    #[tokio::test]
+    async fn progress_is_counted_per_tag() {
+        let server = server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let item = |name: &str, tag: &str| {
+            DownloadItem::new(
+                format!("{}/{}", server.uri(), name),
+                dir.path().join(name),
+                tag,
+            )
+        };
+        let items = vec![item("a", "Asset"), item("b", "Asset"), item("c", "Library")];
+
+        let emitter = Emitter::default();
+        let progress = Arc::new(StdMutex::new(Vec::new()));
+        emitter
+            .on_file_type_download_progress({
+                let progress = progress.clone();
+                move |tag, current, total| {
+                    progress
+                        .lock()
+                        .unwrap()
+                        .push((tag.to_string(), current, total))
+                }
+            })
+            .await;
+        download_many(items, &DownloadManyOptions::default(), Some(&emitter))
+            .await
+            .unwrap();
+
+        let mut progress = progress.lock().unwrap().clone();
+        progress.sort();
+        assert_eq!(
+            progress,
+            [
+                ("Asset".to_string(), 1, 2),
+                ("Asset".to_string(), 2, 2),
+                ("Library".to_string(), 1, 1),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn progress_is_emitted_once_per_file() {
+        let server = server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let items = ["a", "b"]
+            .into_iter()
+            .map(|name| {
+                DownloadItem::new(
+                    format!("{}/{}", server.uri(), name),
+                    dir.path().join(name),
+                    "Asset",
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let emitter = Emitter::default();
+        let progress = Arc::new(StdMutex::new(Vec::new()));
+        emitter
+            .on(Event::MultipleDownloadProgress, {
+                let progress = progress.clone();
+                move |(_, current, total, tag): (String, u64, u64, String)| {
+                    progress.lock().unwrap().push((current, total, tag))
+                }
+            })
+            .await;
+        download_many(
+            items.clone(),
+            &DownloadManyOptions::default(),
+            Some(&emitter),
+        )
+        .await
+        .unwrap();
+
+        let mut received = progress.lock().unwrap().clone();
+        received.sort();
+        assert_eq!(
+            received,
+            [(1, 2, "Asset".to_string()), (2, 2, "Asset".to_string())]
+        );
+
+        // The legacy event reaches its own listeners, once per file as well.
+        let legacy = Arc::new(StdMutex::new(0));
+        emitter
+            .on(Event::LegacyMultipleDownloadProgress, {
+                let legacy = legacy.clone();
+                move |_: (String, u64, u64, String)| *legacy.lock().unwrap() += 1
+            })
+            .await;
+        progress.lock().unwrap().clear();
+        for item in &items {
+            std::fs::remove_file(&item.dest).unwrap();
+        }
+        download_many(items, &DownloadManyOptions::default(), Some(&emitter))
+            .await
+            .unwrap();
+
+        assert_eq!(*legacy.lock().unwrap(), 2);
+        assert_eq!(progress.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn custom_tags_report_bytes() {
+        let server = server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let item = |name: &str, tag: &str| {
+            DownloadItem::new(
+                format!("{}/{}", server.uri(), name),
+                dir.path().join(name),
+                tag,
+            )
+            .size(4)
+        };
+        let items = vec![
+            item("a", "modpack"),
+            item("b", "modpack"),
+            item("c", "self-update"),
+        ];
+
+        let emitter = Emitter::default();
+        let progress = Arc::new(StdMutex::new(Vec::new()));
+        emitter
+            .on_multiple_download_progress({
+                let progress = progress.clone();
+                move |payload| progress.lock().unwrap().push(payload.clone())
+            })
+            .await;
+        download_many(items, &DownloadManyOptions::default(), Some(&emitter))
+            .await
+            .unwrap();
+
+        let mut progress = progress.lock().unwrap().clone();
+        progress.sort_by_key(|payload| payload.current);
+        assert_eq!(
+            progress
+                .iter()
+                .map(|payload| (payload.bytes_current, payload.bytes_total))
+                .collect::<Vec<_>>(),
+            [(4, Some(12)), (8, Some(12)), (12, Some(12))]
+        );
+        let mut tags = progress
+            .iter()
+            .map(|payload| {
+                (
+                    payload.tag.as_str(),
+                    payload.type_current,
+                    payload.type_total,
+                )
+            })
+            .collect::<Vec<_>>();
+        tags.sort();
+        assert_eq!(
+            tags,
+            [("modpack", 1, 2), ("modpack", 2, 2), ("self-update", 1, 1)]
+        );
+    }
+
+    /// Downloads a file expected to contain `data` from `url`, falling back
+    /// to `fallback_url`.
+    ///
+    /// # Returns
+    /// The contents of the file, the attempts and maximums of the emitted
+    /// `Event::HashRetrying` and the number of emitted download errors.
+    async fn download_data(
+        url: String,
+        fallback_url: Option<String>,
+    ) -> (String, Vec<(u32, u32)>, usize) {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("file");
+        let mut item = DownloadItem::new(url, &dest, "modpack")
+            .expected_hash("a17c9aaa61e80a1bf71d0d850af4e5baa9800bbd");
+        item.fallback_url = fallback_url;
+
+        let emitter = Emitter::default();
+        let retries = Arc::new(StdMutex::new(Vec::new()));
+        emitter
+            .on_hash_retrying({
+                let retries = retries.clone();
+                move |_, attempt, max| retries.lock().unwrap().push((attempt, max))
+            })
+            .await;
+        let errors = Arc::new(StdMutex::new(0));
+        emitter
+            .on_download_error({
+                let errors = errors.clone();
+                move |_, _| *errors.lock().unwrap() += 1
+            })
+            .await;
+        download_many(vec![item], &DownloadManyOptions::default(), Some(&emitter))
+            .await
+            .unwrap();
+
+        let retries = retries.lock().unwrap().clone();
+        let errors = *errors.lock().unwrap();
+        (std::fs::read_to_string(&dest).unwrap(), retries, errors)
+    }
+
+    #[tokio::test]
+    async fn hash_mismatch_is_retried() {
+        let server = MockServer::start().await;
+        // The first response is cut short.
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("da"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("data"))
+            .mount(&server)
+            .await;
+
+        let (data, retries, errors) = download_data(format!("{}/file", server.uri()), None).await;
+
+        assert_eq!(data, "data");
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+        assert_eq!(retries, [(1, DEFAULT_MAX_HASH_RETRIES)]);
+        assert_eq!(errors, 0);
+    }
+
+    #[tokio::test]
+    async fn hash_mismatch_is_retried_from_the_fallback() {
+        let corrupt = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("dada"))
+            .expect(1)
+            .mount(&corrupt)
+            .await;
+        let fallback = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("data"))
+            .expect(1)
+            .mount(&fallback)
+            .await;
+
+        let (data, retries, errors) = download_data(
+            format!("{}/file", corrupt.uri()),
+            Some(format!("{}/file", fallback.uri())),
+        )
+        .await;
+
+        assert_eq!(data, "data");
+        assert_eq!(retries, [(1, 2)]);
+        assert_eq!(errors, 0);
+    }
+}
@@ -1,23 +1,100 @@
-use reqwest::{Client, IntoUrl, Response};
-use serde::{de::DeserializeOwned, Serialize};
-use std::collections::HashMap;
+use reqwest::{
+    header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    Client, IntoUrl, Method, Response, StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use super::{check_status, network_error};
+use crate::{
+    minecraft::emitter::Emitter,
+    util::{
+        json::{read_json_streaming, write_json},
+        retry::{retry_rate_limited, RateLimit},
+    },
+};
 
 /// A struct to hold optional fetch request parameters.
 #[derive(Default)]
 pub struct FetchOptions<B: Serialize> {
-    pub method: reqwest::Method,
+    /// The HTTP method of the request.
+    pub method: Method,
+    /// Additional headers sent with the request.
     pub headers: HashMap<String, String>,
+    /// Query parameters appended to the URL.
     pub query_params: HashMap<String, String>,
+    /// A body serialized as JSON.
     pub body: Option<B>,
 }
 
+impl<B: Serialize> FetchOptions<B> {
+    /// Creates the options of a POST request with a JSON body.
+    ///
+    /// # Parameters
+    /// - `body`: The body of the request.
+    ///
+    /// # Returns
+    /// The options of the request.
+    pub fn post(body: B) -> Self {
+        Self {
+            method: Method::POST,
+            headers: HashMap::new(),
+            query_params: HashMap::new(),
+            body: Some(body),
+        }
+    }
+}
+
+/// Fetches a URL with a GET request and deserializes the JSON response.
+///
+/// Servers asking to retry later through `Retry-After` are waited for, within
+/// the default `RateLimit`. The response may be compressed with gzip or
+/// zstd, which the client advertises and decodes by itself.
+///
+/// # Parameters
+/// - `url`: The URL to fetch.
+/// - `client`: An optional HTTP client, a default one is used if not provided.
+///
+/// # Returns
+/// A result containing the deserialized response.
 pub async fn fetch<T: DeserializeOwned>(
     url: impl IntoUrl,
     client: Option<&Client>,
+) -> crate::Result<T> {
+    fetch_rate_limited(url, client, RateLimit::default(), None).await
+}
+
+/// Fetches a URL like `fetch`, waiting for servers that ask to retry later
+/// within the given `RateLimit`.
+///
+/// # Parameters
+/// - `url`: The URL to fetch.
+/// - `client`: An optional HTTP client, a default one is used if not provided.
+/// - `rate_limit`: How long the request may wait for the server.
+/// - `emitter`: An optional emitter, warned about every wait.
+///
+/// # Returns
+/// A result containing the deserialized response.
+pub async fn fetch_rate_limited<T: DeserializeOwned>(
+    url: impl IntoUrl,
+    client: Option<&Client>,
+    rate_limit: RateLimit,
+    emitter: Option<&Emitter>,
 ) -> crate::Result<T> {
     // Call the fetch function with default options
     let default_client = Client::default();
-    fetch_with_options::<T, ()>(url, None, client.unwrap_or(&default_client)).await
+    let client = client.unwrap_or(&default_client);
+    let url = url.into_url()?;
+    retry_rate_limited(
+        || fetch_with_options::<T, ()>(url.clone(), None, client),
+        rate_limit,
+        emitter,
+    )
+    .await
 }
 
 /// Performs a customizable fetch request.
@@ -26,6 +103,9 @@ pub async fn fetch<T: DeserializeOwned>(
 /// the HTTP method, headers, query parameters, and body. If no options are provided,
 /// it defaults to a GET request that returns JSON.
 ///
+/// The client sends `Accept-Encoding` and decompresses gzip and zstd
+/// responses, unless `Accept-Encoding` is set in the headers of the options.
+///
 /// # Parameters
 ///
 /// - `url`: A string slice representing the URL to send the request to.
@@ -48,8 +128,11 @@ pub async fn fetch<T: DeserializeOwned>(
 /// # Errors
 ///
 /// The function can fail in several ways, including but not limited to:
-/// - Network errors when sending the request.
-/// - Non-success HTTP status codes (e.g., 404 Not Found).
+/// - `Error::NetworkError` when the server can't be reached.
+/// - `Error::HttpClientError` for a 4xx status (e.g., 404 Not Found), which
+///   shouldn't be retried.
+/// - `Error::HttpServerError` for a 5xx status.
+/// - `Error::RateLimited` for a 429 or 503 response with a `Retry-After` header.
 /// - Errors during the deserialization of the response body.
 pub async fn fetch_with_options<T: DeserializeOwned, B: Serialize + Default>(
     url: impl IntoUrl,
@@ -58,7 +141,8 @@ pub async fn fetch_with_options<T: DeserializeOwned, B: Serialize + Default>(
 ) -> crate::Result<T> {
     let options = options.unwrap_or_default(); // Use default options if none provided
 
-    let mut request_builder = client.request(options.method.clone(), url);
+    let url = url.into_url()?;
+    let mut request_builder = client.request(options.method.clone(), url.clone());
 
     // Add headers if provided
     for (key, value) in options.headers {
@@ -76,8 +160,261 @@ pub async fn fetch_with_options<T: DeserializeOwned, B: Serialize + Default>(
     }
 
     // Send the request and await the response
-    let response: Response = request_builder.send().await?;
+    let response: Response = request_builder
+        .send()
+        .await
+        .map_err(|e| network_error(&url, e))?;
+    check_status(&response)?;
 
     // Deserialize the response body
     Ok(response.json::<T>().await?)
 }
+
+/// Fetches a URL and caches the JSON response on disk.
+///
+/// The cached file is used instead of fetching while it is younger than
+/// `max_age`, pass `Duration::MAX` for responses that never change.
+///
+/// # Parameters
+/// - `url`: The URL to fetch.
+/// - `cache_path`: The path of the cached response.
+/// - `max_age`: How long the cached response stays fresh.
+/// - `client`: An optional HTTP client, a default one is used if not provided.
+/// - `rate_limit`: How long the request may wait for the server.
+/// - `emitter`: An optional emitter, warned about every wait.
+///
+/// # Returns
+/// A result containing the deserialized response.
+pub async fn fetch_json_cached<T: DeserializeOwned + Serialize + Send + 'static>(
+    url: impl IntoUrl,
+    cache_path: &Path,
+    max_age: Duration,
+    client: Option<&Client>,
+    rate_limit: RateLimit,
+    emitter: Option<&Emitter>,
+) -> crate::Result<T> {
+    if is_fresh(cache_path, max_age) {
+        if let Ok(cached) = read_json_streaming(cache_path).await {
+            return Ok(cached);
+        }
+    }
+
+    let data = fetch_rate_limited(url, client, rate_limit, emitter).await?;
+    write_json(cache_path, &data).await?;
+    Ok(data)
+}
+
+/// A response cached by `fetch_json_revalidated`, along with the validators
+/// the server sent for it.
+#[derive(Serialize, Deserialize)]
+struct RevalidatedResponse<T> {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    data: T,
+}
+
+/// Fetches a URL and caches the JSON response on disk along with its `ETag`
+/// and `Last-Modified` headers.
+///
+/// Unlike `fetch_json_cached`, the cached response is revalidated on every
+/// call. A `304 Not Modified` answer reuses it without transferring the
+/// response again.
+///
+/// # Parameters
+/// - `url`: The URL to fetch.
+/// - `cache_path`: The path of the cached response.
+/// - `client`: An optional HTTP client, a default one is used if not provided.
+/// - `rate_limit`: How long the request may wait for the server.
+/// - `emitter`: An optional emitter, warned about every wait.
+///
+/// # Returns
+/// A result containing the deserialized response.
+pub async fn fetch_json_revalidated<T: DeserializeOwned + Serialize + Send + 'static>(
+    url: impl IntoUrl,
+    cache_path: &Path,
+    client: Option<&Client>,
+    rate_limit: RateLimit,
+    emitter: Option<&Emitter>,
+) -> crate::Result<T> {
+    let cached: Option<RevalidatedResponse<T>> = read_json_streaming(cache_path).await.ok();
+    let default_client = Client::default();
+    let client = client.unwrap_or(&default_client);
+    let url = url.into_url()?;
+
+    let response = retry_rate_limited(
+        || async {
+            let mut request = client.get(url.clone());
+            if let Some(cached) = &cached {
+                if let Some(etag) = &cached.etag {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &cached.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+            let response = request.send().await.map_err(|e| network_error(&url, e))?;
+            if response.status() != StatusCode::NOT_MODIFIED || cached.is_none() {
+                check_status(&response)?;
+            }
+            Ok(response)
+        },
+        rate_limit,
+        emitter,
+    )
+    .await?;
+
+    if let Some(cached) = cached.filter(|_| response.status() == StatusCode::NOT_MODIFIED) {
+        return Ok(cached.data);
+    }
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    let response = RevalidatedResponse {
+        etag,
+        last_modified,
+        data: response.json::<T>().await?,
+    };
+    write_json(cache_path, &response).await?;
+    Ok(response.data)
+}
+
+/// Checks whether a cached file exists and is younger than `max_age`.
+fn is_fresh(path: &Path, max_age: Duration) -> bool {
+    if max_age == Duration::MAX {
+        return path.is_file();
+    }
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age <= max_age)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use serde_json::{json, Value};
+    use wiremock::{
+        matchers::{header, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::{error::Error, minecraft::emitter::WarningCode};
+
+    /// Starts a server answering with `429 Too Many Requests` twice, then
+    /// with a JSON object.
+    async fn server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "1.21.4" })))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn rate_limited_fetch_waits_and_warns() {
+        let server = server().await;
+        let emitter = Emitter::default();
+        let warnings = Arc::new(AtomicUsize::new(0));
+        let counter = warnings.clone();
+        emitter
+            .on_warning(move |warning| {
+                if warning.code == WarningCode::RateLimited {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+            .await;
+
+        let rate_limit = RateLimit {
+            max_wait: Duration::from_millis(10),
+            max_total: Duration::from_secs(1),
+        };
+        let value: Value = fetch_rate_limited(server.uri(), None, rate_limit, Some(&emitter))
+            .await
+            .unwrap();
+
+        assert_eq!(value["id"], "1.21.4");
+        assert_eq!(warnings.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn zero_retry_after_is_not_retried_endlessly() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .expect(11)
+            .mount(&server)
+            .await;
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(10),
+            fetch_rate_limited::<Value>(server.uri(), None, RateLimit::default(), None),
+        )
+        .await
+        .expect("the request was retried endlessly");
+
+        assert!(matches!(result, Err(Error::RateLimited { .. })));
+    }
+
+    #[tokio::test]
+    async fn revalidated_fetch_reuses_unmodified_responses() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("If-None-Match", "\"1.21.4\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"1.21.4\"")
+                    .set_body_json(json!({ "id": "1.21.4" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join("cache.json");
+
+        for _ in 0..2 {
+            let value: Value =
+                fetch_json_revalidated(server.uri(), &cache_path, None, RateLimit::default(), None)
+                    .await
+                    .unwrap();
+            assert_eq!(value["id"], "1.21.4");
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limited_fetch_gives_up_past_the_limit() {
+        let server = server().await;
+        let rate_limit = RateLimit {
+            max_wait: Duration::from_millis(10),
+            max_total: Duration::ZERO,
+        };
+
+        let result: crate::Result<Value> =
+            fetch_rate_limited(server.uri(), None, rate_limit, None).await;
+
+        assert!(matches!(result, Err(Error::RateLimited { .. })));
+    }
+}
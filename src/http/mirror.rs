@@ -0,0 +1,268 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::{stream::FuturesUnordered, FutureExt, StreamExt};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tokio::{sync::OnceCell, time::timeout};
+
+use crate::minecraft::VERSION_MANIFEST_ENDPOINT;
+
+/// Origins of the official endpoints that a mirror can replace.
+const OFFICIAL_ORIGINS: [&str; 5] = [
+    "https://piston-meta.mojang.com",
+    "https://piston-data.mojang.com",
+    "https://launchermeta.mojang.com",
+    "https://libraries.minecraft.net",
+    "https://resources.download.minecraft.net",
+];
+
+/// Represents a mirror of the official Mojang endpoints.
+///
+/// A mirror is expected to serve the same paths as the official servers,
+/// so only the origin of the URLs is replaced with `base_url`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MirrorConfig {
+    pub base_url: String,
+    /// Used to break ties between equally fast mirrors, lower is preferred.
+    pub priority: u8,
+}
+
+impl MirrorConfig {
+    /// Rewrites an official URL to point to this mirror.
+    ///
+    /// URLs that don't belong to an official endpoint are returned unchanged.
+    ///
+    /// # Parameters
+    /// - `url`: The URL to rewrite.
+    ///
+    /// # Returns
+    /// The rewritten URL.
+    pub fn resolve(&self, url: &str) -> String {
+        OFFICIAL_ORIGINS
+            .iter()
+            .find_map(|origin| url.strip_prefix(origin))
+            .map(|path| format!("{}{}", self.base_url.trim_end_matches('/'), path))
+            .unwrap_or_else(|| url.to_string())
+    }
+}
+
+/// Rewrites the URL with the given mirror, if any.
+///
+/// # Parameters
+/// - `mirror`: The selected mirror.
+/// - `url`: The URL to rewrite.
+///
+/// # Returns
+/// The rewritten URL, or the original one when no mirror is selected.
+pub fn resolve(mirror: Option<&MirrorConfig>, url: &str) -> String {
+    mirror
+        .map(|mirror| mirror.resolve(url))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// Gets the mirror to fall back to when a file from the selected mirror is
+/// broken, which is the next one in `mirrors`.
+///
+/// # Parameters
+/// - `mirrors`: The configured mirrors.
+/// - `selected`: The selected mirror.
+///
+/// # Returns
+/// The mirror after the selected one, wrapping around, or `None` if no
+/// mirror is selected or there is no other mirror.
+pub fn next_mirror<'a>(
+    mirrors: &'a [MirrorConfig],
+    selected: Option<&MirrorConfig>,
+) -> Option<&'a MirrorConfig> {
+    let selected = selected?;
+    let position = mirrors
+        .iter()
+        .position(|mirror| mirror.base_url == selected.base_url)?;
+    mirrors[position + 1..]
+        .iter()
+        .chain(&mirrors[..position])
+        .find(|mirror| mirror.base_url != selected.base_url)
+}
+
+/// Probes the mirrors in parallel and returns the fastest one to respond.
+///
+/// Each mirror is probed with a HEAD request to its copy of the version
+/// manifest. Mirrors that fail or don't respond within five seconds are
+/// ignored. The probing stops as soon as a mirror responds, the remaining
+/// requests are dropped.
+///
+/// # Parameters
+/// - `mirrors`: The mirrors to probe.
+/// - `client`: The HTTP client used for making requests.
+///
+/// # Returns
+/// The fastest mirror, or `None` if every mirror failed.
+pub async fn probe_fastest(mirrors: &[MirrorConfig], client: &Client) -> Option<MirrorConfig> {
+    let mut probes: FuturesUnordered<_> = mirrors
+        .iter()
+        .map(|mirror| async move {
+            let response = timeout(
+                Duration::from_secs(5),
+                client
+                    .head(mirror.resolve(VERSION_MANIFEST_ENDPOINT))
+                    .send(),
+            )
+            .await
+            .ok()?
+            .ok()?;
+
+            response.status().is_success().then_some(mirror)
+        })
+        .collect();
+
+    while let Some(probe) = probes.next().await {
+        let Some(mut fastest) = probe else {
+            continue;
+        };
+        // Mirrors that responded at the same time are told apart by priority.
+        while let Some(Some(probe)) = probes.next().now_or_never() {
+            if let Some(mirror) = probe.filter(|mirror| mirror.priority < fastest.priority) {
+                fastest = mirror;
+            }
+        }
+        return Some(fastest.clone());
+    }
+    None
+}
+
+/// The mirror selected for a configuration, probed once and shared by its
+/// clones.
+#[derive(Debug, Clone, Default)]
+pub struct SelectedMirror(Arc<OnceCell<Option<MirrorConfig>>>);
+
+impl SelectedMirror {
+    /// Gets the selected mirror, probing the mirrors the first time.
+    ///
+    /// # Parameters
+    /// - `mirrors`: The mirrors to probe.
+    /// - `client`: The HTTP client used for making requests.
+    ///
+    /// # Returns
+    /// The fastest mirror, or `None` if there are no mirrors or every mirror
+    /// failed.
+    pub async fn get_or_probe(
+        &self,
+        mirrors: &[MirrorConfig],
+        client: &Client,
+    ) -> Option<&MirrorConfig> {
+        self.0
+            .get_or_init(|| async {
+                if mirrors.is_empty() {
+                    None
+                } else {
+                    probe_fastest(mirrors, client).await
+                }
+            })
+            .await
+            .as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    async fn mirror(status: u16, delay: Duration, priority: u8) -> (MockServer, MirrorConfig) {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(status).set_delay(delay))
+            .mount(&server)
+            .await;
+        let mirror = MirrorConfig {
+            base_url: server.uri(),
+            priority,
+        };
+        (server, mirror)
+    }
+
+    #[test]
+    fn resolve_replaces_official_origins() {
+        let mirror = MirrorConfig {
+            base_url: "https://mirror.example/".to_string(),
+            priority: 0,
+        };
+        assert_eq!(
+            mirror.resolve("https://libraries.minecraft.net/a/b.jar"),
+            "https://mirror.example/a/b.jar"
+        );
+        assert_eq!(
+            mirror.resolve("https://maven.fabricmc.net/a/b.jar"),
+            "https://maven.fabricmc.net/a/b.jar"
+        );
+    }
+
+    #[test]
+    fn next_mirror_wraps_around() {
+        let mirrors: Vec<MirrorConfig> = ["https://a.example", "https://b.example"]
+            .into_iter()
+            .map(|base_url| MirrorConfig {
+                base_url: base_url.to_string(),
+                priority: 0,
+            })
+            .collect();
+
+        let next = |selected: &MirrorConfig| {
+            next_mirror(&mirrors, Some(selected)).map(|mirror| mirror.base_url.as_str())
+        };
+        assert_eq!(next(&mirrors[0]), Some("https://b.example"));
+        assert_eq!(next(&mirrors[1]), Some("https://a.example"));
+        assert!(next_mirror(&mirrors[..1], Some(&mirrors[0])).is_none());
+        assert!(next_mirror(&mirrors, None).is_none());
+    }
+
+    #[tokio::test]
+    async fn fastest_mirror_wins() {
+        let (_slow_server, slow) = mirror(200, Duration::from_secs(2), 0).await;
+        let (_failing_server, failing) = mirror(500, Duration::ZERO, 0).await;
+        let (_fast_server, fast) = mirror(200, Duration::from_millis(50), 1).await;
+
+        let started = std::time::Instant::now();
+        let selected = probe_fastest(&[slow, failing, fast.clone()], &Client::new())
+            .await
+            .unwrap();
+
+        assert_eq!(selected.base_url, fast.base_url);
+        // The slow mirror is not waited for.
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn no_mirror_responds() {
+        let (_server, failing) = mirror(404, Duration::ZERO, 0).await;
+        assert!(probe_fastest(&[failing], &Client::new()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn selected_mirror_is_probed_once() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let mirrors = [MirrorConfig {
+            base_url: server.uri(),
+            priority: 0,
+        }];
+
+        let selected = SelectedMirror::default();
+        let shared = selected.clone();
+        let client = Client::new();
+        assert!(selected.get_or_probe(&mirrors, &client).await.is_some());
+        assert_eq!(
+            shared
+                .get_or_probe(&mirrors, &client)
+                .await
+                .unwrap()
+                .base_url,
+            server.uri()
+        );
+    }
+}
@@ -1,2 +1,88 @@
 pub mod downloader;
-pub mod fetch;
\ No newline at end of file
+pub mod fetch;
+pub mod mirror;
+
+use std::time::Duration;
+
+use reqwest::{header::RETRY_AFTER, Response, StatusCode};
+
+use crate::error::Error;
+
+/// Checks whether a response asks to retry the request later, as rate
+/// limited and overloaded servers do with a `Retry-After` header.
+///
+/// Only a `Retry-After` in seconds is recognized, other responses are left
+/// to the usual error handling.
+///
+/// # Parameters
+/// - `response`: The response to check.
+///
+/// # Returns
+/// `Error::RateLimited` if the request should be retried later.
+pub(crate) fn check_rate_limit(response: &Response) -> crate::Result<()> {
+    let status = response.status();
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+        return Ok(());
+    }
+
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+    match retry_after {
+        Some(seconds) => Err(Error::RateLimited {
+            url: response.url().to_string(),
+            retry_after: Duration::from_secs(seconds),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Turns an unsuccessful response into the error of its status class.
+///
+/// Responses asking to retry later are reported first, see
+/// `check_rate_limit`.
+///
+/// # Parameters
+/// - `response`: The response to check.
+///
+/// # Returns
+/// `Error::HttpClientError` for a 4xx status, `Error::HttpServerError` for a
+/// 5xx status and `Error::Download` for any other unsuccessful status.
+pub(crate) fn check_status(response: &Response) -> crate::Result<()> {
+    check_rate_limit(response)?;
+
+    let status = response.status();
+    let url = response.url().to_string();
+    if status.is_client_error() {
+        Err(Error::HttpClientError {
+            status: status.as_u16(),
+            url,
+        })
+    } else if status.is_server_error() {
+        Err(Error::HttpServerError {
+            status: status.as_u16(),
+            url,
+        })
+    } else if !status.is_success() {
+        Err(Error::Download(status.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Wraps a failure to reach a server or to receive its response.
+///
+/// # Parameters
+/// - `url`: The requested URL.
+/// - `error`: The underlying error.
+///
+/// # Returns
+/// The `Error::NetworkError`.
+pub(crate) fn network_error(url: impl ToString, error: impl ToString) -> Error {
+    Error::NetworkError {
+        url: url.to_string(),
+        message: error.to_string(),
+    }
+}
@@ -11,6 +11,14 @@ pub struct AssetIndex {
     pub map_to_resources: Option<bool>
 }
 
+/// Describes when a cached asset index was fetched, stored next to it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AssetIndexCacheInfo {
+    /// The time of the fetch in seconds since the Unix epoch.
+    pub fetched_at: u64,
+    pub asset_index_id: String,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct File {
     pub hash: String,
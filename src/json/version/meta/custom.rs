@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use super::vanilla::{Element, LibraryDownloads};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CustomMeta {
     pub id: String,
@@ -18,7 +18,7 @@ pub struct CustomMeta {
     pub libraries: Vec<Library>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Arguments {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub game: Option<Vec<Element>>,
@@ -26,7 +26,7 @@ pub struct Arguments {
     pub jvm: Option<Vec<Element>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Library {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -51,7 +51,7 @@ pub struct Library {
     pub downloads: Option<LibraryDownloads>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 struct Mirror {
     name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -60,7 +60,7 @@ struct Mirror {
     url: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Installer {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -71,18 +71,21 @@ pub struct Installer {
     pub mirror_list: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Processor {
     pub classpath: Vec<String>,
     pub args: Vec<String>,
     pub sides: Option<Vec<String>>,
     pub outputs: Option<HashMap<String, String>>,
     pub jar: String,
+    /// The class to run instead of the `Main-Class` of the jar manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main_class: Option<String>,
     #[serde(default)]
     pub success: bool,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Data {
     pub client: String,
     pub server: String,
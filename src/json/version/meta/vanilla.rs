@@ -1,11 +1,22 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use serde::{Deserialize, Serialize};
 
 use super::custom::{Data, Processor};
+use crate::{json::version::migration::serialize_schema_version, util::time::parse_iso8601};
+
+/// The major Java version of the versions whose metadata predates the
+/// `javaVersion` field, which all run on the legacy runtime.
+const LEGACY_JAVA_MAJOR_VERSION: i64 = 8;
 
 /// Represents the metadata for a Minecraft version, including its libraries and processors.
-#[derive(Serialize, Deserialize)]
+///
+/// Version JSONs written by older releases of lyceris are migrated to the
+/// current schema when they are read from the versions directory.
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct VersionMeta {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -31,22 +42,190 @@ pub struct VersionMeta {
     pub r#type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub processors: Option<Vec<Processor>>,
-    pub data: Option<HashMap<String, Data>>
+    pub data: Option<HashMap<String, Data>>,
+    /// The schema the version JSON was written with, see
+    /// `json::version::migration`.
+    #[serde(default, serialize_with = "serialize_schema_version")]
+    pub lyceris_schema_version: Option<u32>,
+}
+
+impl VersionMeta {
+    /// Returns the major Java version required to run this version.
+    ///
+    /// # Returns
+    /// The major Java version, 8 for versions that do not specify one.
+    pub fn required_java_major(&self) -> i64 {
+        self.java_version
+            .as_ref()
+            .map_or(LEGACY_JAVA_MAJOR_VERSION, |java_version| {
+                java_version.major_version
+            })
+    }
+
+    /// Checks whether this version uses the structured `arguments` instead of
+    /// the legacy `minecraftArguments` string.
+    ///
+    /// Loaders may add structured arguments to a legacy version, which stays
+    /// legacy as long as it has the string.
+    ///
+    /// # Returns
+    /// Whether the arguments are structured.
+    pub fn is_modern_arguments(&self) -> bool {
+        self.arguments.is_some() && self.minecraft_arguments.is_none()
+    }
+
+    /// Parses the release time of this version.
+    ///
+    /// # Returns
+    /// A result containing the time the version was released.
+    pub fn release_datetime(&self) -> crate::Result<SystemTime> {
+        let seconds = parse_iso8601(&self.release_time)?;
+        Ok(UNIX_EPOCH + Duration::from_secs(seconds))
+    }
 }
 
-#[derive(Serialize, Deserialize)]
+/// Describes what changed between two version metadata, for example what a
+/// loader changed while merging its libraries and arguments.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct VersionMetaDiff {
+    /// Names of the libraries that were added.
+    pub added_libraries: Vec<String>,
+    /// Names of the libraries that were removed.
+    pub removed_libraries: Vec<String>,
+    /// The previous and the new main class, if it changed.
+    pub changed_main_class: Option<(String, String)>,
+    /// The number of JVM arguments that were added.
+    pub added_jvm_args: usize,
+    /// The number of game arguments that were added.
+    pub added_game_args: usize,
+    /// The previous and the new major Java version, if it changed.
+    pub changed_java_version: Option<(u32, u32)>,
+}
+
+/// Compares two version metadata.
+///
+/// # Parameters
+/// - `before`: The metadata before the change.
+/// - `after`: The metadata after the change.
+///
+/// # Returns
+/// The differences between the two metadata.
+pub fn diff_meta(before: &VersionMeta, after: &VersionMeta) -> VersionMetaDiff {
+    let library_names = |meta: &VersionMeta| -> HashSet<String> {
+        meta.libraries.iter().map(|lib| lib.name.clone()).collect()
+    };
+    let before_libraries = library_names(before);
+    let after_libraries = library_names(after);
+
+    let java_version = |meta: &VersionMeta| {
+        meta.java_version.as_ref().map_or(0, |java_version| {
+            u32::try_from(java_version.major_version).unwrap_or_default()
+        })
+    };
+
+    VersionMetaDiff {
+        added_libraries: after
+            .libraries
+            .iter()
+            .filter(|lib| !before_libraries.contains(&lib.name))
+            .map(|lib| lib.name.clone())
+            .collect(),
+        removed_libraries: before
+            .libraries
+            .iter()
+            .filter(|lib| !after_libraries.contains(&lib.name))
+            .map(|lib| lib.name.clone())
+            .collect(),
+        changed_main_class: (before.main_class != after.main_class)
+            .then(|| (before.main_class.clone(), after.main_class.clone())),
+        added_jvm_args: argument_counts(after)
+            .0
+            .saturating_sub(argument_counts(before).0),
+        added_game_args: argument_counts(after)
+            .1
+            .saturating_sub(argument_counts(before).1),
+        changed_java_version: (java_version(before) != java_version(after))
+            .then(|| (java_version(before), java_version(after))),
+    }
+}
+
+/// Counts the JVM and game arguments of a version, including the legacy
+/// `minecraftArguments` string.
+fn argument_counts(meta: &VersionMeta) -> (usize, usize) {
+    let (jvm, game) = meta.arguments.as_ref().map_or((0, 0), |arguments| {
+        (arguments.jvm.len(), arguments.game.len())
+    });
+    let legacy = meta
+        .minecraft_arguments
+        .as_ref()
+        .map_or(0, |arguments| arguments.split_whitespace().count());
+    (jvm, game + legacy)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Arguments {
     pub game: Vec<Element>,
     pub jvm: Vec<Element>,
 }
 
-#[derive(Serialize, Deserialize)]
+impl Arguments {
+    /// Returns the JVM arguments the vanilla launcher uses for versions that
+    /// do not specify any.
+    ///
+    /// # Returns
+    /// The default JVM arguments, ending with the classpath.
+    pub fn default_jvm() -> Vec<Element> {
+        let os_specific = |os: Os, value: Value| {
+            Element::Class(Class {
+                rules: vec![Rule {
+                    action: Action::Allow,
+                    os: Some(os),
+                    features: None,
+                }],
+                value,
+            })
+        };
+        let os = |name: Option<Name>, arch: Option<&str>, version: Option<&str>| Os {
+            name,
+            arch: arch.map(str::to_string),
+            version: version.map(str::to_string),
+        };
+
+        vec![
+            os_specific(
+                os(Some(Name::Windows), None, None),
+                Value::Single(
+                    "-XX:HeapDumpPath=MojangTricksIntelNeedsThisToMakeJavaHappy.dumpheap"
+                        .to_string(),
+                ),
+            ),
+            os_specific(
+                os(Some(Name::Windows), None, Some("^10\\.")),
+                Value::Multiple(vec![
+                    "-Dos.name=Windows 10".to_string(),
+                    "-Dos.version=10.0".to_string(),
+                ]),
+            ),
+            os_specific(
+                os(None, Some("x86"), None),
+                Value::Single("-Xss1M".to_string()),
+            ),
+            Element::String("-Djava.library.path=${natives_directory}".to_string()),
+            Element::String("-Dminecraft.launcher.brand=${launcher_name}".to_string()),
+            Element::String("-Dminecraft.launcher.version=${launcher_version}".to_string()),
+            Element::String("-cp".to_string()),
+            Element::String("${classpath}".to_string()),
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct GameClass {
     pub rules: Vec<Rule>,
     pub value: Value,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Features {
     pub is_demo_user: Option<bool>,
@@ -57,36 +236,82 @@ pub struct Features {
     pub is_quick_play_realms: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize)]
+impl Features {
+    /// Lists the state of every feature by its name in the version JSON.
+    ///
+    /// # Returns
+    /// The names and states of the features, in the same order for any
+    /// `Features`.
+    pub fn feature_flags(&self) -> [(&'static str, Option<bool>); 6] {
+        [
+            ("is_demo_user", self.is_demo_user),
+            ("has_custom_resolution", self.has_custom_resolution),
+            ("has_quick_plays_support", self.has_quick_plays_support),
+            ("is_quick_play_singleplayer", self.is_quick_play_singleplayer),
+            ("is_quick_play_multiplayer", self.is_quick_play_multiplayer),
+            ("is_quick_play_realms", self.is_quick_play_realms),
+        ]
+    }
+}
+
+/// Launcher features by their name in the version JSON, e.g. `is_demo_user`,
+/// including ones `Features` doesn't know.
+///
+/// In a rule these are the states the features must have, for a launch they
+/// are the enabled and disabled features.
+pub type FeatureFlags = HashMap<String, bool>;
+
+impl From<Features> for FeatureFlags {
+    /// Converts `Features` into feature flags, leaving out the features
+    /// without a state.
+    ///
+    /// # Parameters
+    /// - `features`: The features to convert.
+    ///
+    /// # Returns
+    /// The feature flags.
+    fn from(features: Features) -> Self {
+        features
+            .feature_flags()
+            .into_iter()
+            .filter_map(|(name, enabled)| Some((name.to_string(), enabled?)))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Class {
     pub rules: Vec<Rule>,
     pub value: Value,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Rule {
     pub action: Action,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub os: Option<Os>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub features: Option<Features>,
+    pub features: Option<FeatureFlags>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Extract {
     #[serde(rename = "exclude")]
     pub exclude: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Os {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<Name>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub arch: Option<String>,
+    /// A regular expression the version of the operating system must match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AssetIndex {
     pub id: String,
@@ -97,7 +322,7 @@ pub struct AssetIndex {
     pub url: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Downloads {
     pub client: File,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -109,13 +334,16 @@ pub struct Downloads {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct File {
+    /// The SHA-1 hash of the file, empty when the metadata lacks one, like
+    /// for some libraries added by loaders.
+    #[serde(default)]
     pub sha1: String,
     pub size: i64,
     pub url: String,
     pub path: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct JavaVersion {
     #[serde(default = "default_java_version")]
@@ -123,12 +351,22 @@ pub struct JavaVersion {
     pub major_version: i64,
 }
 
+impl Default for JavaVersion {
+    /// The legacy runtime, which versions without `javaVersion` run on.
+    fn default() -> Self {
+        Self {
+            component: default_java_version(),
+            major_version: LEGACY_JAVA_MAJOR_VERSION,
+        }
+    }
+}
+
 fn default_java_version() -> String {
     "jre-legacy".to_string()
 }
 
 /// Represents a library required for a Minecraft version.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Library {
     pub downloads: Option<LibraryDownloads>,
     pub name: String,
@@ -142,7 +380,7 @@ pub struct Library {
     pub skip_args: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Natives {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub linux: Option<String>,
@@ -166,19 +404,19 @@ pub struct LibraryDownloads {
     pub classifiers: Option<Classifiers>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Logging {
     pub client: LoggingClient,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct LoggingClient {
     pub argument: String,
     pub file: AssetIndex,
     pub r#type: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum Element {
     Class(Class),
@@ -186,7 +424,7 @@ pub enum Element {
     String(String),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum Value {
     Single(String),
@@ -213,7 +451,7 @@ pub struct Classifiers {
     pub natives_windows: Option<File>,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub enum Action {
     #[serde(rename = "allow")]
     Allow,
@@ -241,3 +479,100 @@ pub enum Name {
     #[serde(rename = "linux-arm32")]
     LinuxArm32,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_util::version_json;
+
+    fn meta(json: Value) -> VersionMeta {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn with_libraries(names: &[&str]) -> Value {
+        let mut json = version_json("1.8.9");
+        json["libraries"] = names.iter().map(|name| json!({ "name": name })).collect();
+        json
+    }
+
+    #[test]
+    fn identical_metadata_has_no_differences() {
+        let before = meta(version_json("1.8.9"));
+        let after = meta(version_json("1.8.9"));
+
+        assert_eq!(diff_meta(&before, &after), VersionMetaDiff::default());
+    }
+
+    #[test]
+    fn added_removed_and_changed_libraries_are_reported() {
+        let before = meta(with_libraries(&[
+            "org.ow2.asm:asm:9.6",
+            "com.google.guava:guava:17.0",
+        ]));
+        let after = meta(with_libraries(&[
+            "org.ow2.asm:asm:9.7",
+            "net.sf.jopt-simple:jopt-simple:5.0.4",
+        ]));
+
+        let diff = diff_meta(&before, &after);
+
+        // A changed version is reported as the old library being replaced.
+        assert_eq!(
+            diff.added_libraries,
+            [
+                "org.ow2.asm:asm:9.7",
+                "net.sf.jopt-simple:jopt-simple:5.0.4"
+            ]
+        );
+        assert_eq!(
+            diff.removed_libraries,
+            ["org.ow2.asm:asm:9.6", "com.google.guava:guava:17.0"]
+        );
+    }
+
+    #[test]
+    fn changed_main_class_is_reported() {
+        let before = meta(version_json("1.8.9"));
+        let mut after = version_json("1.8.9");
+        after["mainClass"] = json!("cpw.mods.bootstraplauncher.BootstrapLauncher");
+
+        let diff = diff_meta(&before, &meta(after));
+
+        assert_eq!(
+            diff.changed_main_class,
+            Some((
+                "net.minecraft.client.main.Main".to_string(),
+                "cpw.mods.bootstraplauncher.BootstrapLauncher".to_string()
+            ))
+        );
+        assert_eq!(diff.changed_java_version, None);
+    }
+
+    #[test]
+    fn changed_java_version_is_reported() {
+        let mut before = version_json("1.8.9");
+        before["javaVersion"] = json!({ "component": "jre-legacy", "majorVersion": 8 });
+        let mut after = version_json("1.8.9");
+        after["javaVersion"] = json!({ "component": "java-runtime-delta", "majorVersion": 21 });
+
+        let diff = diff_meta(&meta(before), &meta(after));
+
+        assert_eq!(diff.changed_java_version, Some((8, 21)));
+        assert_eq!(diff.changed_main_class, None);
+    }
+
+    #[test]
+    fn added_arguments_are_counted() {
+        let before = meta(version_json("1.8.9"));
+        let mut after = version_json("1.8.9");
+        after["minecraftArguments"] =
+            json!("--username ${auth_player_name} --version ${version_name} --tweakClass x");
+
+        let diff = diff_meta(&before, &meta(after));
+
+        assert_eq!(diff.added_game_args, 2);
+        assert_eq!(diff.added_jvm_args, 0);
+    }
+}
@@ -0,0 +1,196 @@
+/// A module for upgrading the version JSON files written by older releases
+/// of lyceris to the current `VersionMeta` schema.
+///
+/// Every written version JSON records the schema it was written with, the
+/// migrations of newer schemas run when it is read from the versions
+/// directory.
+use serde::Serializer;
+use serde_json::Value;
+
+/// The key of the schema version in a version JSON.
+pub const SCHEMA_VERSION_KEY: &str = "lycerisSchemaVersion";
+
+/// The current schema version, raised along with every migration that is
+/// added.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Gets the schema version a version JSON was written with.
+///
+/// # Parameters
+/// - `json`: The version JSON.
+///
+/// # Returns
+/// The schema version, zero for files written before it was recorded and for
+/// version JSONs of Mojang and the loaders.
+pub fn schema_version(json: &Value) -> u32 {
+    json.get(SCHEMA_VERSION_KEY)
+        .and_then(Value::as_u64)
+        .and_then(|version| u32::try_from(version).ok())
+        .unwrap_or_default()
+}
+
+/// Serializes the schema version of a `VersionMeta`, which is always the
+/// current one since the metadata was migrated when it was read.
+pub(crate) fn serialize_schema_version<S: Serializer>(
+    _: &Option<u32>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_some(&SCHEMA_VERSION)
+}
+
+/// An upgrade of the version JSON to a newer schema.
+pub trait Migration: Send + Sync {
+    /// Returns the schema version this migration upgrades to.
+    fn schema_version(&self) -> u32;
+
+    /// Upgrades a version JSON written with an older schema.
+    ///
+    /// # Parameters
+    /// - `json`: The version JSON, modified in place.
+    ///
+    /// # Returns
+    /// A result indicating success.
+    fn migrate(&self, json: &mut Value) -> crate::Result<()>;
+}
+
+/// The migrations applied to version JSONs, in the order of their schema
+/// versions.
+pub struct MigrationRegistry {
+    migrations: Vec<Box<dyn Migration>>,
+}
+
+impl Default for MigrationRegistry {
+    /// Creates a registry with the migrations of lyceris.
+    fn default() -> Self {
+        Self::new().register(Box::new(EmptyChecksums))
+    }
+}
+
+impl MigrationRegistry {
+    /// Creates a registry without any migration.
+    pub fn new() -> Self {
+        Self {
+            migrations: Vec::new(),
+        }
+    }
+
+    /// Adds a migration, which runs after the migrations of older schemas.
+    ///
+    /// # Parameters
+    /// - `migration`: The migration to add.
+    pub fn register(mut self, migration: Box<dyn Migration>) -> Self {
+        self.migrations.push(migration);
+        self.migrations
+            .sort_by_key(|migration| migration.schema_version());
+        self
+    }
+
+    /// Runs every migration newer than the schema of a version JSON and
+    /// records the current schema in it.
+    ///
+    /// JSONs written by a newer release of lyceris are left as they are.
+    ///
+    /// # Parameters
+    /// - `json`: The version JSON, modified in place.
+    ///
+    /// # Returns
+    /// A result containing whether a migration ran.
+    pub fn migrate(&self, json: &mut Value) -> crate::Result<bool> {
+        let current = SCHEMA_VERSION;
+        let version = schema_version(json);
+        if version >= current {
+            return Ok(false);
+        }
+
+        let mut migrated = false;
+        for migration in self.migrations.iter().filter(|migration| {
+            migration.schema_version() > version && migration.schema_version() <= current
+        }) {
+            migration.migrate(json)?;
+            migrated = true;
+        }
+        if let Some(object) = json.as_object_mut() {
+            object.insert(SCHEMA_VERSION_KEY.to_string(), current.into());
+        }
+        Ok(migrated)
+    }
+}
+
+/// Removes the empty checksums older releases wrote for the files without
+/// one, which now leave the checksum out.
+struct EmptyChecksums;
+
+impl EmptyChecksums {
+    /// Removes the empty `sha1` fields of every nested file.
+    fn remove_empty_sha1(value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                if object.get("sha1").and_then(Value::as_str) == Some("") {
+                    object.remove("sha1");
+                }
+                object.values_mut().for_each(Self::remove_empty_sha1);
+            }
+            Value::Array(array) => array.iter_mut().for_each(Self::remove_empty_sha1),
+            _ => {}
+        }
+    }
+}
+
+impl Migration for EmptyChecksums {
+    fn schema_version(&self) -> u32 {
+        1
+    }
+
+    fn migrate(&self, json: &mut Value) -> crate::Result<()> {
+        for key in ["downloads", "libraries"] {
+            if let Some(value) = json.get_mut(key) {
+                Self::remove_empty_sha1(value);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn library(sha1: &str) -> Value {
+        json!({
+            "name": "org.ow2.asm:asm:9.6",
+            "downloads": { "artifact": { "sha1": sha1, "size": 0, "url": "", "path": "" } },
+        })
+    }
+
+    #[test]
+    fn empty_checksums_are_removed() {
+        let mut json = json!({
+            "downloads": { "client": { "sha1": "", "size": 0, "url": "" } },
+            "libraries": [library(""), library("dc6e")],
+        });
+        assert!(MigrationRegistry::default().migrate(&mut json).unwrap());
+
+        assert_eq!(json["downloads"]["client"].get("sha1"), None);
+        assert_eq!(
+            json["libraries"][0]["downloads"]["artifact"].get("sha1"),
+            None
+        );
+        assert_eq!(
+            json["libraries"][1]["downloads"]["artifact"]["sha1"],
+            "dc6e"
+        );
+        assert_eq!(schema_version(&json), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn current_and_newer_schemas_are_left_alone() {
+        for version in [SCHEMA_VERSION, SCHEMA_VERSION + 1] {
+            let mut json = json!({ SCHEMA_VERSION_KEY: version, "libraries": [library("")] });
+            let before = json.clone();
+            assert!(!MigrationRegistry::default().migrate(&mut json).unwrap());
+            assert_eq!(json, before);
+        }
+    }
+}
@@ -1,3 +1,4 @@
 pub mod manifest;
 pub mod meta;
-pub mod asset_index;
\ No newline at end of file
+pub mod asset_index;
+pub mod migration;
\ No newline at end of file
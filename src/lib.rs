@@ -5,6 +5,7 @@
 ///
 /// # Modules
 /// - `auth`: Handles authentication with Microsoft and Xbox Live.
+/// - `blocking`: Blocking wrappers for consumers without an async runtime, behind the `blocking` feature.
 /// - `error`: Defines error types used throughout the library.
 /// - `http`: Provides HTTP utilities for making requests.
 /// - `json`: Contains utilities for handling JSON data.
@@ -60,13 +61,10 @@
 ///         .await;
 ///
 ///     let current_dir = env::current_dir()?; 
-///     let config = ConfigBuilder::new(
+///     let config = ConfigBuilder::offline(
 ///         current_dir.join("game"),
 ///         "1.21.4".into(),
-///         lyceris::auth::AuthMethod::Offline {
-///             username: "Lyceris".into(),
-///             uuid: None,
-///         },
+///         "Lyceris".into(),
 ///     )
 ///     .build();
 ///
@@ -77,20 +75,44 @@
 /// }
 /// ```
 pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod error;
 pub mod http;
 pub mod json;
 pub mod minecraft;
 pub mod util;
 
+#[cfg(test)]
+mod test_util;
+
 // Re-export commonly used items for easier access
 pub use auth::AuthMethod;
 pub use error::Error;
-pub use http::downloader::{download, download_multiple};
-pub use json::version::meta::vanilla::{Library, VersionMeta};
-pub use minecraft::config::Config;
-pub use minecraft::{install::install, launch::launch};
-pub use util::json::{read_json, write_json};
+#[allow(deprecated)]
+pub use http::downloader::download_multiple;
+pub use http::downloader::{
+    download, download_many, download_to_memory, download_with_options, DownloadItem,
+    DownloadManyOptions, DownloadOptions,
+};
+pub use http::fetch::{
+    fetch, fetch_json_cached, fetch_json_revalidated, fetch_rate_limited, fetch_with_options,
+    FetchOptions,
+};
+pub use json::version::asset_index::AssetIndex;
+pub use json::version::meta::vanilla::{Arguments, Downloads, Element, Library, VersionMeta};
+pub use minecraft::config::{Config, ConfigBuilder, ConfigPaths};
+pub use minecraft::{
+    install::{install, install_with_meta, verify_natives, InstallReport},
+    launch::{
+        is_instance_running, launch, launch_with_options, verify_runtime,
+        GameInstance, LaunchOptions, OnDrop,
+    },
+};
+pub use util::json::{read_json, read_json_streaming, write_json, write_json_pretty};
+
+#[cfg(feature = "macros")]
+pub use lyceris_macros::EventEnum;
 
 /// A type alias for results returned by library functions.
 pub type Result<T> = std::result::Result<T, Error>;
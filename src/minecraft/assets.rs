@@ -0,0 +1,491 @@
+/// This module gives access to single objects of the installed asset index,
+/// such as the game icon or sounds, and to the objects store as a whole.
+use std::path::{Path, PathBuf};
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::Client;
+use tokio::{
+    fs::{copy, create_dir_all},
+    task::spawn_blocking,
+};
+
+use crate::{
+    error::Error,
+    http::{downloader::download_and_verify, mirror::resolve},
+    json::version::{asset_index::AssetIndex, meta::vanilla::VersionMeta},
+    util::{hash::calculate_sha1, json::read_json},
+};
+
+use super::{
+    config::Config,
+    emitter::{Emit, Emitter, Event, EventPayload},
+    inheritance::read_version_meta,
+    loader::Loader,
+    RESOURCES_ENDPOINT,
+};
+
+/// The outcome of placing the assets under their logical names.
+#[derive(Debug, Clone, Default)]
+pub struct MaterializeReport {
+    /// The number of assets placed, or that would be placed in a dry run.
+    pub materialized: usize,
+    /// The number of assets already present under their logical names.
+    pub already_present: usize,
+    /// The size of the placed assets in bytes.
+    pub total_size: u64,
+    /// The logical names of the assets whose object is missing or corrupt.
+    pub missing: Vec<String>,
+}
+
+/// The state of the objects store of a version.
+#[derive(Debug, Clone, Default)]
+pub struct ObjectsReport {
+    /// The number of objects present with the expected hash.
+    pub valid: usize,
+    /// The logical names of the assets whose object doesn't exist.
+    pub missing: Vec<String>,
+    /// The logical names of the assets whose object doesn't match its hash.
+    pub corrupt: Vec<String>,
+}
+
+/// The state of a single object in the objects store.
+enum ObjectState {
+    Valid,
+    Missing,
+    Corrupt,
+}
+
+/// Reads the asset index of the installed version.
+///
+/// # Parameters
+/// - `config`: The configuration of the installed version.
+///
+/// # Returns
+/// A result containing the `AssetIndex`.
+pub async fn read_asset_index<T: Loader>(config: &Config<T>) -> crate::Result<AssetIndex> {
+    let meta: VersionMeta =
+        read_version_meta(&config.get_version_json_path(), &config.get_versions_path()).await?;
+    read_json(
+        &config
+            .get_indexes_path()
+            .join(format!("{}.json", meta.asset_index.id)),
+    )
+    .await
+}
+
+/// Gets the path of an asset object by its logical name, e.g. `icons/icon_128x128.png`.
+///
+/// The object is downloaded again, from the selected mirror if any, if it is
+/// missing or its hash doesn't match the asset index.
+///
+/// # Parameters
+/// - `config`: The configuration of the installed version.
+/// - `logical_name`: The name of the asset in the asset index.
+///
+/// # Returns
+/// A result containing the path of the object in the objects store.
+pub async fn get<T: Loader>(config: &Config<T>, logical_name: &str) -> crate::Result<PathBuf> {
+    let asset_index = read_asset_index(config).await?;
+    let object = asset_index
+        .objects
+        .get(logical_name)
+        .ok_or_else(|| Error::NotFound(format!("Asset '{}'", logical_name)))?;
+
+    let hash = &object.hash;
+    let prefix = object_prefix(hash)?;
+    let path = object_path(&objects_path(config), hash)?;
+
+    if !path.is_file() || calculate_sha1(&path)? != *hash {
+        let default_client = Client::default();
+        let client = config.client.as_ref().unwrap_or(&default_client);
+        let mirror = config
+            .selected_mirror
+            .get_or_probe(&config.mirrors, client)
+            .await;
+        download_and_verify(
+            resolve(
+                mirror,
+                &format!("{}/{}/{}", RESOURCES_ENDPOINT, prefix, hash),
+            ),
+            &path,
+            Some(hash),
+            None,
+            Some(client),
+        )
+        .await?;
+    }
+
+    Ok(path)
+}
+
+/// Copies an asset object out of the objects store under its logical name.
+///
+/// # Parameters
+/// - `config`: The configuration of the installed version.
+/// - `logical_name`: The name of the asset in the asset index.
+/// - `destination`: The path where the asset should be copied to.
+///
+/// # Returns
+/// A result indicating success or failure of the export.
+pub async fn export<T: Loader>(
+    config: &Config<T>,
+    logical_name: &str,
+    destination: &Path,
+) -> crate::Result<()> {
+    let path = get(config, logical_name).await?;
+
+    if let Some(parent) = destination.parent() {
+        if !parent.is_dir() {
+            create_dir_all(parent).await?;
+        }
+    }
+
+    copy(&path, destination).await?;
+    Ok(())
+}
+
+/// Places every asset of the installed version under its logical name, like
+/// the `virtual` directory of legacy versions but for any version.
+///
+/// Objects are hardlinked into the destination, falling back to copies when
+/// hardlinks aren't supported. Assets already present with the expected hash
+/// are left alone.
+///
+/// # Parameters
+/// - `config`: The configuration of the installed version.
+/// - `dest_dir`: The directory to place the assets in.
+/// - `dry_run`: Whether to only report what would be placed.
+/// - `emitter`: An optional emitter for reporting the progress.
+///
+/// # Returns
+/// A result containing the report of the placed assets.
+pub async fn materialize<T: Loader>(
+    config: &Config<T>,
+    dest_dir: &Path,
+    dry_run: bool,
+    emitter: Option<&Emitter>,
+) -> crate::Result<MaterializeReport> {
+    let asset_index = read_asset_index(config).await?;
+    let objects_path = objects_path(config);
+    let mut report = MaterializeReport::default();
+
+    // Hashing every asset blocks, so it runs off the async runtime. Assets
+    // already in place have no state to check.
+    let (asset_index, states) = {
+        let objects_path = objects_path.clone();
+        let dest_dir = dest_dir.to_path_buf();
+        spawn_blocking(move || {
+            let states: Vec<(String, Option<ObjectState>)> = asset_index
+                .objects
+                .par_iter()
+                .map(|(name, object)| {
+                    let target = dest_dir.join(name);
+                    if target.is_file()
+                        && calculate_sha1(&target).is_ok_and(|sha1| sha1 == object.hash)
+                    {
+                        (name.clone(), None)
+                    } else {
+                        (
+                            name.clone(),
+                            Some(object_state(&objects_path, &object.hash)),
+                        )
+                    }
+                })
+                .collect();
+            (asset_index, states)
+        })
+        .await
+        .map_err(|e| Error::Fail(e.to_string()))?
+    };
+
+    let mut pending = Vec::new();
+    for (name, state) in states {
+        match state {
+            None => report.already_present += 1,
+            Some(ObjectState::Valid) => pending.push(name),
+            Some(ObjectState::Missing | ObjectState::Corrupt) => report.missing.push(name),
+        }
+    }
+    pending.sort();
+    report.missing.sort();
+
+    let total = pending.len() as u64;
+    for (current, name) in pending.into_iter().enumerate() {
+        let object = &asset_index.objects[&name];
+        report.materialized += 1;
+        report.total_size += object.size;
+        if dry_run {
+            continue;
+        }
+
+        let target = dest_dir.join(&name);
+        if let Some(parent) = target.parent() {
+            create_dir_all(parent).await?;
+        }
+        if target.exists() {
+            tokio::fs::remove_file(&target).await?;
+        }
+        let source = object_path(&objects_path, &object.hash)?;
+        if tokio::fs::hard_link(&source, &target).await.is_err() {
+            copy(&source, &target).await?;
+        }
+
+        emitter
+            .emit(
+                Event::AssetMaterializeProgress,
+                EventPayload::AssetMaterializeProgress {
+                    path: name,
+                    current: current as u64 + 1,
+                    total,
+                },
+            )
+            .await;
+    }
+
+    Ok(report)
+}
+
+/// Checks every object of the installed version's asset index against its
+/// hash.
+///
+/// # Parameters
+/// - `config`: The configuration of the installed version.
+///
+/// # Returns
+/// A result containing the report of the objects store.
+pub async fn verify_objects<T: Loader>(config: &Config<T>) -> crate::Result<ObjectsReport> {
+    let asset_index = read_asset_index(config).await?;
+    let objects_path = objects_path(config);
+    // Hashing every object blocks, so it runs off the async runtime.
+    let states: Vec<(String, ObjectState)> = spawn_blocking(move || {
+        asset_index
+            .objects
+            .par_iter()
+            .map(|(name, object)| (name.clone(), object_state(&objects_path, &object.hash)))
+            .collect()
+    })
+    .await
+    .map_err(|e| Error::Fail(e.to_string()))?;
+
+    let mut report = ObjectsReport::default();
+    for (name, state) in states {
+        match state {
+            ObjectState::Valid => report.valid += 1,
+            ObjectState::Missing => report.missing.push(name),
+            ObjectState::Corrupt => report.corrupt.push(name),
+        }
+    }
+    report.missing.sort();
+    report.corrupt.sort();
+
+    Ok(report)
+}
+
+/// Gets the directory of an object in the objects store and on the
+/// resources server, which is named after the first two characters of its
+/// hash.
+///
+/// # Parameters
+/// - `hash`: The SHA-1 hash of the object.
+///
+/// # Returns
+/// A result containing the name of the directory, or `Error::Parse` if the
+/// hash is not a SHA-1 hash.
+pub(crate) fn object_prefix(hash: &str) -> crate::Result<&str> {
+    if hash.len() != 40 || !hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(Error::Parse(format!("Invalid asset hash '{}'", hash)));
+    }
+    Ok(&hash[0..2])
+}
+
+/// Gets the objects store of a configuration.
+fn objects_path<T: Loader>(config: &Config<T>) -> PathBuf {
+    config.get_assets_path().join("objects")
+}
+
+/// Gets the path of an object in the objects store.
+///
+/// # Parameters
+/// - `objects_path`: The objects store.
+/// - `hash`: The SHA-1 hash of the object.
+///
+/// # Returns
+/// A result containing the path of the object, or `Error::Parse` if the
+/// hash is not a SHA-1 hash.
+fn object_path(objects_path: &Path, hash: &str) -> crate::Result<PathBuf> {
+    Ok(objects_path.join(object_prefix(hash)?).join(hash))
+}
+
+/// Checks an object in the objects store against its hash. An object whose
+/// hash is not a SHA-1 hash can't be in the store and counts as corrupt.
+fn object_state(objects_path: &Path, hash: &str) -> ObjectState {
+    let Ok(path) = object_path(objects_path, hash) else {
+        return ObjectState::Corrupt;
+    };
+    if !path.is_file() {
+        ObjectState::Missing
+    } else if calculate_sha1(path).is_ok_and(|sha1| sha1 == hash) {
+        ObjectState::Valid
+    } else {
+        ObjectState::Corrupt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util;
+
+    #[test]
+    fn object_prefix_of_hashes() {
+        assert_eq!(
+            object_prefix("bdf48ef6b5d0d23bbb02e17d04865216179f510a").unwrap(),
+            "bd"
+        );
+        for hash in [
+            "",
+            "b",
+            "bd",
+            "../../../../etc/passwd",
+            "ébdf48ef6b5d0d23bbb02e17d04865216179f51",
+        ] {
+            assert!(
+                matches!(object_prefix(hash), Err(Error::Parse(_))),
+                "{}",
+                hash
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn get_rejects_invalid_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = test_util::config(dir.path(), "1.8.9");
+        test_util::write_version(&config, "1.8.9", &test_util::version_json("1.8.9")).await;
+        test_util::write_asset_index(
+            &config,
+            "1.8.9",
+            json!({ "icons/icon_16x16.png": { "hash": "b", "size": 3 } }),
+        )
+        .await;
+
+        assert!(matches!(
+            get(&config, "icons/icon_16x16.png").await,
+            Err(Error::Parse(_))
+        ));
+        assert!(matches!(
+            get(&config, "icons/icon_32x32.png").await,
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn get_returns_valid_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = test_util::config(dir.path(), "1.8.9");
+        test_util::write_version(&config, "1.8.9", &test_util::version_json("1.8.9")).await;
+        // The SHA-1 hash of "icon".
+        let hash = "f8995ba5891b07e328c60d6bd6c10159878c5a13";
+        let object = config
+            .get_assets_path()
+            .join("objects")
+            .join("f8")
+            .join(hash);
+        fs::create_dir_all(object.parent().unwrap()).unwrap();
+        fs::write(&object, "icon").unwrap();
+        test_util::write_asset_index(
+            &config,
+            "1.8.9",
+            json!({ "icons/icon_16x16.png": { "hash": hash, "size": 4 } }),
+        )
+        .await;
+
+        assert_eq!(get(&config, "icons/icon_16x16.png").await.unwrap(), object);
+    }
+
+    #[tokio::test]
+    async fn get_downloads_corrupt_objects_from_the_mirror() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        // The SHA-1 hashes of "icon" and "sound".
+        let icon = "f8995ba5891b07e328c60d6bd6c10159878c5a13";
+        let sound = "5e2e97c3c7ba3035c6e6fc3a17e1bc22029c0c3e";
+        let server = MockServer::start().await;
+        // The mirror is probed before it is selected.
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(path(format!("/{}/{}", &sound[..2], sound)))
+            .respond_with(ResponseTemplate::new(200).set_body_string("sound"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![test_util::mirror(&server)];
+        test_util::write_version(&config, "1.8.9", &test_util::version_json("1.8.9")).await;
+        test_util::write_asset_index(
+            &config,
+            "1.8.9",
+            json!({
+                "icons/icon_16x16.png": { "hash": icon, "size": 4 },
+                "sounds/click.ogg": { "hash": sound, "size": 5 },
+            }),
+        )
+        .await;
+        for (hash, contents) in [(icon, "icon"), (sound, "corrupt")] {
+            let object = objects_path(&config).join(&hash[..2]).join(hash);
+            fs::create_dir_all(object.parent().unwrap()).unwrap();
+            fs::write(object, contents).unwrap();
+        }
+
+        let icon_path = get(&config, "icons/icon_16x16.png").await.unwrap();
+        let sound_path = get(&config, "sounds/click.ogg").await.unwrap();
+
+        assert_eq!(fs::read_to_string(icon_path).unwrap(), "icon");
+        assert_eq!(fs::read_to_string(sound_path).unwrap(), "sound");
+    }
+
+    #[tokio::test]
+    async fn invalid_hashes_count_as_corrupt() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = test_util::config(dir.path(), "1.8.9");
+        test_util::write_version(&config, "1.8.9", &test_util::version_json("1.8.9")).await;
+        // The SHA-1 hash of "icon".
+        let hash = "f8995ba5891b07e328c60d6bd6c10159878c5a13";
+        let object = objects_path(&config).join("f8").join(hash);
+        fs::create_dir_all(object.parent().unwrap()).unwrap();
+        fs::write(&object, "icon").unwrap();
+        test_util::write_asset_index(
+            &config,
+            "1.8.9",
+            json!({
+                "icons/icon_16x16.png": { "hash": hash, "size": 4 },
+                "icons/icon_32x32.png": { "hash": "b", "size": 3 },
+            }),
+        )
+        .await;
+
+        let report = verify_objects(&config).await.unwrap();
+        assert_eq!(report.valid, 1);
+        assert!(report.missing.is_empty());
+        assert_eq!(report.corrupt, ["icons/icon_32x32.png"]);
+
+        let dest_dir = dir.path().join("resources");
+        let report = materialize(&config, &dest_dir, false, None).await.unwrap();
+        assert_eq!(report.materialized, 1);
+        assert_eq!(report.missing, ["icons/icon_32x32.png"]);
+        assert_eq!(
+            fs::read_to_string(dest_dir.join("icons/icon_16x16.png")).unwrap(),
+            "icon"
+        );
+    }
+}
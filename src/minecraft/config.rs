@@ -1,4 +1,9 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 #[cfg(not(target_os = "windows"))]
 use std::os::unix::fs::PermissionsExt;
@@ -6,9 +11,20 @@ use std::os::unix::fs::PermissionsExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
-use crate::{auth::AuthMethod, json::version::meta::vanilla::JavaVersion};
+use crate::{
+    auth::{microsoft::MinecraftAccount, AuthMethod},
+    http::{
+        downloader::DownloadOptions,
+        mirror::{MirrorConfig, SelectedMirror},
+    },
+    json::version::meta::vanilla::{JavaVersion, VersionMeta},
+    util::{
+        json::{read_json, write_json},
+        retry::RateLimit,
+    },
+};
 
-use super::loader::Loader;
+use super::{console::ConsoleOptions, loader::Loader, platform::Platform};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub enum Memory {
@@ -16,6 +32,115 @@ pub enum Memory {
     Gigabyte(u16),
 }
 
+impl fmt::Display for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Memory::Megabyte(m) => write!(f, "{}M", m),
+            Memory::Gigabyte(m) => write!(f, "{}G", m),
+        }
+    }
+}
+
+/// The scheduling priority of the game process.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessPriority {
+    Normal,
+    /// The default on Windows, so that the game doesn't starve the launcher.
+    BelowNormal,
+    Idle,
+    /// Raising the priority usually needs elevated rights on Unix.
+    AboveNormal,
+    High,
+}
+
+impl Default for ProcessPriority {
+    /// Returns `BelowNormal` on Windows and `Normal` elsewhere.
+    fn default() -> Self {
+        if cfg!(target_os = "windows") {
+            ProcessPriority::BelowNormal
+        } else {
+            ProcessPriority::Normal
+        }
+    }
+}
+
+impl ProcessPriority {
+    /// Gets the niceness of the priority on Unix.
+    ///
+    /// # Returns
+    /// The niceness, `None` for `Normal`, which leaves the inherited one.
+    pub fn nice(&self) -> Option<i32> {
+        match self {
+            ProcessPriority::Normal => None,
+            ProcessPriority::BelowNormal => Some(10),
+            ProcessPriority::Idle => Some(19),
+            ProcessPriority::AboveNormal => Some(-5),
+            ProcessPriority::High => Some(-10),
+        }
+    }
+
+    /// Gets the priority class of the priority on Windows.
+    ///
+    /// # Returns
+    /// The priority class, `None` for `Normal`, which leaves the inherited
+    /// one.
+    pub fn priority_class(&self) -> Option<u32> {
+        match self {
+            ProcessPriority::Normal => None,
+            ProcessPriority::BelowNormal => Some(0x4000),
+            ProcessPriority::Idle => Some(0x40),
+            ProcessPriority::AboveNormal => Some(0x8000),
+            ProcessPriority::High => Some(0x80),
+        }
+    }
+}
+
+/// The file inside the version directory that records the pinned Java
+/// runtime component.
+const JAVA_PIN_FILE: &str = ".lyceris-java.json";
+
+/// The Java runtime component pinned by the installation of a version.
+#[derive(Serialize, Deserialize)]
+struct JavaPin {
+    component: String,
+}
+
+/// The size of the game window.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Where the game goes right after starting, skipping the title screen.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum QuickPlay {
+    /// Opens the singleplayer world with the given directory name.
+    Singleplayer(String),
+    /// Joins the server with the given address.
+    Multiplayer(String),
+    /// Joins the Realm with the given id.
+    Realms(String),
+}
+
+/// A resource pack or shader pack downloaded during the installation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProvisionedPack {
+    /// The URL to download the pack from, the file is named after its last
+    /// path segment.
+    pub url: String,
+    /// The SHA-1 hash of the pack, if known.
+    pub sha1: Option<String>,
+    /// Whether the pack is enabled in the game options.
+    pub enabled: bool,
+}
+
+impl ProvisionedPack {
+    pub fn new(url: String, sha1: Option<String>, enabled: bool) -> Self {
+        Self { url, sha1, enabled }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Profile {
     pub name: String,
@@ -36,6 +161,54 @@ impl Profile {
     }
 }
 
+/// Checks whether the library is running in a headless environment.
+///
+/// An environment is considered headless when the `CI` variable is set or
+/// when there is no `TERM` variable.
+///
+/// # Returns
+/// `true` if the environment is headless.
+pub fn is_headless() -> bool {
+    std::env::var("CI").is_ok() || std::env::var("TERM").is_err()
+}
+
+/// Gets the runtime directory shared by every game directory, inside the
+/// data directory of the platform.
+///
+/// # Returns
+/// The path to the shared runtime directory, or `None` if the data directory
+/// couldn't be determined.
+pub fn shared_runtimes_path() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let data_dir = std::env::var_os("APPDATA").map(PathBuf::from);
+
+    #[cfg(target_os = "macos")]
+    let data_dir = std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join("Library")
+            .join("Application Support")
+    });
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+        });
+
+    data_dir.map(|data_dir| data_dir.join("lyceris").join("runtimes"))
+}
+
+/// Gets the directory a relative game directory is resolved against when no
+/// base directory is set, which is the current working directory.
+fn default_base_dir(game_dir: &Path) -> Option<PathBuf> {
+    game_dir
+        .is_relative()
+        .then(|| std::env::current_dir().ok())
+        .flatten()
+}
+
 /// Configuration structure for managing Minecraft installation settings.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Config<T: Loader> {
@@ -43,15 +216,69 @@ pub struct Config<T: Loader> {
     pub version: String,
     pub authentication: AuthMethod,
     pub memory: Option<Memory>,
+    pub min_memory: Option<Memory>,
     pub version_name: Option<String>,
     pub profile: Option<Profile>,
     pub loader: Option<T>,
-    pub java_version: Option<String>,
+    #[serde(default, alias = "java_version")]
+    pub java_component: Option<String>,
     pub runtime_dir: Option<PathBuf>,
+    pub loader_profiles_dir: Option<PathBuf>,
+    pub assets_dir: Option<PathBuf>,
+    pub libraries_dir: Option<PathBuf>,
+    pub versions_dir: Option<PathBuf>,
+    pub natives_dir: Option<PathBuf>,
     pub custom_java_args: Vec<String>,
     pub custom_args: Vec<String>,
+    #[serde(default)]
+    pub custom_env_vars: HashMap<String, String>,
+    #[serde(default)]
+    pub resource_packs: Vec<ProvisionedPack>,
+    #[serde(default)]
+    pub shader_packs: Vec<ProvisionedPack>,
+    #[serde(default)]
+    pub quick_play: Option<QuickPlay>,
+    #[serde(default)]
+    pub dock_name: Option<String>,
+    #[serde(default)]
+    pub dock_icon: Option<PathBuf>,
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub target_platform: Option<Platform>,
+    #[serde(default)]
+    pub base_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub mirrors: Vec<MirrorConfig>,
+    #[serde(default)]
+    pub skip_progress_events: bool,
+    #[serde(default)]
+    pub download_options: DownloadOptions,
+    #[serde(default)]
+    pub asset_index_max_age: Option<Duration>,
+    #[serde(default)]
+    pub resolution: Option<Resolution>,
+    #[serde(default)]
+    pub demo: bool,
+    #[serde(default)]
+    pub clear_env: bool,
+    #[serde(default)]
+    pub java_console: bool,
+    #[serde(default)]
+    pub shared_runtimes: bool,
+    #[serde(default)]
+    pub sanitize_username: bool,
+    #[serde(default)]
+    pub console: ConsoleOptions,
+    #[serde(default)]
+    pub process_priority: ProcessPriority,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
     #[serde(skip)]
-    pub client: Option<Client>
+    pub client: Option<Client>,
+    /// The fastest of `mirrors`, probed on the first install.
+    #[serde(skip)]
+    pub selected_mirror: SelectedMirror,
 }
 
 impl<T: Loader> Config<T> {
@@ -61,14 +288,43 @@ impl<T: Loader> Config<T> {
             version: self.version.clone(),
             authentication: self.authentication.clone(),
             memory: self.memory.clone(),
+            min_memory: self.min_memory.clone(),
             version_name: self.version_name.clone(),
             loader: None,
             profile: self.profile.clone(),
-            java_version: self.java_version.clone(),
+            java_component: self.java_component.clone(),
             runtime_dir: self.runtime_dir.clone(),
+            loader_profiles_dir: self.loader_profiles_dir.clone(),
+            assets_dir: self.assets_dir.clone(),
+            libraries_dir: self.libraries_dir.clone(),
+            versions_dir: self.versions_dir.clone(),
+            natives_dir: self.natives_dir.clone(),
             custom_java_args: self.custom_java_args.clone(),
             custom_args: self.custom_args.clone(),
-            client: self.client.clone()
+            custom_env_vars: self.custom_env_vars.clone(),
+            resource_packs: self.resource_packs.clone(),
+            shader_packs: self.shader_packs.clone(),
+            quick_play: self.quick_play.clone(),
+            dock_name: self.dock_name.clone(),
+            dock_icon: self.dock_icon.clone(),
+            working_dir: self.working_dir.clone(),
+            target_platform: self.target_platform.clone(),
+            base_dir: self.base_dir.clone(),
+            mirrors: self.mirrors.clone(),
+            skip_progress_events: self.skip_progress_events,
+            download_options: self.download_options,
+            asset_index_max_age: self.asset_index_max_age,
+            resolution: self.resolution,
+            demo: self.demo,
+            clear_env: self.clear_env,
+            java_console: self.java_console,
+            shared_runtimes: self.shared_runtimes,
+            sanitize_username: self.sanitize_username,
+            console: self.console.clone(),
+            process_priority: self.process_priority,
+            rate_limit: self.rate_limit,
+            client: self.client.clone(),
+            selected_mirror: self.selected_mirror.clone(),
         }
     }
 }
@@ -79,13 +335,64 @@ pub struct ConfigBuilder<T: Loader = ()> {
     version: String,
     authentication: AuthMethod,
     memory: Option<Memory>,
+    min_memory: Option<Memory>,
     version_name: Option<String>,
     pub profile: Option<Profile>,
     loader: Option<T>,
-    java_version: Option<String>,
+    #[serde(default, alias = "java_version")]
+    java_component: Option<String>,
     runtime_dir: Option<PathBuf>,
+    loader_profiles_dir: Option<PathBuf>,
+    assets_dir: Option<PathBuf>,
+    libraries_dir: Option<PathBuf>,
+    versions_dir: Option<PathBuf>,
+    natives_dir: Option<PathBuf>,
     custom_java_args: Vec<String>,
     custom_args: Vec<String>,
+    #[serde(default)]
+    custom_env_vars: HashMap<String, String>,
+    #[serde(default)]
+    resource_packs: Vec<ProvisionedPack>,
+    #[serde(default)]
+    shader_packs: Vec<ProvisionedPack>,
+    #[serde(default)]
+    quick_play: Option<QuickPlay>,
+    #[serde(default)]
+    dock_name: Option<String>,
+    #[serde(default)]
+    dock_icon: Option<PathBuf>,
+    #[serde(default)]
+    working_dir: Option<PathBuf>,
+    #[serde(default)]
+    target_platform: Option<Platform>,
+    #[serde(default)]
+    base_dir: Option<PathBuf>,
+    #[serde(default)]
+    mirrors: Vec<MirrorConfig>,
+    #[serde(default)]
+    skip_progress_events: bool,
+    #[serde(default)]
+    download_options: DownloadOptions,
+    #[serde(default)]
+    asset_index_max_age: Option<Duration>,
+    #[serde(default)]
+    resolution: Option<Resolution>,
+    #[serde(default)]
+    demo: bool,
+    #[serde(default)]
+    clear_env: bool,
+    #[serde(default)]
+    java_console: bool,
+    #[serde(default)]
+    shared_runtimes: bool,
+    #[serde(default)]
+    sanitize_username: bool,
+    #[serde(default)]
+    console: ConsoleOptions,
+    #[serde(default)]
+    process_priority: ProcessPriority,
+    #[serde(default)]
+    rate_limit: RateLimit,
     #[serde(skip)]
     client: Option<Client>  
 }
@@ -101,16 +408,165 @@ impl ConfigBuilder<()> {
             version,
             authentication,
             memory: None,
+            min_memory: None,
             version_name: None,
             loader: None,
-            java_version: None,
+            java_component: None,
             profile: None,
             runtime_dir: None,
+            loader_profiles_dir: None,
+            assets_dir: None,
+            libraries_dir: None,
+            versions_dir: None,
+            natives_dir: None,
             custom_java_args: Vec::new(),
             custom_args: Vec::new(),
+            custom_env_vars: HashMap::new(),
+            resource_packs: Vec::new(),
+            shader_packs: Vec::new(),
+            quick_play: None,
+            dock_name: None,
+            dock_icon: None,
+            working_dir: None,
+            target_platform: None,
+            base_dir: None,
+            mirrors: Vec::new(),
+            skip_progress_events: false,
+            download_options: DownloadOptions::default(),
+            asset_index_max_age: None,
+            resolution: None,
+            demo: false,
+            clear_env: false,
+            java_console: false,
+            shared_runtimes: false,
+            sanitize_username: false,
+            console: ConsoleOptions::default(),
+            process_priority: ProcessPriority::default(),
+            rate_limit: RateLimit::default(),
             client: None
         }
     }
+
+    /// Creates a builder for an offline player, whose UUID is generated when
+    /// the game is launched.
+    ///
+    /// # Parameters
+    /// - `game_dir`: The directory of the game.
+    /// - `version`: The Minecraft version.
+    /// - `username`: The username of the player.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn offline<T: AsRef<Path>>(
+        game_dir: T,
+        version: String,
+        username: String,
+    ) -> ConfigBuilder<()> {
+        Self::new(
+            game_dir,
+            version,
+            AuthMethod::Offline {
+                username,
+                uuid: None,
+            },
+        )
+    }
+
+    /// Creates a builder for a Microsoft account, e.g. one returned by
+    /// `authenticate` or `refresh`.
+    ///
+    /// # Parameters
+    /// - `game_dir`: The directory of the game.
+    /// - `version`: The Minecraft version.
+    /// - `account`: The Microsoft account of the player.
+    ///
+    /// # Returns
+    /// The builder.
+    pub fn from_account<T: AsRef<Path>>(
+        game_dir: T,
+        version: String,
+        account: MinecraftAccount,
+    ) -> ConfigBuilder<()> {
+        Self::new(game_dir, version, account.into())
+    }
+
+    /// Reconstructs a builder from a JSON serialized `Config`.
+    ///
+    /// The loader is not preserved since its concrete type is unknown at this
+    /// point, it has to be attached again via `.loader(...)`. The HTTP client is
+    /// never serialized either, set it with `.client(...)` if needed.
+    ///
+    /// # Parameters
+    /// - `json`: The JSON string of a saved configuration.
+    ///
+    /// # Returns
+    /// A result containing the reconstructed builder.
+    pub fn from_json(json: &str) -> crate::Result<ConfigBuilder<()>> {
+        let config: Config<()> = serde_json::from_str(json)?;
+        Ok(config.into())
+    }
+
+    /// Reconstructs a builder from a JSON serialized `Config` file.
+    ///
+    /// See [`ConfigBuilder::from_json`] for the caveats about the loader and
+    /// the HTTP client.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the saved configuration file.
+    ///
+    /// # Returns
+    /// A result containing the reconstructed builder.
+    pub async fn from_file(path: &Path) -> crate::Result<ConfigBuilder<()>> {
+        let config: Config<()> = read_json(path).await?;
+        Ok(config.into())
+    }
+}
+
+impl<T: Loader> From<Config<T>> for ConfigBuilder<T> {
+    fn from(config: Config<T>) -> Self {
+        ConfigBuilder {
+            game_dir: config.game_dir,
+            version: config.version,
+            authentication: config.authentication,
+            memory: config.memory,
+            min_memory: config.min_memory,
+            version_name: config.version_name,
+            profile: config.profile,
+            loader: config.loader,
+            java_component: config.java_component,
+            runtime_dir: config.runtime_dir,
+            loader_profiles_dir: config.loader_profiles_dir,
+            assets_dir: config.assets_dir,
+            libraries_dir: config.libraries_dir,
+            versions_dir: config.versions_dir,
+            natives_dir: config.natives_dir,
+            custom_java_args: config.custom_java_args,
+            custom_args: config.custom_args,
+            custom_env_vars: config.custom_env_vars,
+            resource_packs: config.resource_packs,
+            shader_packs: config.shader_packs,
+            quick_play: config.quick_play,
+            dock_name: config.dock_name,
+            dock_icon: config.dock_icon,
+            working_dir: config.working_dir,
+            target_platform: config.target_platform,
+            base_dir: config.base_dir,
+            mirrors: config.mirrors,
+            skip_progress_events: config.skip_progress_events,
+            download_options: config.download_options,
+            asset_index_max_age: config.asset_index_max_age,
+            resolution: config.resolution,
+            demo: config.demo,
+            clear_env: config.clear_env,
+            java_console: config.java_console,
+            shared_runtimes: config.shared_runtimes,
+            sanitize_username: config.sanitize_username,
+            console: config.console,
+            process_priority: config.process_priority,
+            rate_limit: config.rate_limit,
+            client: config.client
+        }
+    }
 }
 
 impl<T: Loader> ConfigBuilder<T> {
@@ -119,6 +575,11 @@ impl<T: Loader> ConfigBuilder<T> {
         self
     }
 
+    pub fn min_memory(mut self, min_memory: Memory) -> Self {
+        self.min_memory = Some(min_memory);
+        self
+    }
+
     pub fn version_name(mut self, version_name: String) -> Self {
         self.version_name = Some(version_name);
         self
@@ -130,27 +591,92 @@ impl<T: Loader> ConfigBuilder<T> {
             version: self.version,
             authentication: self.authentication,
             memory: self.memory,
+            min_memory: self.min_memory,
             version_name: self.version_name,
             profile: self.profile,
             loader: Some(loader),
-            java_version: self.java_version,
+            java_component: self.java_component,
             runtime_dir: self.runtime_dir,
+            loader_profiles_dir: self.loader_profiles_dir,
+            assets_dir: self.assets_dir,
+            libraries_dir: self.libraries_dir,
+            versions_dir: self.versions_dir,
+            natives_dir: self.natives_dir,
             custom_java_args: self.custom_java_args,
             custom_args: self.custom_args,
+            custom_env_vars: self.custom_env_vars,
+            resource_packs: self.resource_packs,
+            shader_packs: self.shader_packs,
+            quick_play: self.quick_play,
+            dock_name: self.dock_name,
+            dock_icon: self.dock_icon,
+            working_dir: self.working_dir,
+            target_platform: self.target_platform,
+            base_dir: self.base_dir,
+            mirrors: self.mirrors,
+            skip_progress_events: self.skip_progress_events,
+            download_options: self.download_options,
+            asset_index_max_age: self.asset_index_max_age,
+            resolution: self.resolution,
+            demo: self.demo,
+            clear_env: self.clear_env,
+            java_console: self.java_console,
+            shared_runtimes: self.shared_runtimes,
+            sanitize_username: self.sanitize_username,
+            console: self.console,
+            process_priority: self.process_priority,
+            rate_limit: self.rate_limit,
             client: self.client
         }
     }
 
-    pub fn java_version(mut self, java_version: String) -> Self {
-        self.java_version = Some(java_version);
+    /// Pins the Java runtime component, e.g. `java-runtime-delta`, used
+    /// instead of the one the version asks for. The component has to be
+    /// available for the current platform in Mojang's Java manifest.
+    pub fn java_component(mut self, java_component: String) -> Self {
+        self.java_component = Some(java_component);
         self
     }
 
+    #[deprecated(note = "use `java_component`, which this sets")]
+    pub fn java_version(self, java_version: String) -> Self {
+        self.java_component(java_version)
+    }
+
     pub fn runtime_dir(mut self, runtime_dir: PathBuf) -> Self {
         self.runtime_dir = Some(runtime_dir);
         self
     }
 
+    /// Sets the directory the Forge and NeoForge installer profiles are kept
+    /// in, `.lyceris/loader_profiles` in the game directory by default.
+    pub fn loader_profiles_dir(mut self, loader_profiles_dir: PathBuf) -> Self {
+        self.loader_profiles_dir = Some(loader_profiles_dir);
+        self
+    }
+
+    pub fn assets_dir(mut self, assets_dir: PathBuf) -> Self {
+        self.assets_dir = Some(assets_dir);
+        self
+    }
+
+    pub fn libraries_dir(mut self, libraries_dir: PathBuf) -> Self {
+        self.libraries_dir = Some(libraries_dir);
+        self
+    }
+
+    pub fn versions_dir(mut self, versions_dir: PathBuf) -> Self {
+        self.versions_dir = Some(versions_dir);
+        self
+    }
+
+    /// Sets the directory the natives of this version are extracted to,
+    /// instead of a directory named after the version inside `natives`.
+    pub fn natives_dir(mut self, natives_dir: PathBuf) -> Self {
+        self.natives_dir = Some(natives_dir);
+        self
+    }
+
     pub fn custom_java_args(mut self, custom_java_args: Vec<String>) -> Self {
         self.custom_java_args = custom_java_args;
         self
@@ -161,6 +687,186 @@ impl<T: Loader> ConfigBuilder<T> {
         self
     }
 
+    /// Sets environment variables for the game process, e.g.
+    /// `MESA_GL_VERSION_OVERRIDE` or `LD_PRELOAD`.
+    pub fn custom_env_vars(mut self, custom_env_vars: HashMap<String, String>) -> Self {
+        self.custom_env_vars = custom_env_vars;
+        self
+    }
+
+    /// Sets the resource packs downloaded into the `resourcepacks`
+    /// directory during the installation. Enabled packs are added to the
+    /// resource pack list of `options.txt`.
+    pub fn resource_packs(mut self, resource_packs: Vec<ProvisionedPack>) -> Self {
+        self.resource_packs = resource_packs;
+        self
+    }
+
+    /// Sets the shader packs downloaded into the `shaderpacks` directory
+    /// during the installation. An enabled pack is selected in the OptiFine
+    /// and Iris options.
+    pub fn shader_packs(mut self, shader_packs: Vec<ProvisionedPack>) -> Self {
+        self.shader_packs = shader_packs;
+        self
+    }
+
+    /// Sets whether the game starts as the demo version.
+    pub fn demo(mut self, demo: bool) -> Self {
+        self.demo = demo;
+        self
+    }
+
+    /// Sets the size of the game window.
+    pub fn resolution(mut self, width: u32, height: u32) -> Self {
+        self.resolution = Some(Resolution { width, height });
+        self
+    }
+
+    /// Sets where the game goes right after starting. Versions older than
+    /// 1.20 ignore it.
+    pub fn quick_play(mut self, quick_play: QuickPlay) -> Self {
+        self.quick_play = Some(quick_play);
+        self
+    }
+
+    /// Sets whether the game process starts without inheriting the
+    /// environment of the launcher, so that only `custom_env_vars` are set.
+    pub fn clear_env(mut self, clear_env: bool) -> Self {
+        self.clear_env = clear_env;
+        self
+    }
+
+    /// Sets whether the game runs with `java` instead of `javaw` on Windows,
+    /// which keeps the console attached so that its output can be read.
+    /// Ignored on other platforms.
+    pub fn use_java_console(mut self, java_console: bool) -> Self {
+        self.java_console = java_console;
+        self
+    }
+
+    /// Sets the name of the game in the macOS Dock, ignored on other
+    /// platforms.
+    pub fn dock_name(mut self, dock_name: String) -> Self {
+        self.dock_name = Some(dock_name);
+        self
+    }
+
+    /// Sets the icon of the game in the macOS Dock, ignored on other
+    /// platforms.
+    pub fn dock_icon(mut self, dock_icon: PathBuf) -> Self {
+        self.dock_icon = Some(dock_icon);
+        self
+    }
+
+    /// Sets the working directory of the game process, the game directory
+    /// by default. The game resolves `options.txt`, `servers.dat` and the
+    /// like against `--gameDir`, which has to be passed when the version
+    /// doesn't pass it already.
+    pub fn working_dir(mut self, working_dir: PathBuf) -> Self {
+        self.working_dir = Some(working_dir);
+        self
+    }
+
+    /// Sets the platform the game directory is installed for, the host by
+    /// default. This selects the libraries, natives and Java runtime of
+    /// another operating system, e.g. to prepare a Windows instance on a
+    /// Linux machine.
+    pub fn target_platform(mut self, target_platform: Platform) -> Self {
+        self.target_platform = Some(target_platform);
+        self
+    }
+
+    /// Sets the directory a relative `game_dir` is resolved against, instead
+    /// of the working directory at the time the configuration is built.
+    pub fn base_dir(mut self, base_dir: PathBuf) -> Self {
+        self.base_dir = Some(base_dir);
+        self
+    }
+
+    /// Resolves a relative `game_dir` against the directory of the running
+    /// executable, so that a launcher can be moved along with its game
+    /// directory, e.g. on a USB stick.
+    pub fn portable(mut self) -> Self {
+        if let Some(exe_dir) = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        {
+            self.base_dir = Some(exe_dir);
+        }
+        self
+    }
+
+    pub fn mirrors(mut self, mirrors: Vec<MirrorConfig>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// Sets how old the cached asset index may get before it is fetched
+    /// again, by default it is never fetched again.
+    pub fn asset_index_max_age(mut self, asset_index_max_age: Duration) -> Self {
+        self.asset_index_max_age = Some(asset_index_max_age);
+        self
+    }
+
+    /// Sets the scheduling priority of the game process, `BelowNormal` on
+    /// Windows and `Normal` elsewhere by default.
+    pub fn process_priority(mut self, process_priority: ProcessPriority) -> Self {
+        self.process_priority = process_priority;
+        self
+    }
+
+    /// Sets how long downloads wait for servers that ask to retry later.
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Sets whether an invalid offline username is sanitized when launching,
+    /// so that vanilla servers accept it, instead of failing the launch with
+    /// `Error::InvalidUsername`.
+    pub fn sanitize_username(mut self, sanitize_username: bool) -> Self {
+        self.sanitize_username = sanitize_username;
+        self
+    }
+
+    /// Sets whether the Java runtimes are installed to a directory shared by
+    /// every game directory, see [`shared_runtimes_path`]. An explicit
+    /// `runtime_dir` takes precedence.
+    pub fn shared_runtimes(mut self, shared_runtimes: bool) -> Self {
+        self.shared_runtimes = shared_runtimes;
+        self
+    }
+
+    pub fn skip_progress_events(mut self, skip_progress_events: bool) -> Self {
+        self.skip_progress_events = skip_progress_events;
+        self
+    }
+
+    /// Sets the size of the buffer downloaded files are written through,
+    /// 64 KiB by default.
+    pub fn download_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.download_options.buffer_size = buffer_size;
+        self
+    }
+
+    pub fn console(mut self, console: ConsoleOptions) -> Self {
+        self.console = console;
+        self
+    }
+
+    /// Adjusts the configuration for headless environments such as CI.
+    ///
+    /// When [`is_headless`] reports a headless environment, progress events
+    /// are no longer emitted during downloads and the `--fullscreen` game
+    /// argument is removed. Otherwise the builder is returned unchanged.
+    pub fn detect_headless(mut self) -> Self {
+        if is_headless() {
+            self.skip_progress_events = true;
+            self.custom_args.retain(|arg| arg != "--fullscreen");
+        }
+        self
+    }
+
     pub fn client(mut self, client: Client) -> Self {
         self.client = Some(client);
         self
@@ -172,38 +878,98 @@ impl<T: Loader> ConfigBuilder<T> {
     }
 
     pub fn build(self) -> Config<T> {
+        let base_dir = self.base_dir.or_else(|| default_base_dir(&self.game_dir));
         Config {
             game_dir: self.game_dir,
             version: self.version,
             authentication: self.authentication,
             memory: self.memory,
+            min_memory: self.min_memory,
             version_name: self.version_name,
             loader: self.loader,
-            java_version: self.java_version,
+            java_component: self.java_component,
             runtime_dir: self.runtime_dir,
+            loader_profiles_dir: self.loader_profiles_dir,
+            assets_dir: self.assets_dir,
+            libraries_dir: self.libraries_dir,
+            versions_dir: self.versions_dir,
+            natives_dir: self.natives_dir,
             profile: self.profile,
             custom_java_args: self.custom_java_args,
             custom_args: self.custom_args,
-            client: self.client
+            custom_env_vars: self.custom_env_vars,
+            resource_packs: self.resource_packs,
+            shader_packs: self.shader_packs,
+            quick_play: self.quick_play,
+            dock_name: self.dock_name,
+            dock_icon: self.dock_icon,
+            working_dir: self.working_dir,
+            target_platform: self.target_platform,
+            base_dir,
+            mirrors: self.mirrors,
+            skip_progress_events: self.skip_progress_events,
+            download_options: self.download_options,
+            asset_index_max_age: self.asset_index_max_age,
+            resolution: self.resolution,
+            demo: self.demo,
+            clear_env: self.clear_env,
+            java_console: self.java_console,
+            shared_runtimes: self.shared_runtimes,
+            sanitize_username: self.sanitize_username,
+            console: self.console,
+            process_priority: self.process_priority,
+            rate_limit: self.rate_limit,
+            client: self.client,
+            selected_mirror: SelectedMirror::default(),
         }
     }
 }
 
 impl<T: Loader> Config<T> {
     pub fn new(game_dir: PathBuf, version: String, authentication: AuthMethod) -> Self {
+        let base_dir = default_base_dir(&game_dir);
         Self {
             game_dir,
             version,
             authentication,
             memory: None,
+            min_memory: None,
             version_name: None,
             profile: None,
             loader: None,
-            java_version: None,
+            java_component: None,
             runtime_dir: None,
+            loader_profiles_dir: None,
+            assets_dir: None,
+            libraries_dir: None,
+            versions_dir: None,
+            natives_dir: None,
             custom_java_args: Vec::new(),
             custom_args: Vec::new(),
-            client: None
+            custom_env_vars: HashMap::new(),
+            resource_packs: Vec::new(),
+            shader_packs: Vec::new(),
+            quick_play: None,
+            dock_name: None,
+            dock_icon: None,
+            working_dir: None,
+            target_platform: None,
+            base_dir,
+            mirrors: Vec::new(),
+            skip_progress_events: false,
+            download_options: DownloadOptions::default(),
+            asset_index_max_age: None,
+            resolution: None,
+            demo: false,
+            clear_env: false,
+            java_console: false,
+            shared_runtimes: false,
+            sanitize_username: false,
+            console: ConsoleOptions::default(),
+            process_priority: ProcessPriority::default(),
+            rate_limit: RateLimit::default(),
+            client: None,
+            selected_mirror: SelectedMirror::default(),
         }
     }
 
@@ -219,11 +985,91 @@ impl<T: Loader> Config<T> {
             .unwrap_or_else(|| self.version.to_string())
     }
 
+    /// Gets the game directory.
+    ///
+    /// A relative `game_dir` is resolved against `base_dir`, which is the
+    /// working directory at the time the configuration was built unless set
+    /// otherwise, so later changes of the working directory don't move the
+    /// game.
+    ///
+    /// # Returns
+    /// The path to the game directory.
+    pub fn get_game_dir(&self) -> PathBuf {
+        match &self.base_dir {
+            Some(base_dir) if self.game_dir.is_relative() => base_dir.join(&self.game_dir),
+            _ => self.game_dir.clone(),
+        }
+    }
+
     pub fn get_libraries_path(&self) -> PathBuf {
-        self.game_dir.join("libraries")
+        self.libraries_dir
+            .clone()
+            .unwrap_or_else(|| self.get_game_dir().join("libraries"))
+    }
+
+    /// Gets the platform the game directory is installed for.
+    ///
+    /// # Returns
+    /// A result containing `target_platform` if set, otherwise the platform
+    /// of the host, `Error::UnsupportedOperatingSystem` or
+    /// `Error::UnsupportedArchitecture` if Minecraft isn't published for it.
+    pub fn get_platform(&self) -> crate::Result<Platform> {
+        match &self.target_platform {
+            Some(platform) => Ok(platform.clone()),
+            None => Platform::current(),
+        }
+    }
+
+    /// Gets the Java runtime a version runs on.
+    ///
+    /// The component pinned through `java_component` takes precedence, then
+    /// the one recorded by the installation, then the one of the version.
+    ///
+    /// # Parameters
+    /// - `meta`: The version metadata.
+    ///
+    /// # Returns
+    /// The Java version, with the component whose runtime directory is used.
+    pub async fn get_java_version(&self, meta: &VersionMeta) -> JavaVersion {
+        let mut java_version = meta.java_version.clone().unwrap_or_default();
+        let pinned = match &self.java_component {
+            Some(component) => Some(component.clone()),
+            None => read_json::<JavaPin>(&self.get_version_path().join(JAVA_PIN_FILE))
+                .await
+                .ok()
+                .map(|pin| pin.component),
+        };
+        if let Some(component) = pinned {
+            java_version.component = component;
+        }
+        java_version
     }
 
-    /// Gets the path to the Java executable for the specified version.
+    /// Records the pinned Java runtime component next to the version, so
+    /// that launches without the pin use the same runtime. The record is
+    /// removed when nothing is pinned.
+    ///
+    /// # Returns
+    /// A result indicating success.
+    pub(crate) async fn record_java_component(&self) -> crate::Result<()> {
+        let pin_path = self.get_version_path().join(JAVA_PIN_FILE);
+        match &self.java_component {
+            Some(component) => {
+                write_json(
+                    &pin_path,
+                    &JavaPin {
+                        component: component.clone(),
+                    },
+                )
+                .await
+            }
+            None if pin_path.is_file() => Ok(tokio::fs::remove_file(&pin_path).await?),
+            None => Ok(()),
+        }
+    }
+
+    /// Gets the path to the Java executable for the specified version and
+    /// makes it executable.
     ///
     /// # Parameters
     /// - `version`: The Java version for which to retrieve the path.
@@ -231,29 +1077,7 @@ impl<T: Loader> Config<T> {
     /// # Returns
     /// A result containing the path to the Java executable.
     pub async fn get_java_path(&self, version: &JavaVersion) -> crate::Result<PathBuf> {
-        #[cfg(target_os = "windows")]
-        let java_path = self
-            .get_runtime_path()
-            .join(version.component.clone())
-            .join("bin")
-            .join("javaw");
-
-        #[cfg(target_os = "linux")]
-        let java_path = self
-            .get_runtime_path()
-            .join(version.component.clone())
-            .join("bin")
-            .join("java");
-
-        #[cfg(target_os = "macos")]
-        let java_path = self
-            .get_runtime_path()
-            .join(&version.component)
-            .join("jre.bundle")
-            .join("Contents")
-            .join("Home")
-            .join("bin")
-            .join("java");
+        let java_path = self.get_java_executable_path(version);
 
         #[cfg(not(target_os = "windows"))]
         {
@@ -265,12 +1089,39 @@ impl<T: Loader> Config<T> {
         Ok(java_path)
     }
 
+    /// Gets the path to the Java executable for the specified version without
+    /// touching the file, which may not exist.
+    ///
+    /// # Parameters
+    /// - `version`: The Java version for which to retrieve the path.
+    ///
+    /// # Returns
+    /// The path to the Java executable, `javaw` on Windows unless
+    /// `java_console` is set.
+    pub fn get_java_executable_path(&self, version: &JavaVersion) -> PathBuf {
+        let java_home = self.get_runtime_path().join(&version.component);
+        if cfg!(target_os = "windows") && !self.java_console {
+            java_home.join("bin").join("javaw")
+        } else if cfg!(target_os = "macos") {
+            java_home
+                .join("jre.bundle")
+                .join("Contents")
+                .join("Home")
+                .join("bin")
+                .join("java")
+        } else {
+            java_home.join("bin").join("java")
+        }
+    }
+
     /// Gets the path to the versions directory.
     ///
     /// # Returns
     /// The path to the versions directory.
     pub fn get_versions_path(&self) -> PathBuf {
-        self.game_dir.join("versions")
+        self.versions_dir
+            .clone()
+            .unwrap_or_else(|| self.get_game_dir().join("versions"))
     }
 
     /// Gets the path to the assets directory.
@@ -278,7 +1129,9 @@ impl<T: Loader> Config<T> {
     /// # Returns
     /// The path to the assets directory.
     pub fn get_assets_path(&self) -> PathBuf {
-        self.game_dir.join("assets")
+        self.assets_dir
+            .clone()
+            .unwrap_or_else(|| self.get_game_dir().join("assets"))
     }
 
     /// Gets the path to the natives directory.
@@ -286,17 +1139,52 @@ impl<T: Loader> Config<T> {
     /// # Returns
     /// The path to the natives directory.
     pub fn get_natives_path(&self) -> PathBuf {
-        self.game_dir.join("natives")
+        self.get_game_dir().join("natives")
+    }
+
+    /// Gets the path to the natives directory of the version.
+    ///
+    /// Natives are kept apart per version name, so different loaders of the
+    /// same Minecraft version never share them.
+    ///
+    /// # Returns
+    /// The configured natives directory, or the version directory inside the
+    /// natives directory.
+    pub fn get_natives_path_for_version(&self) -> PathBuf {
+        self.natives_dir
+            .clone()
+            .unwrap_or_else(|| self.get_natives_path().join(self.get_version_name()))
     }
 
     /// Gets the path to the runtime directory.
     ///
     /// # Returns
-    /// The path to the runtime directory.
+    /// The path to the runtime directory, which is the shared runtime
+    /// directory when `shared_runtimes` is enabled and no `runtime_dir` is
+    /// set.
     pub fn get_runtime_path(&self) -> PathBuf {
         self.runtime_dir
             .clone()
-            .unwrap_or_else(|| self.game_dir.join("runtimes"))
+            .or_else(|| {
+                self.shared_runtimes
+                    .then(shared_runtimes_path)
+                    .flatten()
+            })
+            .unwrap_or_else(|| self.get_game_dir().join("runtimes"))
+    }
+
+    /// Gets the path to the directory the loaders keep their installer
+    /// profiles in, one directory per loader.
+    ///
+    /// # Returns
+    /// The configured directory, or `.lyceris/loader_profiles` in the game
+    /// directory.
+    pub fn get_loader_profiles_path(&self) -> PathBuf {
+        self.loader_profiles_dir.clone().unwrap_or_else(|| {
+            self.get_game_dir()
+                .join(".lyceris")
+                .join("loader_profiles")
+        })
     }
 
     /// Gets the path to the indexes directory.
@@ -328,4 +1216,185 @@ impl<T: Loader> Config<T> {
         self.get_version_path()
             .join(format!("{}.jar", self.get_version_name()))
     }
+
+    /// Gets the directory the game runs in.
+    ///
+    /// This is the profile directory when a profile is set, otherwise the
+    /// game directory itself.
+    ///
+    /// # Returns
+    /// The path to the game's working directory.
+    pub fn get_game_path(&self) -> PathBuf {
+        self.profile
+            .as_ref()
+            .map(|profile| profile.root.join(&profile.name))
+            .unwrap_or_else(|| self.get_game_dir())
+    }
+
+    /// Gets the path to the mods directory.
+    ///
+    /// # Returns
+    /// The path to the mods directory.
+    pub fn get_mods_path(&self) -> PathBuf {
+        self.get_game_path().join("mods")
+    }
+
+    /// Gets the path to the resource packs directory.
+    ///
+    /// # Returns
+    /// The path to the resource packs directory.
+    pub fn get_resource_packs_path(&self) -> PathBuf {
+        self.get_game_path().join("resourcepacks")
+    }
+
+    /// Gets the path to the shader packs directory.
+    ///
+    /// # Returns
+    /// The path to the shader packs directory.
+    pub fn get_shader_packs_path(&self) -> PathBuf {
+        self.get_game_path().join("shaderpacks")
+    }
+
+    /// Gets the path to the logs directory.
+    ///
+    /// # Returns
+    /// The path to the logs directory.
+    pub fn get_logs_path(&self) -> PathBuf {
+        self.get_game_path().join("logs")
+    }
+
+    /// Gets the path to the crash reports directory.
+    ///
+    /// # Returns
+    /// The path to the crash reports directory.
+    pub fn get_crash_reports_path(&self) -> PathBuf {
+        self.get_game_path().join("crash-reports")
+    }
+
+    /// Gets the path to the screenshots directory.
+    ///
+    /// # Returns
+    /// The path to the screenshots directory.
+    pub fn get_screenshots_path(&self) -> PathBuf {
+        self.get_game_path().join("screenshots")
+    }
+
+    /// Gets the path to the saves directory.
+    ///
+    /// # Returns
+    /// The path to the saves directory.
+    pub fn get_saves_path(&self) -> PathBuf {
+        self.get_game_path().join("saves")
+    }
+
+    /// Computes every path used by the instance at once.
+    ///
+    /// # Returns
+    /// The paths of the instance.
+    pub fn paths(&self) -> ConfigPaths {
+        ConfigPaths {
+            game: self.get_game_path(),
+            versions: self.get_versions_path(),
+            version: self.get_version_path(),
+            version_jar: self.get_version_jar_path(),
+            version_json: self.get_version_json_path(),
+            libraries: self.get_libraries_path(),
+            assets: self.get_assets_path(),
+            indexes: self.get_indexes_path(),
+            natives: self.get_natives_path_for_version(),
+            runtime: self.get_runtime_path(),
+            mods: self.get_mods_path(),
+            logs: self.get_logs_path(),
+        }
+    }
+}
+
+/// Represents the paths of an instance, as returned by [`Config::paths`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfigPaths {
+    /// The working directory of the game.
+    pub game: PathBuf,
+    pub versions: PathBuf,
+    pub version: PathBuf,
+    pub version_jar: PathBuf,
+    pub version_json: PathBuf,
+    pub libraries: PathBuf,
+    pub assets: PathBuf,
+    pub indexes: PathBuf,
+    /// The natives directory of the version.
+    pub natives: PathBuf,
+    pub runtime: PathBuf,
+    pub mods: PathBuf,
+    pub logs: PathBuf,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder(game_dir: &str) -> ConfigBuilder<()> {
+        ConfigBuilder::offline(game_dir, "1.21.4".to_string(), "Player".to_string())
+    }
+
+    #[test]
+    fn relative_game_dir_is_resolved_against_base_dir() {
+        let base_dir = tempfile::tempdir().unwrap();
+        let config = builder("instances/survival")
+            .base_dir(base_dir.path().to_path_buf())
+            .build();
+
+        let game_dir = base_dir.path().join("instances/survival");
+        assert_eq!(config.get_game_dir(), game_dir);
+        assert_eq!(config.get_game_path(), game_dir);
+        assert_eq!(config.get_versions_path(), game_dir.join("versions"));
+        assert_eq!(config.get_libraries_path(), game_dir.join("libraries"));
+    }
+
+    #[test]
+    fn absolute_game_dir_ignores_base_dir() {
+        let (base_dir, game_dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let config = builder(game_dir.path().to_str().unwrap())
+            .base_dir(base_dir.path().to_path_buf())
+            .build();
+
+        assert_eq!(config.get_game_dir(), game_dir.path());
+    }
+
+    #[test]
+    fn relative_game_dir_defaults_to_the_working_directory() {
+        let config = builder("instances/survival").build();
+
+        assert_eq!(
+            config.get_game_dir(),
+            std::env::current_dir().unwrap().join("instances/survival")
+        );
+    }
+
+    #[test]
+    fn portable_game_dir_is_next_to_the_executable() {
+        let config = builder("instances/survival").portable().build();
+
+        let exe_dir = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        assert_eq!(config.base_dir.as_deref(), Some(exe_dir.as_path()));
+        assert_eq!(config.get_game_dir(), exe_dir.join("instances/survival"));
+    }
+
+    #[test]
+    fn process_priorities_map_to_the_platform_values() {
+        let priorities = [
+            (ProcessPriority::Normal, None, None),
+            (ProcessPriority::BelowNormal, Some(10), Some(0x4000)),
+            (ProcessPriority::Idle, Some(19), Some(0x40)),
+            (ProcessPriority::AboveNormal, Some(-5), Some(0x8000)),
+            (ProcessPriority::High, Some(-10), Some(0x80)),
+        ];
+        for (priority, nice, priority_class) in priorities {
+            assert_eq!(priority.nice(), nice, "{:?}", priority);
+            assert_eq!(priority.priority_class(), priority_class, "{:?}", priority);
+        }
+    }
 }
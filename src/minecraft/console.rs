@@ -0,0 +1,544 @@
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    pin::pin,
+    process::ExitStatus,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use futures::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::{rename, File},
+    io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader, BufWriter},
+    process::{ChildStderr, ChildStdout},
+    sync::watch,
+    time::timeout,
+};
+
+use super::emitter::{Emitter, Event};
+
+/// How much of the first output is kept for `ExitSummary::early_output`.
+const EARLY_OUTPUT_LIMIT: usize = 16 * 1024;
+
+/// How soon after launching a game that stops is considered to have failed
+/// to start.
+const EARLY_EXIT_WINDOW: Duration = Duration::from_secs(15);
+
+/// Options for how the game output is forwarded and stored.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConsoleOptions {
+    /// Interval in milliseconds at which buffered lines are emitted together
+    /// as an `Event::ConsoleBatch`. Every line is emitted on its own as an
+    /// `Event::Console` when this is `None`.
+    pub batch_interval: Option<u64>,
+    /// Maximum number of lines kept between two batches. The oldest lines
+    /// are dropped when the buffer is full.
+    pub buffer_capacity: usize,
+    /// Size in bytes after which the log file of a launch, set through
+    /// `LaunchOptions::log_file`, is rotated.
+    pub log_file_max_size: u64,
+}
+
+impl Default for ConsoleOptions {
+    fn default() -> Self {
+        Self {
+            batch_interval: None,
+            buffer_capacity: 10_000,
+            log_file_max_size: 10 * 1024 * 1024,
+        }
+    }
+}
+
+/// The known reasons for a game to fail right after launching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchFailureKind {
+    /// Java couldn't load the main class, usually because of a broken
+    /// classpath or a missing library.
+    MainClassNotFound,
+    /// Java rejected a JVM option, usually one of `custom_java_args`.
+    UnrecognizedVmOption,
+    /// Any other failure.
+    Other,
+}
+
+impl LaunchFailureKind {
+    /// Recognizes the failure from the output of the game.
+    ///
+    /// # Parameters
+    /// - `output`: The output of the game.
+    ///
+    /// # Returns
+    /// The kind of the failure, `Other` if it isn't recognized.
+    pub fn from_output(output: &str) -> Self {
+        if output.contains("Could not find or load main class") {
+            Self::MainClassNotFound
+        } else if output.contains("Unrecognized VM option") {
+            Self::UnrecognizedVmOption
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// A game that stopped right after launching, along with what it printed.
+#[derive(Debug, Clone)]
+pub struct EarlyExit {
+    /// The reason of the failure.
+    pub kind: LaunchFailureKind,
+    /// The first output of the game, including the errors of the JVM.
+    pub early_output: String,
+}
+
+/// How a game exited, returned by `GameInstance::wait_for_exit`.
+#[derive(Debug, Clone)]
+pub struct ExitSummary {
+    /// The exit status of the game.
+    pub status: ExitStatus,
+    /// The first output of a game that exited with an error within 15
+    /// seconds of launching, which usually holds the errors of the JVM.
+    pub early_output: Option<EarlyExit>,
+}
+
+/// The first output of a game, kept until its exit is awaited.
+#[derive(Debug)]
+pub(crate) struct EarlyOutput {
+    started: Instant,
+    output: Mutex<String>,
+    /// When the output was closed, which happens when the game exits.
+    closed: watch::Sender<Option<Instant>>,
+}
+
+impl EarlyOutput {
+    /// Creates an empty output for a game launched now.
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            output: Mutex::new(String::new()),
+            closed: watch::Sender::new(None),
+        }
+    }
+
+    /// Takes the first output of a game that exited, if it failed right
+    /// after launching.
+    ///
+    /// # Parameters
+    /// - `status`: The exit status of the game.
+    ///
+    /// # Returns
+    /// The early exit of the game, `None` if it exited successfully or after
+    /// `EARLY_EXIT_WINDOW`.
+    pub(crate) async fn take_early_exit(&self, status: &ExitStatus) -> Option<EarlyExit> {
+        if status.success() {
+            return None;
+        }
+
+        // The output is read to the end shortly after the exit, unless a
+        // helper process of the game keeps it open.
+        let mut closed = self.closed.subscribe();
+        let closed_at = timeout(Duration::from_secs(1), async {
+            closed
+                .wait_for(Option::is_some)
+                .await
+                .ok()
+                .and_then(|at| *at)
+        })
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_else(Instant::now);
+        if closed_at.duration_since(self.started) >= EARLY_EXIT_WINDOW {
+            return None;
+        }
+
+        let early_output =
+            std::mem::take(&mut *self.output.lock().unwrap_or_else(|e| e.into_inner()));
+        Some(EarlyExit {
+            kind: LaunchFailureKind::from_output(&early_output),
+            early_output,
+        })
+    }
+}
+
+/// Lines waiting to be emitted in the next batch.
+#[derive(Default)]
+struct ConsoleBuffer {
+    lines: VecDeque<String>,
+    dropped: u64,
+    closed: bool,
+}
+
+/// A log file that is rotated once it grows past its size limit.
+struct LogFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    size: u64,
+    max_size: u64,
+}
+
+impl LogFile {
+    /// Creates the log file, truncating any previous content.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the log file.
+    /// - `max_size`: The size in bytes after which the file is rotated.
+    ///
+    /// # Returns
+    /// A result containing the opened log file.
+    async fn create(path: &Path, max_size: u64) -> crate::Result<Self> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(File::create(path).await?),
+            size: 0,
+            max_size,
+        })
+    }
+
+    /// Writes a line prefixed with the current time.
+    ///
+    /// The current file is moved to `<name>.1` before writing if the line
+    /// would make it exceed the size limit.
+    ///
+    /// # Parameters
+    /// - `line`: The line to write.
+    ///
+    /// # Returns
+    /// A result indicating success or failure of the write.
+    async fn write_line(&mut self, line: &str) -> crate::Result<()> {
+        let entry = format!("[{}] {}\n", timestamp(), line);
+
+        if self.size > 0 && self.size + entry.len() as u64 > self.max_size {
+            self.writer.flush().await?;
+            let mut rotated = self.path.clone().into_os_string();
+            rotated.push(".1");
+            rename(&self.path, rotated).await?;
+            self.writer = BufWriter::new(File::create(&self.path).await?);
+            self.size = 0;
+        }
+
+        self.writer.write_all(entry.as_bytes()).await?;
+        self.size += entry.len() as u64;
+        Ok(())
+    }
+}
+
+/// Formats the current UTC time as `HH:MM:SS.mmm`.
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = now.as_secs() % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        now.subsec_millis()
+    )
+}
+
+/// Reads the game output in the background and forwards it.
+///
+/// The output is always read to the end, even without an emitter or a log
+/// file, so the game can't block on a full pipe. Reading never waits for the
+/// listeners when batching is enabled, so a slow listener can't either.
+///
+/// The first output is kept regardless of the listeners, so that
+/// `GameInstance::wait_for_exit` can report the errors of a game that failed
+/// right after launching.
+///
+/// # Parameters
+/// - `stdout`: The standard output of the game.
+/// - `stderr`: The standard error of the game, read along with `stdout`.
+/// - `emitter`: An optional emitter that receives the output.
+/// - `options`: The options for forwarding and storing the output.
+/// - `log_file`: An optional file that receives every line with a timestamp.
+/// - `on_exit`: Called once the output is closed, which happens when the game
+///   exits.
+///
+/// # Returns
+/// The first output of the game, read until its exit is awaited.
+pub(crate) fn spawn_reader(
+    stdout: ChildStdout,
+    stderr: ChildStderr,
+    emitter: Option<Emitter>,
+    options: ConsoleOptions,
+    log_file: Option<PathBuf>,
+    on_exit: impl FnOnce() + Send + 'static,
+) -> Arc<EarlyOutput> {
+    let buffer = match (&emitter, options.batch_interval) {
+        (Some(emitter), Some(interval)) => {
+            let buffer = Arc::new(Mutex::new(ConsoleBuffer::default()));
+            spawn_flusher(emitter.clone(), buffer.clone(), interval);
+            Some(buffer)
+        }
+        _ => None,
+    };
+
+    let early_output = Arc::new(EarlyOutput::new());
+    let reader_output = early_output.clone();
+    tokio::spawn(async move {
+        let early_output = reader_output;
+        let mut log_file = match &log_file {
+            Some(path) => LogFile::create(path, options.log_file_max_size).await.ok(),
+            None => None,
+        };
+
+        let mut lines = pin!(stream::select(output_lines(stdout), output_lines(stderr)));
+        while let Some(line) = lines.next().await {
+            {
+                let mut output = early_output
+                    .output
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                if output.len() < EARLY_OUTPUT_LIMIT {
+                    output.push_str(&line);
+                    output.push('\n');
+                }
+            }
+
+            if let Some(file) = &mut log_file {
+                if file.write_line(&line).await.is_err() {
+                    log_file = None;
+                }
+            }
+
+            if let Some(buffer) = &buffer {
+                let mut buffer = buffer.lock().unwrap_or_else(|e| e.into_inner());
+                if options.buffer_capacity == 0 {
+                    buffer.dropped += 1;
+                    continue;
+                }
+                if buffer.lines.len() >= options.buffer_capacity {
+                    buffer.lines.pop_front();
+                    buffer.dropped += 1;
+                }
+                buffer.lines.push_back(line);
+            } else if let Some(emitter) = &emitter {
+                emitter.emit(Event::Console, line).await;
+            }
+        }
+
+        early_output.closed.send_replace(Some(Instant::now()));
+        if let Some(file) = &mut log_file {
+            let _ = file.writer.flush().await;
+        }
+        if let Some(buffer) = &buffer {
+            buffer.lock().unwrap_or_else(|e| e.into_inner()).closed = true;
+        }
+
+        on_exit();
+    });
+
+    early_output
+}
+
+/// Reads the lines of an output until it is closed, replacing the bytes that
+/// aren't valid UTF-8.
+///
+/// # Parameters
+/// - `reader`: The output to read.
+///
+/// # Returns
+/// A stream of the lines without their line endings.
+fn output_lines(reader: impl AsyncRead + Unpin) -> impl Stream<Item = String> {
+    stream::unfold(BufReader::new(reader), |mut reader| async move {
+        let mut line = Vec::new();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => None,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&line)
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string();
+                Some((line, reader))
+            }
+        }
+    })
+}
+
+/// Periodically emits the buffered lines until the reader is closed.
+///
+/// # Parameters
+/// - `emitter`: The emitter that receives the batches.
+/// - `buffer`: The buffer filled by the reader.
+/// - `interval`: The interval between batches in milliseconds.
+fn spawn_flusher(emitter: Emitter, buffer: Arc<Mutex<ConsoleBuffer>>, interval: u64) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_millis(interval.max(1)));
+        loop {
+            ticker.tick().await;
+
+            let (lines, dropped, closed) = {
+                let mut buffer = buffer.lock().unwrap_or_else(|e| e.into_inner());
+                (
+                    buffer.lines.drain(..).collect::<Vec<_>>(),
+                    std::mem::take(&mut buffer.dropped),
+                    buffer.closed,
+                )
+            };
+
+            if !lines.is_empty() || dropped > 0 {
+                emitter.emit(Event::ConsoleBatch, (lines, dropped)).await;
+            }
+            if closed {
+                break;
+            }
+        }
+    });
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{
+        process::Stdio,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio::{process::Command, sync::oneshot, time::timeout};
+
+    use super::{spawn_reader, ConsoleOptions};
+    use crate::minecraft::emitter::Emitter;
+
+    const LINES: usize = 20_000;
+
+    /// Spawns a shell printing `LINES` numbered lines to stdout as fast as
+    /// it can, and reads them with `spawn_reader`.
+    ///
+    /// # Returns
+    /// A receiver completed once the output is closed.
+    fn print_rapidly(
+        emitter: Option<Emitter>,
+        options: ConsoleOptions,
+        log_file: Option<std::path::PathBuf>,
+    ) -> (tokio::process::Child, oneshot::Receiver<()>) {
+        let mut child = Command::new("sh")
+            .args(["-c", &format!("seq -f 'line %.0f' {}", LINES)])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (closed, on_closed) = oneshot::channel();
+        spawn_reader(
+            child.stdout.take().unwrap(),
+            child.stderr.take().unwrap(),
+            emitter,
+            options,
+            log_file,
+            move || {
+                let _ = closed.send(());
+            },
+        );
+        (child, on_closed)
+    }
+
+    /// Waits for the shell to exit and its output to be closed.
+    async fn finish(mut child: tokio::process::Child, closed: oneshot::Receiver<()>) {
+        let status = timeout(Duration::from_secs(30), child.wait())
+            .await
+            .expect("the game blocked on a full pipe")
+            .unwrap();
+        assert!(status.success());
+        timeout(Duration::from_secs(30), closed)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn drains_output_without_listeners() {
+        let (child, closed) = print_rapidly(None, ConsoleOptions::default(), None);
+        finish(child, closed).await;
+    }
+
+    #[tokio::test]
+    async fn tees_every_line_to_the_log_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_file = dir.path().join("logs").join("game.log");
+        let (child, closed) =
+            print_rapidly(None, ConsoleOptions::default(), Some(log_file.clone()));
+        finish(child, closed).await;
+
+        let log = std::fs::read_to_string(&log_file).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), LINES);
+        for (index, line) in lines.iter().enumerate() {
+            let (timestamp, text) = line.split_once("] ").unwrap();
+            assert!(
+                timestamp.starts_with('[') && timestamp.len() == 13,
+                "{}",
+                line
+            );
+            assert_eq!(text, format!("line {}", index + 1));
+        }
+    }
+
+    #[tokio::test]
+    async fn rotates_the_log_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_file = dir.path().join("game.log");
+        let options = ConsoleOptions {
+            log_file_max_size: 4096,
+            ..Default::default()
+        };
+        let (child, closed) = print_rapidly(None, options, Some(log_file.clone()));
+        finish(child, closed).await;
+
+        let current = std::fs::read_to_string(&log_file).unwrap();
+        let rotated = std::fs::read_to_string(dir.path().join("game.log.1")).unwrap();
+        assert!(current.len() <= 4096);
+        assert!(rotated.len() <= 4096 && rotated.len() > 4000);
+        assert!(current.ends_with(&format!("line {}\n", LINES)));
+        // The rotated file continues right where the current one starts.
+        let last_rotated = rotated.lines().last().unwrap().split_once("] ").unwrap().1;
+        let first_current = current.lines().next().unwrap().split_once("] ").unwrap().1;
+        let number = |line: &str| line["line ".len()..].parse::<usize>().unwrap();
+        assert_eq!(number(last_rotated) + 1, number(first_current));
+    }
+
+    #[tokio::test]
+    async fn batches_drop_the_oldest_lines() {
+        let emitter = Emitter::default();
+        let received = Arc::new(Mutex::new((Vec::<String>::new(), 0u64)));
+        emitter
+            .on_console_batch({
+                let received = received.clone();
+                move |lines, dropped| {
+                    assert!(lines.len() <= 100);
+                    let mut received = received.lock().unwrap();
+                    received.0.extend_from_slice(lines);
+                    received.1 += dropped;
+                }
+            })
+            .await;
+
+        let options = ConsoleOptions {
+            batch_interval: Some(200),
+            buffer_capacity: 100,
+            ..Default::default()
+        };
+        let (child, closed) = print_rapidly(Some(emitter), options, None);
+        finish(child, closed).await;
+
+        // The last batch is emitted on the tick after the output is closed.
+        let all_received = || {
+            let (lines, dropped) = &*received.lock().unwrap();
+            lines.len() as u64 + dropped == LINES as u64
+        };
+        timeout(Duration::from_secs(30), async {
+            while !all_received() {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let (lines, dropped) = &*received.lock().unwrap();
+        assert!(*dropped > 0);
+        assert_eq!(lines.last().unwrap(), &format!("line {}", LINES));
+    }
+}
@@ -1,23 +1,495 @@
-use event_emitter_rs::EventEmitter;
-use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use serde::Serialize;
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::{mpsc, RwLock};
 
-/// A struct that wraps an `EventEmitter` for handling events asynchronously.
+use crate::json::version::meta::vanilla::VersionMetaDiff;
+
+use super::{console::EarlyExit, install::InstallReport};
+
+/// A boxed listener that receives the payload by reference.
+pub type Listener = Box<dyn Fn(&EventPayload) + Send + Sync>;
+
+/// The number of payloads a subscription buffers before dropping new ones.
+const SUBSCRIPTION_CAPACITY: usize = 1024;
+
+/// The registered listeners of every event, along with their ids.
+pub type Listeners = HashMap<Event, Vec<(ListenerId, Listener)>>;
+
+/// A struct that dispatches events to the registered listeners.
+///
+/// Payloads are passed to the listeners by reference, so emitting an event
+/// never serializes or copies its data.
 #[derive(Clone, Default)]
 pub struct Emitter {
-    pub wrap: Arc<Mutex<EventEmitter>>,
+    /// The listeners of every event, shared by the clones of the emitter.
+    pub wrap: Arc<RwLock<Listeners>>,
+    next_id: Arc<AtomicU64>,
+    verbose: Arc<AtomicBool>,
 }
 
+/// Identifies a registered listener so that it can be removed again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListenerId(u64);
+
 /// Enum representing different types of events that can be emitted.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Event {
-    /// Event triggered for multiple download progress updates.
+    /// Event triggered for multiple download progress updates, carrying a
+    /// `DownloadProgressPayload`.
     MultipleDownloadProgress,
+    /// Event triggered along with `MultipleDownloadProgress`, carrying its
+    /// former `(path, current, total, file_type)` data. It is only emitted
+    /// while it has listeners, so listeners of `MultipleDownloadProgress`
+    /// receive a single event per file.
+    ///
+    /// Listeners matching `EventPayload::MultipleDownloadProgress { .. }` can
+    /// switch to this event unchanged by matching
+    /// `EventPayload::LegacyMultipleDownloadProgress { .. }` instead, or move
+    /// to the fields of `DownloadProgressPayload`. Listeners receiving the
+    /// `(String, u64, u64, String)` tuple through `on` work with both events.
+    LegacyMultipleDownloadProgress,
     /// Event triggered for a single download progress update.
     SingleDownloadProgress,
     /// Event triggered for console output.
     Console,
+    /// Event triggered for a batch of console output.
+    ConsoleBatch,
+    /// Event triggered for a file that is already present and valid, so it
+    /// is not downloaded again.
+    FileCached,
+    /// Event triggered for a recoverable anomaly that was skipped.
+    Warning,
+    /// Event triggered when an installation starts.
+    InstallStarted,
+    /// Event triggered once an installation finishes, carrying its report.
+    InstallFinished,
+    /// Event triggered once an installation completed successfully, after
+    /// `InstallFinished`.
+    InstallComplete,
+    /// Event triggered once a loader merged itself into the version metadata,
+    /// carrying what it changed.
+    LoaderMergeComplete,
+    /// Event triggered for every failed download attempt, including the
+    /// attempts that are retried successfully.
+    DownloadError,
+    /// Event triggered when a host failed too many downloads in a row and
+    /// further downloads from it fail immediately.
+    CircuitBreakerOpen,
+    /// Event triggered when downloads from a host are attempted again after
+    /// its circuit was open.
+    CircuitBreakerReset,
+    /// Event triggered when the network is unavailable and the cached
+    /// version manifest is used instead.
+    OfflineMode,
+    /// Event triggered when a downloaded file did not match its hash and is
+    /// downloaded again.
+    HashRetrying,
+    /// Event triggered for every asset placed under its logical name by
+    /// `assets::materialize`.
+    AssetMaterializeProgress,
+    /// Event triggered by `GameInstance::wait_for_exit` when the game exits with an
+    /// error right after launching, carrying its first output and the
+    /// recognized reason.
+    EarlyExit,
+}
+
+/// Identifies the kind of a recoverable anomaly reported as a `Warning`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningCode {
+    /// A library was skipped because its rules exclude the current platform.
+    LibraryExcludedByRules,
+    /// A library was skipped because it has nothing to download.
+    LibraryWithoutArtifact,
+    /// A native library has no classifier for the current operating system.
+    MissingNativeClassifier,
+    /// A library was skipped because its name couldn't be parsed.
+    InvalidLibraryName,
+    /// A legacy asset couldn't be copied to the virtual or resources directory.
+    #[deprecated(note = "a failed legacy asset copy fails the installation instead")]
+    LegacyAssetCopyFailed,
+    /// A server asked to retry later, the request waits before retrying.
+    RateLimited,
+    /// The game runs in a working directory other than the game directory
+    /// without being told the game directory through `--gameDir`.
+    GameDirNotPassed,
+    /// Arguments depend on launcher features that are neither built in nor
+    /// set in the `LaunchOptions`, which are treated as disabled.
+    UnknownFeatures,
+    /// The priority of the game process couldn't be changed, the game runs
+    /// with the priority of the launcher.
+    ProcessPriorityNotSet,
+}
+
+impl WarningCode {
+    /// Checks whether the warning is expected during a normal installation,
+    /// such warnings are only emitted in verbose mode.
+    ///
+    /// # Returns
+    /// `true` if the warning is noisy.
+    pub fn is_noisy(&self) -> bool {
+        matches!(
+            self,
+            WarningCode::LibraryExcludedByRules | WarningCode::LibraryWithoutArtifact
+        )
+    }
+}
+
+/// A recoverable anomaly that was skipped during the installation or launch.
+#[derive(Serialize, Debug, Clone)]
+pub struct Warning {
+    pub code: WarningCode,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(code: WarningCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Progress of a batch of file downloads, along with the progress of the
+/// files with the same tag within the batch.
+#[derive(Debug, Clone)]
+pub struct DownloadProgressPayload {
+    /// The path of the downloaded file.
+    pub path: String,
+    /// The number of files of the batch downloaded so far.
+    pub current: u64,
+    /// The number of files of the batch.
+    pub total: u64,
+    /// The tag of the downloaded file, the name of its `FileType` for the
+    /// files of an installation.
+    pub tag: String,
+    /// The number of files with this tag downloaded so far.
+    pub type_current: u64,
+    /// The number of files with this tag in the batch.
+    pub type_total: u64,
+    /// The number of bytes of the batch downloaded so far.
+    pub bytes_current: u64,
+    /// The number of bytes of the batch, `None` if the size of a file is not
+    /// known up front.
+    pub bytes_total: Option<u64>,
+}
+
+/// Enum representing the data carried by an emitted event.
+#[derive(Debug, Clone)]
+pub enum EventPayload {
+    /// Progress of a single file download.
+    SingleDownloadProgress {
+        path: String,
+        current: u64,
+        total: u64,
+    },
+    /// Progress of a batch of file downloads.
+    MultipleDownloadProgress(DownloadProgressPayload),
+    /// Progress of a batch of file downloads, in the format used before
+    /// `DownloadProgressPayload`.
+    LegacyMultipleDownloadProgress {
+        path: String,
+        current: u64,
+        total: u64,
+        file_type: String,
+    },
+    /// A line printed by the game.
+    Console(String),
+    /// Lines printed by the game since the previous batch, along with the
+    /// number of lines dropped because the buffer was full.
+    ConsoleBatch { lines: Vec<String>, dropped: u64 },
+    /// A file that was served from the disk instead of being downloaded.
+    FileCached { path: String, file_type: String },
+    /// A recoverable anomaly that was skipped.
+    Warning(Warning),
+    /// The name of the version whose installation started.
+    InstallStarted { version_name: String },
+    /// The report of a finished installation.
+    InstallFinished(Box<InstallReport>),
+    /// The name of the version whose installation completed.
+    InstallComplete { version_name: String },
+    /// The changes a loader made to the version metadata.
+    LoaderMergeComplete(VersionMetaDiff),
+    /// A failed download attempt.
+    DownloadError { url: String, error: String },
+    /// A host whose circuit opened.
+    CircuitBreakerOpen { host: String },
+    /// A host whose circuit was reset.
+    CircuitBreakerReset { host: String },
+    /// The network error that caused the cached version manifest to be used.
+    OfflineMode { error: String },
+    /// A download retried after a hash mismatch, `attempt` out of `max`.
+    HashRetrying {
+        path: String,
+        attempt: u32,
+        max: u32,
+    },
+    /// Progress of placing assets under their logical names.
+    AssetMaterializeProgress {
+        path: String,
+        current: u64,
+        total: u64,
+    },
+    /// A game that stopped right after launching.
+    EarlyExit(EarlyExit),
+}
+
+/// Trait for types that can be emitted as an event payload.
+pub trait IntoPayload {
+    /// Converts the value into an `EventPayload`.
+    fn into_payload(self) -> EventPayload;
+}
+
+/// Trait for types that listeners can receive instead of the raw payload.
+pub trait FromPayload: Sized {
+    /// Extracts the value from an `EventPayload`.
+    ///
+    /// # Returns
+    /// `None` if the payload doesn't have the shape of this type.
+    fn from_payload(payload: &EventPayload) -> Option<Self>;
+}
+
+impl IntoPayload for EventPayload {
+    fn into_payload(self) -> EventPayload {
+        self
+    }
+}
+
+impl FromPayload for EventPayload {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        Some(payload.clone())
+    }
+}
+
+impl IntoPayload for String {
+    fn into_payload(self) -> EventPayload {
+        EventPayload::Console(self)
+    }
+}
+
+impl FromPayload for String {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::Console(line) => Some(line.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoPayload for (String, u64, u64) {
+    fn into_payload(self) -> EventPayload {
+        EventPayload::SingleDownloadProgress {
+            path: self.0,
+            current: self.1,
+            total: self.2,
+        }
+    }
+}
+
+impl FromPayload for (String, u64, u64) {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::SingleDownloadProgress {
+                path,
+                current,
+                total,
+            } => Some((path.clone(), *current, *total)),
+            _ => None,
+        }
+    }
+}
+
+impl IntoPayload for (String, u64, u64, String) {
+    fn into_payload(self) -> EventPayload {
+        EventPayload::LegacyMultipleDownloadProgress {
+            path: self.0,
+            current: self.1,
+            total: self.2,
+            file_type: self.3,
+        }
+    }
+}
+
+impl FromPayload for (String, u64, u64, String) {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::MultipleDownloadProgress(progress) => Some((
+                progress.path.clone(),
+                progress.current,
+                progress.total,
+                progress.tag.clone(),
+            )),
+            EventPayload::LegacyMultipleDownloadProgress {
+                path,
+                current,
+                total,
+                file_type,
+            } => Some((path.clone(), *current, *total, file_type.clone())),
+            _ => None,
+        }
+    }
+}
+
+impl FromPayload for (String, u64, u64, String, u64, u64) {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::MultipleDownloadProgress(progress) => Some((
+                progress.path.clone(),
+                progress.current,
+                progress.total,
+                progress.tag.clone(),
+                progress.type_current,
+                progress.type_total,
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl IntoPayload for DownloadProgressPayload {
+    fn into_payload(self) -> EventPayload {
+        EventPayload::MultipleDownloadProgress(self)
+    }
+}
+
+impl FromPayload for DownloadProgressPayload {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::MultipleDownloadProgress(progress) => Some(progress.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoPayload for (String, String) {
+    fn into_payload(self) -> EventPayload {
+        EventPayload::FileCached {
+            path: self.0,
+            file_type: self.1,
+        }
+    }
+}
+
+impl FromPayload for (String, String) {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::FileCached { path, file_type } => {
+                Some((path.clone(), file_type.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl IntoPayload for (Vec<String>, u64) {
+    fn into_payload(self) -> EventPayload {
+        EventPayload::ConsoleBatch {
+            lines: self.0,
+            dropped: self.1,
+        }
+    }
+}
+
+impl FromPayload for (Vec<String>, u64) {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::ConsoleBatch { lines, dropped } => Some((lines.clone(), *dropped)),
+            _ => None,
+        }
+    }
+}
+
+impl FromPayload for Vec<String> {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::ConsoleBatch { lines, .. } => Some(lines.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoPayload for Warning {
+    fn into_payload(self) -> EventPayload {
+        EventPayload::Warning(self)
+    }
+}
+
+impl FromPayload for Warning {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::Warning(warning) => Some(warning.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoPayload for InstallReport {
+    fn into_payload(self) -> EventPayload {
+        EventPayload::InstallFinished(Box::new(self))
+    }
+}
+
+impl FromPayload for InstallReport {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::InstallFinished(report) => Some(report.as_ref().clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoPayload for VersionMetaDiff {
+    fn into_payload(self) -> EventPayload {
+        EventPayload::LoaderMergeComplete(self)
+    }
+}
+
+impl FromPayload for VersionMetaDiff {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::LoaderMergeComplete(diff) => Some(diff.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoPayload for EarlyExit {
+    fn into_payload(self) -> EventPayload {
+        EventPayload::EarlyExit(self)
+    }
+}
+
+impl FromPayload for EarlyExit {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::EarlyExit(early_exit) => Some(early_exit.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromPayload for (u64, u64) {
+    fn from_payload(payload: &EventPayload) -> Option<Self> {
+        match payload {
+            EventPayload::SingleDownloadProgress { current, total, .. }
+            | EventPayload::LegacyMultipleDownloadProgress { current, total, .. }
+            | EventPayload::AssetMaterializeProgress { current, total, .. } => {
+                Some((*current, *total))
+            }
+            EventPayload::MultipleDownloadProgress(progress) => {
+                Some((progress.current, progress.total))
+            }
+            _ => None,
+        }
+    }
 }
 
 /// Trait for emitting events.
@@ -28,18 +500,27 @@ pub trait Emit {
     /// - `event`: The event to emit.
     /// - `data`: The data associated with the event.
     #[allow(async_fn_in_trait)]
-    async fn emit<T: Serialize>(&self, event: Event, data: T);
+    async fn emit<T: IntoPayload>(&self, event: Event, data: T);
+
+    /// Emits a warning, see [`Emitter::warn`].
+    ///
+    /// # Parameters
+    /// - `warning`: The warning to emit.
+    #[allow(async_fn_in_trait)]
+    async fn warn(&self, warning: Warning);
 }
 
 /// Implementation of the `Emit` trait for an optional reference to `Emitter`.
 impl Emit for Option<&Emitter> {
-    async fn emit<T: Serialize>(&self, event: Event, data: T) {
+    async fn emit<T: IntoPayload>(&self, event: Event, data: T) {
         if let Some(emitter) = self {
-            emitter
-                .wrap
-                .lock()
-                .await
-                .emit(&format!("{:?}", event), data);
+            emitter.emit(event, data).await;
+        }
+    }
+
+    async fn warn(&self, warning: Warning) {
+        if let Some(emitter) = self {
+            emitter.warn(warning).await;
         }
     }
 }
@@ -50,12 +531,49 @@ impl Emitter {
     /// # Parameters
     /// - `event`: The event to emit.
     /// - `data`: The data associated with the event.
-    pub async fn emit<T: Serialize>(&self, event: Event, data: T) {
-        self.wrap.lock().await.emit(&format!("{:?}", event), data);
+    pub async fn emit<T: IntoPayload>(&self, event: Event, data: T) {
+        let listeners = self.wrap.read().await;
+        if let Some(listeners) = listeners.get(&event) {
+            let payload = data.into_payload();
+            listeners
+                .iter()
+                .for_each(|(_, listener)| listener(&payload));
+        }
+    }
+
+    /// Emits a warning as an `Event::Warning`.
+    ///
+    /// Noisy warnings are dropped unless the emitter is verbose.
+    ///
+    /// # Parameters
+    /// - `warning`: The warning to emit.
+    pub async fn warn(&self, warning: Warning) {
+        if warning.code.is_noisy() && !self.is_verbose() {
+            return;
+        }
+        self.emit(Event::Warning, warning).await;
+    }
+
+    /// Sets whether noisy warnings are emitted.
+    ///
+    /// # Parameters
+    /// - `verbose`: Whether noisy warnings should be emitted.
+    pub fn set_verbose(&self, verbose: bool) {
+        self.verbose.store(verbose, Ordering::Relaxed);
+    }
+
+    /// Checks whether noisy warnings are emitted.
+    ///
+    /// # Returns
+    /// `true` if the emitter is verbose.
+    pub fn is_verbose(&self) -> bool {
+        self.verbose.load(Ordering::Relaxed)
     }
 
     /// Registers a listener for a specific event.
     ///
+    /// The listener is skipped for payloads that can't be converted into `T`.
+    ///
     /// # Parameters
     /// - `event`: The event to listen for.
     /// - `listener`: A function that will be called when the event is emitted.
@@ -63,11 +581,623 @@ impl Emitter {
     /// # Type Parameters
     /// - `F`: The type of the listener function.
     /// - `T`: The type of data that the listener will receive.
-    pub async fn on<F, T>(&self, event: Event, listener: F)
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on<F, T>(&self, event: Event, listener: F) -> ListenerId
     where
         F: Fn(T) + Send + Sync + 'static,
-        T: for<'de> Deserialize<'de> + Serialize,
+        T: FromPayload,
+    {
+        self.on_payload(event, move |payload| {
+            if let Some(data) = T::from_payload(payload) {
+                listener(data);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener that receives the payload by reference.
+    ///
+    /// # Parameters
+    /// - `event`: The event to listen for.
+    /// - `listener`: A function that will be called when the event is emitted.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_payload<F>(&self, event: Event, listener: F) -> ListenerId
+    where
+        F: Fn(&EventPayload) + Send + Sync + 'static,
+    {
+        let id = ListenerId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.wrap
+            .write()
+            .await
+            .entry(event)
+            .or_default()
+            .push((id, Box::new(listener)));
+        id
+    }
+
+    /// Subscribes to an event through a channel instead of a callback.
+    ///
+    /// The channel buffers up to 1024 payloads, payloads emitted while it is
+    /// full are dropped. The subscription is removed once the receiver is
+    /// dropped.
+    ///
+    /// # Parameters
+    /// - `event`: The event to subscribe to.
+    ///
+    /// # Returns
+    /// The receiving half of the channel.
+    pub async fn subscribe(&self, event: Event) -> mpsc::Receiver<EventPayload> {
+        let (sender, receiver) = mpsc::channel(SUBSCRIPTION_CAPACITY);
+        let closed = sender.clone();
+        let id = self
+            .on_payload(event, move |payload| {
+                let _ = sender.try_send(payload.clone());
+            })
+            .await;
+
+        let emitter = self.clone();
+        tokio::spawn(async move {
+            closed.closed().await;
+            emitter.off(id).await;
+        });
+
+        receiver
+    }
+
+    /// Removes a single listener.
+    ///
+    /// # Parameters
+    /// - `id`: The id returned when the listener was registered.
+    ///
+    /// # Returns
+    /// `true` if the listener was registered.
+    pub async fn off(&self, id: ListenerId) -> bool {
+        self.wrap.write().await.values_mut().any(|listeners| {
+            let len = listeners.len();
+            listeners.retain(|(listener_id, _)| *listener_id != id);
+            listeners.len() != len
+        })
+    }
+
+    /// Registers a listener for `Event::SingleDownloadProgress`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the path, the downloaded bytes and
+    ///   the total bytes.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_single_download_progress<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str, u64, u64) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::SingleDownloadProgress, move |payload| {
+            if let EventPayload::SingleDownloadProgress {
+                path,
+                current,
+                total,
+            } = payload
+            {
+                listener(path, *current, *total);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::MultipleDownloadProgress`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the progress.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_multiple_download_progress<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&DownloadProgressPayload) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::MultipleDownloadProgress, move |payload| {
+            if let EventPayload::MultipleDownloadProgress(progress) = payload {
+                listener(progress);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::MultipleDownloadProgress` receiving
+    /// the progress of each tag, e.g. to show the assets and the libraries
+    /// separately.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the tag, the downloaded files with
+    ///   that tag and the total files with that tag.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_file_type_download_progress<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str, u64, u64) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::MultipleDownloadProgress, move |payload| {
+            if let EventPayload::MultipleDownloadProgress(progress) = payload {
+                listener(&progress.tag, progress.type_current, progress.type_total);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::Console`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the printed line.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_console<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::Console, move |payload| {
+            if let EventPayload::Console(line) = payload {
+                listener(line);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::ConsoleBatch`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the printed lines and the number of
+    ///   dropped lines.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_console_batch<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&[String], u64) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::ConsoleBatch, move |payload| {
+            if let EventPayload::ConsoleBatch { lines, dropped } = payload {
+                listener(lines, *dropped);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::FileCached`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the path and the file type.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_file_cached<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::FileCached, move |payload| {
+            if let EventPayload::FileCached { path, file_type } = payload {
+                listener(path, file_type);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::Warning`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the warning.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_warning<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&Warning) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::Warning, move |payload| {
+            if let EventPayload::Warning(warning) = payload {
+                listener(warning);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::InstallFinished`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the installation report.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_install_finished<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&InstallReport) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::InstallFinished, move |payload| {
+            if let EventPayload::InstallFinished(report) = payload {
+                listener(report);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::InstallStarted`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the name of the version.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_install_started<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::InstallStarted, move |payload| {
+            if let EventPayload::InstallStarted { version_name } = payload {
+                listener(version_name);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::InstallComplete`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the name of the version.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_install_complete<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::InstallComplete, move |payload| {
+            if let EventPayload::InstallComplete { version_name } = payload {
+                listener(version_name);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::LoaderMergeComplete`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the changes the loader made.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_loader_merge_complete<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&VersionMetaDiff) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::LoaderMergeComplete, move |payload| {
+            if let EventPayload::LoaderMergeComplete(diff) = payload {
+                listener(diff);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::DownloadError`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the URL and the error message.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_download_error<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::DownloadError, move |payload| {
+            if let EventPayload::DownloadError { url, error } = payload {
+                listener(url, error);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::CircuitBreakerOpen`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the host.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_circuit_breaker_open<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::CircuitBreakerOpen, move |payload| {
+            if let EventPayload::CircuitBreakerOpen { host } = payload {
+                listener(host);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::CircuitBreakerReset`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the host.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_circuit_breaker_reset<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::CircuitBreakerReset, move |payload| {
+            if let EventPayload::CircuitBreakerReset { host } = payload {
+                listener(host);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::OfflineMode`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the network error.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_offline_mode<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::OfflineMode, move |payload| {
+            if let EventPayload::OfflineMode { error } = payload {
+                listener(error);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::HashRetrying`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the path, the attempt and the
+    ///   maximum number of attempts.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_hash_retrying<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str, u32, u32) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::HashRetrying, move |payload| {
+            if let EventPayload::HashRetrying { path, attempt, max } = payload {
+                listener(path, *attempt, *max);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::AssetMaterializeProgress`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the logical name of the asset, the
+    ///   number of assets placed so far and the total number of assets.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_asset_materialize_progress<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&str, u64, u64) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::AssetMaterializeProgress, move |payload| {
+            if let EventPayload::AssetMaterializeProgress {
+                path,
+                current,
+                total,
+            } = payload
+            {
+                listener(path, *current, *total);
+            }
+        })
+        .await
+    }
+
+    /// Registers a listener for `Event::EarlyExit`.
+    ///
+    /// # Parameters
+    /// - `listener`: A function receiving the reason of the failure and the
+    ///   first output of the game.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on_early_exit<F>(&self, listener: F) -> ListenerId
+    where
+        F: Fn(&EarlyExit) + Send + Sync + 'static,
+    {
+        self.on_payload(Event::EarlyExit, move |payload| {
+            if let EventPayload::EarlyExit(early_exit) = payload {
+                listener(early_exit);
+            }
+        })
+        .await
+    }
+
+    /// Checks whether any listener is registered for an event.
+    ///
+    /// # Parameters
+    /// - `event`: The event to check.
+    ///
+    /// # Returns
+    /// Whether emitting the event reaches a listener.
+    pub(crate) async fn has_listeners(&self, event: Event) -> bool {
+        self.wrap
+            .read()
+            .await
+            .get(&event)
+            .is_some_and(|listeners| !listeners.is_empty())
+    }
+
+    /// Removes every listener registered for a specific event.
+    ///
+    /// # Parameters
+    /// - `event`: The event whose listeners should be removed.
+    ///
+    /// # Returns
+    /// The number of removed listeners.
+    pub async fn clear_listeners(&self, event: Event) -> usize {
+        self.wrap
+            .write()
+            .await
+            .remove(&event)
+            .map_or(0, |listeners| listeners.len())
+    }
+
+    /// Removes every listener of every event.
+    ///
+    /// # Returns
+    /// The number of removed listeners.
+    pub async fn clear_all_listeners(&self) -> usize {
+        self.wrap
+            .write()
+            .await
+            .drain()
+            .map(|(_, listeners)| listeners.len())
+            .sum()
+    }
+}
+
+/// An enum of events emitted through a `TypedEmitter`, usually derived with
+/// `#[derive(EventEnum)]` from the `macros` feature.
+pub trait EventEnum: Send + Sync + 'static {
+    /// Gets the `TypeId` of the marker type of the variant, which keys the
+    /// listeners of the variant.
+    fn variant(&self) -> TypeId;
+}
+
+/// The marker type of a variant of an `EventEnum`, which names the variant
+/// when registering a listener.
+pub trait EventVariant: 'static {
+    /// The enum the variant belongs to.
+    type Event: EventEnum;
+    /// The data the variant carries.
+    type Payload;
+
+    /// Extracts the data of the variant from an event.
+    ///
+    /// # Returns
+    /// `None` if the event is another variant.
+    fn payload(event: &Self::Event) -> Option<Self::Payload>;
+}
+
+/// A boxed listener of a `TypedEmitter`.
+type TypedListener<E> = Box<dyn Fn(&E) + Send + Sync>;
+
+/// The registered listeners of every variant, keyed by their marker type.
+type TypedListeners<E> = HashMap<TypeId, Vec<(ListenerId, TypedListener<E>)>>;
+
+/// An emitter of the events of a user-defined `EventEnum`.
+///
+/// Unlike `Emitter`, the payload type of a listener is taken from the variant
+/// it listens to, so listening with the wrong type fails to compile.
+///
+/// ```ignore
+/// #[derive(EventEnum)]
+/// enum AppEvent {
+///     Started(String),
+///     Progress(u64, u64),
+/// }
+///
+/// let emitter = TypedEmitter::new();
+/// emitter
+///     .on(PhantomData::<AppEventProgress>, |(current, total)| {
+///         println!("{}/{}", current, total);
+///     })
+///     .await;
+/// emitter.emit(AppEvent::Progress(1, 10)).await;
+/// ```
+pub struct TypedEmitter<E> {
+    listeners: Arc<RwLock<TypedListeners<E>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl<E> Clone for TypedEmitter<E> {
+    fn clone(&self) -> Self {
+        Self {
+            listeners: Arc::clone(&self.listeners),
+            next_id: Arc::clone(&self.next_id),
+        }
+    }
+}
+
+impl<E: EventEnum> Default for TypedEmitter<E> {
+    fn default() -> Self {
+        Self {
+            listeners: Arc::default(),
+            next_id: Arc::default(),
+        }
+    }
+}
+
+impl<E: EventEnum> TypedEmitter<E> {
+    /// Creates an emitter without any listener.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emits an event to the listeners of its variant.
+    ///
+    /// # Parameters
+    /// - `event`: The event to emit.
+    pub async fn emit(&self, event: E) {
+        let listeners = self.listeners.read().await;
+        if let Some(listeners) = listeners.get(&event.variant()) {
+            listeners.iter().for_each(|(_, listener)| listener(&event));
+        }
+    }
+
+    /// Registers a listener for a variant.
+    ///
+    /// # Parameters
+    /// - `_variant`: The marker type of the variant.
+    /// - `listener`: A function receiving the data of the variant.
+    ///
+    /// # Returns
+    /// The id of the listener, which can be passed to `off`.
+    pub async fn on<V, F>(&self, _variant: PhantomData<V>, listener: F) -> ListenerId
+    where
+        V: EventVariant<Event = E>,
+        F: Fn(V::Payload) + Send + Sync + 'static,
     {
-        self.wrap.lock().await.on(&format!("{:?}", event), listener);
+        let id = ListenerId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.listeners
+            .write()
+            .await
+            .entry(TypeId::of::<V>())
+            .or_default()
+            .push((
+                id,
+                Box::new(move |event| {
+                    if let Some(payload) = V::payload(event) {
+                        listener(payload);
+                    }
+                }),
+            ));
+        id
+    }
+
+    /// Removes a single listener.
+    ///
+    /// # Parameters
+    /// - `id`: The id returned when the listener was registered.
+    ///
+    /// # Returns
+    /// `true` if the listener was registered.
+    pub async fn off(&self, id: ListenerId) -> bool {
+        self.listeners.write().await.values_mut().any(|listeners| {
+            let len = listeners.len();
+            listeners.retain(|(listener_id, _)| *listener_id != id);
+            listeners.len() != len
+        })
     }
 }
@@ -0,0 +1,518 @@
+/// This module reuses the files of an existing installation of the official
+/// launcher, so `install` only has to download what is missing.
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::Error,
+    json::version::{
+        asset_index::AssetIndex,
+        meta::{
+            custom,
+            vanilla::{self, LibraryDownloads, VersionMeta},
+        },
+        migration::MigrationRegistry,
+    },
+    util::{hash::calculate_sha1, json::read_json},
+};
+
+use super::{
+    assets::object_prefix, config::Config, inheritance::version_json_path, loader::Loader,
+    parse::parse_lib_path,
+};
+
+/// Options for importing an existing `.minecraft` directory.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImportOptions {
+    /// Whether files are hardlinked instead of copied. Files on another
+    /// filesystem are always copied.
+    pub hardlink: bool,
+    pub include_assets: bool,
+    pub include_libraries: bool,
+    /// The versions directory of the source, for launchers that keep it
+    /// outside of the `.minecraft` directory. `<source_dir>/versions` by
+    /// default.
+    #[serde(default)]
+    pub versions_dir: Option<PathBuf>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            hardlink: true,
+            include_assets: true,
+            include_libraries: true,
+            versions_dir: None,
+        }
+    }
+}
+
+/// Represents the outcome of an import.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct ImportReport {
+    /// Files linked or copied from the source directory.
+    pub imported: usize,
+    /// Files that were already valid in the game directory.
+    pub already_present: usize,
+    /// Files missing or invalid in the source directory, which still have
+    /// to be downloaded.
+    pub missing: usize,
+}
+
+impl ImportReport {
+    /// Returns the number of files that don't have to be downloaded.
+    ///
+    /// # Returns
+    /// The number of satisfied files.
+    pub fn satisfied(&self) -> usize {
+        self.imported + self.already_present
+    }
+}
+
+/// A file expected by the installation of the configured version.
+struct ExpectedFile {
+    source: PathBuf,
+    target: PathBuf,
+    sha1: String,
+}
+
+/// The result of importing a single file.
+enum Outcome {
+    Imported,
+    AlreadyPresent,
+    Missing,
+}
+
+/// Imports the files of the configured version from an existing `.minecraft`
+/// directory of the official launcher, including the libraries of the
+/// configured loader when the official launcher has it installed.
+///
+/// Only files whose SHA-1 matches the one expected by the version are
+/// imported, the rest is left to `install`.
+///
+/// # Parameters
+/// - `source_dir`: The `.minecraft` directory to import from.
+/// - `config`: The configuration of the instance to import into.
+/// - `options`: The options of the import.
+///
+/// # Returns
+/// A result containing the report of the import.
+pub async fn from_dot_minecraft<T: Loader>(
+    source_dir: &Path,
+    config: &Config<T>,
+    options: &ImportOptions,
+) -> crate::Result<ImportReport> {
+    let version = &config.version;
+    let versions_path = options
+        .versions_dir
+        .clone()
+        .unwrap_or_else(|| source_dir.join("versions"));
+    let meta_path = version_json_path(&versions_path, version);
+    if !meta_path.is_file() {
+        return Err(Error::NotFound(format!(
+            "Version {} in {}",
+            version,
+            source_dir.display()
+        )));
+    }
+    let mut meta: Value = read_json(&meta_path).await?;
+    MigrationRegistry::default().migrate(&mut meta)?;
+    let meta: VersionMeta = serde_json::from_value(meta)?;
+
+    let mut files = vec![ExpectedFile {
+        source: versions_path.join(version).join(format!("{}.jar", version)),
+        target: config.get_version_jar_path(),
+        sha1: meta.downloads.client.sha1.clone(),
+    }];
+
+    if options.include_libraries {
+        let libraries_path = config.get_libraries_path();
+        let mut library_file = |path: &str, sha1: &str| {
+            files.push(ExpectedFile {
+                source: source_dir.join("libraries").join(path),
+                target: libraries_path.join(path),
+                sha1: sha1.to_string(),
+            })
+        };
+        for downloads in meta
+            .libraries
+            .iter()
+            .filter_map(|lib| lib.downloads.as_ref())
+        {
+            for file in download_files(downloads) {
+                if let Some(path) = &file.path {
+                    library_file(path, &file.sha1);
+                }
+            }
+        }
+
+        // The official launcher names loader versions differently, e.g.
+        // `fabric-loader-0.16.9-1.21.4`, so it is found through its parent.
+        if let Some(loader) = &config.loader {
+            let loader_json = find_loader_version(
+                &versions_path,
+                version,
+                &config.get_version_name(),
+                &loader.get_version(),
+            )
+            .await?;
+            for library in loader_json.iter().flat_map(|json| &json.libraries) {
+                match &library.downloads {
+                    Some(downloads) => {
+                        for file in download_files(downloads) {
+                            if let Some(path) = &file.path {
+                                library_file(path, &file.sha1);
+                            }
+                        }
+                    }
+                    // Fabric-like loaders only list the coordinates.
+                    None => {
+                        if let (Ok(path), Some(sha1)) =
+                            (parse_lib_path(&library.name), &library.sha1)
+                        {
+                            library_file(&path, sha1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if options.include_assets {
+        let index_name = format!("{}.json", meta.asset_index.id);
+        let index_path = source_dir.join("assets").join("indexes").join(&index_name);
+        files.push(ExpectedFile {
+            source: index_path.clone(),
+            target: config.get_indexes_path().join(&index_name),
+            sha1: meta.asset_index.sha1.clone(),
+        });
+
+        if calculate_sha1(&index_path).is_ok_and(|sha1| sha1 == meta.asset_index.sha1) {
+            let asset_index: AssetIndex = read_json(&index_path).await?;
+            let source_objects_path = source_dir.join("assets").join("objects");
+            let objects_path = config.get_assets_path().join("objects");
+            for object in asset_index.objects.values() {
+                let hash = &object.hash;
+                let prefix = object_prefix(hash)?;
+                files.push(ExpectedFile {
+                    source: source_objects_path.join(prefix).join(hash),
+                    target: objects_path.join(prefix).join(hash),
+                    sha1: hash.clone(),
+                });
+            }
+        }
+    }
+
+    let outcomes = files
+        .par_iter()
+        .map(|file| import_file(file, options.hardlink))
+        .collect::<crate::Result<Vec<_>>>()?;
+
+    Ok(outcomes
+        .into_iter()
+        .fold(ImportReport::default(), |mut report, outcome| {
+            match outcome {
+                Outcome::Imported => report.imported += 1,
+                Outcome::AlreadyPresent => report.already_present += 1,
+                Outcome::Missing => report.missing += 1,
+            }
+            report
+        }))
+}
+
+/// The part of a loader version JSON needed to import its libraries.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LoaderVersionJson {
+    inherits_from: Option<String>,
+    #[serde(default)]
+    libraries: Vec<custom::Library>,
+}
+
+/// Finds the loader version inheriting from a Minecraft version in the
+/// versions directory of the source.
+///
+/// # Parameters
+/// - `versions_path`: The versions directory of the source.
+/// - `version`: The Minecraft version the loader inherits from.
+/// - `version_name`: The name lyceris gives the loader version.
+/// - `loader_version`: The version of the loader, which the official launcher
+///   puts in the name of the loader version.
+///
+/// # Returns
+/// A result containing the loader version JSON, or `None` if it is missing.
+async fn find_loader_version(
+    versions_path: &Path,
+    version: &str,
+    version_name: &str,
+    loader_version: &str,
+) -> crate::Result<Option<LoaderVersionJson>> {
+    let Ok(mut entries) = tokio::fs::read_dir(versions_path).await else {
+        return Ok(None);
+    };
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name != version_name && !name.contains(loader_version) {
+            continue;
+        }
+        let Ok(json) =
+            read_json::<LoaderVersionJson>(&version_json_path(versions_path, &name)).await
+        else {
+            continue;
+        };
+        if json.inherits_from.as_deref() == Some(version) {
+            return Ok(Some(json));
+        }
+    }
+    Ok(None)
+}
+
+/// Lists the artifact and the natives of a library.
+fn download_files(downloads: &LibraryDownloads) -> impl Iterator<Item = &vanilla::File> {
+    let classifiers = downloads.classifiers.iter().flat_map(|classifiers| {
+        [
+            &classifiers.natives_linux,
+            &classifiers.natives_osx,
+            &classifiers.natives_macos,
+            &classifiers.natives_windows,
+        ]
+    });
+    std::iter::once(&downloads.artifact)
+        .chain(classifiers)
+        .flatten()
+}
+
+/// Imports a single file if the source is valid and the target isn't.
+///
+/// # Parameters
+/// - `file`: The file to import.
+/// - `hardlink`: Whether to hardlink the file instead of copying it.
+///
+/// # Returns
+/// A result containing the outcome of the import.
+fn import_file(file: &ExpectedFile, hardlink: bool) -> crate::Result<Outcome> {
+    let is_valid = |path: &Path| {
+        path.is_file()
+            && (file.sha1.is_empty() || calculate_sha1(path).is_ok_and(|sha1| sha1 == file.sha1))
+    };
+
+    if is_valid(&file.target) {
+        return Ok(Outcome::AlreadyPresent);
+    }
+    if !is_valid(&file.source) {
+        return Ok(Outcome::Missing);
+    }
+
+    if let Some(parent) = file.target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if file.target.exists() {
+        fs::remove_file(&file.target)?;
+    }
+    if !hardlink || fs::hard_link(&file.source, &file.target).is_err() {
+        fs::copy(&file.source, &file.target)?;
+    }
+
+    Ok(Outcome::Imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        minecraft::{config::ConfigBuilder, loader::fabric::Fabric},
+        test_util::{config, version_json, write_asset_index, write_version},
+    };
+
+    const OPTIONS: ImportOptions = ImportOptions {
+        hardlink: false,
+        include_assets: false,
+        include_libraries: false,
+        versions_dir: None,
+    };
+
+    #[tokio::test]
+    async fn imports_from_the_versions_directory_of_the_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = config(&dir.path().join(".minecraft"), "1.8.9");
+        write_version(&source, "1.8.9", &version_json("1.8.9")).await;
+        fs::write(source.get_version_jar_path(), "client").unwrap();
+
+        let mut target = config(&dir.path().join("game"), "1.8.9");
+        target.versions_dir = Some(dir.path().join("versions"));
+
+        let report = from_dot_minecraft(&source.get_game_dir(), &target, &OPTIONS)
+            .await
+            .unwrap();
+        assert_eq!(report.imported, 1);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("versions/1.8.9/1.8.9.jar")).unwrap(),
+            "client"
+        );
+
+        let report = from_dot_minecraft(&source.get_game_dir(), &target, &OPTIONS)
+            .await
+            .unwrap();
+        assert_eq!(report.already_present, 1);
+    }
+
+    #[tokio::test]
+    async fn source_versions_directory_can_be_overridden() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut source = config(&dir.path().join(".minecraft"), "1.8.9");
+        source.versions_dir = Some(dir.path().join("launcher-versions"));
+        write_version(&source, "1.8.9", &version_json("1.8.9")).await;
+        fs::write(source.get_version_jar_path(), "client").unwrap();
+        let target = config(&dir.path().join("game"), "1.8.9");
+
+        assert!(matches!(
+            from_dot_minecraft(&source.get_game_dir(), &target, &OPTIONS).await,
+            Err(Error::NotFound(_))
+        ));
+
+        let options = ImportOptions {
+            versions_dir: source.versions_dir.clone(),
+            ..OPTIONS
+        };
+        let report = from_dot_minecraft(&source.get_game_dir(), &target, &options)
+            .await
+            .unwrap();
+        assert_eq!(report.imported, 1);
+        assert!(target.get_version_jar_path().is_file());
+    }
+
+    /// Writes a file of the source and returns its SHA-1.
+    fn source_file(path: &Path, content: &str) -> String {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+        calculate_sha1(path).unwrap()
+    }
+
+    #[tokio::test]
+    async fn imports_the_libraries_of_the_loader_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = config(&dir.path().join(".minecraft"), "1.21.4");
+        let libraries = source.get_libraries_path();
+
+        let vanilla_path = "com/mojang/brigadier/1.3.10/brigadier-1.3.10.jar";
+        let vanilla_sha1 = source_file(&libraries.join(vanilla_path), "brigadier");
+        let mut vanilla = version_json("1.21.4");
+        vanilla["libraries"] = json!([{
+            "name": "com.mojang:brigadier:1.3.10",
+            "downloads": {
+                "artifact": { "path": vanilla_path, "sha1": vanilla_sha1, "size": 9, "url": "" }
+            }
+        }]);
+        write_version(&source, "1.21.4", &vanilla).await;
+
+        let loader_path = "net/fabricmc/fabric-loader/0.16.9/fabric-loader-0.16.9.jar";
+        let loader_sha1 = source_file(&libraries.join(loader_path), "loader");
+        let unverified_path = "org/ow2/asm/asm/9.7.1/asm-9.7.1.jar";
+        source_file(&libraries.join(unverified_path), "asm");
+        let fabric = json!({
+            "id": "fabric-loader-0.16.9-1.21.4",
+            "inheritsFrom": "1.21.4",
+            "libraries": [
+                {
+                    "name": "net.fabricmc:fabric-loader:0.16.9",
+                    "url": "https://maven.fabricmc.net/",
+                    "sha1": loader_sha1
+                },
+                { "name": "org.ow2.asm:asm:9.7.1", "url": "https://maven.fabricmc.net/" }
+            ]
+        });
+        write_version(&source, "fabric-loader-0.16.9-1.21.4", &fabric).await;
+        // Another loader version of the same Minecraft version is ignored.
+        let other = json!({
+            "id": "fabric-loader-0.16.8-1.21.4",
+            "inheritsFrom": "1.21.4",
+            "libraries": [{ "name": "net.fabricmc:fabric-loader:0.16.8", "sha1": loader_sha1 }]
+        });
+        write_version(&source, "fabric-loader-0.16.8-1.21.4", &other).await;
+
+        let target = ConfigBuilder::offline(
+            dir.path().join("game"),
+            "1.21.4".to_string(),
+            "Player".to_string(),
+        )
+        .loader(Fabric("0.16.9".to_string()).into())
+        .build();
+        let options = ImportOptions {
+            include_libraries: true,
+            ..OPTIONS
+        };
+        let report = from_dot_minecraft(&source.get_game_dir(), &target, &options)
+            .await
+            .unwrap();
+
+        // The client jar of the vanilla version is missing.
+        assert_eq!((report.imported, report.missing), (2, 1));
+        let target_libraries = target.get_libraries_path();
+        assert!(target_libraries.join(vanilla_path).is_file());
+        assert!(target_libraries.join(loader_path).is_file());
+        assert!(!target_libraries.join(unverified_path).exists());
+        assert!(!target_libraries
+            .join("net/fabricmc/fabric-loader/0.16.8")
+            .exists());
+    }
+
+    #[tokio::test]
+    async fn imports_asset_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = config(&dir.path().join(".minecraft"), "1.21.4");
+        let target = config(&dir.path().join("game"), "1.21.4");
+        let options = ImportOptions {
+            include_assets: true,
+            ..OPTIONS
+        };
+
+        let hash = "f8995ba5891b07e328c60d6bd6c10159878c5a13";
+        source_file(
+            &source.get_assets_path().join("objects/f8").join(hash),
+            "icon",
+        );
+        write_asset_index(
+            &source,
+            "1.21.4",
+            json!({ "icons/icon.png": { "hash": hash, "size": 4 } }),
+        )
+        .await;
+        let index_sha1 = calculate_sha1(source.get_indexes_path().join("1.21.4.json")).unwrap();
+        let mut vanilla = version_json("1.21.4");
+        vanilla["assetIndex"]["sha1"] = json!(index_sha1);
+        write_version(&source, "1.21.4", &vanilla).await;
+
+        let report = from_dot_minecraft(&source.get_game_dir(), &target, &options)
+            .await
+            .unwrap();
+        // The index and the object, the client jar is missing.
+        assert_eq!((report.imported, report.missing), (2, 1));
+        assert_eq!(
+            fs::read_to_string(target.get_assets_path().join("objects/f8").join(hash)).unwrap(),
+            "icon"
+        );
+
+        // A hash that can't name an object fails instead of panicking.
+        write_asset_index(
+            &source,
+            "1.21.4",
+            json!({ "icons/icon.png": { "hash": "f", "size": 4 } }),
+        )
+        .await;
+        let index_sha1 = calculate_sha1(source.get_indexes_path().join("1.21.4.json")).unwrap();
+        vanilla["assetIndex"]["sha1"] = json!(index_sha1);
+        write_version(&source, "1.21.4", &vanilla).await;
+        assert!(matches!(
+            from_dot_minecraft(&source.get_game_dir(), &target, &options).await,
+            Err(Error::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,319 @@
+/// This module resolves version JSONs that inherit from another version
+/// through the `inheritsFrom` field.
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    error::Error,
+    json::version::{meta::vanilla::VersionMeta, migration::MigrationRegistry},
+    util::json::{read_json, read_json_streaming, write_json},
+};
+
+use super::{config::Config, loader::Loader, meta};
+
+/// Gets the path to the JSON of a version inside the versions directory.
+///
+/// # Parameters
+/// - `versions_path`: The path to the versions directory.
+/// - `id`: The id of the version.
+///
+/// # Returns
+/// The path to the version JSON.
+pub fn version_json_path(versions_path: &Path, id: &str) -> PathBuf {
+    versions_path.join(id).join(format!("{}.json", id))
+}
+
+/// The part of a version JSON naming the version it inherits from, which
+/// is read without the rest of the JSON.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Parent {
+    inherits_from: Option<String>,
+}
+
+/// Finds the first version of the `inheritsFrom` chain that is missing from
+/// the versions directory.
+///
+/// # Parameters
+/// - `json_path`: The path to the version JSON to start from.
+/// - `versions_path`: The path to the versions directory.
+///
+/// # Returns
+/// A result containing the id of the missing parent, or `None` if the whole
+/// chain is present.
+pub async fn missing_parent(
+    json_path: &Path,
+    versions_path: &Path,
+) -> crate::Result<Option<String>> {
+    let mut parent = read_json_streaming::<Parent>(json_path)
+        .await?
+        .inherits_from;
+    let mut seen = HashSet::new();
+
+    while let Some(id) = parent {
+        if !seen.insert(id.clone()) {
+            return Err(Error::Parse(format!("Cyclic inheritsFrom chain at {}", id)));
+        }
+        let parent_path = version_json_path(versions_path, &id);
+        if !parent_path.is_file() {
+            return Ok(Some(id));
+        }
+        parent = read_json_streaming::<Parent>(&parent_path)
+            .await?
+            .inherits_from;
+    }
+
+    Ok(None)
+}
+
+/// Reads a version JSON and merges every version it inherits from into it.
+///
+/// Values of the child take precedence over the ones of its parent, except
+/// for the libraries and the arguments which are combined. Libraries of the
+/// child replace the parent libraries with the same group and artifact.
+///
+/// # Parameters
+/// - `json_path`: The path to the version JSON.
+/// - `versions_path`: The path to the versions directory containing the parents.
+///
+/// # Returns
+/// A result containing the resolved version metadata.
+pub async fn read_version_meta(
+    json_path: &Path,
+    versions_path: &Path,
+) -> crate::Result<VersionMeta> {
+    let json: Value = read_json(json_path).await?;
+    resolve(json, versions_path, false, None).await
+}
+
+/// Reads a version JSON and merges every version it inherits from into it,
+/// fetching the parents missing from the versions directory first.
+///
+/// Loaders may write a partial version JSON that only holds what differs
+/// from its vanilla parent, this resolves it the way the vanilla launcher
+/// does. Fetched parents are written to the versions directory of the
+/// configuration.
+///
+/// # Parameters
+/// - `base_path`: The path to the version JSON.
+/// - `config`: The configuration whose versions directory holds the parents
+///   and whose client fetches the missing ones.
+///
+/// # Returns
+/// A result containing the resolved version metadata.
+pub async fn read_version_meta_with_inheritance<T: Loader>(
+    base_path: &Path,
+    config: &Config<T>,
+) -> crate::Result<VersionMeta> {
+    let json: Value = read_json(base_path).await?;
+    resolve(
+        json,
+        &config.get_versions_path(),
+        true,
+        config.client.as_ref(),
+    )
+    .await
+}
+
+/// Merges every version a version JSON inherits from into it, migrating
+/// each of them to the current schema first.
+///
+/// # Parameters
+/// - `json`: The version JSON.
+/// - `versions_path`: The path to the versions directory containing the parents.
+/// - `fetch_missing`: Whether to fetch the parents missing from the versions
+///   directory and write them there, instead of failing.
+/// - `client`: An optional HTTP client for fetching missing parents.
+///
+/// # Returns
+/// A result containing the resolved version metadata.
+pub(crate) async fn resolve(
+    mut json: Value,
+    versions_path: &Path,
+    fetch_missing: bool,
+    client: Option<&Client>,
+) -> crate::Result<VersionMeta> {
+    let migrations = MigrationRegistry::default();
+    migrations.migrate(&mut json)?;
+    let mut seen = HashSet::new();
+
+    while let Some(parent) = inherits_from(&json) {
+        if !seen.insert(parent.clone()) {
+            return Err(Error::Parse(format!(
+                "Cyclic inheritsFrom chain at {}",
+                parent
+            )));
+        }
+        let parent_path = version_json_path(versions_path, &parent);
+        if fetch_missing && !parent_path.is_file() {
+            let parent_meta = meta::fetch(&parent, client).await?;
+            write_json(&parent_path, &parent_meta).await?;
+        }
+        let mut parent_json: Value = read_json(&parent_path).await?;
+        migrations.migrate(&mut parent_json)?;
+        json = merge(json, parent_json);
+    }
+
+    Ok(serde_json::from_value(json)?)
+}
+
+/// Gets the `inheritsFrom` field of a version JSON.
+fn inherits_from(json: &Value) -> Option<String> {
+    json.get("inheritsFrom")?.as_str().map(str::to_string)
+}
+
+/// Merges a child version JSON into its parent.
+///
+/// The `inheritsFrom` field of the child is dropped, the one of the parent is
+/// kept so the chain can continue.
+///
+/// # Parameters
+/// - `child`: The inheriting version JSON.
+/// - `parent`: The version JSON it inherits from.
+///
+/// # Returns
+/// The merged version JSON.
+fn merge(child: Value, parent: Value) -> Value {
+    let (Value::Object(child), Value::Object(mut merged)) = (child, parent) else {
+        return Value::Null;
+    };
+
+    for (key, value) in child {
+        match key.as_str() {
+            "inheritsFrom" => continue,
+            "libraries" => {
+                let libraries = merge_libraries(value, merged.remove(&key));
+                merged.insert(key, libraries);
+            }
+            "arguments" => {
+                let arguments = merge_arguments(value, merged.remove(&key));
+                merged.insert(key, arguments);
+            }
+            _ => {
+                merged.insert(key, value);
+            }
+        }
+    }
+
+    Value::Object(merged)
+}
+
+/// Places the child libraries before the parent libraries they don't replace.
+fn merge_libraries(child: Value, parent: Option<Value>) -> Value {
+    let Value::Array(mut libraries) = child else {
+        return parent.unwrap_or(child);
+    };
+    let Some(Value::Array(parent)) = parent else {
+        return Value::Array(libraries);
+    };
+
+    let keys = libraries
+        .iter()
+        .filter_map(library_key)
+        .collect::<HashSet<_>>();
+    libraries.extend(
+        parent
+            .into_iter()
+            .filter(|library| library_key(library).is_none_or(|key| !keys.contains(&key))),
+    );
+
+    Value::Array(libraries)
+}
+
+/// Gets the group, artifact and classifier of a library.
+fn library_key(library: &Value) -> Option<String> {
+    let name = library.get("name")?.as_str()?;
+    let mut parts = name.split('@').next()?.split(':');
+    let (group, artifact) = (parts.next()?, parts.next()?);
+    let classifier = parts.nth(1).unwrap_or_default();
+    Some(format!("{}:{}:{}", group, artifact, classifier))
+}
+
+/// Appends the child game and JVM arguments to the parent ones.
+fn merge_arguments(child: Value, parent: Option<Value>) -> Value {
+    let (Value::Object(child), Some(Value::Object(mut merged))) = (&child, parent) else {
+        return child;
+    };
+
+    for (key, value) in child {
+        match (merged.get_mut(key), value) {
+            (Some(Value::Array(arguments)), Value::Array(extra)) => {
+                arguments.extend(extra.iter().cloned())
+            }
+            _ => {
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    Value::Object(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{config, version_json, write_version};
+
+    #[tokio::test]
+    async fn parents_are_read_from_the_overridden_versions_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config(&dir.path().join("game"), "1.8.9");
+        config.versions_dir = Some(dir.path().join("versions"));
+
+        write_version(&config, "1.8.9", &version_json("1.8.9")).await;
+        let child = json!({
+            "id": "fabric-loader-0.16.9-1.8.9",
+            "inheritsFrom": "1.8.9",
+            "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+        });
+        write_version(&config, "fabric-loader-0.16.9-1.8.9", &child).await;
+        let child_path =
+            version_json_path(&config.get_versions_path(), "fabric-loader-0.16.9-1.8.9");
+
+        // The parent is found without fetching it.
+        let meta = read_version_meta_with_inheritance(&child_path, &config)
+            .await
+            .unwrap();
+        assert_eq!(meta.id, "fabric-loader-0.16.9-1.8.9");
+        assert_eq!(
+            meta.main_class,
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+        assert_eq!(meta.asset_index.id, "1.8.9");
+        assert!(!dir.path().join("game").join("versions").exists());
+
+        assert_eq!(
+            missing_parent(&child_path, &config.get_versions_path())
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn parents_written_by_older_releases_are_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(dir.path(), "1.8.9");
+        let mut parent = version_json("1.8.9");
+        parent["downloads"]["client"]["sha1"] = json!("");
+        write_version(&config, "1.8.9", &parent).await;
+        let child = json!({ "id": "1.8.9-forge", "inheritsFrom": "1.8.9" });
+        write_version(&config, "1.8.9-forge", &child).await;
+
+        let meta = read_version_meta(
+            &version_json_path(&config.get_versions_path(), "1.8.9-forge"),
+            &config.get_versions_path(),
+        )
+        .await
+        .unwrap();
+        assert!(meta.downloads.client.sha1.is_empty());
+    }
+}
@@ -2,25 +2,36 @@ use core::fmt;
 /// This module handles the installation of Minecraft, including downloading
 /// necessary files and managing the Java runtime environment.
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
 use std::{
-    env::consts::{ARCH, OS},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs,
     path::{Path, PathBuf, MAIN_SEPARATOR_STR},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use tokio::{fs::create_dir_all, process::Command};
+use tokio::{fs::create_dir_all, process::Command, sync::OnceCell};
 
 use crate::{
-    error::Error,
+    error::{Error, ErrorExt},
     http::{
-        downloader::{download, download_multiple},
-        fetch::fetch,
+        downloader::{download_many_with, DownloadItem, DownloadManyOptions},
+        fetch::{fetch_json_cached, fetch_json_revalidated, fetch_rate_limited},
+        mirror::{next_mirror, resolve, MirrorConfig},
     },
     json::{
-        java::{JavaFileManifest, JavaManifest},
+        java::{FileMap, JavaFileManifest, JavaManifest},
         version::{
-            asset_index::AssetIndex,
+            asset_index::{AssetIndex, AssetIndexCacheInfo},
             manifest::VersionManifest,
-            meta::vanilla::{self, JavaVersion, VersionMeta},
+            meta::{
+                custom::{Data, Processor},
+                vanilla::{self, diff_meta, JavaVersion, VersionMeta},
+            },
         },
     },
     minecraft::{
@@ -29,19 +40,115 @@ use crate::{
     util::{
         extract::{extract_file, read_file_from_jar},
         hash::calculate_sha1,
-        json::{read_json, write_json},
+        json::{read_json, read_json_streaming, write_json},
+        lock::DirLock,
+        retry::{CircuitBreaker, RateLimit},
     },
 };
 
 use super::{
-    config::Config,
-    emitter::Emitter,
-    loader::Loader,
+    config::{Config, ProvisionedPack},
+    emitter::{Emit, Emitter, Event, EventPayload, Warning, WarningCode},
+    inheritance::{self, missing_parent, read_version_meta},
+    launch::hide_java_console,
+    loader::{CompatibilityStatus, Loader},
+    options::{
+        enable_resource_packs, select_shader_pack, uses_prefixed_resource_packs, OptionsFile,
+        OPTIONS_FILE,
+    },
     parse::{parse_lib_path, ParseRule},
+    platform::Platform,
 };
 
+/// The file the version manifest is cached in, inside the indexes directory.
+const MANIFEST_CACHE_FILE: &str = "version_manifest_cache.json";
+
+/// The file Mojang's Java manifest is cached in, inside the indexes directory.
+const JAVA_MANIFEST_CACHE_FILE: &str = "java_manifest_cache.json";
+
+/// The file inside the natives directory of a version that records what was
+/// extracted into it, see `NativesManifest`.
+const NATIVES_MANIFEST_FILE: &str = ".lyceris-natives.json";
+
+/// The file inside the directory of a Java runtime that records what it was
+/// installed from, see `RuntimeManifest`.
+const RUNTIME_MANIFEST_FILE: &str = ".lyceris-runtime.json";
+
+/// The file inside the game directory that lists the provisioned packs
+/// that were enabled once, so that the packs the user turned off stay off.
+const ENABLED_PACKS_FILE: &str = ".lyceris-packs.json";
+
+/// Records the natives extracted into the natives directory of a version, so
+/// that an interrupted extraction or changed native libraries are noticed.
+#[derive(Serialize, Deserialize)]
+struct NativesManifest {
+    /// The SHA-1 of every extracted classifier jar, keyed by its path.
+    classifiers: BTreeMap<String, String>,
+    /// The extracted files, relative to the natives directory.
+    files: Vec<PathBuf>,
+}
+
+impl NativesManifest {
+    /// Checks whether the natives directory was extracted from the expected
+    /// classifier jars and still contains every extracted file.
+    ///
+    /// # Parameters
+    /// - `classifiers`: The SHA-1 of every expected classifier jar, keyed by
+    ///   its path.
+    /// - `natives_path`: The natives directory.
+    ///
+    /// # Returns
+    /// Whether the natives don't have to be extracted again.
+    fn is_valid(&self, classifiers: &BTreeMap<String, String>, natives_path: &Path) -> bool {
+        self.classifiers == *classifiers
+            && self
+                .files
+                .iter()
+                .all(|file| natives_path.join(file).is_file())
+    }
+}
+
+/// Records the file manifest a Java runtime was installed from, so that an
+/// installed runtime is verified without fetching Mojang's Java manifests.
+#[derive(Serialize, Deserialize)]
+struct RuntimeManifest {
+    /// The key of the platform in Mojang's Java manifest, e.g. `windows-x64`.
+    platform: String,
+    /// The SHA-1 Mojang's Java manifest lists for the file manifest, empty
+    /// for runtimes installed by older releases.
+    #[serde(default)]
+    sha1: String,
+    /// The file manifest the runtime was installed from.
+    files: JavaFileManifest,
+}
+
+impl RuntimeManifest {
+    /// Checks whether the runtime was installed for the platform and still
+    /// contains every file of its manifest.
+    ///
+    /// # Parameters
+    /// - `platform`: The key of the platform in Mojang's Java manifest.
+    /// - `runtime_path`: The directory of the runtime.
+    ///
+    /// # Returns
+    /// Whether the manifests don't have to be fetched again.
+    fn is_valid(&self, platform: &str, runtime_path: &Path) -> bool {
+        self.platform == platform
+            && self
+                .files
+                .files
+                .iter()
+                .filter(|(_, file)| file.downloads.is_some())
+                .all(|(name, _)| {
+                    runtime_path
+                        .join(name.replace("/", MAIN_SEPARATOR_STR))
+                        .is_file()
+                })
+    }
+}
+
 /// Represents the type of file being downloaded.
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum FileType {
     Asset { is_virtual: bool, is_map: bool },
     Library,
@@ -62,6 +169,47 @@ impl fmt::Display for FileType {
     }
 }
 
+/// Summary of an installation, describing where the time went and how much
+/// was downloaded versus reused.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct InstallReport {
+    /// The duration of every installation phase.
+    pub timings: PhaseTimings,
+    /// The number of bytes written by downloads.
+    pub bytes_downloaded: u64,
+    /// The number of files that were downloaded.
+    pub files_downloaded: usize,
+    /// The number of files that were already present and valid.
+    pub files_reused: usize,
+    /// The number of files that were present but corrupted, so they were
+    /// downloaded again.
+    pub files_repaired: usize,
+    /// The number of classifier jars extracted into the natives directory,
+    /// zero if the natives were already extracted and complete.
+    pub natives_extracted: usize,
+    /// The warnings collected while planning the installation.
+    pub warnings: Vec<Warning>,
+}
+
+/// The duration of every installation phase.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PhaseTimings {
+    /// Fetching the version manifest, version, asset index and Java metadata.
+    pub meta_fetch: Duration,
+    /// Building the list of files the version needs.
+    pub planning: Duration,
+    /// Hashing the files that are already present.
+    pub hashing: Duration,
+    /// Downloading the missing files, keyed by their file type. The files
+    /// are downloaded concurrently, so each duration runs from the start of
+    /// the downloads until the last file of its type was finished.
+    pub downloads: BTreeMap<String, Duration>,
+    /// Downloading and extracting the native libraries.
+    pub extraction: Duration,
+    /// Running the loader processors.
+    pub processors: Duration,
+}
+
 /// Represents a file to be downloaded, including its metadata.
 #[derive(Clone)]
 struct DownloadFile {
@@ -70,6 +218,14 @@ struct DownloadFile {
     url: String,
     path: PathBuf,
     r#type: FileType,
+    /// Whether the metadata provides the checksum of the file, which is
+    /// verified only then.
+    has_checksum: bool,
+    /// The size of the file in bytes, if the metadata provides it.
+    size: Option<u64>,
+    /// The URL of the file on the next mirror, downloaded from when the file
+    /// from `url` is broken.
+    fallback_url: Option<String>,
 }
 
 /// Installs the specified version of Minecraft by downloading necessary files
@@ -80,151 +236,674 @@ struct DownloadFile {
 /// - `emitter`: An optional emitter for logging progress.
 ///
 /// # Returns
-/// A result indicating success or failure of the installation process.
+/// A report of the installation, which is also emitted as an
+/// `Event::InstallFinished`.
 pub async fn install<T: Loader>(
     config: &Config<T>,
     emitter: Option<&Emitter>,
-) -> crate::Result<()> {
-    let manifest: VersionManifest =
-        fetch(VERSION_MANIFEST_ENDPOINT, config.client.as_ref()).await?;
+) -> crate::Result<InstallReport> {
+    install_version(config, None, emitter).await
+}
+
+/// Installs the specified version of Minecraft from an already available
+/// version metadata, without fetching the version manifest for it.
+///
+/// The metadata is written as the version JSON and used as is, so it must
+/// already be merged with the loader if there is one.
+///
+/// # Parameters
+/// - `config`: The configuration for the installation process.
+/// - `meta`: The version metadata to install.
+/// - `emitter`: An optional emitter for logging progress.
+///
+/// # Returns
+/// A report of the installation, or `Error::VersionMismatch` if the id of
+/// the metadata matches neither the version nor the version name.
+pub async fn install_with_meta<T: Loader>(
+    config: &Config<T>,
+    meta: VersionMeta,
+    emitter: Option<&Emitter>,
+) -> crate::Result<InstallReport> {
+    if meta.id != config.version && meta.id != config.get_version_name() {
+        return Err(Error::VersionMismatch {
+            expected: config.version.clone(),
+            actual: meta.id,
+        });
+    }
+
+    install_version(config, Some(meta), emitter).await
+}
+
+/// Installs the version, fetching its metadata unless it is provided.
+///
+/// # Parameters
+/// - `config`: The configuration for the installation process.
+/// - `meta`: The version metadata, if it is already available.
+/// - `emitter`: An optional emitter for logging progress.
+///
+/// # Returns
+/// A report of the installation.
+async fn install_version<T: Loader>(
+    config: &Config<T>,
+    meta: Option<VersionMeta>,
+    emitter: Option<&Emitter>,
+) -> crate::Result<InstallReport> {
+    let version_name = config.get_version_name();
+    let platform = config.get_platform()?;
+    emitter
+        .emit(
+            Event::InstallStarted,
+            EventPayload::InstallStarted {
+                version_name: version_name.clone(),
+            },
+        )
+        .await;
+
+    let mut report = InstallReport::default();
+    let default_client = reqwest::Client::default();
+    let mirror = config
+        .selected_mirror
+        .get_or_probe(
+            &config.mirrors,
+            config.client.as_ref().unwrap_or(&default_client),
+        )
+        .await;
+    let progress_emitter = if config.skip_progress_events {
+        None
+    } else {
+        emitter
+    };
+
     let version_json_path = config.get_version_json_path();
-    let mut meta: VersionMeta = if !version_json_path.exists() {
-        let mut meta =
-            fetch_version_meta(&manifest, &config.version, config.client.as_ref()).await?;
+    if let Some(loader) = &config.loader {
+        if meta.is_none() && !version_json_path.exists() {
+            if let CompatibilityStatus::Incompatible { reason } = loader
+                .check_compatibility(&config.version, config.client.as_ref())
+                .await?
+            {
+                return Err(Error::IncompatibleLoader(reason));
+            }
+        }
+    }
+
+    let started = Instant::now();
+    // The manifest is only fetched once a version has to be looked up in it.
+    let manifest = OnceCell::new();
+    let get_manifest = || manifest.get_or_try_init(|| fetch_manifest(config, mirror, emitter));
+    let mut meta: VersionMeta = if meta.is_none() && !version_json_path.exists() {
+        let mut meta = fetch_version_meta(
+            get_manifest().await?,
+            &config.version,
+            mirror,
+            config.client.as_ref(),
+            config.rate_limit,
+            emitter,
+        )
+        .await?;
         if let Some(loader) = &config.loader {
+            // The vanilla metadata is only kept around when someone can
+            // receive the diff.
+            let vanilla = emitter.is_some().then(|| meta.clone());
             meta = loader.merge(&config.into_vanilla(), meta, emitter).await?;
+            if let Some(vanilla) = vanilla {
+                emitter
+                    .emit(Event::LoaderMergeComplete, diff_meta(&vanilla, &meta))
+                    .await;
+            }
         }
         write_json(&version_json_path, &meta).await?;
         meta
     } else {
-        read_json(&version_json_path).await?
-    };
-
-    let asset_index_path = config
-        .get_indexes_path()
-        .join(format!("{}.json", &meta.asset_index.id));
-    let asset_index: AssetIndex = if !asset_index_path.exists() {
-        let asset_index = fetch(&meta.asset_index.url, config.client.as_ref()).await?;
-        write_json(&asset_index_path, &asset_index).await?;
-        asset_index
-    } else {
-        read_json(&asset_index_path).await?
+        if let Some(meta) = meta {
+            write_json(&version_json_path, &meta).await?;
+        }
+        let versions_path = config.get_versions_path();
+        while let Some(parent) = missing_parent(&version_json_path, &versions_path).await? {
+            let parent_meta = fetch_version_meta(
+                get_manifest().await?,
+                &parent,
+                mirror,
+                config.client.as_ref(),
+                config.rate_limit,
+                emitter,
+            )
+            .await?;
+            write_json(
+                &inheritance::version_json_path(&versions_path, &parent),
+                &parent_meta,
+            )
+            .await?;
+        }
+        read_version_meta(&version_json_path, &versions_path).await?
     };
 
-    let natives_path = config.get_natives_path().join(&config.version);
-    if !natives_path.is_dir() {
-        create_dir_all(&natives_path).await?;
-    }
+    let (asset_index, known_objects) = fetch_asset_index(config, &meta, mirror, emitter).await?;
 
-    let check_natives = fs::read_dir(&natives_path)?.count() == 0;
     let mut to_be_extracted = Vec::with_capacity(10);
+    let mut warnings = Vec::new();
 
-    let default_java_version = JavaVersion::default();
-    let java_version = meta.java_version.as_ref().unwrap_or(&default_java_version);
+    let java_version = config.get_java_version(&meta).await;
     let runtime_path = config.get_runtime_path().join(&java_version.component);
 
-    let java_manifest: JavaManifest = fetch(JAVA_MANIFEST_ENDPOINT, config.client.as_ref()).await?;
-    let java_url = get_java_url(&java_manifest, java_version)?;
-    let java_files: JavaFileManifest = fetch(java_url, config.client.as_ref()).await?;
+    // An installed runtime is verified against the manifest it was installed
+    // from, Mojang's Java manifests are only fetched to install or repair it.
+    // A repaired runtime reuses the file manifest while its SHA-1 is still
+    // the one Mojang lists.
+    let runtime_platform = platform.java_manifest_key(&java_version.component);
+    let runtime_manifest_path = runtime_path.join(RUNTIME_MANIFEST_FILE);
+    let runtime_manifest = read_json::<RuntimeManifest>(&runtime_manifest_path)
+        .await
+        .ok()
+        .filter(|manifest| manifest.platform == runtime_platform);
+    let (java_files, java_files_sha1) = match runtime_manifest {
+        Some(manifest) if manifest.is_valid(&runtime_platform, &runtime_path) => {
+            (manifest.files, None)
+        }
+        runtime_manifest => {
+            let java_manifest: JavaManifest = fetch_json_revalidated(
+                resolve(mirror, JAVA_MANIFEST_ENDPOINT),
+                &config.get_indexes_path().join(JAVA_MANIFEST_CACHE_FILE),
+                config.client.as_ref(),
+                config.rate_limit,
+                emitter,
+            )
+            .await?;
+            let java_files = get_java_files(&java_manifest, &java_version, &platform)?;
+            let files = match runtime_manifest {
+                Some(manifest) if manifest.sha1 == java_files.sha1 => manifest.files,
+                _ => {
+                    fetch_rate_limited(
+                        resolve(mirror, &java_files.url),
+                        config.client.as_ref(),
+                        config.rate_limit,
+                        emitter,
+                    )
+                    .await?
+                }
+            };
+            (files, Some(java_files.sha1.clone()))
+        }
+    };
+    config.record_java_component().await?;
+    report.timings.meta_fetch = started.elapsed();
 
-    let file_map = build_file_map(
+    let started = Instant::now();
+    let mut file_map = build_file_map(
         &asset_index,
         &meta,
         &java_files,
         &runtime_path,
+        &platform,
         config,
-        check_natives,
         &mut to_be_extracted,
+        &mut warnings,
     )?;
+    report.timings.planning = started.elapsed();
+    for warning in &warnings {
+        emitter.warn(warning.clone()).await;
+    }
+    report.warnings = warnings;
+    let fallback_mirror = next_mirror(&config.mirrors, mirror);
+    file_map.iter_mut().for_each(|file| {
+        file.fallback_url = fallback_mirror.map(|fallback| fallback.resolve(&file.url));
+        file.url = resolve(mirror, &file.url);
+    });
+    // Objects are named by their hash, so the ones a refreshed asset index
+    // shares with its previous version were already verified. They are still
+    // copied to the locations a legacy index asks for.
+    file_map
+        .iter_mut()
+        .filter(|file| {
+            matches!(file.r#type, FileType::Asset { .. })
+                && known_objects.contains(&file.sha1)
+                && file.path.is_file()
+        })
+        .for_each(|file| file.has_checksum = false);
+    // The packs are not hosted by Mojang, so they are never mirrored.
+    file_map.extend(build_pack_files(config)?);
 
     download_necessary(
         file_map,
-        &config.game_dir,
+        config,
         asset_index.map_to_resources.unwrap_or_default()
             || asset_index.r#virtual.unwrap_or_default(),
-        emitter,
-        config.client.as_ref(),
+        config.shared_runtimes.then_some(runtime_path.as_path()),
+        progress_emitter,
+        &mut report,
     )
     .await?;
+    if let Some(sha1) = java_files_sha1 {
+        write_json(
+            &runtime_manifest_path,
+            &RuntimeManifest {
+                platform: runtime_platform,
+                sha1,
+                files: java_files,
+            },
+        )
+        .await?;
+    }
+
+    // The classifier jars were downloaded along with the other libraries,
+    // they are only extracted again when the natives directory doesn't match
+    // them anymore.
+    let started = Instant::now();
+    let classifiers: BTreeMap<String, String> = to_be_extracted
+        .into_iter()
+        .filter_map(|file| file.path.map(|path| (path, file.sha1)))
+        .collect();
+    report.natives_extracted = extract_natives(config, classifiers).await?;
+    report.timings.extraction = started.elapsed();
+
+    let started = Instant::now();
+    execute_processors_if_exists(&mut meta, config).await?;
+    report.timings.processors = started.elapsed();
+
+    enable_packs(config, &meta).await?;
+
+    emitter.emit(Event::InstallFinished, report.clone()).await;
+    emitter
+        .emit(
+            Event::InstallComplete,
+            EventPayload::InstallComplete { version_name },
+        )
+        .await;
 
-    if !to_be_extracted.is_empty() {
-        create_dir_all(&natives_path).await?;
-        for extract in to_be_extracted {
-            if let Some(path) = extract.path {
-                let path = PathBuf::from(path);
-                download(&extract.url, &path, emitter, config.client.as_ref()).await?;
-                extract_file(&path, &natives_path)?;
+    Ok(report)
+}
+
+/// Fetches the version manifest and caches it, falling back to the cached
+/// manifest when the network is unavailable.
+///
+/// # Parameters
+/// - `config`: The configuration of the installation.
+/// - `mirror`: An optional mirror to fetch the manifest from.
+/// - `emitter`: An optional emitter, notified when the cache is used.
+///
+/// # Returns
+/// A result containing the manifest, `Error::OfflineNoCache` if the network
+/// is unavailable and nothing is cached.
+async fn fetch_manifest<T: Loader>(
+    config: &Config<T>,
+    mirror: Option<&MirrorConfig>,
+    emitter: Option<&Emitter>,
+) -> crate::Result<VersionManifest> {
+    let cache_path = config.get_indexes_path().join(MANIFEST_CACHE_FILE);
+    match fetch_rate_limited(
+        resolve(mirror, VERSION_MANIFEST_ENDPOINT),
+        config.client.as_ref(),
+        config.rate_limit,
+        emitter,
+    )
+    .await
+    {
+        Ok(manifest) => {
+            write_json(&cache_path, &manifest).await?;
+            Ok(manifest)
+        }
+        Err(
+            e @ (Error::NetworkError { .. } | Error::HttpServerError { .. } | Error::Reqwest(_)),
+        ) => {
+            if !cache_path.is_file() {
+                return Err(Error::OfflineNoCache);
             }
+            emitter
+                .emit(
+                    Event::OfflineMode,
+                    EventPayload::OfflineMode {
+                        error: e.to_string(),
+                    },
+                )
+                .await;
+            read_json(&cache_path).await
         }
+        Err(e) => Err(e),
     }
+}
 
-    execute_processors_if_exists(&mut meta, config).await?;
+/// Checks whether the natives of an installed version were extracted
+/// completely, so that an interrupted extraction is noticed before the game
+/// fails to load them. Installing the version again repairs them.
+///
+/// # Parameters
+/// - `config`: The configuration of the installed version.
+///
+/// # Returns
+/// A result containing whether the natives directory records a finished
+/// extraction and still contains every extracted file.
+pub async fn verify_natives<T: Loader>(config: &Config<T>) -> crate::Result<bool> {
+    let natives_path = config.get_natives_path_for_version();
+    let manifest_path = natives_path.join(NATIVES_MANIFEST_FILE);
+    if !manifest_path.is_file() {
+        return Ok(false);
+    }
+    let manifest: NativesManifest = read_json(&manifest_path).await?;
+    Ok(manifest.is_valid(&manifest.classifiers, &natives_path))
+}
+
+/// Extracts the classifier jars into the natives directory of the version,
+/// unless it was already extracted from them and is still complete.
+///
+/// # Parameters
+/// - `config`: The configuration of the installation.
+/// - `classifiers`: The SHA-1 of every classifier jar, keyed by its path.
+///
+/// # Returns
+/// The number of extracted classifier jars, zero if the natives directory
+/// was left as is.
+async fn extract_natives<T: Loader>(
+    config: &Config<T>,
+    classifiers: BTreeMap<String, String>,
+) -> crate::Result<usize> {
+    migrate_legacy_natives(config).await?;
 
+    let natives_path = config.get_natives_path_for_version();
+    create_dir_all(&natives_path).await?;
+    let manifest_path = natives_path.join(NATIVES_MANIFEST_FILE);
+    let manifest: Option<NativesManifest> = read_json(&manifest_path).await.ok();
+    if manifest.is_some_and(|manifest| manifest.is_valid(&classifiers, &natives_path)) {
+        return Ok(0);
+    }
+
+    let mut files = Vec::new();
+    for path in classifiers.keys() {
+        files.extend(extract_file(&PathBuf::from(path), &natives_path)?);
+    }
+    let extracted = classifiers.len();
+    write_json(&manifest_path, &NativesManifest { classifiers, files }).await?;
+    Ok(extracted)
+}
+
+/// Moves the natives of a loader out of the directory named after the
+/// Minecraft version, where they were extracted before natives were kept
+/// apart per version name.
+///
+/// The old directory is only moved when no vanilla installation of the
+/// version uses it, and only when it predates the natives manifest. Either
+/// way the natives are extracted again, as nothing records what they were
+/// extracted from.
+///
+/// # Parameters
+/// - `config`: The configuration of the installation.
+async fn migrate_legacy_natives<T: Loader>(config: &Config<T>) -> crate::Result<()> {
+    if config.natives_dir.is_some() || config.get_version_name() == config.version {
+        return Ok(());
+    }
+    let legacy_path = config.get_natives_path().join(&config.version);
+    let natives_path = config.get_natives_path_for_version();
+    let vanilla_json_path =
+        inheritance::version_json_path(&config.get_versions_path(), &config.version);
+    if legacy_path.is_dir()
+        && !legacy_path.join(NATIVES_MANIFEST_FILE).exists()
+        && !vanilla_json_path.exists()
+        && !natives_path.exists()
+    {
+        tokio::fs::rename(&legacy_path, &natives_path).await?;
+    }
     Ok(())
 }
 
+/// Fetches the asset index of a version, unless a cached one exists and is
+/// younger than `asset_index_max_age`.
+///
+/// # Parameters
+/// - `config`: The configuration of the installation.
+/// - `meta`: The version metadata referencing the asset index.
+/// - `mirror`: An optional mirror to fetch the asset index from.
+/// - `emitter`: An optional emitter, warned when the server rate limits.
+///
+/// # Returns
+/// A result containing the asset index, along with the hashes of the
+/// objects of the previous index when a cached one was refreshed.
+async fn fetch_asset_index<T: Loader>(
+    config: &Config<T>,
+    meta: &VersionMeta,
+    mirror: Option<&MirrorConfig>,
+    emitter: Option<&Emitter>,
+) -> crate::Result<(AssetIndex, HashSet<String>)> {
+    let id = &meta.asset_index.id;
+    let index_path = config.get_indexes_path().join(format!("{}.json", id));
+    let Some(max_age) = config.asset_index_max_age else {
+        // Asset indexes are versioned by their id and never change.
+        let asset_index = fetch_json_cached(
+            resolve(mirror, &meta.asset_index.url),
+            &index_path,
+            Duration::MAX,
+            config.client.as_ref(),
+            config.rate_limit,
+            emitter,
+        )
+        .await?;
+        return Ok((asset_index, HashSet::new()));
+    };
+    let info_path = config.get_indexes_path().join(format!("{}.info.json", id));
+
+    let is_fresh = is_asset_index_fresh(max_age, &index_path, &info_path);
+    let previous = match read_json_streaming::<AssetIndex>(&index_path).await {
+        Ok(previous) if is_fresh => {
+            return Ok((previous, HashSet::new()));
+        }
+        Ok(previous) => Some(previous),
+        Err(_) => None,
+    };
+
+    let asset_index: AssetIndex = fetch_rate_limited(
+        resolve(mirror, &meta.asset_index.url),
+        config.client.as_ref(),
+        config.rate_limit,
+        emitter,
+    )
+    .await?;
+    write_json(&index_path, &asset_index).await?;
+    write_json(
+        &info_path,
+        &AssetIndexCacheInfo {
+            fetched_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            asset_index_id: id.clone(),
+        },
+    )
+    .await?;
+
+    let known_objects = previous
+        .map(|previous| {
+            previous
+                .objects
+                .into_values()
+                .map(|object| object.hash)
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok((asset_index, known_objects))
+}
+
+/// Checks whether a cached asset index is younger than its maximum age.
+///
+/// # Parameters
+/// - `max_age`: The maximum age.
+/// - `index_path`: The path of the cached asset index.
+/// - `info_path`: The path of the `AssetIndexCacheInfo` stored next to it.
+///
+/// # Returns
+/// Whether the cached asset index can be used.
+fn is_asset_index_fresh(max_age: Duration, index_path: &Path, info_path: &Path) -> bool {
+    let info: Option<AssetIndexCacheInfo> = fs::read(info_path)
+        .ok()
+        .and_then(|info| serde_json::from_slice(&info).ok());
+    // Indexes cached before the info was stored fall back to their mtime
+    let fetched_at = info
+        .map(|info| UNIX_EPOCH + Duration::from_secs(info.fetched_at))
+        .or_else(|| {
+            fs::metadata(index_path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        });
+    fetched_at
+        .and_then(|fetched_at| SystemTime::now().duration_since(fetched_at).ok())
+        .is_some_and(|age| age <= max_age)
+}
+
 /// Fetches the version metadata for the specified version from the manifest.
 ///
 /// # Parameters
 /// - `manifest`: The version manifest containing available versions.
 /// - `version`: The version to fetch metadata for.
+/// - `mirror`: An optional mirror to fetch the metadata from.
 /// - `client`: An optional HTTP client for making requests.
+/// - `rate_limit`: How long the request may wait for the server.
+/// - `emitter`: An optional emitter, warned when the server rate limits.
 ///
 /// # Returns
 /// The version metadata for the specified version.
-async fn fetch_version_meta(
+pub(crate) async fn fetch_version_meta(
     manifest: &VersionManifest,
     version: &str,
+    mirror: Option<&MirrorConfig>,
     client: Option<&reqwest::Client>,
+    rate_limit: RateLimit,
+    emitter: Option<&Emitter>,
 ) -> crate::Result<VersionMeta> {
     let version_url = manifest
         .versions
         .iter()
         .find(|v| v.id == version)
-        .ok_or_else(|| Error::UnknownVersion("Vanilla".to_string()))?
+        .ok_or_else(|| Error::UnknownVersion {
+            loader_type: "Vanilla".to_string(),
+            version: version.to_string(),
+        })?
         .url
         .clone();
-    fetch(&version_url, client).await
+    fetch_rate_limited(resolve(mirror, &version_url), client, rate_limit, emitter).await
 }
 
-/// Gets the download URL for the specified Java version based on the operating system and architecture.
+/// Lists the resource packs and shader packs of the configuration as files
+/// to download, named after the last path segment of their URL.
 ///
 /// # Parameters
-/// - `java_manifest`: The manifest containing Java version information.
-/// - `java_version`: The specific Java version to retrieve the URL for.
+/// - `config`: The configuration of the installation.
 ///
 /// # Returns
-/// The download URL for the specified Java version.
-fn get_java_url(java_manifest: &JavaManifest, java_version: &JavaVersion) -> crate::Result<String> {
-    let os = if OS == "macos" { "mac-os" } else { OS };
-    let arch = match ARCH {
-        "x86" => {
-            if os == "linux" {
-                "i386"
-            } else {
-                "x86"
-            }
-        }
-        "x86_64" => "x64",
-        "aarch64" => "arm64",
-        _ => return Err(Error::UnsupportedArchitecture),
-    };
-    let os_arch = if (os == "linux" && arch != "i386")
-        || (os == "mac-os" && (arch != "arm64" || java_version.major_version == 8))
-    {
-        os.to_string()
-    } else {
-        format!("{}-{}", os, arch)
+/// A result containing the files, `Error::Parse` if a URL has no file name.
+fn build_pack_files<T: Loader>(config: &Config<T>) -> crate::Result<Vec<DownloadFile>> {
+    let resource_packs_path = config.get_resource_packs_path();
+    let shader_packs_path = config.get_shader_packs_path();
+    config
+        .resource_packs
+        .iter()
+        .map(|pack| (pack, &resource_packs_path))
+        .chain(
+            config
+                .shader_packs
+                .iter()
+                .map(|pack| (pack, &shader_packs_path)),
+        )
+        .map(|(pack, dir)| {
+            let file_name = pack_file_name(pack)?;
+            Ok(DownloadFile {
+                path: dir.join(&file_name),
+                file_name,
+                sha1: pack.sha1.clone().unwrap_or_default(),
+                url: pack.url.clone(),
+                r#type: FileType::Custom,
+                has_checksum: pack.sha1.is_some(),
+                size: None,
+                fallback_url: None,
+            })
+        })
+        .collect()
+}
+
+/// Enables the provisioned packs marked as enabled, adding the resource
+/// packs to `options.txt` and selecting the first new shader pack.
+///
+/// A pack is only enabled the first time it is provisioned, the packs that
+/// were enabled before are listed in `ENABLED_PACKS_FILE`.
+///
+/// # Parameters
+/// - `config`: The configuration of the installation.
+/// - `meta`: The version metadata, deciding the format of `options.txt`.
+///
+/// # Returns
+/// A result indicating success.
+async fn enable_packs<T: Loader>(config: &Config<T>, meta: &VersionMeta) -> crate::Result<()> {
+    let game_path = config.get_game_path();
+    let enabled_packs_path = game_path.join(ENABLED_PACKS_FILE);
+    let mut enabled_packs: BTreeSet<String> =
+        read_json(&enabled_packs_path).await.unwrap_or_default();
+    let mut newly_enabled = |packs: &[ProvisionedPack], dir: &str| {
+        packs
+            .iter()
+            .filter(|pack| pack.enabled)
+            .map(pack_file_name)
+            .filter(|file_name| {
+                file_name.as_ref().map_or(true, |file_name| {
+                    enabled_packs.insert(format!("{}/{}", dir, file_name))
+                })
+            })
+            .collect::<crate::Result<Vec<_>>>()
     };
+    let resource_packs = newly_enabled(&config.resource_packs, "resourcepacks")?;
+    let shader_packs = newly_enabled(&config.shader_packs, "shaderpacks")?;
+    if resource_packs.is_empty() && shader_packs.is_empty() {
+        return Ok(());
+    }
+
+    if !resource_packs.is_empty() {
+        let options_path = game_path.join(OPTIONS_FILE);
+        let mut options = OptionsFile::read(&options_path, ':').await?;
+        enable_resource_packs(
+            &mut options,
+            &resource_packs,
+            uses_prefixed_resource_packs(meta),
+        )?;
+        options.write(&options_path).await?;
+    }
+    if let Some(file_name) = shader_packs.first() {
+        select_shader_pack(&game_path, file_name).await?;
+    }
+    write_json(&enabled_packs_path, &enabled_packs).await
+}
+
+/// Gets the file name of a provisioned pack from its URL.
+fn pack_file_name(pack: &ProvisionedPack) -> crate::Result<String> {
+    pack.url
+        .split(['?', '#'])
+        .next()
+        .and_then(|url| url.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| Error::Parse(format!("file name of pack {}", pack.url)))
+}
+
+/// Gets the file manifest of the specified Java version for a platform.
+///
+/// # Parameters
+/// - `java_manifest`: The manifest containing Java version information.
+/// - `java_version`: The specific Java version to retrieve the file manifest
+///   for.
+/// - `platform`: The platform the runtime is installed for.
+///
+/// # Returns
+/// The SHA-1, size and download URL of the file manifest.
+fn get_java_files<'a>(
+    java_manifest: &'a JavaManifest,
+    java_version: &JavaVersion,
+    platform: &Platform,
+) -> crate::Result<&'a FileMap> {
+    let os_arch = platform.java_manifest_key(&java_version.component);
     java_manifest
         .get(&os_arch)
         .ok_or_else(|| Error::NotFound("Java map by operating system".to_string()))?
         .get(&java_version.component)
-        .ok_or_else(|| Error::UnknownVersion("Java version".to_string()))?
+        .ok_or_else(|| Error::UnavailableJavaRuntime {
+            component: java_version.component.clone(),
+            platform: os_arch.clone(),
+        })?
         .first()
         .ok_or_else(|| Error::NotFound("Java gamecore".to_string()))
-        .map(|entry| &entry.manifest.url)
-        .cloned()
+        .map(|entry| &entry.manifest)
 }
 
 /// Builds a map of files to be downloaded based on the asset index, version metadata, and Java files.
@@ -234,24 +913,28 @@ fn get_java_url(java_manifest: &JavaManifest, java_version: &JavaVersion) -> cra
 /// - `meta`: The version metadata.
 /// - `java_files`: The Java file manifest.
 /// - `runtime_path`: The path to the Java runtime.
+/// - `platform`: The platform the libraries and natives are selected for.
 /// - `config`: The configuration for the installation process.
-/// - `check_natives`: A flag indicating whether to check for native files.
 /// - `to_be_extracted`: A mutable vector to store files that need to be extracted.
+/// - `warnings`: A mutable vector to store the libraries that were skipped.
 ///
 /// # Returns
 /// A vector of `DownloadFile` representing the files to be downloaded.
+#[allow(clippy::too_many_arguments)]
 fn build_file_map(
     asset_index: &AssetIndex,
     meta: &VersionMeta,
     java_files: &JavaFileManifest,
     runtime_path: &Path,
+    platform: &Platform,
     config: &Config<impl Loader>,
-    check_natives: bool,
     to_be_extracted: &mut Vec<vanilla::File>,
+    warnings: &mut Vec<Warning>,
 ) -> crate::Result<Vec<DownloadFile>> {
     let version_jar_path = config.get_version_jar_path();
+    let client = &meta.downloads.client;
     let version_download = if !version_jar_path.exists()
-        || !calculate_sha1(&version_jar_path)?.eq(&meta.downloads.client.sha1)
+        || (!client.sha1.is_empty() && calculate_sha1(&version_jar_path)? != client.sha1)
     {
         Some(DownloadFile {
             file_name: version_jar_path
@@ -261,8 +944,11 @@ fn build_file_map(
                 .to_string(),
             r#type: FileType::Library,
             path: version_jar_path,
-            sha1: meta.downloads.client.sha1.clone(),
-            url: meta.downloads.client.url.clone(),
+            sha1: client.sha1.clone(),
+            url: client.url.clone(),
+            has_checksum: !client.sha1.is_empty(),
+            size: u64::try_from(client.size).ok(),
+            fallback_url: None,
         })
     } else {
         None
@@ -283,6 +969,9 @@ fn build_file_map(
                     is_map: asset_index.map_to_resources.unwrap_or_default(),
                     is_virtual: asset_index.r#virtual.unwrap_or_default(),
                 },
+                has_checksum: true,
+                size: Some(meta.size),
+                fallback_url: None,
             }
         })
         .collect::<Vec<_>>();
@@ -291,48 +980,67 @@ fn build_file_map(
         .libraries
         .iter()
         .filter_map(|lib| {
-            if !lib.rules.parse_rule() {
+            if !lib.rules.parse_rule(platform) {
+                warnings.push(Warning::new(
+                    WarningCode::LibraryExcludedByRules,
+                    format!("{} is excluded by its rules", lib.name),
+                ));
                 return None;
             }
-            let downloads = lib.downloads.as_ref()?;
-            if check_natives {
-                if let Some(classifiers) = &downloads.classifiers {
-                    let classifier = match OS {
-                        "windows" => &classifiers.natives_windows,
-                        "linux" => &classifiers.natives_linux,
-                        "macos" => &classifiers.natives_macos,
-                        _ => return None,
-                    };
-                    if let Some(classifier) = classifier {
-                        if let Some(classifier_path) = &classifier.path {
-                            let path = config
-                                .game_dir
-                                .join("libraries")
-                                .join(classifier_path.replace("/", MAIN_SEPARATOR_STR));
-                            let url = classifier.url.clone();
-                            let sha1 = classifier.sha1.clone();
-                            to_be_extracted.push(vanilla::File {
-                                path: Some(path.to_string_lossy().into_owned()),
-                                sha1: sha1.clone(),
-                                size: classifier.size,
-                                url: url.clone(),
-                            });
-                            return Some(DownloadFile {
-                                file_name: PathBuf::from(url.clone())
-                                    .file_name()
-                                    .unwrap_or_default()
-                                    .to_string_lossy()
-                                    .to_string(),
-                                sha1,
-                                url,
-                                path,
-                                r#type: FileType::Library,
-                            });
-                        }
+            let Some(downloads) = lib.downloads.as_ref() else {
+                warnings.push(Warning::new(
+                    WarningCode::LibraryWithoutArtifact,
+                    format!("{} has no downloads", lib.name),
+                ));
+                return None;
+            };
+            if let Some(classifiers) = &downloads.classifiers {
+                if let Some(classifier) = platform.natives(classifiers) {
+                    if let Some(classifier_path) = &classifier.path {
+                        let path = config
+                            .get_libraries_path()
+                            .join(classifier_path.replace("/", MAIN_SEPARATOR_STR));
+                        let url = classifier.url.clone();
+                        let sha1 = classifier.sha1.clone();
+                        to_be_extracted.push(vanilla::File {
+                            path: Some(path.to_string_lossy().into_owned()),
+                            sha1: sha1.clone(),
+                            size: classifier.size,
+                            url: url.clone(),
+                        });
+                        return Some(DownloadFile {
+                            file_name: PathBuf::from(url.clone())
+                                .file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .to_string(),
+                            has_checksum: !sha1.is_empty(),
+                            size: u64::try_from(classifier.size).ok(),
+                            fallback_url: None,
+                            sha1,
+                            url,
+                            path,
+                            r#type: FileType::Library,
+                        });
                     }
                 }
             }
-            let artifact = downloads.artifact.as_ref()?;
+            let Some(artifact) = downloads.artifact.as_ref() else {
+                warnings.push(if downloads.classifiers.is_some() {
+                    Warning::new(
+                        WarningCode::MissingNativeClassifier,
+                        format!("{} has no natives for {:?}", lib.name, platform.os),
+                    )
+                } else {
+                    Warning::new(
+                        WarningCode::LibraryWithoutArtifact,
+                        format!("{} has no artifact", lib.name),
+                    )
+                });
+                return None;
+            };
+            // Libraries added by loaders have an empty checksum when their
+            // metadata lacks one.
             Some(DownloadFile {
                 file_name: PathBuf::from(artifact.url.clone())
                     .file_name()
@@ -340,10 +1048,12 @@ fn build_file_map(
                     .to_string_lossy()
                     .to_string(),
                 sha1: artifact.sha1.clone(),
+                has_checksum: !artifact.sha1.is_empty(),
+                size: u64::try_from(artifact.size).ok(),
+                fallback_url: None,
                 url: artifact.url.clone(),
                 path: config
-                    .game_dir
-                    .join("libraries")
+                    .get_libraries_path()
                     .join(artifact.path.as_ref()?.replace("/", MAIN_SEPARATOR_STR)),
                 r#type: FileType::Library,
             })
@@ -365,6 +1075,9 @@ fn build_file_map(
                 sha1: downloads.raw.sha1.clone(),
                 url: downloads.raw.url.clone(),
                 r#type: FileType::Java,
+                has_checksum: true,
+                size: Some(downloads.raw.size),
+                fallback_url: None,
             })
         })
         .collect::<Vec<_>>();
@@ -378,8 +1091,22 @@ fn build_file_map(
     .concat())
 }
 
+/// Completion state of the processors of a version, stored apart from the
+/// version JSON so regenerating it doesn't re-run the processors.
+#[derive(Serialize, Deserialize, Default)]
+struct ProcessorState {
+    lyceris_version: String,
+    loader_version: Option<String>,
+    completed: HashSet<String>,
+}
+
 /// Executes any processors defined in the version metadata, if they exist.
 ///
+/// Completed processors are recorded in `processors.json` inside the version
+/// directory, keyed by a hash of their jar and arguments. A recorded processor
+/// is only run again if one of its outputs is missing or has a different hash,
+/// or if the state was written by another lyceris or loader version.
+///
 /// # Parameters
 /// - `meta`: The version metadata containing processor information.
 /// - `config`: The configuration for the installation process.
@@ -390,14 +1117,43 @@ async fn execute_processors_if_exists(
     meta: &mut VersionMeta,
     config: &Config<impl Loader>,
 ) -> crate::Result<()> {
+    let java_version = config.get_java_version(meta).await;
     if let Some(ref mut processors) = meta.processors {
-        let data = meta
-            .data
-            .as_ref()
-            .ok_or_else(|| Error::NotFound("Forge Installer Data".to_string()))?;
+        // Only the processors of Forge and NeoForge refer to installer data
+        let no_data = HashMap::new();
+        let data = match &meta.data {
+            Some(data) => data,
+            None if processors.iter().all(|processor| {
+                processor
+                    .args
+                    .iter()
+                    .all(|arg| !(arg.starts_with('{') && arg.ends_with('}')))
+            }) =>
+            {
+                &no_data
+            }
+            None => return Err(Error::NotFound("Forge Installer Data".to_string())),
+        };
 
         let libraries_path = config.get_libraries_path();
 
+        let state_path = config.get_version_path().join("processors.json");
+        let lyceris_version = env!("CARGO_PKG_VERSION").to_string();
+        let loader_version = config.loader.as_ref().map(|loader| loader.get_version());
+        let mut state: ProcessorState = if state_path.is_file() {
+            read_json(&state_path).await.unwrap_or_default()
+        } else {
+            ProcessorState::default()
+        };
+        if state.lyceris_version != lyceris_version || state.loader_version != loader_version {
+            state = ProcessorState {
+                lyceris_version,
+                loader_version,
+                completed: HashSet::new(),
+            };
+        }
+
+        let mut main_classes: HashMap<String, String> = HashMap::new();
         for processor in processors {
             if let Some(sides) = &processor.sides {
                 if !sides.contains(&"client".to_string()) {
@@ -409,6 +1165,28 @@ async fn execute_processors_if_exists(
                 continue;
             }
 
+            let args = processor
+                .args
+                .iter()
+                .map(|arg| resolve_processor_arg(arg, data, &libraries_path))
+                .collect::<Vec<_>>();
+
+            let key = processor_key(&processor.jar, &args);
+            if state.completed.contains(&key)
+                && processor_outputs_valid(processor, data, &libraries_path)
+            {
+                processor.success = true;
+                continue;
+            }
+
+            let jar_path = libraries_path
+                .join(parse_lib_path(&processor.jar)?)
+                .to_string_lossy()
+                .into_owned();
+
+            // The processor jar always comes last, and only once, since some
+            // JVMs fail to load classes from a classpath listing a jar twice.
+            let mut seen = HashSet::new();
             let classpath = processor
                 .classpath
                 .iter()
@@ -420,83 +1198,42 @@ async fn execute_processors_if_exists(
                             .into_owned(),
                     )
                 })
+                .filter(|path| *path != jar_path)
+                .chain(std::iter::once(jar_path.clone()))
+                .filter(|path| seen.insert(path.clone()))
                 .collect::<Vec<String>>()
                 .join(CLASSPATH_SEPARATOR);
 
-            let main_class = read_file_from_jar(
-                &libraries_path
-                    .join(parse_lib_path(&processor.jar)?)
-                    .to_string_lossy()
-                    .into_owned(),
-                "META-INF/MANIFEST.MF",
-            )?
-            .lines()
-            .find(|line| line.starts_with("Main-Class:"))
-            .ok_or_else(|| Error::NotFound("Main-Class of processor".to_string()))?
-            .split(":")
-            .last()
-            .ok_or_else(|| Error::NotFound("Main-Class of processor".to_string()))?
-            .trim()
-            .to_string();
-
-            let args = processor
-                .args
-                .iter()
-                .map(|arg| {
-                    let trimmed_arg = &arg[1..arg.len() - 1];
-                    if arg.starts_with('{') {
-                        if let Some(entry) = data.get(trimmed_arg) {
-                            if entry.client.starts_with('[') {
-                                if let Ok(parsed_path) =
-                                    parse_lib_path(&entry.client[1..entry.client.len() - 1])
-                                {
-                                    return libraries_path
-                                        .join(parsed_path)
-                                        .to_string_lossy()
-                                        .into_owned();
-                                }
-                            }
-                            return entry.client.clone();
-                        }
-                    } else if arg.starts_with('[') {
-                        if let Ok(parsed_path) = parse_lib_path(trimmed_arg) {
-                            return libraries_path
-                                .join(parsed_path)
-                                .to_string_lossy()
-                                .into_owned();
-                        }
-                    }
-
-                    arg.clone()
-                })
-                .collect::<Vec<_>>();
+            let main_class = match processor
+                .main_class
+                .as_ref()
+                .or_else(|| main_classes.get(&jar_path))
+            {
+                Some(main_class) => main_class.clone(),
+                None => {
+                    let main_class = read_file_from_jar(&jar_path, "META-INF/MANIFEST.MF")?
+                        .lines()
+                        .find(|line| line.starts_with("Main-Class:"))
+                        .ok_or_else(|| Error::NotFound("Main-Class of processor".to_string()))?
+                        .split(":")
+                        .last()
+                        .ok_or_else(|| Error::NotFound("Main-Class of processor".to_string()))?
+                        .trim()
+                        .to_string();
+                    main_classes.insert(jar_path.clone(), main_class.clone());
+                    main_class
+                }
+            };
 
-            let child = Command::new(
-                config
-                    .get_java_path(
-                        meta.java_version
-                            .as_ref()
-                            .unwrap_or(&JavaVersion::default()),
-                    )
-                    .await?,
-            )
-            .arg("-cp")
-            .arg(format!(
-                "{}{}{}",
-                classpath,
-                CLASSPATH_SEPARATOR,
-                libraries_path
-                    .join(parse_lib_path(&processor.jar)?)
-                    .to_string_lossy()
-                    .into_owned()
-            ))
-            .arg(main_class)
-            .args(args)
-            .output()
-            .await?;
+            let mut command = Command::new(config.get_java_path(&java_version).await?);
+            command.arg("-cp").arg(classpath).arg(main_class).args(args);
+            hide_java_console(&mut command, config);
+            let child = command.output().await?;
 
             if child.status.success() {
                 processor.success = true;
+                state.completed.insert(key);
+                write_json(&state_path, &state).await?;
             } else {
                 return Err(Error::Fail(format!(
                     "Processor failed: {}",
@@ -504,79 +1241,1313 @@ async fn execute_processors_if_exists(
                 )));
             }
         }
-    }
 
-    write_json(&config.get_version_json_path(), &meta).await?;
+        write_json(&config.get_version_json_path(), &meta).await?;
+    }
 
     Ok(())
 }
 
+/// Resolves a processor argument into its final value.
+///
+/// `{KEY}` arguments are looked up in the installer data and `[coordinate]`
+/// arguments are turned into paths inside the libraries directory.
+///
+/// # Parameters
+/// - `arg`: The raw processor argument.
+/// - `data`: The installer data.
+/// - `libraries_path`: The path to the libraries directory.
+///
+/// # Returns
+/// The resolved argument.
+fn resolve_processor_arg(arg: &str, data: &HashMap<String, Data>, libraries_path: &Path) -> String {
+    let library_path = |coordinate: &str| {
+        coordinate
+            .strip_prefix('[')
+            .and_then(|coordinate| coordinate.strip_suffix(']'))
+            .and_then(|coordinate| parse_lib_path(coordinate).ok())
+            .map(|path| libraries_path.join(path).to_string_lossy().into_owned())
+    };
+
+    if let Some(key) = arg.strip_prefix('{').and_then(|arg| arg.strip_suffix('}')) {
+        if let Some(entry) = data.get(key) {
+            return library_path(&entry.client).unwrap_or_else(|| entry.client.clone());
+        }
+    } else if let Some(path) = library_path(arg) {
+        return path;
+    }
+
+    arg.to_string()
+}
+
+/// Builds the key a processor is recorded under in the processor state.
+///
+/// # Parameters
+/// - `jar`: The coordinate of the processor jar.
+/// - `args`: The resolved arguments of the processor.
+///
+/// # Returns
+/// The SHA-1 hash of the jar and arguments as a hexadecimal string.
+fn processor_key(jar: &str, args: &[String]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(jar.as_bytes());
+    args.iter().for_each(|arg| {
+        hasher.update([0]);
+        hasher.update(arg.as_bytes());
+    });
+    format!("{:x}", hasher.finalize())
+}
+
+/// Checks whether the outputs of a processor are present with the expected hashes.
+///
+/// # Parameters
+/// - `processor`: The processor whose outputs should be checked.
+/// - `data`: The installer data.
+/// - `libraries_path`: The path to the libraries directory.
+///
+/// # Returns
+/// `true` if every output exists and matches its hash.
+fn processor_outputs_valid(
+    processor: &Processor,
+    data: &HashMap<String, Data>,
+    libraries_path: &Path,
+) -> bool {
+    processor.outputs.as_ref().is_none_or(|outputs| {
+        outputs.iter().all(|(path, sha1)| {
+            let path = resolve_processor_arg(path, data, libraries_path);
+            let sha1 = resolve_processor_arg(sha1, data, libraries_path);
+            calculate_sha1(path).is_ok_and(|actual| actual == sha1.trim_matches('\''))
+        })
+    })
+}
+
 /// Downloads the necessary files based on the provided file list.
 ///
 /// # Parameters
 /// - `files`: A vector of files to be downloaded.
-/// - `game_dir`: The directory where the game is installed.
+/// - `config`: The configuration of the installation, providing the game and
+///   assets directories, the HTTP client and the rate limit.
 /// - `legacy`: A flag indicating whether to handle legacy assets.
+/// - `shared_runtime`: The Java runtime directory if it is shared with other
+///   game directories, its files are only downloaded while holding its lock.
 /// - `emitter`: An optional emitter for logging progress.
-/// - `client`: An optional HTTP client for making requests.
+/// - `report`: The report that the download metrics are recorded in.
 ///
 /// # Returns
 /// A result indicating success or failure of the download process.
-async fn download_necessary(
+async fn download_necessary<T: Loader>(
     files: Vec<DownloadFile>,
-    game_dir: &Path,
+    config: &Config<T>,
     legacy: bool,
+    shared_runtime: Option<&Path>,
     emitter: Option<&Emitter>,
-    client: Option<&reqwest::Client>,
+    report: &mut InstallReport,
 ) -> crate::Result<()> {
-    let broken_ones: Vec<(String, PathBuf, FileType)> = files
+    let started = Instant::now();
+    let (broken_ones, cached_ones): (Vec<&DownloadFile>, Vec<&DownloadFile>) = files
         .par_iter()
-        .filter_map(|file| {
-            if file.url.is_empty() {
-                return None;
-            }
-            if !file.path.exists()
-                || (!file.sha1.is_empty() && calculate_sha1(&file.path).ok()? != file.sha1)
-            {
-                return Some((file.url.clone(), file.path.clone(), file.r#type.clone()));
-            }
-            None
+        .filter(|file| !file.url.is_empty())
+        .partition(|file| {
+            !file.path.exists()
+                || (file.has_checksum
+                    && calculate_sha1(&file.path).is_ok_and(|sha1| sha1 != file.sha1))
+        });
+    report.timings.hashing = started.elapsed();
+    report.files_reused = cached_ones.len();
+    report.files_repaired = broken_ones.iter().filter(|file| file.path.exists()).count();
+
+    for file in cached_ones {
+        emitter
+            .emit(
+                Event::FileCached,
+                (
+                    file.path.to_string_lossy().into_owned(),
+                    file.r#type.to_string(),
+                ),
+            )
+            .await;
+    }
+
+    // Every file type is downloaded concurrently, the report tells how long
+    // each of them took until its last file was finished.
+    // A host that keeps failing fails the remaining downloads right away.
+    let options = DownloadManyOptions {
+        client: config.client.clone(),
+        circuit_breaker: Some(Arc::new(tokio::sync::Mutex::new(CircuitBreaker::default()))),
+        rate_limit: config.rate_limit,
+        download: config.download_options,
+    };
+    let java_tag = FileType::Java.to_string();
+    let mut items: Vec<DownloadItem> = broken_ones
+        .into_iter()
+        .map(|file| DownloadItem {
+            url: file.url.clone(),
+            dest: file.path.clone(),
+            expected_hash: file.has_checksum.then(|| file.sha1.clone()),
+            fallback_url: file.fallback_url.clone(),
+            size: file.size,
+            tag: file.r#type.to_string(),
         })
         .collect();
+    // The files of a shared runtime wait for its lock, so they come last to
+    // let the other files download meanwhile.
+    if shared_runtime.is_some() {
+        items.sort_by_key(|item| item.tag == java_tag);
+    }
+    let paths: Vec<PathBuf> = items.iter().map(|item| item.dest.clone()).collect();
 
-    download_multiple(broken_ones, emitter, client).await?;
+    // The lock is released as soon as the last Java file is finished, so
+    // another installation sharing the runtime only waits for those.
+    let java_remaining = AtomicUsize::new(items.iter().filter(|item| item.tag == java_tag).count());
+    let runtime_lock = tokio::sync::Mutex::new(None);
+    let skipped = Mutex::new(HashSet::new());
+    let before_start = |item: &DownloadItem| {
+        let shared_runtime = shared_runtime.filter(|_| item.tag == java_tag);
+        let dest = item.dest.clone();
+        let expected_hash = item.expected_hash.clone();
+        let runtime_lock = &runtime_lock;
+        let skipped = &skipped;
+        async move {
+            let Some(shared_runtime) = shared_runtime else {
+                return Ok(true);
+            };
+            let mut lock = runtime_lock.lock().await;
+            if lock.is_none() {
+                *lock = Some(DirLock::acquire(shared_runtime).await?);
+            }
+            // Another installation may have downloaded the file meanwhile
+            let needed = !dest.is_file()
+                || expected_hash.is_some_and(|hash| {
+                    calculate_sha1(&dest).is_ok_and(|sha1| !sha1.eq_ignore_ascii_case(&hash))
+                });
+            if !needed {
+                skipped
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(dest);
+            }
+            Ok(needed)
+        }
+    };
+
+    let started = Instant::now();
+    let finished = Mutex::new(BTreeMap::new());
+    download_many_with(items, &options, emitter, before_start, |item, _| {
+        finished
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(item.tag.clone(), started.elapsed());
+        if item.tag == java_tag && java_remaining.fetch_sub(1, Ordering::SeqCst) == 1 {
+            // No Java file is waiting for the lock anymore, otherwise it is
+            // released when the downloads end.
+            if let Ok(mut lock) = runtime_lock.try_lock() {
+                lock.take();
+            }
+        }
+    })
+    .await?;
+    report
+        .timings
+        .downloads
+        .extend(finished.into_inner().unwrap_or_else(|e| e.into_inner()));
+    let skipped = skipped.into_inner().unwrap_or_else(|e| e.into_inner());
+    let paths: Vec<PathBuf> = paths
+        .into_iter()
+        .filter(|path| !skipped.contains(path))
+        .collect();
+    report.files_downloaded += paths.len();
+    report.bytes_downloaded += paths
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum::<u64>();
 
     if legacy {
-        files.par_iter().try_for_each(|file| {
-            if let FileType::Asset { is_virtual, is_map } = file.r#type {
-                let target_path = if is_virtual {
-                    game_dir
-                        .join("assets")
-                        .join("virtual")
-                        .join("legacy")
-                        .join(&file.file_name)
-                } else if is_map {
-                    game_dir.join("resources").join(&file.file_name)
-                } else {
-                    return None::<()>;
-                };
+        copy_legacy_assets(&files, &config.get_assets_path(), &config.get_game_dir())?;
+    }
 
-                if let Some(parent) = target_path.parent() {
-                    if !parent.is_dir() {
-                        fs::create_dir_all(parent).ok();
-                    }
+    Ok(())
+}
+
+/// Copies the downloaded objects of a legacy asset index to the locations
+/// the index asks for.
+///
+/// # Parameters
+/// - `files`: The files of the installation, only assets are copied.
+/// - `assets_path`: The assets directory, containing the virtual assets.
+/// - `game_dir`: The game directory, containing the resources.
+///
+/// # Returns
+/// A result indicating success or failure of the copies.
+fn copy_legacy_assets(
+    files: &[DownloadFile],
+    assets_path: &Path,
+    game_dir: &Path,
+) -> crate::Result<()> {
+    let errors = Mutex::new(Vec::new());
+    files.par_iter().for_each(|file| {
+        if let FileType::Asset { is_virtual, is_map } = file.r#type {
+            // Like the vanilla launcher, an index that is both virtual and
+            // mapped to resources gets copied to both locations.
+            let virtual_path = is_virtual.then(|| {
+                assets_path
+                    .join("virtual")
+                    .join("legacy")
+                    .join(&file.file_name)
+            });
+            let resources_path = is_map.then(|| game_dir.join("resources").join(&file.file_name));
+
+            for target_path in [virtual_path, resources_path].into_iter().flatten() {
+                if let Err(e) = copy_legacy_asset(file, &target_path) {
+                    errors
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(e.context(format!(
+                            "Could not copy {} to {}",
+                            file.file_name,
+                            target_path.display()
+                        )));
                 }
+            }
+        }
+    });
 
-                if !target_path.exists() || calculate_sha1(&target_path).ok()? != file.sha1 {
-                    fs::copy(&file.path, &target_path).ok();
+    let errors = errors.into_inner().unwrap_or_else(|e| e.into_inner());
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+/// Copies a downloaded asset object to its legacy location, unless an
+/// identical copy is already there.
+///
+/// # Parameters
+/// - `file`: The downloaded asset.
+/// - `target_path`: The legacy location of the asset.
+///
+/// # Returns
+/// A result indicating success or failure of the copy.
+fn copy_legacy_asset(file: &DownloadFile, target_path: &Path) -> crate::Result<()> {
+    if let Some(parent) = target_path.parent() {
+        if !parent.is_dir() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    if !target_path.exists() || calculate_sha1(target_path).is_ok_and(|sha1| sha1 != file.sha1) {
+        fs::copy(&file.path, target_path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minecraft::{config::ConfigBuilder, loader::fabric::Fabric};
+
+    fn asset(
+        dir: &Path,
+        file_name: &str,
+        content: &str,
+        is_virtual: bool,
+        is_map: bool,
+    ) -> DownloadFile {
+        let path = dir.join("objects").join(file_name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        DownloadFile {
+            file_name: file_name.to_string(),
+            sha1: calculate_sha1(&path).unwrap(),
+            url: String::new(),
+            path,
+            r#type: FileType::Asset { is_virtual, is_map },
+            has_checksum: true,
+            size: None,
+            fallback_url: None,
+        }
+    }
+
+    #[test]
+    fn legacy_assets_are_copied_to_both_locations() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets_path = dir.path().join("assets");
+        let game_dir = dir.path().join("game");
+        let files = [
+            asset(dir.path(), "sound/step.ogg", "step", true, true),
+            asset(dir.path(), "lang/en_US.lang", "lang", true, false),
+            asset(dir.path(), "music/menu.ogg", "menu", false, true),
+            asset(dir.path(), "icons/icon.png", "icon", false, false),
+        ];
+
+        copy_legacy_assets(&files, &assets_path, &game_dir).unwrap();
+
+        let virtual_path = assets_path.join("virtual").join("legacy");
+        let resources_path = game_dir.join("resources");
+        assert_eq!(
+            fs::read_to_string(virtual_path.join("sound/step.ogg")).unwrap(),
+            "step"
+        );
+        assert_eq!(
+            fs::read_to_string(resources_path.join("sound/step.ogg")).unwrap(),
+            "step"
+        );
+        assert!(virtual_path.join("lang/en_US.lang").is_file());
+        assert!(!resources_path.join("lang/en_US.lang").exists());
+        assert!(!virtual_path.join("music/menu.ogg").exists());
+        assert!(resources_path.join("music/menu.ogg").is_file());
+        assert!(!virtual_path.join("icons/icon.png").exists());
+        assert!(!resources_path.join("icons/icon.png").exists());
+    }
+
+    #[test]
+    fn outdated_legacy_copies_are_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets_path = dir.path().join("assets");
+        let game_dir = dir.path().join("game");
+        let files = [asset(dir.path(), "sound/step.ogg", "step", true, true)];
+        let copy = game_dir.join("resources").join("sound/step.ogg");
+        fs::create_dir_all(copy.parent().unwrap()).unwrap();
+        fs::write(&copy, "outdated").unwrap();
+
+        copy_legacy_assets(&files, &assets_path, &game_dir).unwrap();
+
+        assert_eq!(fs::read_to_string(copy).unwrap(), "step");
+    }
+
+    fn processor_data() -> HashMap<String, Data> {
+        let data = |client: &str| Data {
+            client: client.to_string(),
+            server: String::new(),
+        };
+        HashMap::from([
+            ("SIDE".to_string(), data("client")),
+            (
+                "MOJMAPS".to_string(),
+                data("[net.minecraft:client:1.20.1-20230612.114412:mappings@txt]"),
+            ),
+            ("MAPPINGS_SHA".to_string(), data("'a1b2c3'")),
+        ])
+    }
+
+    #[test]
+    fn processor_args_are_resolved() {
+        let data = processor_data();
+        let libraries = Path::new("libraries");
+        let library = |path: &str| libraries.join(path).to_string_lossy().into_owned();
+
+        assert_eq!(resolve_processor_arg("{SIDE}", &data, libraries), "client");
+        assert_eq!(
+            resolve_processor_arg("{MOJMAPS}", &data, libraries),
+            library("net/minecraft/client/1.20.1-20230612.114412/client-1.20.1-20230612.114412-mappings.txt")
+        );
+        assert_eq!(
+            resolve_processor_arg(
+                "[net.minecraftforge:binarypatcher:1.1.1:fatjar]",
+                &data,
+                libraries
+            ),
+            library("net/minecraftforge/binarypatcher/1.1.1/binarypatcher-1.1.1-fatjar.jar")
+        );
+        assert_eq!(
+            resolve_processor_arg("{MAPPINGS_SHA}", &data, libraries),
+            "'a1b2c3'"
+        );
+    }
+
+    #[test]
+    fn malformed_processor_args_are_kept() {
+        let data = processor_data();
+        let libraries = Path::new("libraries");
+        for arg in [
+            "",
+            "{",
+            "}",
+            "[",
+            "]",
+            "{}",
+            "[]",
+            "{UNKNOWN}",
+            "{SIDE",
+            "SIDE}",
+            "[a:b]",
+            "[a:b:c",
+            "é",
+            "{é}",
+            "[é]",
+            "--side",
+        ] {
+            assert_eq!(resolve_processor_arg(arg, &data, libraries), arg);
+        }
+    }
+
+    #[test]
+    fn processor_outputs_are_checked_against_their_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir
+            .path()
+            .join("net/minecraft/client/1.20.1-20230612.114412/client-1.20.1-20230612.114412-mappings.txt");
+        fs::create_dir_all(output.parent().unwrap()).unwrap();
+        fs::write(&output, "icon").unwrap();
+        let mut data = processor_data();
+        // The SHA-1 hash of "icon".
+        data.get_mut("MAPPINGS_SHA").unwrap().client =
+            "'f8995ba5891b07e328c60d6bd6c10159878c5a13'".to_string();
+        let mut processor = Processor {
+            classpath: Vec::new(),
+            args: Vec::new(),
+            sides: None,
+            outputs: Some(HashMap::from([(
+                "{MOJMAPS}".to_string(),
+                "{MAPPINGS_SHA}".to_string(),
+            )])),
+            jar: "net.minecraftforge:installertools:1.4.1:fatjar".to_string(),
+            main_class: None,
+            success: false,
+        };
+
+        assert!(processor_outputs_valid(&processor, &data, dir.path()));
+        fs::write(&output, "changed").unwrap();
+        assert!(!processor_outputs_valid(&processor, &data, dir.path()));
+        processor.outputs = None;
+        assert!(processor_outputs_valid(&processor, &data, dir.path()));
+    }
+
+    #[test]
+    fn processor_keys_depend_on_jar_and_args() {
+        let args = ["--task".to_string(), "MCP_DATA".to_string()];
+        let key = processor_key("net.minecraftforge:installertools:1.4.1:fatjar", &args);
+        assert_eq!(
+            key,
+            processor_key("net.minecraftforge:installertools:1.4.1:fatjar", &args)
+        );
+        assert_ne!(
+            key,
+            processor_key("net.minecraftforge:installertools:1.4.2:fatjar", &args)
+        );
+        assert_ne!(
+            key,
+            processor_key(
+                "net.minecraftforge:installertools:1.4.1:fatjar",
+                &["--taskMCP_DATA".to_string()]
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn libraries_excluded_everywhere_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::test_util::config(dir.path(), "1.8.9");
+        let mut json = crate::test_util::version_json("1.8.9");
+        json["libraries"] = serde_json::json!([{
+            "name": "com.example:excluded:1.0",
+            "rules": [{ "action": "disallow" }],
+            "downloads": {
+                "artifact": {
+                    "path": "com/example/excluded/1.0/excluded-1.0.jar",
+                    "sha1": "",
+                    "size": 0,
+                    "url": "https://libraries.minecraft.net/com/example/excluded/1.0/excluded-1.0.jar"
                 }
+            }
+        }]);
+        let meta: VersionMeta = serde_json::from_value(json).unwrap();
+        let asset_index: AssetIndex =
+            serde_json::from_value(serde_json::json!({ "objects": {} })).unwrap();
+        let java_files = JavaFileManifest {
+            files: HashMap::new(),
+        };
 
-                return None;
+        let mut warnings = Vec::new();
+        let files = build_file_map(
+            &asset_index,
+            &meta,
+            &java_files,
+            &config.get_runtime_path(),
+            &config.get_platform().unwrap(),
+            &config,
+            &mut Vec::new(),
+            &mut warnings,
+        )
+        .unwrap();
+
+        assert!(files.iter().all(|file| !file.url.contains("excluded")));
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(
+            warnings[0].code,
+            WarningCode::LibraryExcludedByRules
+        ));
+        assert!(warnings[0].message.contains("com.example:excluded:1.0"));
+
+        // The warning is expected for every OS specific library, so it only
+        // reaches the listeners of a verbose emitter.
+        let emitter = Emitter::default();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        emitter
+            .on_warning({
+                let received = received.clone();
+                move |warning| received.lock().unwrap().push(warning.code)
+            })
+            .await;
+        emitter.warn(warnings[0].clone()).await;
+        assert!(received.lock().unwrap().is_empty());
+        emitter.set_verbose(true);
+        emitter.warn(warnings[0].clone()).await;
+        assert!(matches!(
+            received.lock().unwrap()[..],
+            [WarningCode::LibraryExcludedByRules]
+        ));
+    }
+
+    #[tokio::test]
+    async fn file_types_download_concurrently() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+        /// Counts the received requests, holding back every response.
+        struct Counting(Arc<AtomicUsize>);
+
+        impl Respond for Counting {
+            fn respond(&self, _: &Request) -> ResponseTemplate {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                ResponseTemplate::new(200)
+                    .set_body_string("data")
+                    .set_delay(Duration::from_millis(500))
             }
+        }
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let server = MockServer::start().await;
+        Mock::given(wiremock::matchers::method("GET"))
+            .respond_with(Counting(received.clone()))
+            .mount(&server)
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::test_util::config(dir.path(), "1.8.9");
+        let sha1 = format!("{:x}", Sha1::digest("data"));
+        let file = |name: &str, r#type: FileType| DownloadFile {
+            file_name: name.to_string(),
+            sha1: sha1.clone(),
+            url: format!("{}/{}", server.uri(), name),
+            path: dir.path().join(name),
+            r#type,
+            has_checksum: true,
+            size: Some(4),
+            fallback_url: None,
+        };
+        let asset = || FileType::Asset {
+            is_virtual: false,
+            is_map: false,
+        };
+        let files = vec![
+            file("a", asset()),
+            file("b", asset()),
+            file("c", FileType::Library),
+            file("d", FileType::Java),
+        ];
+
+        let emitter = Emitter::default();
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        emitter
+            .on_multiple_download_progress({
+                let progress = progress.clone();
+                let received = received.clone();
+                move |payload| {
+                    progress.lock().unwrap().push((
+                        payload.current,
+                        payload.total,
+                        received.load(Ordering::SeqCst),
+                    ))
+                }
+            })
+            .await;
+
+        let mut report = InstallReport::default();
+        download_necessary(files, &config, false, None, Some(&emitter), &mut report)
+            .await
+            .unwrap();
+
+        // One batch after another would only have requested the assets by
+        // the time the first file is done.
+        let mut progress = progress.lock().unwrap().clone();
+        progress.sort();
+        assert_eq!(progress[0].2, 4);
+        assert_eq!(report.files_downloaded, 4);
+        assert_eq!(report.bytes_downloaded, 16);
+        assert_eq!(
+            report.timings.downloads.keys().collect::<Vec<_>>(),
+            ["Asset", "Java", "Library"]
+        );
+        // The progress counts the files of every type together.
+        let progress: Vec<_> = progress
+            .into_iter()
+            .map(|(current, total, _)| (current, total))
+            .collect();
+        assert_eq!(progress, [(1, 4), (2, 4), (3, 4), (4, 4)]);
+    }
+
+    #[tokio::test]
+    async fn shared_runtime_is_downloaded_once() {
+        use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let response = ResponseTemplate::new(200)
+            .set_body_string("data")
+            .set_delay(Duration::from_millis(800));
+        Mock::given(path("/java"))
+            .respond_with(response.clone())
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/asset"))
+            .respond_with(response)
+            .expect(2)
+            .mount(&server)
+            .await;
+        let runtime = tempfile::tempdir().unwrap();
+        let sha1 = format!("{:x}", Sha1::digest("data"));
+        let file = |name: &str, path: PathBuf, r#type: FileType| DownloadFile {
+            file_name: name.to_string(),
+            sha1: sha1.clone(),
+            url: format!("{}/{}", server.uri(), name),
+            path,
+            r#type,
+            has_checksum: true,
+            size: Some(4),
+            fallback_url: None,
+        };
+        let install = |game_dir: PathBuf| {
+            let files = vec![
+                file(
+                    "asset",
+                    game_dir.join("asset"),
+                    FileType::Asset {
+                        is_virtual: false,
+                        is_map: false,
+                    },
+                ),
+                file("java", runtime.path().join("java"), FileType::Java),
+            ];
+            let config = crate::test_util::config(&game_dir, "1.8.9");
+            let runtime = runtime.path();
+            async move {
+                let mut report = InstallReport::default();
+                download_necessary(files, &config, false, Some(runtime), None, &mut report)
+                    .await
+                    .map(|_| report.files_downloaded)
+            }
+        };
+
+        let (first, second) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let (first, second) = tokio::join!(
+            install(first.path().to_path_buf()),
+            install(second.path().to_path_buf())
+        );
+
+        // The second installation waited for the Java file instead of
+        // downloading it again.
+        assert_eq!(first.unwrap() + second.unwrap(), 3);
+        assert!(!runtime.path().with_extension("lock").exists());
+    }
+
+    /// Writes a classifier jar containing a native library, returning the
+    /// classifiers to extract.
+    fn classifier(dir: &Path, contents: &str) -> BTreeMap<String, String> {
+        let path = dir.join("lwjgl-platform-2.9.4-natives-linux.jar");
+        crate::test_util::write_zip(&path, &[("liblwjgl.so", contents)]);
+        let sha1 = calculate_sha1(&path).unwrap();
+        BTreeMap::from([(path.to_string_lossy().into_owned(), sha1)])
+    }
+
+    #[tokio::test]
+    async fn natives_are_kept_apart_per_loader() {
+        let dir = tempfile::tempdir().unwrap();
+        let game_dir = dir.path().join("game");
+        let vanilla = crate::test_util::config(&game_dir, "1.8.9");
+        let fabric = ConfigBuilder::offline(&game_dir, "1.8.9".to_string(), "Player".to_string())
+            .loader(Box::new(Fabric("0.16.9".to_string())))
+            .build();
+        // Natives extracted before they were kept apart per version name.
+        let legacy_path = vanilla.get_natives_path().join("1.8.9");
+        fs::create_dir_all(&legacy_path).unwrap();
+        fs::write(legacy_path.join("liblwjgl.so"), "outdated").unwrap();
+        crate::test_util::write_version(
+            &vanilla,
+            "1.8.9",
+            &crate::test_util::version_json("1.8.9"),
+        )
+        .await;
+
+        let classifiers = classifier(dir.path(), "lwjgl");
+        assert_eq!(
+            extract_natives(&vanilla, classifiers.clone())
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            extract_natives(&fabric, classifiers.clone()).await.unwrap(),
+            1
+        );
+
+        let vanilla_path = vanilla.get_natives_path_for_version();
+        let fabric_path = fabric.get_natives_path_for_version();
+        assert_eq!(vanilla_path, legacy_path);
+        assert_eq!(fabric_path, game_dir.join("natives").join("1.8.9-0.16.9"));
+        for path in [&vanilla_path, &fabric_path] {
+            assert_eq!(
+                fs::read_to_string(path.join("liblwjgl.so")).unwrap(),
+                "lwjgl"
+            );
+            assert!(path.join(NATIVES_MANIFEST_FILE).is_file());
+        }
+        assert_eq!(
+            extract_natives(&vanilla, classifiers.clone())
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(extract_natives(&fabric, classifiers).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn legacy_natives_of_a_loader_are_moved() {
+        let dir = tempfile::tempdir().unwrap();
+        let game_dir = dir.path().join("game");
+        let fabric = ConfigBuilder::offline(&game_dir, "1.8.9".to_string(), "Player".to_string())
+            .loader(Box::new(Fabric("0.16.9".to_string())))
+            .build();
+        let legacy_path = fabric.get_natives_path().join("1.8.9");
+        fs::create_dir_all(&legacy_path).unwrap();
+        fs::write(legacy_path.join("liblwjgl.so"), "outdated").unwrap();
+
+        let classifiers = classifier(dir.path(), "lwjgl");
+        assert_eq!(extract_natives(&fabric, classifiers).await.unwrap(), 1);
+
+        let natives_path = fabric.get_natives_path_for_version();
+        assert!(!legacy_path.exists());
+        assert_eq!(
+            fs::read_to_string(natives_path.join("liblwjgl.so")).unwrap(),
+            "lwjgl"
+        );
+    }
+
+    #[tokio::test]
+    async fn interrupted_extraction_is_repaired() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::test_util::config(&dir.path().join("game"), "1.8.9");
+        let classifiers = classifier(dir.path(), "lwjgl");
+        assert!(!verify_natives(&config).await.unwrap());
+        extract_natives(&config, classifiers.clone()).await.unwrap();
+        assert!(verify_natives(&config).await.unwrap());
+
+        let native = config.get_natives_path_for_version().join("liblwjgl.so");
+        fs::remove_file(&native).unwrap();
+
+        assert!(!verify_natives(&config).await.unwrap());
+        assert_eq!(extract_natives(&config, classifiers).await.unwrap(), 1);
+        assert_eq!(fs::read_to_string(native).unwrap(), "lwjgl");
+        assert!(verify_natives(&config).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn changed_classifiers_are_extracted_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::test_util::config(&dir.path().join("game"), "1.8.9");
+        extract_natives(&config, classifier(dir.path(), "lwjgl"))
+            .await
+            .unwrap();
+
+        let classifiers = classifier(dir.path(), "lwjgl 2");
+        assert_eq!(extract_natives(&config, classifiers).await.unwrap(), 1);
+        let native = config.get_natives_path_for_version().join("liblwjgl.so");
+        assert_eq!(fs::read_to_string(native).unwrap(), "lwjgl 2");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn refreshed_legacy_index_copies_known_objects() {
+        use serde_json::json;
+        use wiremock::{matchers::path, Mock, ResponseTemplate};
 
-            None
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let hash = |contents: &str| format!("{:x}", Sha1::digest(contents));
+        for contents in ["a", "b"] {
+            Mock::given(path(format!(
+                "/{}/{}",
+                &hash(contents)[..2],
+                hash(contents)
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_string(contents))
+            .mount(&server)
+            .await;
+        }
+        let object = |contents: &str| json!({ "hash": hash(contents), "size": 1 });
+        let index = |objects| {
+            ResponseTemplate::new(200).set_body_json(json!({ "virtual": true, "objects": objects }))
+        };
+        Mock::given(path("/indexes/1.8.9.json"))
+            .respond_with(index(json!({ "a.txt": object("a") })))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        // The refreshed index gives the known object a new name.
+        Mock::given(path("/indexes/1.8.9.json"))
+            .respond_with(index(json!({
+                "a.txt": object("a"),
+                "copy of a.txt": object("a"),
+                "b.txt": object("b"),
+            })))
+            .with_priority(2)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        config.asset_index_max_age = Some(Duration::ZERO);
+        install(&config, None).await.unwrap();
+        let legacy_path = config.get_assets_path().join("virtual").join("legacy");
+        fs::remove_file(legacy_path.join("a.txt")).unwrap();
+
+        install(&config, None).await.unwrap();
+        for (name, contents) in [("a.txt", "a"), ("copy of a.txt", "a"), ("b.txt", "b")] {
+            assert_eq!(
+                fs::read_to_string(legacy_path.join(name)).unwrap(),
+                contents
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn packs_are_enabled_only_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        let meta: VersionMeta =
+            serde_json::from_value(crate::test_util::version_json("1.8.9")).unwrap();
+        let pack =
+            |name: &str| ProvisionedPack::new(format!("https://example.com/{}", name), None, true);
+        config.resource_packs = vec![pack("server.zip")];
+        config.shader_packs = vec![pack("shaders.zip")];
+        let options_path = dir.path().join(OPTIONS_FILE);
+        let shaders_path = dir.path().join("optionsshaders.txt");
+
+        enable_packs(&config, &meta).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(&options_path).unwrap(),
+            "resourcePacks:[\"server.zip\"]\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&shaders_path).unwrap(),
+            "shaderPack=shaders.zip\n"
+        );
+
+        // The user turns the packs off, which the next installation keeps.
+        fs::write(&options_path, "resourcePacks:[]\n").unwrap();
+        fs::write(&shaders_path, "shaderPack=OFF\n").unwrap();
+        enable_packs(&config, &meta).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(&options_path).unwrap(),
+            "resourcePacks:[]\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&shaders_path).unwrap(),
+            "shaderPack=OFF\n"
+        );
+
+        // A pack provisioned later is still enabled.
+        config.resource_packs.push(pack("event.zip"));
+        enable_packs(&config, &meta).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(&options_path).unwrap(),
+            "resourcePacks:[\"event.zip\"]\n"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn target_platform_selects_windows_natives() {
+        use serde_json::{json, Value};
+        use wiremock::{matchers::path, Mock, ResponseTemplate};
+
+        use crate::minecraft::platform::{TargetArch, TargetOs};
+
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let mirror = crate::test_util::mirror(&server);
+        let get = |url: String| async move {
+            let json = reqwest::get(&url)
+                .await
+                .unwrap()
+                .json::<Value>()
+                .await
+                .unwrap();
+            (reqwest::Url::parse(&url).unwrap().path().to_string(), json)
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let mut classifiers = serde_json::Map::new();
+        for (classifier, native) in [
+            ("natives-linux", "liblwjgl.so"),
+            ("natives-windows", "lwjgl.dll"),
+        ] {
+            let jar_path = dir.path().join(format!("{}.jar", classifier));
+            crate::test_util::write_zip(&jar_path, &[(native, classifier)]);
+            let jar = fs::read(&jar_path).unwrap();
+            let url_path = format!("/lwjgl-platform-2.9.4-{}.jar", classifier);
+            classifiers.insert(
+                classifier.to_string(),
+                json!({
+                    "path": format!("org/lwjgl/lwjgl/lwjgl-platform/2.9.4/lwjgl-platform-2.9.4-{}.jar", classifier),
+                    "sha1": format!("{:x}", Sha1::digest(&jar)),
+                    "size": jar.len(),
+                    "url": format!("https://libraries.minecraft.net{}", url_path),
+                }),
+            );
+            Mock::given(path(url_path))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(jar))
+                .mount(&server)
+                .await;
+        }
+
+        let (version_path, mut version) =
+            get(format!("{}/versions/1.8.9.json", server.uri())).await;
+        version["libraries"] = json!([{
+            "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+            "natives": { "linux": "natives-linux", "windows": "natives-windows" },
+            "downloads": { "classifiers": classifiers },
+        }]);
+        Mock::given(path(version_path))
+            .respond_with(ResponseTemplate::new(200).set_body_json(version))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        let (java_manifest_path, mut java_manifest) =
+            get(mirror.resolve(JAVA_MANIFEST_ENDPOINT)).await;
+        java_manifest["windows-x64"] = java_manifest["linux"].clone();
+        Mock::given(path(java_manifest_path))
+            .respond_with(ResponseTemplate::new(200).set_body_json(java_manifest))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+
+        let mut config = crate::test_util::config(&dir.path().join("game"), "1.8.9");
+        config.mirrors = vec![mirror];
+        config.target_platform = Some(Platform::new(TargetOs::Windows, TargetArch::X86_64));
+        install(&config, None).await.unwrap();
+
+        let natives_path = config.get_natives_path_for_version();
+        assert_eq!(
+            fs::read_to_string(natives_path.join("lwjgl.dll")).unwrap(),
+            "natives-windows"
+        );
+        assert!(!natives_path.join("liblwjgl.so").exists());
+    }
+
+    #[tokio::test]
+    async fn installed_runtime_makes_no_java_requests() {
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        let java_requests = || async {
+            server
+                .received_requests()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|request| request.url.path().to_string())
+                .filter(|path| path.contains("java"))
+                .collect::<Vec<_>>()
+        };
+
+        install(&config, None).await.unwrap();
+        let installed = java_requests().await.len();
+        install(&config, None).await.unwrap();
+        assert_eq!(java_requests().await.len(), installed);
+
+        // A damaged runtime is repaired from the file manifest it was
+        // installed from, which Mojang still lists.
+        let runtime_path = config.get_runtime_path().join("jre-legacy");
+        fs::remove_file(runtime_path.join("lib/rt.jar")).unwrap();
+        install(&config, None).await.unwrap();
+        assert!(runtime_path.join("lib/rt.jar").is_file());
+        assert_eq!(
+            java_requests().await[installed..],
+            [
+                "/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json",
+                "/java/lib/rt.jar"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn reinstall_after_deleting_the_version_json_reuses_outputs() {
+        use serde_json::{json, Value};
+        use wiremock::{matchers::path, Mock, ResponseTemplate};
+
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let mut version: Value = reqwest::get(format!("{}/versions/1.8.9.json", server.uri()))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        version["processors"] = json!([{
+            "jar": "net.example:processor:1.0",
+            "classpath": [],
+            "args": ["--processor"],
+            "main_class": "net.example.Processor",
+        }]);
+        Mock::given(path("/versions/1.8.9.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(version))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        let requested_files = || async {
+            server
+                .received_requests()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|request| request.url.path().to_string())
+                .filter(|path| !path.ends_with(".json"))
+                .collect::<Vec<_>>()
+        };
+
+        install(&config, None).await.unwrap();
+        let downloaded = requested_files().await;
+        fs::remove_file(config.get_version_json_path()).unwrap();
+        let report = install(&config, None).await.unwrap();
+
+        assert!(config.get_version_json_path().is_file());
+        assert_eq!(report.files_downloaded, 0);
+        assert_eq!(requested_files().await, downloaded);
+        let log =
+            fs::read_to_string(config.get_runtime_path().join("jre-legacy/bin/java.log")).unwrap();
+        assert_eq!(log.matches("net.example.Processor").count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn overridden_directories_are_used() {
+        use serde_json::{json, Value};
+        use wiremock::{matchers::path, Mock, ResponseTemplate};
+
+        // The SHA-1 hash of "icon".
+        let hash = "f8995ba5891b07e328c60d6bd6c10159878c5a13";
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let mut version: Value = reqwest::get(format!("{}/versions/1.8.9.json", server.uri()))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        version["libraries"] = json!([{
+            "name": "net.example:library:1.0",
+            "downloads": { "artifact": {
+                "path": "net/example/library/1.0/library-1.0.jar",
+                "size": 7,
+                "url": "https://libraries.minecraft.net/net/example/library/1.0/library-1.0.jar",
+            } },
+        }]);
+        let responses = [
+            ("/versions/1.8.9.json".to_string(), version.to_string()),
+            (
+                "/indexes/1.8.9.json".to_string(),
+                json!({ "objects": { "icons/icon_16x16.png": { "hash": hash, "size": 4 } } })
+                    .to_string(),
+            ),
+            (format!("/f8/{}", hash), "icon".to_string()),
+            (
+                "/net/example/library/1.0/library-1.0.jar".to_string(),
+                "library".to_string(),
+            ),
+        ];
+        for (file, body) in responses {
+            Mock::given(path(file))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .with_priority(1)
+                .mount(&server)
+                .await;
+        }
+        let (dir, shared) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        config.assets_dir = Some(shared.path().join("assets"));
+        config.libraries_dir = Some(shared.path().join("libraries"));
+        config.versions_dir = Some(shared.path().join("versions"));
+
+        install(&config, None).await.unwrap();
+        let mut child = crate::minecraft::launch::launch(&config, None)
+            .await
+            .unwrap();
+        child.wait().await.unwrap();
+
+        for name in ["assets", "libraries", "versions"] {
+            assert!(!dir.path().join(name).exists(), "{}", name);
+        }
+        let library = shared
+            .path()
+            .join("libraries/net/example/library/1.0/library-1.0.jar");
+        assert!(library.is_file());
+        assert!(shared.path().join("assets/objects/f8").join(hash).is_file());
+        assert!(shared.path().join("versions/1.8.9/1.8.9.jar").is_file());
+        let log =
+            fs::read_to_string(config.get_runtime_path().join("jre-legacy/bin/java.log")).unwrap();
+        assert!(log.contains(&library.to_string_lossy().into_owned()));
+    }
+
+    /// Starts a server whose version manifest is unavailable.
+    async fn unavailable_manifest_server() -> wiremock::MockServer {
+        use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/mc/game/version_manifest_v2.json"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn cached_manifest_is_used_offline() {
+        let server = unavailable_manifest_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::test_util::config(dir.path(), "1.8.9");
+        let cached = serde_json::json!({
+            "latest": { "release": "1.8.9", "snapshot": "1.8.9" },
+            "versions": [{
+                "id": "1.8.9",
+                "type": "release",
+                "url": "https://piston-meta.mojang.com/v1/packages/1.8.9.json",
+                "time": "",
+                "releaseTime": "",
+            }],
         });
+        write_json(
+            &config.get_indexes_path().join(MANIFEST_CACHE_FILE),
+            &cached,
+        )
+        .await
+        .unwrap();
+        let emitter = Emitter::default();
+        let offline = Arc::new(Mutex::new(Vec::new()));
+        emitter
+            .on_offline_mode({
+                let offline = offline.clone();
+                move |error| offline.lock().unwrap().push(error.to_string())
+            })
+            .await;
+
+        let manifest = fetch_manifest(
+            &config,
+            Some(&crate::test_util::mirror(&server)),
+            Some(&emitter),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(manifest.latest.release, "1.8.9");
+        assert_eq!(manifest.versions[0].id, "1.8.9");
+        assert_eq!(offline.lock().unwrap().len(), 1);
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn offline_without_a_cached_manifest_fails() {
+        let server = unavailable_manifest_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::test_util::config(dir.path(), "1.8.9");
+
+        let result = fetch_manifest(&config, Some(&crate::test_util::mirror(&server)), None).await;
+
+        assert!(matches!(result, Err(Error::OfflineNoCache)));
+    }
+
+    #[tokio::test]
+    async fn library_checksums_are_verified_when_present() {
+        use serde_json::{json, Value};
+        use wiremock::{matchers::path, Mock, ResponseTemplate};
+
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let mut version: Value = reqwest::get(format!("{}/versions/1.8.9.json", server.uri()))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let library = |name: &str, sha1: Option<&str>| {
+            let path = format!("net/example/{0}/1.0/{0}-1.0.jar", name);
+            let mut artifact = json!({
+                "path": path,
+                "size": 5,
+                "url": format!("https://libraries.minecraft.net/{}", path),
+            });
+            if let Some(sha1) = sha1 {
+                artifact["sha1"] = json!(sha1);
+            }
+            json!({
+                "name": format!("net.example:{}:1.0", name),
+                "downloads": { "artifact": artifact },
+            })
+        };
+        // The SHA-1 hash of "fresh".
+        let hash = "67a4c84cb83788005285d9c9e6f6d6c046b4c39e";
+        version["libraries"] = json!([library("unhashed", None), library("hashed", Some(hash))]);
+        let responses = [
+            ("/versions/1.8.9.json", version.to_string()),
+            (
+                "/net/example/unhashed/1.0/unhashed-1.0.jar",
+                "any".to_string(),
+            ),
+            (
+                "/net/example/hashed/1.0/hashed-1.0.jar",
+                "fresh".to_string(),
+            ),
+        ];
+        for (file, body) in responses {
+            Mock::given(path(file))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .with_priority(1)
+                .mount(&server)
+                .await;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        let hashed = config
+            .get_libraries_path()
+            .join("net/example/hashed/1.0/hashed-1.0.jar");
+        let unhashed = config
+            .get_libraries_path()
+            .join("net/example/unhashed/1.0/unhashed-1.0.jar");
+        fs::create_dir_all(hashed.parent().unwrap()).unwrap();
+        fs::write(&hashed, "stale").unwrap();
+
+        install(&config, None).await.unwrap();
+
+        // The library without a checksum is taken as the server sends it.
+        assert_eq!(fs::read_to_string(&unhashed).unwrap(), "any");
+        // The library with the wrong checksum is downloaded again.
+        assert_eq!(fs::read_to_string(&hashed).unwrap(), "fresh");
+        let metadata =
+            read_version_meta(&config.get_version_json_path(), &config.get_versions_path())
+                .await
+                .unwrap();
+        let artifact = |name: &str| {
+            metadata
+                .libraries
+                .iter()
+                .find(|lib| lib.name == format!("net.example:{}:1.0", name))
+                .and_then(|lib| lib.downloads.as_ref()?.artifact.clone())
+                .unwrap()
+        };
+        assert_eq!(artifact("unhashed").sha1, "");
+        assert_eq!(artifact("hashed").sha1, hash);
+    }
 }
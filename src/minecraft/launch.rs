@@ -1,24 +1,177 @@
-use std::{collections::HashMap, fs::create_dir_all, process::Stdio};
-
-use tokio::{
-    io::{AsyncBufReadExt, BufReader},
-    process::{Child, Command},
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    env::consts::EXE_EXTENSION,
+    fs::{self, create_dir_all, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+    time::Duration,
 };
+
+use serde::{Deserialize, Serialize};
+use tokio::process::{Child, Command};
 use uuid::Uuid;
 
 use crate::{
-    auth::AuthMethod,
+    auth::{check_username, sanitize_username, AuthMethod},
     error::Error,
-    json::version::meta::vanilla::{Arguments, Element, Value, VersionMeta},
-    minecraft::{config::Memory, emitter::Event, parse::ParseRule},
-    util::json::read_json,
+    json::version::meta::vanilla::{
+        Arguments, Element, FeatureFlags, Features, JavaVersion, Value, VersionMeta,
+    },
+    minecraft::{
+        config::{Memory, ProcessPriority, QuickPlay},
+        inheritance::{missing_parent, read_version_meta},
+        parse::ParseRule,
+    },
+};
+
+use super::{
+    config::Config,
+    console::{spawn_reader, EarlyOutput, ExitSummary},
+    CLASSPATH_SEPARATOR,
+};
+use super::{
+    emitter::{Emit, Emitter, Event, Warning, WarningCode},
+    loader::Loader,
+    platform::Platform,
 };
 
-use super::{config::Config, CLASSPATH_SEPARATOR};
-use super::{emitter::Emitter, loader::Loader};
+/// The file in the game directory holding the process id of the running
+/// game.
+const INSTANCE_LOCK_FILE: &str = "lyceris-instance.lock";
+
+/// What happens to the game process when its handle is dropped.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnDrop {
+    /// The game keeps running on its own, this is the default.
+    #[default]
+    Detach,
+    /// The game is killed along with its handle.
+    Kill,
+}
+
+/// Options of a single launch, which unlike the `Config` don't carry over to
+/// the next launch.
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    /// Game arguments appended for this launch, e.g. `--quickPlayMultiplayer`.
+    /// Options that are already passed are skipped along with their values.
+    pub extra_game_args: Vec<String>,
+    /// JVM arguments appended for this launch. Options that are already
+    /// passed are skipped.
+    pub extra_jvm_args: Vec<String>,
+    /// Launcher features the argument rules are evaluated with, by their name
+    /// in the version JSON. These override the built-in features, which are
+    /// `is_demo_user`, `has_custom_resolution` and the quick play features.
+    pub features: FeatureFlags,
+    /// File that receives every line of the game output with a timestamp.
+    /// It is rotated past `ConsoleOptions::log_file_max_size`.
+    pub log_file: Option<PathBuf>,
+    /// Whether to run `java -version` before launching, which catches a
+    /// damaged Java executable at the cost of starting a JVM. Only the files
+    /// of the runtime are checked otherwise.
+    pub verify_runtime_execution: bool,
+    /// What happens to the game when the returned `GameInstance` is dropped,
+    /// for example when the launcher panics before waiting for it.
+    pub on_drop: OnDrop,
+}
+
+/// A game launched by `launch_with_options`.
+#[derive(Debug)]
+pub struct GameInstance {
+    child: Child,
+    early_output: Arc<EarlyOutput>,
+}
+
+impl GameInstance {
+    /// Gets the process id of the game.
+    ///
+    /// # Returns
+    /// The process id, `None` once the game has been awaited.
+    pub fn id(&self) -> Option<u32> {
+        self.child.id()
+    }
+
+    /// Gets the game process.
+    pub fn child(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// Takes the game process out of the handle.
+    pub fn into_child(self) -> Child {
+        self.child
+    }
+
+    /// Waits for the game to exit.
+    ///
+    /// A game that exits with an error within 15 seconds of launching most
+    /// likely failed to start, in which case its first output is reported in
+    /// `ExitSummary::early_output` and emitted as an `Event::EarlyExit`.
+    ///
+    /// # Parameters
+    /// - `emitter`: An optional emitter that receives the early exit.
+    ///
+    /// # Returns
+    /// A result containing how the game exited.
+    pub async fn wait_for_exit(&mut self, emitter: Option<&Emitter>) -> crate::Result<ExitSummary> {
+        let status = self.child.wait().await?;
+        let early_output = self.early_output.take_early_exit(&status).await;
+        if let Some(early_exit) = &early_output {
+            emitter.emit(Event::EarlyExit, early_exit.clone()).await;
+        }
+        Ok(ExitSummary {
+            status,
+            early_output,
+        })
+    }
+
+    /// Kills the game along with the processes it spawned.
+    ///
+    /// On Unix the whole process group of the game is killed, on Windows
+    /// every descendant of the game is terminated before the game itself.
+    ///
+    /// # Returns
+    /// A result indicating whether the processes could be killed.
+    pub async fn kill_tree(&mut self) -> crate::Result<()> {
+        let child = &mut self.child;
+        let Some(pid) = child.id() else {
+            // The game has already exited and been reaped.
+            return Ok(());
+        };
+
+        // SAFETY: `kill` only sends a signal, the negative pid addresses the
+        // process group the game leads.
+        #[cfg(unix)]
+        let killed = unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) } == 0;
+        #[cfg(windows)]
+        let killed = {
+            kill_descendants(pid);
+            child.start_kill().is_ok()
+        };
+        #[cfg(not(any(unix, windows)))]
+        let killed = child.start_kill().is_ok();
+
+        if !killed {
+            // The processes may have exited in the meantime, in which case
+            // there is nothing left to kill.
+            if child.try_wait()?.is_none() {
+                return Err(Error::Fail(format!(
+                    "Could not kill the process tree of {}",
+                    pid
+                )));
+            }
+        }
+
+        child.wait().await?;
+        Ok(())
+    }
+}
 
 /// Launches the Minecraft game with the specified configuration and arguments.
 ///
+/// See [`launch_with_options`] for what is read and returned.
+///
 /// # Parameters
 /// - `config`: The configuration for the Minecraft launch.
 /// - `emitter`: An optional emitter for logging progress.
@@ -29,25 +182,104 @@ pub async fn launch<T: Loader>(
     config: &Config<T>,
     emitter: Option<&Emitter>,
 ) -> crate::Result<Child> {
+    launch_with_options(config, &LaunchOptions::default(), emitter)
+        .await
+        .map(GameInstance::into_child)
+}
+
+/// Launches the Minecraft game with the specified configuration and the
+/// arguments of this launch.
+///
+/// Only files on the disk are read, so a version installed before launches
+/// without a network connection.
+///
+/// # Parameters
+/// - `config`: The configuration for the Minecraft launch.
+/// - `options`: The options of this launch.
+/// - `emitter`: An optional emitter for logging progress.
+///
+/// # Returns
+/// A result containing the handle of the game, `Error::InvalidUsername` if
+/// the offline username isn't accepted by vanilla servers and
+/// `Config::sanitize_username` isn't set, `Error::InstanceRunning` if a game
+/// already runs or launches in the game directory, `Error::NotInstalled` if a
+/// file required to launch is missing or `Error::BrokenJavaRuntime` if files
+/// of the Java runtime are missing or, with
+/// `LaunchOptions::verify_runtime_execution`, it doesn't run.
+pub async fn launch_with_options<T: Loader>(
+    config: &Config<T>,
+    options: &LaunchOptions,
+    emitter: Option<&Emitter>,
+) -> crate::Result<GameInstance> {
+    if let AuthMethod::Offline { username, .. } = &config.authentication {
+        if !config.sanitize_username {
+            check_username(username)?;
+        }
+    }
+
+    // Two games sharing a directory corrupt each other's worlds, so the
+    // directory is locked before anything else is checked.
+    let current_dir = config.get_game_path();
+    create_dir_all(&current_dir)?;
+    let lock = InstanceLock::acquire(current_dir.join(INSTANCE_LOCK_FILE)).await?;
+    let platform = config.get_platform()?;
+
     let version_name = config.get_version_name();
+    let version_json_path = config.get_version_json_path();
+    if !version_json_path.is_file() {
+        return Err(Error::NotInstalled(format!("Version {}", version_name)));
+    }
+    if let Some(parent) = missing_parent(&version_json_path, &config.get_versions_path()).await? {
+        return Err(Error::NotInstalled(format!("Parent version {}", parent)));
+    }
+    if !config.get_version_jar_path().is_file() {
+        return Err(Error::NotInstalled(format!(
+            "Client jar of {}",
+            version_name
+        )));
+    }
+
     let mut arguments = Vec::<String>::with_capacity(100);
-    let meta: VersionMeta = read_json(&config.get_version_json_path()).await?;
-    let profile_dir = config.profile.clone().map(|p| p.root.join(p.name));
-    let current_dir = profile_dir.as_ref().unwrap_or(&config.game_dir);
-
-    let meta_arguments = meta.arguments.unwrap_or_else(|| Arguments {
-        game: meta
-            .minecraft_arguments
-            .unwrap_or_default()
-            .split_whitespace()
-            .map(|argument| Element::String(argument.to_string()))
-            .collect(),
-        jvm: vec![
-            Element::String("-Djava.library.path=${natives_directory}".to_string()),
-            Element::String("-cp".to_string()),
-            Element::String("${classpath}".to_string()),
-        ],
+    let meta: VersionMeta =
+        read_version_meta(&version_json_path, &config.get_versions_path()).await?;
+    let java_version = config.get_java_version(&meta).await;
+    if !config
+        .get_runtime_path()
+        .join(&java_version.component)
+        .is_dir()
+    {
+        return Err(Error::NotInstalled(format!(
+            "Java runtime {}",
+            java_version.component
+        )));
+    }
+    if !verify_runtime(config, &java_version, options.verify_runtime_execution).await? {
+        return Err(Error::BrokenJavaRuntime(java_version.component));
+    }
+
+    let is_legacy = !meta.is_modern_arguments();
+    let mut meta_arguments = meta.arguments.unwrap_or_else(|| Arguments {
+        game: Vec::new(),
+        jvm: Vec::new(),
     });
+    // Loaders add their arguments to legacy versions next to the string
+    if let Some(minecraft_arguments) = meta.minecraft_arguments {
+        meta_arguments.game.splice(
+            0..0,
+            minecraft_arguments
+                .split_whitespace()
+                .map(|argument| Element::String(argument.to_string())),
+        );
+    }
+    // Versions with minimal metadata, and loaders that only add their own JVM
+    // arguments to them, rely on the defaults of the vanilla launcher.
+    if !meta_arguments
+        .jvm
+        .iter()
+        .any(|arg| matches!(arg, Element::String(arg) if arg == "${classpath}"))
+    {
+        meta_arguments.jvm.splice(0..0, Arguments::default_jvm());
+    }
 
     let mut variables = HashMap::<&'static str, String>::with_capacity(20);
 
@@ -71,8 +303,13 @@ pub async fn launch<T: Loader>(
             insert_var("${user_type}", "msa".to_string());
         }
         AuthMethod::Offline { username, uuid } => {
+            let username = if config.sanitize_username {
+                sanitize_username(username)
+            } else {
+                username.to_string()
+            };
             let uuid = uuid.clone().unwrap_or(Uuid::new_v4().to_string());
-            insert_var("${auth_player_name}", username.to_string());
+            insert_var("${auth_player_name}", username);
             insert_var("${auth_xuid}", uuid.clone());
             insert_var("${auth_uuid}", uuid);
             insert_var("${auth_access_token}", "token".to_string());
@@ -94,6 +331,27 @@ pub async fn launch<T: Loader>(
         current_dir.to_string_lossy().into_owned(),
     );
 
+    // Feature variables
+    if let Some(resolution) = config.resolution {
+        insert_var("${resolution_width}", resolution.width.to_string());
+        insert_var("${resolution_height}", resolution.height.to_string());
+    }
+    if let Some(quick_play) = &config.quick_play {
+        insert_var(
+            "${quickPlayPath}",
+            current_dir
+                .join("quickPlay")
+                .join("log.json")
+                .to_string_lossy()
+                .into_owned(),
+        );
+        match quick_play {
+            QuickPlay::Singleplayer(world) => insert_var("${quickPlaySingleplayer}", world.clone()),
+            QuickPlay::Multiplayer(server) => insert_var("${quickPlayMultiplayer}", server.clone()),
+            QuickPlay::Realms(realm) => insert_var("${quickPlayRealms}", realm.clone()),
+        }
+    }
+
     let assets_dir = config.get_assets_path();
 
     insert_var("${assets_root}", assets_dir.to_string_lossy().into_owned());
@@ -107,14 +365,11 @@ pub async fn launch<T: Loader>(
     );
     insert_var("${assets_index_name}", meta.asset_index.id);
     insert_var("${version_type}", meta.r#type);
-    insert_var(
-        "${natives_directory}",
-        config
-            .get_natives_path()
-            .join(&config.version)
-            .to_string_lossy()
-            .into_owned(),
-    );
+    let natives_directory = config
+        .get_natives_path_for_version()
+        .to_string_lossy()
+        .into_owned();
+    insert_var("${natives_directory}", natives_directory.clone());
 
     let libraries_path = config.get_libraries_path();
     insert_var("${classpath}", {
@@ -129,7 +384,7 @@ pub async fn launch<T: Loader>(
                 lib.downloads.as_ref().and_then(|downloads| {
                     downloads.artifact.as_ref().and_then(|artifact| {
                         artifact.path.as_ref().and_then(|path| {
-                            if lib.rules.parse_rule() && lib.natives.is_none() {
+                            if lib.rules.parse_rule(&platform) && lib.natives.is_none() {
                                 Some(libraries_path.join(path).to_string_lossy().into_owned())
                             } else {
                                 None
@@ -149,6 +404,60 @@ pub async fn launch<T: Loader>(
         variables.iter().fold(arg, |arg, (k, v)| arg.replace(*k, v))
     }
 
+    fn push_elements(
+        arguments: &mut Vec<String>,
+        elements: &[Element],
+        variables: &HashMap<&'static str, String>,
+        platform: &Platform,
+        features: &FeatureFlags,
+    ) {
+        elements.iter().for_each(|arg| match arg {
+            Element::String(e) => arguments.push(replace_each(variables, e.clone())),
+            Element::Class(e) => {
+                if e.rules.parse_rule_with_features(platform, features) {
+                    match &e.value {
+                        Value::Single(e) => arguments.push(replace_each(variables, e.clone())),
+                        Value::Multiple(e) => {
+                            e.iter()
+                                .for_each(|v| arguments.push(replace_each(variables, v.clone())));
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    // Arguments of the vanilla launcher features are passed as far as the
+    // configuration enables them.
+    let mut features = FeatureFlags::from(Features {
+        is_demo_user: Some(config.demo),
+        has_custom_resolution: Some(config.resolution.is_some()),
+        has_quick_plays_support: Some(config.quick_play.is_some()),
+        is_quick_play_singleplayer: Some(matches!(
+            config.quick_play,
+            Some(QuickPlay::Singleplayer(_))
+        )),
+        is_quick_play_multiplayer: Some(matches!(
+            config.quick_play,
+            Some(QuickPlay::Multiplayer(_))
+        )),
+        is_quick_play_realms: Some(matches!(config.quick_play, Some(QuickPlay::Realms(_)))),
+    });
+    features.extend(options.features.clone());
+
+    let unknown_features = unknown_features(&meta_arguments, &features);
+    if !unknown_features.is_empty() {
+        emitter
+            .warn(Warning::new(
+                WarningCode::UnknownFeatures,
+                format!(
+                    "Arguments depending on unknown launcher features are treated as disabled: {}",
+                    unknown_features.join(", ")
+                ),
+            ))
+            .await;
+    }
+
     // Forge JVM variables
     insert_var(
         "${library_directory}",
@@ -156,74 +465,1136 @@ pub async fn launch<T: Loader>(
     );
     insert_var("${classpath_separator}", CLASSPATH_SEPARATOR.to_string());
 
-    match &config.memory {
-        Some(memory) => arguments.push(format!(
-            "-Xmx{}",
-            match memory {
-                Memory::Gigabyte(m) => format!("{}G", m),
-                Memory::Megabyte(m) => format!("{}M", m),
-            }
-        )),
-        None => arguments.push("-Xmx2G".to_string()),
-    }
-
-    meta_arguments.jvm.iter().for_each(|arg| match arg {
-        Element::String(e) => arguments.push(replace_each(&variables, e.clone())),
-        Element::Class(e) => {
-            if e.rules.parse_rule() {
-                match &e.value {
-                    Value::Single(e) => arguments.push(replace_each(&variables, e.clone())),
-                    Value::Multiple(e) => {
-                        e.iter()
-                            .for_each(|v| arguments.push(replace_each(&variables, v.clone())));
-                    }
-                }
-            }
+    // Memory variables for custom JVM argument templates
+    let memory_max = config
+        .memory
+        .as_ref()
+        .map(Memory::to_string)
+        .unwrap_or_else(|| "2G".to_string());
+    let memory_min = config
+        .min_memory
+        .as_ref()
+        .map(Memory::to_string)
+        .unwrap_or_else(|| memory_max.clone());
+    insert_var("${memory_max}", memory_max.clone());
+    insert_var("${memory_min}", memory_min.clone());
+    insert_var("${natives_dir}", natives_directory);
+    insert_var("${game_dir}", current_dir.to_string_lossy().into_owned());
+
+    // Custom JVM arguments take precedence over the structured memory settings
+    if !config
+        .custom_java_args
+        .iter()
+        .any(|arg| arg.starts_with("-Xmx"))
+    {
+        arguments.push(format!("-Xmx{}", memory_max));
+    }
+    if config.min_memory.is_some()
+        && !config
+            .custom_java_args
+            .iter()
+            .any(|arg| arg.starts_with("-Xms"))
+    {
+        arguments.push(format!("-Xms{}", memory_min));
+    }
+
+    push_elements(
+        &mut arguments,
+        &meta_arguments.jvm,
+        &variables,
+        &platform,
+        &features,
+    );
+
+    // LWJGL 3 has to run on the main thread on macOS, which the metadata of
+    // some versions and loaders doesn't ask for.
+    #[cfg(target_os = "macos")]
+    {
+        let uses_lwjgl3 = meta
+            .libraries
+            .iter()
+            .any(|lib| lib.name.starts_with("org.lwjgl:lwjgl:3."));
+        if uses_lwjgl3 && !arguments.iter().any(|arg| arg == "-XstartOnFirstThread") {
+            arguments.push("-XstartOnFirstThread".to_string());
         }
-    });
+        if let Some(dock_name) = &config.dock_name {
+            arguments.push(format!("-Xdock:name={}", dock_name));
+        }
+        if let Some(dock_icon) = &config.dock_icon {
+            arguments.push(format!("-Xdock:icon={}", dock_icon.to_string_lossy()));
+        }
+    }
 
     config.custom_java_args.iter().for_each(|arg| {
         arguments.push(replace_each(&variables, arg.clone()));
     });
 
+    append_args(&mut arguments, 0, &options.extra_jvm_args);
+
     arguments.push(meta.main_class.to_owned());
+    let game_args_start = arguments.len();
 
-    meta_arguments.game.iter().for_each(|arg| {
-        if let Element::String(e) = arg {
-            arguments.push(replace_each(&variables, e.clone()))
+    push_elements(
+        &mut arguments,
+        &meta_arguments.game,
+        &variables,
+        &platform,
+        &features,
+    );
+    // Legacy versions have no rules, the vanilla launcher adds these itself
+    if is_legacy {
+        if config.demo {
+            arguments.push("--demo".to_string());
         }
-    });
+        if let Some(resolution) = config.resolution {
+            arguments.extend([
+                "--width".to_string(),
+                resolution.width.to_string(),
+                "--height".to_string(),
+                resolution.height.to_string(),
+            ]);
+        }
+    }
 
     config.custom_args.iter().for_each(|arg| {
         arguments.push(replace_each(&variables, arg.clone()));
     });
 
-    let java_path = config
-        .get_java_path(&meta.java_version.unwrap_or_default())
-        .await?;
+    append_args(&mut arguments, game_args_start, &options.extra_game_args);
 
-    create_dir_all(current_dir)?;
+    let java_path = config.get_java_path(&java_version).await?;
 
-    let mut child = Command::new(java_path)
+    let working_dir = config.working_dir.as_ref().unwrap_or(&current_dir);
+    if *working_dir != current_dir && !arguments.iter().any(|arg| arg == "--gameDir") {
+        emitter
+            .warn(Warning::new(
+                WarningCode::GameDirNotPassed,
+                format!(
+                    "The game runs in {} without --gameDir {}",
+                    working_dir.display(),
+                    current_dir.display()
+                ),
+            ))
+            .await;
+    }
+
+    let mut command = Command::new(java_path);
+    if config.clear_env {
+        command.env_clear();
+    }
+    command
+        .envs(&config.custom_env_vars)
         .args(arguments)
         .stdout(Stdio::piped())
-        .current_dir(current_dir)
-        .spawn()?;
+        .stderr(Stdio::piped())
+        .current_dir(working_dir)
+        .kill_on_drop(options.on_drop == OnDrop::Kill);
+    hide_java_console(&mut command, config);
+    // The game leads its own process group so that `GameInstance::kill_tree`
+    // can reach the helper processes it spawns.
+    #[cfg(unix)]
+    command.process_group(0);
+    // Linux applies the niceness to each thread, so it is set before the JVM
+    // starts any thread for all of them to inherit it.
+    #[cfg(unix)]
+    if let Some(nice) = config.process_priority.nice() {
+        // SAFETY: `setpriority` is a system call, which is safe to make
+        // between fork and exec.
+        unsafe {
+            command.pre_exec(move || {
+                libc::setpriority(libc::PRIO_PROCESS, 0, nice);
+                Ok(())
+            });
+        }
+    }
+    let mut child = command.spawn()?;
+    if !set_process_priority(&child, config.process_priority) {
+        emitter
+            .warn(Warning::new(
+                WarningCode::ProcessPriorityNotSet,
+                format!(
+                    "Could not set the priority of the game to {:?}",
+                    config.process_priority
+                ),
+            ))
+            .await;
+    }
 
     let stdout = child
         .stdout
         .take()
         .ok_or_else(|| Error::Take("Child -> stdout".to_string()))?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| Error::Take("Child -> stderr".to_string()))?;
+
+    let pid = child.id().unwrap_or_default();
+    let (lock_path, stamp) = lock.hand_over(pid)?;
+    let early_output = spawn_reader(
+        stdout,
+        stderr,
+        emitter.cloned(),
+        config.console.clone(),
+        options.log_file.clone(),
+        move || {
+            // Leave the lock of a game launched in the meantime alone.
+            if fs::read_to_string(&lock_path).is_ok_and(|lock| lock == stamp) {
+                let _ = fs::remove_file(&lock_path);
+            }
+        },
+    );
+
+    Ok(GameInstance {
+        child,
+        early_output,
+    })
+}
+
+/// Appends arguments, skipping the options that are already passed along
+/// with their values.
+///
+/// # Parameters
+/// - `arguments`: The arguments to append to.
+/// - `start`: The index of the first argument of the same kind, JVM or game.
+/// - `extra`: The arguments to append.
+fn append_args(arguments: &mut Vec<String>, start: usize, extra: &[String]) {
+    let present: HashSet<String> = arguments[start..]
+        .iter()
+        .filter(|arg| arg.starts_with('-'))
+        .map(|arg| option_key(arg))
+        .collect();
+
+    let mut extra = extra.iter().peekable();
+    while let Some(arg) = extra.next() {
+        if arg.starts_with('-') && present.contains(&option_key(arg)) {
+            extra.next_if(|value| !value.starts_with('-'));
+            continue;
+        }
+        arguments.push(arg.clone());
+    }
+}
+
+/// Gets the part of an option that stays the same regardless of its value,
+/// e.g. `-Dkey` of `-Dkey=value`, `-Xmx` of `-Xmx2G` and `-XX:Flag` of
+/// `-XX:+Flag`.
+///
+/// # Parameters
+/// - `arg`: The option.
+///
+/// # Returns
+/// The key of the option.
+fn option_key(arg: &str) -> String {
+    const SIZE_OPTIONS: [&str; 4] = ["-Xmx", "-Xms", "-Xss", "-Xmn"];
+    if let Some(prefix) = SIZE_OPTIONS.iter().find(|prefix| arg.starts_with(**prefix)) {
+        return prefix.to_string();
+    }
+    let key = arg.split('=').next().unwrap_or(arg);
+    match key.strip_prefix("-XX:") {
+        Some(flag) => format!("-XX:{}", flag.trim_start_matches(['+', '-'])),
+        None => key.to_string(),
+    }
+}
+
+/// Checks whether an installed Java runtime is intact, so that a runtime
+/// whose files were deleted fails with an error instead of a game that exits
+/// right away.
+///
+/// # Parameters
+/// - `config`: The configuration of the game.
+/// - `java_version`: The Java version of the runtime.
+/// - `execute`: Whether to also run `java -version`, which catches damaged
+///   executables at the cost of starting a JVM.
+///
+/// # Returns
+/// A result containing whether the runtime is intact.
+pub async fn verify_runtime<T: Loader>(
+    config: &Config<T>,
+    java_version: &JavaVersion,
+    execute: bool,
+) -> crate::Result<bool> {
+    let java_path = config.get_java_executable_path(java_version);
+    if !java_path.with_extension(EXE_EXTENSION).is_file() {
+        return Ok(false);
+    }
+
+    // The class library is `lib/modules` since Java 9 and `lib/rt.jar` before
+    let Some(java_home) = java_path.parent().and_then(|bin| bin.parent()) else {
+        return Ok(false);
+    };
+    let lib_path = java_home.join("lib");
+    if !lib_path.join("modules").is_file() && !lib_path.join("rt.jar").is_file() {
+        return Ok(false);
+    }
+
+    if !execute {
+        return Ok(true);
+    }
+    let java_path = config.get_java_path(java_version).await?;
+    let mut command = Command::new(java_path);
+    command
+        .arg("-version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    hide_java_console(&mut command, config);
+    Ok(command.status().await.is_ok_and(|status| status.success()))
+}
+
+/// Keeps `java` from opening a console window on Windows, which a launcher
+/// without a console of its own would show next to the game. `javaw`, used
+/// unless `java_console` is set, never opens one.
+///
+/// # Parameters
+/// - `command`: The command running the Java executable of the configuration.
+/// - `config`: The configuration of the game.
+pub(crate) fn hide_java_console<T: Loader>(command: &mut Command, config: &Config<T>) {
+    // The output stays readable through the pipes of the command.
+    #[cfg(windows)]
+    if config.java_console {
+        command.creation_flags(windows_sys::Win32::System::Threading::CREATE_NO_WINDOW);
+    }
+    #[cfg(not(windows))]
+    let _ = (command, config);
+}
+
+/// Lists the launcher features the argument rules depend on that are not in
+/// the given features.
+///
+/// # Parameters
+/// - `arguments`: The arguments of the version.
+/// - `features`: The launcher features of the launch.
+///
+/// # Returns
+/// The names of the unknown features, sorted and without duplicates.
+fn unknown_features<'a>(arguments: &'a Arguments, features: &FeatureFlags) -> Vec<&'a str> {
+    arguments
+        .jvm
+        .iter()
+        .chain(&arguments.game)
+        .filter_map(|element| match element {
+            Element::Class(class) => Some(class),
+            Element::String(_) => None,
+        })
+        .flat_map(|class| &class.rules)
+        .filter_map(|rule| rule.features.as_ref())
+        .flat_map(|required| required.keys())
+        .filter(|name| !features.contains_key(*name))
+        .map(String::as_str)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Sets the priority of a spawned game process.
+///
+/// On Unix the niceness was already set before the game started, which is
+/// only checked here. Other platforms are left alone.
+///
+/// # Parameters
+/// - `child`: The game process.
+/// - `priority`: The priority to set.
+///
+/// # Returns
+/// Whether the process has the priority.
+fn set_process_priority(child: &Child, priority: ProcessPriority) -> bool {
+    #[cfg(unix)]
+    {
+        let (Some(nice), Some(pid)) = (priority.nice(), child.id()) else {
+            return true;
+        };
+        // SAFETY: `getpriority` only reads the niceness of the process.
+        unsafe { libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t) == nice }
+    }
+    #[cfg(windows)]
+    {
+        let (Some(priority_class), Some(handle)) = (priority.priority_class(), child.raw_handle())
+        else {
+            return true;
+        };
+        // SAFETY: the handle belongs to the child, which isn't waited on yet.
+        unsafe {
+            windows_sys::Win32::System::Threading::SetPriorityClass(handle, priority_class) != 0
+        }
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (child, priority);
+        true
+    }
+}
+
+/// Checks whether a game launched by `launch` is still running in the game
+/// directory of a configuration.
+///
+/// # Parameters
+/// - `config`: The configuration of the game.
+///
+/// # Returns
+/// Whether the game is running.
+pub fn is_instance_running<T: Loader>(config: &Config<T>) -> bool {
+    running_instance(config).is_some()
+}
+
+/// Gets the process id of the game running in the game directory of a
+/// configuration, ignoring locks left behind by a game that crashed.
+fn running_instance<T: Loader>(config: &Config<T>) -> Option<u32> {
+    read_instance_lock(&config.get_game_path().join(INSTANCE_LOCK_FILE))
+        .filter(|(pid, started)| is_process_alive(*pid, *started))
+        .map(|(pid, _)| pid)
+}
+
+/// The lock of a game directory, taken at the start of a launch.
+///
+/// It names the launcher until the game is spawned and the game afterwards,
+/// and is removed if the launch fails before that.
+struct InstanceLock {
+    path: PathBuf,
+    handed_over: bool,
+}
+
+impl InstanceLock {
+    /// Creates the lock file, replacing a lock whose process is gone.
+    ///
+    /// # Parameters
+    /// - `path`: The path of the lock file.
+    ///
+    /// # Returns
+    /// A result containing the lock, `Error::InstanceRunning` if a game runs
+    /// or launches in the directory.
+    async fn acquire(path: PathBuf) -> crate::Result<Self> {
+        let stamp = process_stamp(std::process::id());
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(stamp.as_bytes())?;
+                    return Ok(Self {
+                        path,
+                        handed_over: false,
+                    });
+                }
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => {}
+                Err(error) => return Err(error.into()),
+            }
+
+            // A lock that was only just created may not have been written
+            // yet, it is read once more before it is considered broken.
+            let lock = match read_instance_lock(&path) {
+                Some(lock) => Some(lock),
+                None => {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    read_instance_lock(&path)
+                }
+            };
+            match lock {
+                Some((pid, started)) if is_process_alive(pid, started) => {
+                    return Err(Error::InstanceRunning(pid));
+                }
+                _ => match fs::remove_file(&path) {
+                    Err(error) if error.kind() != ErrorKind::NotFound => return Err(error.into()),
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    /// Hands the lock over to the spawned game, which keeps it until it
+    /// exits.
+    ///
+    /// # Parameters
+    /// - `pid`: The process id of the game.
+    ///
+    /// # Returns
+    /// A result containing the path of the lock file and its contents, which
+    /// identify the game.
+    fn hand_over(mut self, pid: u32) -> crate::Result<(PathBuf, String)> {
+        let stamp = process_stamp(pid);
+        fs::write(&self.path, &stamp)?;
+        self.handed_over = true;
+        Ok((std::mem::take(&mut self.path), stamp))
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        if !self.handed_over {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Reads an instance lock file.
+///
+/// # Parameters
+/// - `path`: The path of the lock file.
+///
+/// # Returns
+/// The process id along with the start time of the process, if the lock
+/// records one.
+fn read_instance_lock(path: &Path) -> Option<(u32, Option<u64>)> {
+    let lock = fs::read_to_string(path).ok()?;
+    let mut parts = lock.split_whitespace();
+    let pid = parts.next()?.parse().ok()?;
+    Some((pid, parts.next().and_then(|started| started.parse().ok())))
+}
+
+/// Identifies a process by its id and start time, the contents of an
+/// instance lock file.
+fn process_stamp(pid: u32) -> String {
+    match process_start_time(pid) {
+        Some(started) => format!("{} {}", pid, started),
+        None => pid.to_string(),
+    }
+}
+
+/// Checks whether a process is alive, and still the process that was
+/// started at the given time rather than another one that reused its id.
+///
+/// # Parameters
+/// - `pid`: The process id.
+/// - `started`: The start time of the process, as of `process_start_time`.
+///
+/// # Returns
+/// Whether the process is alive.
+fn is_process_alive(pid: u32, started: Option<u64>) -> bool {
+    #[cfg(unix)]
+    // SAFETY: signal 0 only checks whether the process exists.
+    let exists = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0
+        || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+    #[cfg(windows)]
+    let exists = {
+        use windows_sys::Win32::{
+            Foundation::{CloseHandle, STILL_ACTIVE},
+            System::Threading::{
+                GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+            },
+        };
+
+        // SAFETY: the handle is checked before use and closed afterwards.
+        unsafe {
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process.is_null() {
+                false
+            } else {
+                let mut code = 0;
+                let running =
+                    GetExitCodeProcess(process, &mut code) != 0 && code == STILL_ACTIVE as u32;
+                CloseHandle(process);
+                running
+            }
+        }
+    };
+    #[cfg(not(any(unix, windows)))]
+    let exists = true;
+
+    exists
+        && match (started, process_start_time(pid)) {
+            (Some(started), Some(current)) => started == current,
+            _ => true,
+        }
+}
+
+/// Gets the start time of a process, in a unit that only has to be
+/// consistent for the same process.
+///
+/// # Parameters
+/// - `pid`: The process id.
+///
+/// # Returns
+/// The start time, `None` if the process is gone or the platform doesn't
+/// tell.
+fn process_start_time(pid: u32) -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        // The start time is the 22nd field, counted after the name in
+        // parentheses, which may contain spaces itself.
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        let fields = &stat[stat.rfind(')')? + 1..];
+        fields.split_whitespace().nth(19)?.parse().ok()
+    }
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::{
+            Foundation::{CloseHandle, FILETIME},
+            System::Threading::{GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION},
+        };
+
+        // SAFETY: the handle is checked before use and closed afterwards,
+        // the times are written to initialized structures.
+        unsafe {
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process.is_null() {
+                return None;
+            }
+            let mut created = FILETIME::default();
+            let mut unused = [FILETIME::default(); 3];
+            let found = GetProcessTimes(
+                process,
+                &mut created,
+                &mut unused[0],
+                &mut unused[1],
+                &mut unused[2],
+            ) != 0;
+            CloseHandle(process);
+            found.then(|| {
+                (u64::from(created.dwHighDateTime) << 32) | u64::from(created.dwLowDateTime)
+            })
+        }
+    }
+    #[cfg(not(any(target_os = "linux", windows)))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// Terminates every descendant of a process, found through a snapshot of the
+/// running processes.
+///
+/// # Parameters
+/// - `pid`: The process id of the root of the tree, which is left running.
+#[cfg(windows)]
+fn kill_descendants(pid: u32) {
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, INVALID_HANDLE_VALUE},
+        System::{
+            Diagnostics::ToolHelp::{
+                CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+                TH32CS_SNAPPROCESS,
+            },
+            Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE},
+        },
+    };
+
+    // SAFETY: the snapshot is checked before use and closed afterwards, the
+    // entry is initialized with its size as `Process32FirstW` requires.
+    let processes = unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return;
+        }
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut processes = Vec::new();
+        let mut found = Process32FirstW(snapshot, &mut entry) != 0;
+        while found {
+            processes.push((entry.th32ProcessID, entry.th32ParentProcessID));
+            found = Process32NextW(snapshot, &mut entry) != 0;
+        }
+        CloseHandle(snapshot);
+        processes
+    };
+
+    let mut tree = vec![pid];
+    let mut index = 0;
+    while let Some(&parent) = tree.get(index) {
+        let children: Vec<u32> = processes
+            .iter()
+            .filter(|(child, child_parent)| *child_parent == parent && !tree.contains(child))
+            .map(|(child, _)| *child)
+            .collect();
+        tree.extend(children);
+        index += 1;
+    }
 
-    if let Some(emitter) = emitter {
-        let emitter = emitter.clone();
-        tokio::spawn(async move {
-            let mut reader = BufReader::new(stdout).lines();
-            while let Ok(Some(line)) = reader.next_line().await {
-                emitter.emit(Event::Console, line).await;
+    for descendant in &tree[1..] {
+        // SAFETY: the handle is checked before use and closed afterwards.
+        unsafe {
+            let handle = OpenProcess(PROCESS_TERMINATE, 0, *descendant);
+            if !handle.is_null() {
+                TerminateProcess(handle, 1);
+                CloseHandle(handle);
             }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks whether a process has exited, counting zombies as exited since
+    /// nobody may be left to reap them.
+    #[cfg(target_os = "linux")]
+    fn has_exited(pid: u32) -> bool {
+        fs::read_to_string(format!("/proc/{}/stat", pid))
+            .ok()
+            .is_none_or(|stat| stat.rsplit(") ").next().unwrap().starts_with('Z'))
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn kill_tree_kills_spawned_processes() {
+        use tokio::io::{AsyncBufReadExt, BufReader};
+
+        // A game that spawns a long-running helper and waits for it.
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 600 & echo $!; wait"])
+            .stdout(Stdio::piped())
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .await
+            .unwrap();
+        let helper: u32 = line.trim().parse().unwrap();
+        assert!(!has_exited(helper));
+        let mut instance = GameInstance {
+            child,
+            early_output: Arc::new(EarlyOutput::new()),
+        };
+
+        instance.kill_tree().await.unwrap();
+
+        assert!(instance.child().try_wait().unwrap().is_some());
+        let started = std::time::Instant::now();
+        while !has_exited(helper) {
+            assert!(started.elapsed() < std::time::Duration::from_secs(5));
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        // Killing a game that already exited succeeds.
+        instance.kill_tree().await.unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn dropped_instance_is_killed_when_asked() {
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        crate::minecraft::install::install(&config, None)
+            .await
+            .unwrap();
+        let java_path = config.get_runtime_path().join("jre-legacy/bin/java");
+        fs::write(&java_path, "#!/bin/sh\nexec sleep 600\n").unwrap();
+
+        let instance = launch_with_options(&config, &LaunchOptions::default(), None)
+            .await
+            .unwrap();
+        let detached = instance.id().unwrap();
+        drop(instance);
+        let options = LaunchOptions {
+            on_drop: OnDrop::Kill,
+            ..Default::default()
+        };
+        // The detached game still holds the instance lock.
+        fs::remove_file(config.get_game_path().join(INSTANCE_LOCK_FILE)).unwrap();
+        let instance = launch_with_options(&config, &options, None).await.unwrap();
+        let killed = instance.id().unwrap();
+        drop(instance);
+
+        let started = std::time::Instant::now();
+        while !has_exited(killed) {
+            assert!(started.elapsed() < std::time::Duration::from_secs(5));
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(!has_exited(detached));
+        // SAFETY: the detached game is still running with this pid.
+        unsafe { libc::kill(detached as libc::pid_t, libc::SIGKILL) };
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn game_runs_with_the_process_priority() {
+        use crate::minecraft::{config::ProcessPriority, emitter::WarningCode};
+
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        crate::minecraft::install::install(&config, None)
+            .await
+            .unwrap();
+        let java_path = config.get_runtime_path().join("jre-legacy/bin/java");
+        fs::write(&java_path, "#!/bin/sh\nexec sleep 600\n").unwrap();
+        config.process_priority = ProcessPriority::Idle;
+
+        let emitter = Emitter::default();
+        let warnings = Arc::new(std::sync::Mutex::new(Vec::new()));
+        emitter
+            .on_warning({
+                let warnings = warnings.clone();
+                move |warning| warnings.lock().unwrap().push(warning.code)
+            })
+            .await;
+        let mut instance = launch_with_options(&config, &LaunchOptions::default(), Some(&emitter))
+            .await
+            .unwrap();
+        let pid = instance.id().unwrap();
+
+        // SAFETY: `getpriority` only reads the niceness of the running game.
+        let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t) };
+        instance.kill_tree().await.unwrap();
+        assert_eq!(nice, 19);
+        assert!(!warnings
+            .lock()
+            .unwrap()
+            .contains(&WarningCode::ProcessPriorityNotSet));
+    }
+
+    #[tokio::test]
+    async fn instance_lock_is_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(INSTANCE_LOCK_FILE);
+
+        let lock = InstanceLock::acquire(path.clone()).await.unwrap();
+        assert!(matches!(
+            InstanceLock::acquire(path.clone()).await,
+            Err(Error::InstanceRunning(pid)) if pid == std::process::id()
+        ));
+
+        // A launch that fails before the game is spawned releases the lock.
+        drop(lock);
+        assert!(!path.exists());
+        InstanceLock::acquire(path).await.unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn lock_of_a_reused_pid_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(INSTANCE_LOCK_FILE);
+        let pid = std::process::id();
+        let started = process_start_time(pid).unwrap();
+        assert!(is_process_alive(pid, Some(started)));
+
+        // The lock of a game that crashed, whose id now belongs to this
+        // process.
+        fs::write(&path, format!("{} {}", pid, started + 1)).unwrap();
+        assert!(!is_process_alive(pid, Some(started + 1)));
+
+        let lock = InstanceLock::acquire(path.clone()).await.unwrap();
+        assert_eq!(read_instance_lock(&path), Some((pid, Some(started))));
+        let (_, stamp) = lock.hand_over(pid).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), stamp);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn installed_version_launches_without_network() {
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        crate::minecraft::install::install(&config, None)
+            .await
+            .unwrap();
+        let requests = server.received_requests().await.unwrap().len();
+
+        // Every request of the launch would fail, be it to the mirror or
+        // to the official servers.
+        config.client = Some(
+            reqwest::Client::builder()
+                .proxy(reqwest::Proxy::all("http://127.0.0.1:1").unwrap())
+                .build()
+                .unwrap(),
+        );
+        let mut child = launch(&config, None).await.unwrap();
+
+        assert!(child.wait().await.unwrap().success());
+        assert_eq!(server.received_requests().await.unwrap().len(), requests);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn runtime_is_only_executed_when_asked() {
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        crate::minecraft::install::install(&config, None)
+            .await
+            .unwrap();
+        let java = config.get_runtime_path().join("jre-legacy/bin/java");
+
+        let mut child = launch(&config, None).await.unwrap();
+        child.wait().await.unwrap();
+        let log = fs::read_to_string(java.with_extension("log")).unwrap();
+        assert!(!log.lines().any(|line| line == "-version"));
+
+        // A java that doesn't run only fails the launch that executes it.
+        fs::write(&java, "#!/bin/sh\nexit 1\n").unwrap();
+        let options = LaunchOptions {
+            verify_runtime_execution: true,
+            ..Default::default()
+        };
+        let result = launch_with_options(&config, &options, None).await;
+        assert!(matches!(result, Err(Error::BrokenJavaRuntime(_))));
+    }
+
+    #[test]
+    fn made_up_features_are_unknown() {
+        let arguments: Arguments = serde_json::from_value(serde_json::json!({
+            "game": [
+                {
+                    "rules": [{ "action": "allow", "features": { "is_demo_user": true } }],
+                    "value": "--demo"
+                },
+                {
+                    "rules": [{ "action": "allow", "features": { "has_made_up_feature": true } }],
+                    "value": "--madeUp"
+                }
+            ],
+            "jvm": []
+        }))
+        .unwrap();
+        let features = FeatureFlags::from(Features {
+            is_demo_user: Some(false),
+            ..Default::default()
         });
+
+        assert_eq!(
+            unknown_features(&arguments, &features),
+            ["has_made_up_feature"]
+        );
+    }
+
+    #[test]
+    fn extra_args_skip_options_already_passed() {
+        let mut arguments: Vec<String> =
+            ["-Xmx2G", "-XX:+UseG1GC", "-Dfoo=1", "Main", "--server", "a"]
+                .map(String::from)
+                .to_vec();
+        let extra = ["-Xmx4G", "-XX:-UseG1GC", "-Dfoo=2", "-Dbar=3"].map(String::from);
+        append_args(&mut arguments, 0, &extra);
+        assert_eq!(&arguments[6..], ["-Dbar=3"]);
+
+        let extra = ["--server", "b", "--demo"].map(String::from);
+        append_args(&mut arguments, 4, &extra);
+        assert_eq!(&arguments[7..], ["--demo"]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn launch_options_do_not_leak_into_the_next_launch() {
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        crate::minecraft::install::install(&config, None)
+            .await
+            .unwrap();
+
+        // A Java that records the arguments of the game.
+        let java_path = config.get_runtime_path().join("jre-legacy/bin/java");
+        let args_path = dir.path().join("args");
+        fs::write(
+            &java_path,
+            format!(
+                "#!/bin/sh\n[ \"$1\" = -version ] || printf '%s\\n' \"$@\" > {}\n",
+                args_path.display()
+            ),
+        )
+        .unwrap();
+        let launch_args = |options: LaunchOptions| {
+            let config = &config;
+            let args_path = &args_path;
+            async move {
+                let mut instance = launch_with_options(config, &options, None).await.unwrap();
+                assert!(instance.child().wait().await.unwrap().success());
+                fs::read_to_string(args_path).unwrap()
+            }
+        };
+
+        let with_options = launch_args(LaunchOptions {
+            extra_game_args: vec!["--quickPlayMultiplayer".into(), "localhost".into()],
+            extra_jvm_args: vec!["-Dlyceris.test=1".into()],
+            ..Default::default()
+        })
+        .await;
+        assert!(with_options.contains("--quickPlayMultiplayer\nlocalhost\n"));
+        assert!(with_options.contains("-Dlyceris.test=1\n"));
+
+        let without_options = launch_args(LaunchOptions::default()).await;
+        assert!(!without_options.contains("--quickPlayMultiplayer"));
+        assert!(!without_options.contains("-Dlyceris.test"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn invalid_jvm_flag_is_reported() {
+        use crate::minecraft::console::LaunchFailureKind;
+
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        crate::minecraft::install::install(&config, None)
+            .await
+            .unwrap();
+        // A java that rejects the flag the way HotSpot does.
+        fs::write(
+            config.get_runtime_path().join("jre-legacy/bin/java"),
+            "#!/bin/sh\necho \"Unrecognized VM option 'LycerisNoSuchFlag'\" >&2\n\
+             echo 'Error: Could not create the Java Virtual Machine.' >&2\nexit 1\n",
+        )
+        .unwrap();
+
+        let emitter = Emitter::default();
+        let emitted = std::sync::Arc::new(std::sync::Mutex::new(None));
+        emitter
+            .on_early_exit({
+                let emitted = emitted.clone();
+                move |early_exit| *emitted.lock().unwrap() = Some(early_exit.kind)
+            })
+            .await;
+        config.custom_java_args = vec!["-XX:+LycerisNoSuchFlag".to_string()];
+        let mut instance = launch_with_options(&config, &LaunchOptions::default(), None)
+            .await
+            .unwrap();
+        let summary = instance.wait_for_exit(Some(&emitter)).await.unwrap();
+
+        assert!(!summary.status.success());
+        let early_exit = summary.early_output.unwrap();
+        assert_eq!(early_exit.kind, LaunchFailureKind::UnrecognizedVmOption);
+        assert!(early_exit.early_output.contains("LycerisNoSuchFlag"));
+        assert_eq!(
+            *emitted.lock().unwrap(),
+            Some(LaunchFailureKind::UnrecognizedVmOption)
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_offline_username_is_rejected() {
+        use crate::auth::UsernameError;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.authentication = AuthMethod::Offline {
+            username: "a b".to_string(),
+            uuid: None,
+        };
+
+        let result = launch(&config, None).await;
+        assert!(matches!(
+            result,
+            Err(Error::InvalidUsername {
+                name,
+                reason: UsernameError::InvalidCharacter(' '),
+            }) if name == "a b"
+        ));
+
+        // A sanitized username gets past the validation, to the missing
+        // installation.
+        config.sanitize_username = true;
+        let result = launch(&config, None).await;
+        assert!(matches!(result, Err(Error::NotInstalled(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn game_runs_in_the_working_dir() {
+        use crate::minecraft::emitter::WarningCode;
+
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let (dir, working_dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        crate::minecraft::install::install(&config, None)
+            .await
+            .unwrap();
+        let java_path = config.get_runtime_path().join("jre-legacy/bin/java");
+        fs::write(&java_path, "#!/bin/sh\npwd -P > \"$0.cwd\"\n").unwrap();
+        config.working_dir = Some(working_dir.path().to_path_buf());
+
+        let emitter = Emitter::default();
+        let warnings = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        emitter
+            .on_warning({
+                let warnings = warnings.clone();
+                move |warning| warnings.lock().unwrap().push(warning.code)
+            })
+            .await;
+        let mut instance = launch_with_options(&config, &LaunchOptions::default(), Some(&emitter))
+            .await
+            .unwrap();
+        instance.wait_for_exit(None).await.unwrap();
+
+        let cwd = fs::read_to_string(java_path.with_extension("cwd")).unwrap();
+        assert_eq!(
+            Path::new(cwd.trim()),
+            working_dir.path().canonicalize().unwrap()
+        );
+        // The version passes no --gameDir, so the game would look for its
+        // files in the working directory.
+        assert!(warnings
+            .lock()
+            .unwrap()
+            .contains(&WarningCode::GameDirNotPassed));
     }
 
-    Ok(child)
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn successful_exit_has_no_early_output() {
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        crate::minecraft::install::install(&config, None)
+            .await
+            .unwrap();
+
+        let mut instance = launch_with_options(&config, &LaunchOptions::default(), None)
+            .await
+            .unwrap();
+        let summary = instance.wait_for_exit(None).await.unwrap();
+
+        assert!(summary.status.success());
+        assert!(summary.early_output.is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn pinned_java_component_is_used_everywhere() {
+        use serde_json::{json, Value};
+        use wiremock::{matchers::path, Mock, ResponseTemplate};
+
+        // The version asks for a runtime the server doesn't have, and runs
+        // a processor during the installation.
+        let server = crate::test_util::mojang_server("1.8.9").await;
+        let mut version: Value = reqwest::get(format!("{}/versions/1.8.9.json", server.uri()))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        version["javaVersion"] = json!({ "component": "java-runtime-gamma", "majorVersion": 17 });
+        version["processors"] = json!([{
+            "jar": "net.example:processor:1.0",
+            "classpath": [],
+            "args": ["--processor"],
+            "main_class": "net.example.Processor",
+        }]);
+        Mock::given(path("/versions/1.8.9.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(version))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::test_util::config(dir.path(), "1.8.9");
+        config.mirrors = vec![crate::test_util::mirror(&server)];
+        config.java_component = Some("jre-legacy".to_string());
+        crate::minecraft::install::install(&config, None)
+            .await
+            .unwrap();
+        let mut child = launch(&config, None).await.unwrap();
+        assert!(child.wait().await.unwrap().success());
+
+        // Later launches without the pin use the recorded component.
+        config.java_component = None;
+        let mut child = launch(&config, None).await.unwrap();
+        assert!(child.wait().await.unwrap().success());
+
+        let runtime_path = config.get_runtime_path();
+        assert!(!runtime_path.join("java-runtime-gamma").exists());
+        let log = fs::read_to_string(runtime_path.join("jre-legacy/bin/java.log")).unwrap();
+        let count = |needle: &str| log.lines().filter(|line| line.contains(needle)).count();
+        assert_eq!(count("net.example.Processor --processor"), 1);
+        assert_eq!(count("net.minecraft.client.main.Main"), 2);
+    }
 }
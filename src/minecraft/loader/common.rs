@@ -0,0 +1,900 @@
+use std::{
+    collections::HashSet,
+    env::temp_dir,
+    fs::{self, create_dir_all, File},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use zip::ZipArchive;
+
+use crate::{
+    error::Error,
+    http::{downloader::download, fetch::fetch},
+    json::version::meta::{
+        custom::{CustomMeta, Installer, Library},
+        vanilla::{self, VersionMeta},
+    },
+    minecraft::{
+        emitter::{Emit, Emitter, Warning, WarningCode},
+        parse::parse_lib_path,
+    },
+    util::extract::read_file_from_jar,
+};
+
+use super::{CleanupReport, CompatibilityStatus};
+
+/// Represents an entry of the `versions/loader/<game_version>` endpoint of
+/// the Fabric and Quilt meta servers.
+#[derive(Deserialize)]
+struct LoaderEntry {
+    loader: LoaderVersion,
+}
+
+/// Represents the loader of a `LoaderEntry`.
+#[derive(Deserialize)]
+struct LoaderVersion {
+    version: String,
+}
+
+/// Fetches the loader versions a Fabric-like meta server lists for a
+/// Minecraft version, newest first.
+///
+/// # Parameters
+/// - `endpoint`: The base URL of the meta server, ending with a slash.
+/// - `mc_version`: The Minecraft version.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+/// A result containing the loader versions.
+pub async fn fetch_meta_loaders(
+    endpoint: &str,
+    mc_version: &str,
+    client: Option<&Client>,
+) -> crate::Result<Vec<String>> {
+    let entries: Vec<LoaderEntry> = fetch(
+        format!("{}versions/loader/{}", endpoint, mc_version),
+        client,
+    )
+    .await?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| entry.loader.version)
+        .collect())
+}
+
+/// Merges the launch profile a Fabric-like meta server lists for a loader
+/// version into the version metadata.
+///
+/// The libraries of the profile replace the ones with the same name, and its
+/// arguments are added to the version's. Legacy versions keep their
+/// `minecraftArguments` string, the profile's arguments are added next to it.
+///
+/// # Parameters
+/// - `meta`: The version metadata to be merged.
+/// - `profile`: The launch profile of the loader.
+/// - `emitter`: An optional emitter for reporting skipped libraries.
+///
+/// # Returns
+/// The updated `VersionMeta`.
+pub async fn merge_meta_profile(
+    mut meta: VersionMeta,
+    profile: CustomMeta,
+    emitter: Option<&Emitter>,
+) -> VersionMeta {
+    // Retain libraries that are not in the fetched version
+    meta.libraries.retain(|lib| {
+        profile
+            .libraries
+            .iter()
+            .all(|v_lib| v_lib.name.split(':').nth(1) != lib.name.split(':').nth(1))
+    });
+
+    // Extend the libraries with the new ones from the fetched version
+    let mut warnings = Vec::new();
+    meta.libraries.extend(
+        profile
+            .libraries
+            .into_iter()
+            .filter_map(|lib| {
+                let path = match parse_lib_path(&lib.name) {
+                    Ok(path) => path,
+                    Err(e) => {
+                        warnings.push(Warning::new(WarningCode::InvalidLibraryName, e.to_string()));
+                        return None;
+                    }
+                };
+                lib.url.map(|url| vanilla::Library {
+                    downloads: Some(vanilla::LibraryDownloads {
+                        artifact: Some(vanilla::File {
+                            path: Some(path.clone()),
+                            sha1: lib.sha1.unwrap_or_default(),
+                            size: lib.size.unwrap_or_default(),
+                            url: format!("{}/{}", url, path),
+                        }),
+                        classifiers: None,
+                    }),
+                    extract: None,
+                    name: lib.name.clone(),
+                    rules: None,
+                    natives: None,
+                    skip_args: false,
+                })
+            })
+            .collect::<Vec<_>>(),
+    );
+    for warning in warnings {
+        emitter.warn(warning).await;
+    }
+
+    // Update the arguments for the Minecraft launch
+    let arguments = meta.arguments.get_or_insert_with(|| vanilla::Arguments {
+        game: Vec::new(),
+        jvm: Vec::new(),
+    });
+    if let Some(jvm) = profile.arguments.jvm {
+        arguments.jvm.extend(jvm);
+    }
+    if let Some(game) = profile.arguments.game {
+        arguments.game.extend(game);
+    }
+
+    meta.main_class = profile.main_class;
+    meta
+}
+
+/// Estimates the size of the libraries a Fabric-like meta server lists in
+/// the launch profile of a loader version.
+///
+/// # Parameters
+/// - `endpoint`: The base URL of the meta server, ending with a slash.
+/// - `loader_version`: The version of the loader.
+/// - `mc_version`: The Minecraft version.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+/// A result containing the size in bytes.
+pub async fn meta_profile_size(
+    endpoint: &str,
+    loader_version: &str,
+    mc_version: &str,
+    client: Option<&Client>,
+) -> crate::Result<u64> {
+    let profile: CustomMeta = fetch(
+        format!(
+            "{}versions/loader/{}/{}/profile/json",
+            endpoint, mc_version, loader_version
+        ),
+        client,
+    )
+    .await?;
+    Ok(libraries_size(&profile.libraries))
+}
+
+/// Estimates the size of a Forge or NeoForge installation.
+///
+/// The libraries the installer pulls in are only listed inside of it, so an
+/// installer that isn't cached yet is downloaded to `installer_path`, where
+/// the installation picks it up later.
+///
+/// # Parameters
+/// - `installer_url`: The URL of the installer JAR.
+/// - `installer_path`: The path the installer is cached at.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+/// A result containing the size in bytes, see `installer_size`.
+pub async fn estimate_installer_size(
+    installer_url: &str,
+    installer_path: &Path,
+    client: Option<&Client>,
+) -> crate::Result<u64> {
+    if !installer_path.is_file() {
+        download(installer_url, installer_path, None, client).await?;
+    }
+    installer_size(installer_path)
+}
+
+/// Estimates the size of a Forge or NeoForge installation from its
+/// installer, which is the installer itself plus the libraries listed in its
+/// install profile and version JSON.
+///
+/// # Parameters
+/// - `installer_path`: The path to the installer JAR.
+///
+/// # Returns
+/// A result containing the size in bytes.
+pub fn installer_size(installer_path: &Path) -> crate::Result<u64> {
+    let installer: Installer = serde_json::from_str(&read_file_from_jar(
+        &installer_path,
+        "install_profile.json",
+    )?)?;
+    let version: CustomMeta =
+        serde_json::from_str(&read_file_from_jar(&installer_path, "version.json")?)?;
+
+    Ok(fs::metadata(installer_path)?.len()
+        + libraries_size(installer.libraries.iter().chain(&version.libraries)))
+}
+
+/// Sums the sizes of libraries, counting libraries listed several times
+/// once.
+///
+/// # Parameters
+/// - `libraries`: The libraries to sum.
+///
+/// # Returns
+/// The size in bytes, libraries without a size count as zero.
+fn libraries_size<'a>(libraries: impl IntoIterator<Item = &'a Library>) -> u64 {
+    let mut seen = HashSet::new();
+    libraries
+        .into_iter()
+        .filter(|lib| seen.insert(lib.name.as_str()))
+        .map(|lib| {
+            lib.size
+                .or_else(|| {
+                    lib.downloads
+                        .as_ref()
+                        .and_then(|downloads| downloads.artifact.as_ref())
+                        .map(|artifact| artifact.size)
+                })
+                .unwrap_or_default()
+                .max(0) as u64
+        })
+        .sum()
+}
+
+/// Checks a loader version against the loaders a Fabric-like meta server
+/// lists for a Minecraft version.
+///
+/// A meta server that doesn't know the Minecraft version answers with a
+/// client error, which counts as the loader not supporting it.
+///
+/// # Parameters
+/// - `endpoint`: The base URL of the meta server, ending with a slash.
+/// - `name`: The name of the loader, used in the reason.
+/// - `loader_version`: The version of the loader.
+/// - `mc_version`: The Minecraft version.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+/// A result containing the compatibility status.
+pub async fn check_meta_loaders(
+    endpoint: &str,
+    name: &str,
+    loader_version: &str,
+    mc_version: &str,
+    client: Option<&Client>,
+) -> crate::Result<CompatibilityStatus> {
+    let versions = match fetch_meta_loaders(endpoint, mc_version, client).await {
+        Err(Error::HttpClientError {
+            status: 400 | 404, ..
+        }) => Vec::new(),
+        result => result?,
+    };
+
+    if versions.is_empty() {
+        return Ok(CompatibilityStatus::Incompatible {
+            reason: format!("{} doesn't support Minecraft {}", name, mc_version),
+        });
+    }
+
+    if versions.iter().any(|version| version == loader_version) {
+        Ok(CompatibilityStatus::Compatible)
+    } else {
+        Ok(CompatibilityStatus::Incompatible {
+            reason: format!(
+                "{} {} is not available for Minecraft {}",
+                name, loader_version, mc_version
+            ),
+        })
+    }
+}
+
+/// Checks whether an installer exists on a Maven repository.
+///
+/// # Parameters
+/// - `url`: The URL of the installer.
+/// - `reason`: The reason reported when the installer doesn't exist.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+/// A result containing the compatibility status.
+pub async fn check_installer_exists(
+    url: &str,
+    reason: String,
+    client: Option<&Client>,
+) -> crate::Result<CompatibilityStatus> {
+    let default_client = Client::default();
+    let response = client.unwrap_or(&default_client).head(url).send().await?;
+
+    Ok(match response.status() {
+        status if status.is_success() => CompatibilityStatus::Compatible,
+        StatusCode::NOT_FOUND => CompatibilityStatus::Incompatible { reason },
+        _ => CompatibilityStatus::Unknown,
+    })
+}
+
+/// Extracts the libraries bundled in the `maven/` directory of a Forge or
+/// NeoForge installer into the libraries directory.
+///
+/// Some installers ship libraries inside the installer itself instead of
+/// publishing them to a Maven repository, such libraries have no download
+/// URL in the install profile.
+///
+/// # Parameters
+/// - `installer_path`: The path to the installer JAR.
+/// - `libraries_path`: The path to the libraries directory.
+///
+/// # Returns
+/// A result containing the paths of the extracted libraries.
+pub fn extract_maven_libraries(
+    installer_path: &Path,
+    libraries_path: &Path,
+) -> crate::Result<Vec<PathBuf>> {
+    let file = File::open(installer_path)?;
+    let mut archive = ZipArchive::new(file)?;
+    let mut extracted = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut zip_file = archive.by_index(i)?;
+        if zip_file.is_dir() {
+            continue;
+        }
+
+        let relative_path = match zip_file
+            .enclosed_name()
+            .and_then(|name| name.strip_prefix("maven").ok().map(Path::to_path_buf))
+        {
+            Some(path) if !path.as_os_str().is_empty() => path,
+            _ => continue,
+        };
+
+        let output_path = libraries_path.join(relative_path);
+        if let Some(parent) = output_path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        let mut output_file = File::create(&output_path)?;
+        std::io::copy(&mut zip_file, &mut output_file)?;
+        extracted.push(output_path);
+    }
+
+    Ok(extracted)
+}
+
+/// Removes the profile directory and the cached installer of a Forge-like
+/// loader version, tolerating ones that are partially or already removed.
+///
+/// # Parameters
+/// - `loader`: The name of the loader, e.g. `neoforge`.
+/// - `profiles_dir`: The loader profiles directory, see
+///   `Config::get_loader_profiles_path`.
+/// - `version_name`: The version name the profile was created for.
+/// - `dry_run`: Whether to only report what would be removed.
+/// - `report`: The report the removed paths are recorded in.
+///
+/// # Returns
+/// A result indicating success or failure of the removal.
+pub fn remove_installer_data(
+    loader: &str,
+    profiles_dir: &Path,
+    version_name: &str,
+    dry_run: bool,
+    report: &mut CleanupReport,
+) -> crate::Result<()> {
+    let profile_path = profiles_dir.join(loader).join(version_name);
+    let installer_path = temp_dir().join(format!("{}-{}.jar", loader, version_name));
+
+    for path in [profile_path, installer_path] {
+        remove_path(path, dry_run, report)?;
+    }
+
+    Ok(())
+}
+
+/// Removes the cached installers of a Forge-like loader that were left in a
+/// directory without their profile, except for the kept ones.
+///
+/// # Parameters
+/// - `loader`: The name of the loader, e.g. `neoforge`.
+/// - `installers_dir`: The directory the installers are cached in, which is
+///   the temporary directory.
+/// - `is_kept`: Tells whether the installer of a version name is kept.
+/// - `dry_run`: Whether to only report what would be removed.
+/// - `report`: The report the removed paths are recorded in, installers
+///   already recorded in it are skipped.
+///
+/// # Returns
+/// A result indicating success or failure of the removal.
+pub fn remove_orphan_installers(
+    loader: &str,
+    installers_dir: &Path,
+    is_kept: impl Fn(&str) -> bool,
+    dry_run: bool,
+    report: &mut CleanupReport,
+) -> crate::Result<()> {
+    let Ok(entries) = fs::read_dir(installers_dir) else {
+        return Ok(());
+    };
+    let prefix = format!("{}-", loader);
+
+    for entry in entries.filter_map(Result::ok) {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(version_name) = file_name
+            .strip_prefix(&prefix)
+            .and_then(|name| name.strip_suffix(".jar"))
+        else {
+            continue;
+        };
+        let path = entry.path();
+        if is_kept(version_name) || !path.is_file() || report.removed.contains(&path) {
+            continue;
+        }
+        remove_path(path, dry_run, report)?;
+    }
+
+    Ok(())
+}
+
+/// Removes a file or a directory and records it in a report, tolerating one
+/// that is already removed.
+fn remove_path(path: PathBuf, dry_run: bool, report: &mut CleanupReport) -> crate::Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(&path) else {
+        return Ok(());
+    };
+    let size = path_size(&path);
+
+    if !dry_run {
+        let result = if metadata.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if let Err(e) = result {
+            if e.kind() != ErrorKind::NotFound {
+                return Err(e.into());
+            }
+        }
+    }
+
+    report.bytes_freed += size;
+    report.removed.push(path);
+    Ok(())
+}
+
+/// Moves the profiles of a Forge-like loader that older releases kept in
+/// `.<loader>/profiles` of the game directory into the loader profiles
+/// directory, so they aren't installed again.
+///
+/// A profile that already exists in the loader profiles directory wins and
+/// the old copy is removed. The old directories are removed once empty.
+///
+/// # Parameters
+/// - `loader`: The name of the loader, e.g. `neoforge`.
+/// - `game_dir`: The game directory the old profiles are kept in.
+/// - `profiles_dir`: The loader profiles directory, see
+///   `Config::get_loader_profiles_path`.
+///
+/// # Returns
+/// A result indicating success or failure of the migration.
+pub fn migrate_legacy_profiles(
+    loader: &str,
+    game_dir: &Path,
+    profiles_dir: &Path,
+) -> crate::Result<()> {
+    let loader_dir = game_dir.join(format!(".{}", loader));
+    let legacy_path = loader_dir.join("profiles");
+    let Ok(entries) = fs::read_dir(&legacy_path) else {
+        return Ok(());
+    };
+
+    let target_dir = profiles_dir.join(loader);
+    create_dir_all(&target_dir)?;
+
+    for entry in entries.filter_map(Result::ok) {
+        let source = entry.path();
+        if !source.is_dir() {
+            continue;
+        }
+        let target = target_dir.join(entry.file_name());
+        if !target.exists() && fs::rename(&source, &target).is_err() {
+            // The profiles directory may be on another file system.
+            copy_dir(&source, &target)?;
+        }
+        if source.exists() {
+            fs::remove_dir_all(&source)?;
+        }
+    }
+
+    let _ = fs::remove_dir(&legacy_path);
+    let _ = fs::remove_dir(&loader_dir);
+
+    Ok(())
+}
+
+/// Reports the profiles of a Forge-like loader that older releases kept in
+/// `.<loader>/profiles` of the game directory and that a cleanup would remove,
+/// without moving or removing anything.
+///
+/// These are the profiles that aren't kept, along with the old copies of the
+/// profiles that already exist in the loader profiles directory, see
+/// `migrate_legacy_profiles`.
+///
+/// # Parameters
+/// - `loader`: The name of the loader, e.g. `neoforge`.
+/// - `game_dir`: The game directory the old profiles are kept in.
+/// - `profiles_dir`: The loader profiles directory, see
+///   `Config::get_loader_profiles_path`.
+/// - `is_kept`: Whether the profile of a version name is kept.
+/// - `report`: The report the old profiles are added to.
+///
+/// # Returns
+/// A result indicating success or failure.
+pub fn report_legacy_profiles(
+    loader: &str,
+    game_dir: &Path,
+    profiles_dir: &Path,
+    is_kept: impl Fn(&str) -> bool,
+    report: &mut CleanupReport,
+) -> crate::Result<()> {
+    let legacy_path = game_dir.join(format!(".{}", loader)).join("profiles");
+    let Ok(entries) = fs::read_dir(&legacy_path) else {
+        return Ok(());
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let source = entry.path();
+        if !source.is_dir() {
+            continue;
+        }
+        let version_name = entry.file_name().to_string_lossy().into_owned();
+        if !is_kept(&version_name) || profiles_dir.join(loader).join(&version_name).exists() {
+            remove_path(source, true, report)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Recursively copies a directory.
+fn copy_dir(source: &Path, target: &Path) -> crate::Result<()> {
+    create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &target.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, target.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Sums the size of a file or of every file inside a directory, skipping
+/// entries that disappear while walking.
+fn path_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| path_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    /// Checks Fabric 0.16.9 on Minecraft 1.21.4 against a meta server
+    /// answering with a response.
+    async fn check(response: ResponseTemplate) -> crate::Result<CompatibilityStatus> {
+        let server = MockServer::start().await;
+        Mock::given(path("/versions/loader/1.21.4"))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+        check_meta_loaders(
+            &format!("{}/", server.uri()),
+            "Fabric",
+            "0.16.9",
+            "1.21.4",
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn unknown_minecraft_version_is_incompatible() {
+        for status in [400, 404] {
+            assert_eq!(
+                check(ResponseTemplate::new(status)).await.unwrap(),
+                CompatibilityStatus::Incompatible {
+                    reason: "Fabric doesn't support Minecraft 1.21.4".to_string()
+                }
+            );
+        }
+        let status = check(ResponseTemplate::new(200).set_body_json(json!([])))
+            .await
+            .unwrap();
+        assert!(matches!(status, CompatibilityStatus::Incompatible { .. }));
+    }
+
+    #[tokio::test]
+    async fn listed_loader_is_compatible() {
+        let loaders = json!([
+            { "loader": { "version": "0.16.10" } },
+            { "loader": { "version": "0.16.9" } }
+        ]);
+        let status = check(ResponseTemplate::new(200).set_body_json(loaders))
+            .await
+            .unwrap();
+        assert_eq!(status, CompatibilityStatus::Compatible);
+
+        let loaders = json!([{ "loader": { "version": "0.16.10" } }]);
+        let status = check(ResponseTemplate::new(200).set_body_json(loaders))
+            .await
+            .unwrap();
+        assert!(matches!(status, CompatibilityStatus::Incompatible { .. }));
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_not_incompatible() {
+        assert!(matches!(
+            check(ResponseTemplate::new(500)).await,
+            Err(Error::HttpServerError { status: 500, .. })
+        ));
+        assert!(matches!(
+            check(ResponseTemplate::new(403)).await,
+            Err(Error::HttpClientError { status: 403, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn installer_size_counts_the_installer_libraries() {
+        let dir = tempfile::tempdir().unwrap();
+        let jar = dir.path().join("served.jar");
+        let library = |name: &str, size: i64| json!({ "name": name, "size": size });
+        crate::test_util::write_zip(
+            &jar,
+            &[
+                (
+                    "install_profile.json",
+                    &json!({
+                        "processors": [],
+                        "libraries": [
+                            library("net.minecraftforge:installertools:1.4.1", 1000),
+                            library("org.ow2.asm:asm:9.6", 200),
+                        ],
+                    })
+                    .to_string(),
+                ),
+                (
+                    "version.json",
+                    &json!({
+                        "id": "1.20.1-forge-47.3.0",
+                        "inheritsFrom": "1.20.1",
+                        "releaseTime": "",
+                        "time": "",
+                        "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+                        "arguments": {},
+                        "libraries": [
+                            // Listed by both, counted once.
+                            library("org.ow2.asm:asm:9.6", 200),
+                            library("cpw.mods:securejarhandler:2.1.10", 30),
+                        ],
+                    })
+                    .to_string(),
+                ),
+            ],
+        );
+        let installer = fs::read(&jar).unwrap();
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/installer.jar"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(installer.clone()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let installer_url = format!("{}/installer.jar", server.uri());
+        let installer_path = dir.path().join("installer.jar");
+        let estimate = || estimate_installer_size(&installer_url, &installer_path, None);
+        let size = estimate().await.unwrap();
+
+        assert_eq!(size, installer.len() as u64 + 1230);
+        // The installer is cached for the installation and later estimates.
+        assert_eq!(fs::read(&installer_path).unwrap(), installer);
+        assert_eq!(estimate().await.unwrap(), size);
+    }
+
+    #[tokio::test]
+    async fn missing_installer_is_an_error() {
+        let server = MockServer::start().await;
+        let dir = tempfile::tempdir().unwrap();
+        let result = estimate_installer_size(
+            &format!("{}/installer.jar", server.uri()),
+            &dir.path().join("installer.jar"),
+            None,
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(Error::HttpClientError { status: 404, .. })
+        ));
+    }
+
+    #[test]
+    fn legacy_profiles_are_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::test_util::config(dir.path(), "1.20.1");
+        let legacy = |loader: &str, version: &str| {
+            dir.path()
+                .join(format!(".{}", loader))
+                .join("profiles")
+                .join(version)
+        };
+        for (loader, version) in [
+            ("forge", "1.20.1-lyceris-kept"),
+            ("neoforge", "lyceris-old"),
+        ] {
+            fs::create_dir_all(legacy(loader, version)).unwrap();
+            fs::write(legacy(loader, version).join("installer.json"), "{}").unwrap();
+        }
+        // The profile already in the loader profiles directory wins.
+        let profiles_dir = config.get_loader_profiles_path();
+        fs::create_dir_all(profiles_dir.join("forge/1.20.1-lyceris-kept")).unwrap();
+        fs::write(
+            profiles_dir.join("forge/1.20.1-lyceris-kept/installer.json"),
+            "[]",
+        )
+        .unwrap();
+
+        let installers = tempfile::tempdir().unwrap();
+        let report = super::super::cleanup_installers(
+            &config,
+            &[("forge", "1.20.1-lyceris-kept")],
+            false,
+            installers.path(),
+        )
+        .unwrap();
+
+        assert_eq!(report.removed, [profiles_dir.join("neoforge/lyceris-old")]);
+        assert_eq!(
+            fs::read_to_string(profiles_dir.join("forge/1.20.1-lyceris-kept/installer.json"))
+                .unwrap(),
+            "[]"
+        );
+        assert!(!dir.path().join(".forge").exists());
+        assert!(!dir.path().join(".neoforge").exists());
+    }
+
+    #[test]
+    fn legacy_profiles_are_left_in_a_dry_run() {
+        /// Lists every path below a directory with the contents of its files.
+        fn tree(path: &Path) -> Vec<(PathBuf, Option<String>)> {
+            let mut paths = vec![(path.to_path_buf(), fs::read_to_string(path).ok())];
+            if path.is_dir() {
+                for entry in fs::read_dir(path).unwrap() {
+                    paths.extend(tree(&entry.unwrap().path()));
+                }
+            }
+            paths.sort();
+            paths
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::test_util::config(dir.path(), "1.20.1");
+        let legacy = |loader: &str, version: &str| {
+            dir.path()
+                .join(format!(".{}", loader))
+                .join("profiles")
+                .join(version)
+        };
+        for (loader, version) in [
+            ("forge", "1.20.1-lyceris-kept"),
+            ("forge", "1.20.1-lyceris-new"),
+            ("neoforge", "lyceris-old"),
+        ] {
+            fs::create_dir_all(legacy(loader, version)).unwrap();
+            fs::write(legacy(loader, version).join("installer.json"), "{}").unwrap();
+        }
+        let profiles_dir = config.get_loader_profiles_path();
+        fs::create_dir_all(profiles_dir.join("forge/1.20.1-lyceris-kept")).unwrap();
+        fs::write(
+            profiles_dir.join("forge/1.20.1-lyceris-kept/installer.json"),
+            "[]",
+        )
+        .unwrap();
+        let before = tree(dir.path());
+
+        let installers = tempfile::tempdir().unwrap();
+        let mut report = super::super::cleanup_installers(
+            &config,
+            &[
+                ("forge", "1.20.1-lyceris-kept"),
+                ("forge", "1.20.1-lyceris-new"),
+            ],
+            true,
+            installers.path(),
+        )
+        .unwrap();
+
+        assert_eq!(tree(dir.path()), before);
+        // The old copy of a profile that was moved already is removed, and so
+        // is the profile that isn't kept.
+        report.removed.sort();
+        assert_eq!(
+            report.removed,
+            [
+                legacy("forge", "1.20.1-lyceris-kept"),
+                legacy("neoforge", "lyceris-old"),
+            ]
+        );
+        assert_eq!(report.bytes_freed, 4);
+    }
+
+    #[test]
+    fn orphan_installers_are_removed() {
+        let (dir, installers) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let config = crate::test_util::config(dir.path(), "1.20.1");
+        let files = [
+            "forge-1.20.1-47.3.0.jar",
+            "forge-1.20.1-47.2.0.jar",
+            "neoforge-21.4.1.jar",
+            "forge-1.20.1-47.3.0.log",
+            "fabric-installer.jar",
+        ];
+        for file in files {
+            fs::write(installers.path().join(file), "installer").unwrap();
+        }
+        let cleanup = |dry_run| {
+            super::super::cleanup_installers(
+                &config,
+                &[("forge", "1.20.1-47.2.0")],
+                dry_run,
+                installers.path(),
+            )
+            .unwrap()
+        };
+        let orphans = [
+            installers.path().join("forge-1.20.1-47.3.0.jar"),
+            installers.path().join("neoforge-21.4.1.jar"),
+        ];
+
+        let report = cleanup(true);
+        assert_eq!(report.removed, orphans);
+        assert_eq!(report.bytes_freed, 18);
+        assert!(files
+            .iter()
+            .all(|file| installers.path().join(file).is_file()));
+
+        let report = cleanup(false);
+        assert_eq!(report.removed, orphans);
+        assert!(orphans.iter().all(|orphan| !orphan.exists()));
+        for kept in [
+            "forge-1.20.1-47.2.0.jar",
+            "forge-1.20.1-47.3.0.log",
+            "fabric-installer.jar",
+        ] {
+            assert!(installers.path().join(kept).is_file(), "{}", kept);
+        }
+    }
+}
@@ -6,21 +6,33 @@ use std::{
     pin::Pin,
 };
 
+use reqwest::Client;
+use serde::Deserialize;
+
 use crate::{
-    http::downloader::download,
+    http::{downloader::download, fetch::fetch},
     json::version::meta::{
         custom::{CustomMeta, Data, Installer, Library},
         vanilla::{self, VersionMeta},
     },
-    minecraft::{config::Config, emitter::Emitter, parse::parse_lib_path},
-    util::{
-        extract::{extract_specific_directory, extract_specific_file},
-        json::read_json,
+    minecraft::{
+        config::Config,
+        emitter::{Emit, Emitter, Warning, WarningCode},
+        parse::parse_lib_path,
     },
+    util::{extract::extract_specific_file, json::read_json},
 };
 
-use super::Loader;
+use super::{
+    common::{
+        check_installer_exists, estimate_installer_size, extract_maven_libraries,
+        migrate_legacy_profiles, remove_installer_data,
+    },
+    CleanupReport, CompatibilityStatus, Loader,
+};
 
+const PROMOTIONS_ENDPOINT: &str =
+    "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
 const INSTALLER_JAR_ENDPOINT: &str = "https://maven.minecraftforge.net/net/minecraftforge/forge/{loader_version}/forge-{loader_version}-installer.jar";
 
 /// The `Forge` loader implementation for managing Minecraft installations
@@ -40,6 +52,37 @@ impl From<Forge> for Box<dyn Loader> {
     }
 }
 
+impl Forge {
+    /// Removes the installer profile of the configured version along with
+    /// its cached installer, see [`cleanup`](super::cleanup).
+    ///
+    /// # Parameters
+    /// - `config`: The configuration of the Forge installation.
+    ///
+    /// # Returns
+    /// A result containing the report of the removed paths.
+    pub fn clean_profile(&self, config: &Config<impl Loader>) -> crate::Result<CleanupReport> {
+        let version_name = config
+            .version_name
+            .clone()
+            .unwrap_or_else(|| format!("{}-{}", config.version, self.0));
+        migrate_legacy_profiles(
+            "forge",
+            &config.get_game_dir(),
+            &config.get_loader_profiles_path(),
+        )?;
+        let mut report = CleanupReport::default();
+        remove_installer_data(
+            "forge",
+            &config.get_loader_profiles_path(),
+            &version_name,
+            false,
+            &mut report,
+        )?;
+        Ok(report)
+    }
+}
+
 impl Loader for Forge {
     /// Merges the configuration and version metadata with the Forge-specific
     /// data.
@@ -65,10 +108,14 @@ impl Loader for Forge {
                 .or_else(|| Some(format!("{}-{}", config.version, self.0)))
                 .unwrap_or_else(|| config.version.to_string());
 
+            migrate_legacy_profiles(
+                "forge",
+                &config.get_game_dir(),
+                &config.get_loader_profiles_path(),
+            )?;
             let profiles_path = config
-                .game_dir
-                .join(".forge")
-                .join("profiles")
+                .get_loader_profiles_path()
+                .join("forge")
                 .join(&version_name);
 
             let installer_json_path =
@@ -115,20 +162,16 @@ impl Loader for Forge {
                 &meta,
                 installer.data.unwrap_or_default(),
                 config
-                    .game_dir
-                    .join("versions")
+                    .get_versions_path()
                     .join(&version_name)
                     .join(format!("{}.jar", version_name)),
             ));
 
             meta.processors = installer.processors;
 
-            extract_specific_directory(
-                &installer_path,
-                "maven",
-                &config.game_dir.join("libraries"),
-            )
-            .ok();
+            if installer_path.is_file() {
+                extract_maven_libraries(&installer_path, &config.get_libraries_path())?;
+            }
 
             meta.libraries.retain(|lib| {
                 version
@@ -138,15 +181,23 @@ impl Loader for Forge {
             });
 
             let mut seen = HashSet::new();
+            let mut warnings = Vec::new();
 
-            meta.libraries
-                .extend(merge_libraries(config, version.libraries, &mut seen, false));
             meta.libraries.extend(merge_libraries(
-                config,
+                version.libraries,
+                &mut seen,
+                &mut warnings,
+                false,
+            ));
+            meta.libraries.extend(merge_libraries(
                 installer.libraries,
                 &mut seen,
+                &mut warnings,
                 true,
             ));
+            for warning in warnings {
+                emitter.warn(warning).await;
+            }
 
             if let Some(ref mut arguments) = meta.arguments {
                 if let Some(jvm) = version.arguments.jvm {
@@ -166,6 +217,92 @@ impl Loader for Forge {
     fn get_version(&self) -> String {
         self.0.to_string()
     }
+
+    /// Checks whether Forge publishes an installer for this version and the
+    /// given Minecraft version.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version to check against.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the compatibility status.
+    fn check_compatibility<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<CompatibilityStatus>> + Send + 'a>> {
+        Box::pin(async move {
+            let version_name = format!("{}-{}", mc_version, self.0);
+            check_installer_exists(
+                &INSTALLER_JAR_ENDPOINT.replace("{loader_version}", &version_name),
+                format!(
+                    "Forge {} is not available for Minecraft {}",
+                    self.0, mc_version
+                ),
+                client,
+            )
+            .await
+        })
+    }
+
+    /// Gets the recommended Forge version for a Minecraft version, or the
+    /// latest one if none is recommended yet.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the newest recommended version.
+    fn latest_version<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<Option<String>>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut promotions: Promotions = fetch(PROMOTIONS_ENDPOINT, client).await?;
+            Ok(promotions
+                .promos
+                .remove(&format!("{}-recommended", mc_version))
+                .or_else(|| promotions.promos.remove(&format!("{}-latest", mc_version))))
+        })
+    }
+
+    /// Estimates the size of this Forge version, which is its installer plus
+    /// the libraries the installer lists.
+    ///
+    /// The installer is downloaded and cached for `merge` if it isn't
+    /// cached yet.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the estimated size in bytes.
+    fn estimated_install_size<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<u64>> + Send + 'a>> {
+        Box::pin(async move {
+            let version_name = format!("{}-{}", mc_version, self.0);
+            let installer_path = temp_dir().join(format!("forge-{}.jar", version_name));
+            estimate_installer_size(
+                &INSTALLER_JAR_ENDPOINT.replace("{loader_version}", &version_name),
+                &installer_path,
+                client,
+            )
+            .await
+        })
+    }
+}
+
+/// Represents the promoted Forge versions of each Minecraft version.
+#[derive(Deserialize)]
+struct Promotions {
+    promos: HashMap<String, String>,
 }
 
 /// Downloads the installer for the Forge loader if it does not already exist.
@@ -215,18 +352,14 @@ fn merge_data(
         (
             "ROOT".to_string(),
             Data {
-                client: config.game_dir.to_string_lossy().into_owned(),
+                client: config.get_game_dir().to_string_lossy().into_owned(),
                 server: "".to_string(),
             },
         ),
         (
             "LIBRARY_DIR".to_string(),
             Data {
-                client: config
-                    .game_dir
-                    .join("libraries")
-                    .to_string_lossy()
-                    .into_owned(),
+                client: config.get_libraries_path().to_string_lossy().into_owned(),
                 server: "".to_string(),
             },
         ),
@@ -269,10 +402,7 @@ async fn process_data(
                 extract_specific_file(
                     installer_path,
                     file_path,
-                    &config
-                        .game_dir
-                        .join("libraries")
-                        .join(parse_lib_path(&path)?),
+                    &config.get_libraries_path().join(parse_lib_path(&path)?),
                 )?;
 
                 value.client = format!("[{}]", path);
@@ -283,9 +413,9 @@ async fn process_data(
 }
 
 fn merge_libraries(
-    config: &Config<impl Loader>,
     libraries: Vec<Library>,
     seen: &mut HashSet<String>,
+    warnings: &mut Vec<Warning>,
     skip_args: bool,
 ) -> Vec<vanilla::Library> {
     libraries
@@ -296,17 +426,17 @@ fn merge_libraries(
             }
 
             if let Some(url) = lib.url {
-                let path = parse_lib_path(&lib.name).ok()?;
+                let path = match parse_lib_path(&lib.name) {
+                    Ok(path) => path,
+                    Err(e) => {
+                        warnings.push(Warning::new(WarningCode::InvalidLibraryName, e.to_string()));
+                        return None;
+                    }
+                };
                 return Some(vanilla::Library {
                     downloads: Some(vanilla::LibraryDownloads {
                         artifact: Some(vanilla::File {
-                            path: Some(
-                                config
-                                    .get_libraries_path()
-                                    .join(&path)
-                                    .to_string_lossy()
-                                    .into_owned(),
-                            ),
+                            path: Some(path.clone()),
                             sha1: lib.sha1.unwrap_or_default(),
                             size: lib.size.unwrap_or_default(),
                             url: format!("{}/{}", url, path),
@@ -327,14 +457,11 @@ fn merge_libraries(
                         return Some(vanilla::Library {
                             downloads: Some(vanilla::LibraryDownloads {
                                 artifact: Some(vanilla::File {
-                                    path: Some(
-                                        config
-                                            .get_libraries_path()
-                                            .join(path)
-                                            .to_string_lossy()
-                                            .into_owned(),
-                                    ),
-                                    sha1: lib.sha1.unwrap_or_default(),
+                                    path: Some(path),
+                                    sha1: Some(artifact.sha1)
+                                        .filter(|sha1| !sha1.is_empty())
+                                        .or(lib.sha1)
+                                        .unwrap_or_default(),
                                     size: lib.size.unwrap_or_default(),
                                     url: artifact.url,
                                 }),
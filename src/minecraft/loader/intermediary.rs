@@ -0,0 +1,141 @@
+use std::{future::Future, pin::Pin};
+
+use super::{CompatibilityStatus, Loader};
+use reqwest::Client;
+
+use crate::{
+    json::version::meta::vanilla::{self, VersionMeta},
+    minecraft::{config::Config, emitter::Emitter, parse::parse_lib_path},
+};
+
+const MAVEN_ENDPOINT: &str = "https://maven.fabricmc.net";
+
+/// Wraps a Fabric, Legacy Fabric or Quilt loader to also download the intermediary mappings
+/// of the resolved Minecraft version into the libraries directory.
+///
+/// The mappings are only downloaded for mod development setups, they are not
+/// added to the classpath of the game.
+pub struct Intermediary<L: Loader>(pub L);
+
+impl<L: Loader + 'static> From<Intermediary<L>> for Box<dyn Loader> {
+    fn from(value: Intermediary<L>) -> Self {
+        Box::new(value)
+    }
+}
+
+impl<L: Loader> Loader for Intermediary<L> {
+    /// Merges the wrapped loader and appends the intermediary mappings library.
+    ///
+    /// # Parameters
+    /// - `config`: The configuration for the Minecraft installation.
+    /// - `meta`: The version metadata to be merged.
+    /// - `emitter`: An optional emitter for tracking events.
+    ///
+    /// # Returns
+    /// A future that resolves to the updated `VersionMeta`.
+    fn merge<'a>(
+        &'a self,
+        config: &'a Config<()>,
+        meta: VersionMeta,
+        emitter: Option<&'a Emitter>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<VersionMeta>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut meta = self.0.merge(config, meta, emitter).await?;
+
+            // Loaders such as Legacy Fabric publish the intermediary under
+            // their own group, so the one the loader added is followed.
+            let (group, maven) = meta
+                .libraries
+                .iter()
+                .find_map(|lib| {
+                    let mut parts = lib.name.split(':');
+                    let group = parts.next()?;
+                    if parts.next()? != "intermediary" {
+                        return None;
+                    }
+                    let artifact = lib.downloads.as_ref()?.artifact.as_ref()?;
+                    let maven = artifact.url.strip_suffix(artifact.path.as_ref()?)?;
+                    Some((group.to_string(), maven.trim_end_matches('/').to_string()))
+                })
+                .unwrap_or_else(|| ("net.fabricmc".to_string(), MAVEN_ENDPOINT.to_string()));
+
+            let name = format!("{}:intermediary:{}:v2", group, meta.id);
+            let path = parse_lib_path(&name)?;
+
+            meta.libraries.push(vanilla::Library {
+                downloads: Some(vanilla::LibraryDownloads {
+                    artifact: Some(vanilla::File {
+                        path: Some(path.clone()),
+                        sha1: String::new(),
+                        size: 0,
+                        url: format!("{}/{}", maven, path),
+                    }),
+                    classifiers: None,
+                }),
+                extract: None,
+                name,
+                rules: None,
+                natives: None,
+                skip_args: true,
+            });
+
+            Ok(meta)
+        })
+    }
+
+    /// Returns the version of the wrapped loader.
+    ///
+    /// # Returns
+    /// The version as a string.
+    fn get_version(&self) -> String {
+        self.0.get_version()
+    }
+
+    /// Checks the compatibility of the wrapped loader.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version to check against.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the compatibility status.
+    fn check_compatibility<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<CompatibilityStatus>> + Send + 'a>> {
+        self.0.check_compatibility(mc_version, client)
+    }
+
+    /// Gets the newest version of the wrapped loader.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the newest version.
+    fn latest_version<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<Option<String>>> + Send + 'a>> {
+        self.0.latest_version(mc_version, client)
+    }
+
+    /// Estimates the size of the wrapped loader.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the estimated size in bytes.
+    fn estimated_install_size<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<u64>> + Send + 'a>> {
+        self.0.estimated_install_size(mc_version, client)
+    }
+}
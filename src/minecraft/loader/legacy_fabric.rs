@@ -0,0 +1,294 @@
+use std::{future::Future, pin::Pin};
+
+use super::{
+    common::{check_meta_loaders, fetch_meta_loaders, merge_meta_profile, meta_profile_size},
+    CompatibilityStatus, Loader,
+};
+use crate::{
+    error::Error,
+    http::fetch::fetch_rate_limited,
+    json::version::meta::{custom::CustomMeta, vanilla::VersionMeta},
+    minecraft::{config::Config, emitter::Emitter},
+};
+use reqwest::Client;
+use serde::Deserialize;
+
+const VERSION_META_ENDPOINT: &str = "https://meta.legacyfabric.net/v2/";
+
+/// Represents the Legacy Fabric loader metadata.
+#[derive(Deserialize)]
+struct LegacyFabricLoader {
+    version: String,
+}
+
+/// Represents a version of Minecraft supported by Legacy Fabric.
+#[derive(Deserialize)]
+struct Version {
+    version: String,
+}
+
+/// Represents the Legacy Fabric loader, which brings Fabric to Minecraft
+/// 1.13.2 and below.
+pub struct LegacyFabric(pub String);
+
+impl From<LegacyFabric> for Box<dyn Loader> {
+    fn from(value: LegacyFabric) -> Self {
+        Box::new(value)
+    }
+}
+
+impl Loader for LegacyFabric {
+    /// Merges the Legacy Fabric loader with the provided configuration and
+    /// version metadata.
+    ///
+    /// Versions using the legacy `minecraftArguments` string keep it, the
+    /// arguments of the loader are added next to it.
+    ///
+    /// # Parameters
+    /// - `config`: The configuration for the Minecraft installation.
+    /// - `meta`: The version metadata to be merged.
+    /// - `emitter`: An optional emitter for reporting skipped libraries.
+    ///
+    /// # Returns
+    /// A future that resolves to the updated `VersionMeta`.
+    fn merge<'a>(
+        &'a self,
+        config: &'a Config<()>,
+        meta: VersionMeta,
+        emitter: Option<&'a Emitter>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<VersionMeta>> + Send + 'a>> {
+        Box::pin(async move {
+            // Fetch the available Legacy Fabric loaders
+            let loaders: Vec<LegacyFabricLoader> = fetch_rate_limited(
+                format!("{}versions/loader", VERSION_META_ENDPOINT),
+                config.client.as_ref(),
+                config.rate_limit,
+                emitter,
+            )
+            .await?;
+            // Fetch the Minecraft versions supported by Legacy Fabric
+            let versions: Vec<Version> = fetch_rate_limited(
+                format!("{}versions/game", VERSION_META_ENDPOINT),
+                config.client.as_ref(),
+                config.rate_limit,
+                emitter,
+            )
+            .await?;
+
+            // Find the loader that matches the current Legacy Fabric version
+            let loader = loaders
+                .into_iter()
+                .find(|v| v.version == self.0)
+                .ok_or_else(|| Error::UnknownVersion {
+                    loader_type: "Legacy Fabric Loader".into(),
+                    version: self.0.clone(),
+                })?;
+            // Find the Minecraft version that matches the metadata
+            let game = versions
+                .into_iter()
+                .find(|v| v.version == meta.id)
+                .ok_or_else(|| Error::UnknownVersion {
+                    loader_type: "Legacy Fabric".into(),
+                    version: meta.id.clone(),
+                })?;
+
+            // Fetch the custom metadata for the loader
+            let version: CustomMeta = fetch_rate_limited(
+                format!(
+                    "{}versions/loader/{}/{}/profile/json",
+                    VERSION_META_ENDPOINT, game.version, loader.version
+                ),
+                config.client.as_ref(),
+                config.rate_limit,
+                emitter,
+            )
+            .await?;
+
+            Ok(merge_meta_profile(meta, version, emitter).await)
+        })
+    }
+
+    /// Returns the version of the Legacy Fabric loader.
+    ///
+    /// # Returns
+    /// The version as a string.
+    fn get_version(&self) -> String {
+        self.0.to_string()
+    }
+
+    /// Checks whether the Legacy Fabric loader is available for a Minecraft
+    /// version.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version to check against.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the compatibility status.
+    fn check_compatibility<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<CompatibilityStatus>> + Send + 'a>> {
+        Box::pin(check_meta_loaders(
+            VERSION_META_ENDPOINT,
+            "Legacy Fabric",
+            &self.0,
+            mc_version,
+            client,
+        ))
+    }
+
+    /// Gets the newest Legacy Fabric loader available for a Minecraft
+    /// version.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the newest loader version.
+    fn latest_version<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<Option<String>>> + Send + 'a>> {
+        Box::pin(async move {
+            let versions = fetch_meta_loaders(VERSION_META_ENDPOINT, mc_version, client).await?;
+            Ok(versions.into_iter().next())
+        })
+    }
+
+    /// Estimates the size of the libraries of this Legacy Fabric loader.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the sum of the library sizes in bytes.
+    fn estimated_install_size<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<u64>> + Send + 'a>> {
+        Box::pin(meta_profile_size(
+            VERSION_META_ENDPOINT,
+            &self.0,
+            mc_version,
+            client,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// The launch profile of Legacy Fabric loader 0.14.22 for 1.8.9, as
+    /// served by the meta server.
+    fn profile_json() -> serde_json::Value {
+        json!({
+            "id": "fabric-loader-0.14.22-1.8.9",
+            "inheritsFrom": "1.8.9",
+            "releaseTime": "2023-08-26T12:14:02+0000",
+            "time": "2023-08-26T12:14:02+0000",
+            "type": "release",
+            "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+            "arguments": {
+                "game": [],
+                "jvm": ["-DFabricMcEmu= net.minecraft.client.main.Main "]
+            },
+            "libraries": [
+                {
+                    "name": "net.fabricmc:tiny-mappings-parser:0.3.0+build.17",
+                    "url": "https://maven.fabricmc.net/"
+                },
+                {
+                    "name": "org.ow2.asm:asm:9.5",
+                    "url": "https://maven.fabricmc.net/",
+                    "md5": "29721ee4b5eacf0a34b204c345c8bc69",
+                    "sha1": "dc6ea1875f4d64fbc85e1691c95b96a3d8569c90",
+                    "sha256": "b62e84b5980729751b0458c534cf1366f727542bb8d158621335682a460f0353",
+                    "sha512": "9e65f2983783725bae196ca939b45246958731246df1c495089c8ea5ce646de77c4e01a5a9ba10642016bb3258e1727e9ebcece6e74d9e3c32f528025d76b955",
+                    "size": 122004
+                },
+                {
+                    "name": "net.legacyfabric:intermediary:1.8.9",
+                    "url": "https://maven.legacyfabric.net/"
+                },
+                {
+                    "name": "net.fabricmc:fabric-loader:0.14.22",
+                    "url": "https://maven.fabricmc.net/"
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn meta_lists_are_deserialized() {
+        let loaders: Vec<LegacyFabricLoader> = serde_json::from_value(json!([{
+            "separator": ".",
+            "build": 22,
+            "maven": "net.fabricmc:fabric-loader:0.14.22",
+            "version": "0.14.22",
+            "stable": true
+        }]))
+        .unwrap();
+        assert_eq!(loaders[0].version, "0.14.22");
+
+        let versions: Vec<Version> = serde_json::from_value(json!([
+            { "version": "1.13.2", "stable": true },
+            { "version": "1.8.9", "stable": true }
+        ]))
+        .unwrap();
+        assert!(versions.iter().any(|v| v.version == "1.8.9"));
+    }
+
+    #[tokio::test]
+    async fn profile_is_merged_into_legacy_version() {
+        let profile: CustomMeta = serde_json::from_value(profile_json()).unwrap();
+        let meta: VersionMeta =
+            serde_json::from_value(crate::test_util::version_json("1.8.9")).unwrap();
+        let meta = merge_meta_profile(meta, profile, None).await;
+
+        // The version stays legacy, so launch still adds --demo, --width
+        // and --height itself.
+        assert!(!meta.is_modern_arguments());
+        assert_eq!(
+            meta.minecraft_arguments.as_deref(),
+            Some("--username ${auth_player_name} --version ${version_name}")
+        );
+        let arguments = meta.arguments.unwrap();
+        assert!(arguments.game.is_empty());
+        assert_eq!(arguments.jvm.len(), 1);
+        assert_eq!(
+            meta.main_class,
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+
+        let intermediary = meta
+            .libraries
+            .iter()
+            .find(|lib| lib.name == "net.legacyfabric:intermediary:1.8.9")
+            .and_then(|lib| lib.downloads.as_ref()?.artifact.as_ref())
+            .unwrap();
+        assert_eq!(
+            intermediary.path.as_deref(),
+            Some("net/legacyfabric/intermediary/1.8.9/intermediary-1.8.9.jar")
+        );
+        assert!(intermediary
+            .url
+            .starts_with("https://maven.legacyfabric.net/"));
+        let asm = meta
+            .libraries
+            .iter()
+            .find(|lib| lib.name == "org.ow2.asm:asm:9.5")
+            .and_then(|lib| lib.downloads.as_ref()?.artifact.as_ref())
+            .unwrap();
+        assert_eq!(asm.sha1, "dc6ea1875f4d64fbc85e1691c95b96a3d8569c90");
+        assert_eq!(asm.size, 122004);
+    }
+}
@@ -2,14 +2,33 @@ use crate::json::version::meta::vanilla::VersionMeta;
 
 use super::{config::Config, emitter::Emitter};
 
+mod common;
 pub mod fabric;
+pub mod legacy_fabric;
 pub mod forge;
 pub mod quilt;
 pub mod neoforge;
+pub mod optifine;
+pub mod intermediary;
 
+use std::fs;
 use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 
+use reqwest::Client;
+
+/// Represents whether a loader version can be installed on a Minecraft version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompatibilityStatus {
+    Compatible,
+    Incompatible {
+        reason: String,
+    },
+    /// The compatibility couldn't be determined.
+    Unknown,
+}
+
 pub trait Loader where Self: Send + Sync {
     fn merge<'a>(
         &'a self,
@@ -19,6 +38,164 @@ pub trait Loader where Self: Send + Sync {
     ) -> Pin<Box<dyn Future<Output = crate::Result<VersionMeta>> + Send + 'a>>;
 
     fn get_version(&self) -> String;
+
+    /// Checks whether this loader can be installed on a Minecraft version.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version to check against.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the compatibility status, `Unknown` by default.
+    fn check_compatibility<'a>(
+        &'a self,
+        _mc_version: &'a str,
+        _client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<CompatibilityStatus>> + Send + 'a>> {
+        Box::pin(async move { Ok(CompatibilityStatus::Unknown) })
+    }
+
+    /// Gets the newest version of this loader for a Minecraft version.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the newest version, `None` by default.
+    fn latest_version<'a>(
+        &'a self,
+        _mc_version: &'a str,
+        _client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<Option<String>>> + Send + 'a>> {
+        Box::pin(async move { Ok(None) })
+    }
+
+    /// Estimates how many bytes this loader downloads in addition to the
+    /// vanilla version.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the estimated size in bytes, `0` by default.
+    fn estimated_install_size<'a>(
+        &'a self,
+        _mc_version: &'a str,
+        _client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<u64>> + Send + 'a>> {
+        Box::pin(async move { Ok(0) })
+    }
+}
+
+/// The outcome of removing loader profiles and cached installers.
+#[derive(Debug, Clone, Default)]
+pub struct CleanupReport {
+    /// The removed profile directories and installers, or the ones that
+    /// would be removed in a dry run.
+    pub removed: Vec<PathBuf>,
+    /// The number of bytes freed.
+    pub bytes_freed: u64,
+}
+
+/// The loaders that keep installer profiles in the loader profiles directory.
+const PROFILE_LOADERS: [&str; 2] = ["forge", "neoforge"];
+
+/// Removes the Forge and NeoForge installer profiles of a loader profiles
+/// directory, along with their cached installers, except for the kept ones.
+/// Cached installers left in the temporary directory without their profile
+/// are removed as well.
+///
+/// The profiles are only used while installing, so removing them merely
+/// makes the next installation of the same version download its installer
+/// again. The libraries directory is never touched. Profiles older releases
+/// kept in `.forge/profiles` and `.neoforge/profiles` of the game directory
+/// are moved into the loader profiles directory first. A dry run leaves them
+/// in place and reports the ones that would be removed.
+///
+/// # Parameters
+/// - `config`: The configuration whose loader profiles directory is cleaned,
+///   see `Config::get_loader_profiles_path`.
+/// - `keep`: The loader names, `forge` or `neoforge`, and the version names
+///   of the profiles to keep, e.g. `("neoforge", "1.21.4-21.4.1")`.
+/// - `dry_run`: Whether to only report what would be removed.
+///
+/// # Returns
+/// A result containing the report of the removed paths.
+pub fn cleanup(
+    config: &Config<impl Loader>,
+    keep: &[(&str, &str)],
+    dry_run: bool,
+) -> crate::Result<CleanupReport> {
+    cleanup_installers(config, keep, dry_run, &std::env::temp_dir())
+}
+
+/// Removes the loader profiles and cached installers like `cleanup`, looking
+/// for the installers left without their profile in `installers_dir`.
+fn cleanup_installers(
+    config: &Config<impl Loader>,
+    keep: &[(&str, &str)],
+    dry_run: bool,
+    installers_dir: &Path,
+) -> crate::Result<CleanupReport> {
+    let profiles_dir = config.get_loader_profiles_path();
+    let mut report = CleanupReport::default();
+
+    for loader in PROFILE_LOADERS {
+        let is_kept = |version_name: &str| {
+            keep.iter().any(|(kept_loader, kept_version)| {
+                kept_loader.eq_ignore_ascii_case(loader) && *kept_version == version_name
+            })
+        };
+        if dry_run {
+            common::report_legacy_profiles(
+                loader,
+                &config.get_game_dir(),
+                &profiles_dir,
+                is_kept,
+                &mut report,
+            )?;
+        } else {
+            common::migrate_legacy_profiles(loader, &config.get_game_dir(), &profiles_dir)?;
+        }
+        let profiles_path = profiles_dir.join(loader);
+        if let Ok(entries) = fs::read_dir(&profiles_path) {
+            for entry in entries.filter_map(Result::ok) {
+                let version_name = entry.file_name().to_string_lossy().into_owned();
+                if !is_kept(&version_name) {
+                    common::remove_installer_data(
+                        loader,
+                        &profiles_dir,
+                        &version_name,
+                        dry_run,
+                        &mut report,
+                    )?;
+                }
+            }
+        }
+
+        common::remove_orphan_installers(loader, installers_dir, is_kept, dry_run, &mut report)?;
+    }
+
+    Ok(report)
+}
+
+/// Checks whether a loader can be installed on a Minecraft version.
+///
+/// # Parameters
+/// - `loader`: The loader to check.
+/// - `mc_version`: The Minecraft version to check against.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+/// A result containing the compatibility status.
+pub async fn check_loader_compatibility(
+    loader: &dyn Loader,
+    mc_version: &str,
+    client: Option<&Client>,
+) -> crate::Result<CompatibilityStatus> {
+    loader.check_compatibility(mc_version, client).await
 }
 
 impl Loader for () {
@@ -50,4 +227,28 @@ impl Loader for Box<dyn Loader> {
     fn get_version(&self) -> String {
         self.as_ref().get_version()
     }
+
+    fn check_compatibility<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<CompatibilityStatus>> + Send + 'a>> {
+        self.as_ref().check_compatibility(mc_version, client)
+    }
+
+    fn latest_version<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<Option<String>>> + Send + 'a>> {
+        self.as_ref().latest_version(mc_version, client)
+    }
+
+    fn estimated_install_size<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<u64>> + Send + 'a>> {
+        self.as_ref().estimated_install_size(mc_version, client)
+    }
 }
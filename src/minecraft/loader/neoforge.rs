@@ -2,21 +2,36 @@ use std::{
     collections::{HashMap, HashSet}, env::temp_dir, future::Future, path::PathBuf, pin::Pin
 };
 
+use reqwest::Client;
+use serde::Deserialize;
+
 use crate::{
-    http::downloader::download,
+    http::{downloader::download, fetch::fetch},
     json::version::meta::{
         custom::{CustomMeta, Data, Installer, Library},
         vanilla::{self, VersionMeta},
     },
-    minecraft::{config::Config, emitter::Emitter, parse::parse_lib_path},
+    minecraft::{
+        config::Config,
+        emitter::{Emit, Emitter, Warning, WarningCode},
+        parse::parse_lib_path,
+    },
     util::{
-        extract::{extract_specific_directory, extract_specific_file},
+        extract::extract_specific_file,
         json::read_json,
     },
 };
 
-use super::Loader;
+use super::{
+    common::{
+        check_installer_exists, estimate_installer_size, extract_maven_libraries,
+        migrate_legacy_profiles, remove_installer_data,
+    },
+    CleanupReport, CompatibilityStatus, Loader,
+};
 
+const VERSIONS_ENDPOINT: &str =
+    "https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge";
 const INSTALLER_JAR_ENDPOINT: &str = "https://maven.neoforged.net/releases/net/neoforged/neoforge/{loader_version}/neoforge-{loader_version}-installer.jar";
 
 /// The `NeoForge` loader implementation for managing Minecraft installations
@@ -29,6 +44,37 @@ impl From<NeoForge> for Box<dyn Loader> {
     }
 }
 
+impl NeoForge {
+    /// Removes the installer profile of the configured version along with
+    /// its cached installer, see [`cleanup`](super::cleanup).
+    ///
+    /// # Parameters
+    /// - `config`: The configuration of the NeoForge installation.
+    ///
+    /// # Returns
+    /// A result containing the report of the removed paths.
+    pub fn clean_profile(&self, config: &Config<impl Loader>) -> crate::Result<CleanupReport> {
+        let version_name = config
+            .version_name
+            .clone()
+            .unwrap_or_else(|| format!("{}-{}", config.version, self.0));
+        migrate_legacy_profiles(
+            "neoforge",
+            &config.get_game_dir(),
+            &config.get_loader_profiles_path(),
+        )?;
+        let mut report = CleanupReport::default();
+        remove_installer_data(
+            "neoforge",
+            &config.get_loader_profiles_path(),
+            &version_name,
+            false,
+            &mut report,
+        )?;
+        Ok(report)
+    }
+}
+
 impl Loader for NeoForge {
     /// Merges the configuration and version metadata with the NeoForge-specific
     /// data.
@@ -54,10 +100,14 @@ impl Loader for NeoForge {
                 .or_else(|| Some(format!("{}-{}", config.version, self.0)))
                 .unwrap_or_else(|| config.version.to_string());
 
+            migrate_legacy_profiles(
+                "neoforge",
+                &config.get_game_dir(),
+                &config.get_loader_profiles_path(),
+            )?;
             let profiles_path = config
-                .game_dir
-                .join(".neoforge")
-                .join("profiles")
+                .get_loader_profiles_path()
+                .join("neoforge")
                 .join(&version_name);
 
             let installer_json_path =
@@ -104,20 +154,16 @@ impl Loader for NeoForge {
                 &meta,
                 installer.data.unwrap_or_default(),
                 config
-                    .game_dir
-                    .join("versions")
+                    .get_versions_path()
                     .join(&version_name)
                     .join(format!("{}.jar", version_name))
             ));
 
             meta.processors = installer.processors;
 
-            extract_specific_directory(
-                &installer_path,
-                "maven/",
-                &config.game_dir.join("libraries"),
-            )
-            .ok();
+            if installer_path.is_file() {
+                extract_maven_libraries(&installer_path, &config.get_libraries_path())?;
+            }
 
             meta.libraries.retain(|lib| {
                 version
@@ -127,15 +173,23 @@ impl Loader for NeoForge {
             });
 
             let mut seen = HashSet::new();
+            let mut warnings = Vec::new();
 
-            meta.libraries
-                .extend(merge_libraries(config, version.libraries, &mut seen, false));
             meta.libraries.extend(merge_libraries(
-                config,
+                version.libraries,
+                &mut seen,
+                &mut warnings,
+                false,
+            ));
+            meta.libraries.extend(merge_libraries(
                 installer.libraries,
                 &mut seen,
+                &mut warnings,
                 true,
             ));
+            for warning in warnings {
+                emitter.warn(warning).await;
+            }
 
             if let Some(ref mut arguments) = meta.arguments {
                 if let Some(jvm) = version.arguments.jvm {
@@ -155,6 +209,115 @@ impl Loader for NeoForge {
     fn get_version(&self) -> String {
         self.0.to_string()
     }
+
+    /// Checks whether this NeoForge version targets the given Minecraft
+    /// version and has a published installer.
+    ///
+    /// NeoForge versions start with the minor and patch version of the
+    /// Minecraft version they target, e.g. `20.4.80` targets `1.20.4`.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version to check against.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the compatibility status.
+    fn check_compatibility<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<CompatibilityStatus>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut parts = self.0.split(['.', '-']);
+            let target = match (parts.next(), parts.next()) {
+                (Some(minor), Some("0")) => format!("1.{}", minor),
+                (Some(minor), Some(patch)) => format!("1.{}.{}", minor, patch),
+                _ => return Ok(CompatibilityStatus::Unknown),
+            };
+
+            if target != mc_version {
+                return Ok(CompatibilityStatus::Incompatible {
+                    reason: format!(
+                        "NeoForge {} targets Minecraft {}, not {}",
+                        self.0, target, mc_version
+                    ),
+                });
+            }
+
+            check_installer_exists(
+                &INSTALLER_JAR_ENDPOINT.replace("{loader_version}", &self.0),
+                format!("NeoForge {} is not published", self.0),
+                client,
+            )
+            .await
+        })
+    }
+
+    /// Gets the newest NeoForge version targeting a Minecraft version.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the newest version.
+    fn latest_version<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<Option<String>>> + Send + 'a>> {
+        Box::pin(async move {
+            // 1.20.4 -> "20.4.", 1.21 -> "21.0."
+            let mut parts = mc_version.split('.').skip(1);
+            let prefix = match (parts.next(), parts.next()) {
+                (Some(minor), Some(patch)) => format!("{}.{}.", minor, patch),
+                (Some(minor), None) => format!("{}.0.", minor),
+                _ => return Ok(None),
+            };
+
+            let versions: MavenVersions = fetch(VERSIONS_ENDPOINT, client).await?;
+            Ok(versions
+                .versions
+                .into_iter()
+                .rev()
+                .find(|version| version.starts_with(&prefix)))
+        })
+    }
+
+    /// Estimates the size of this NeoForge version, which is its installer plus
+    /// the libraries the installer lists.
+    ///
+    /// The installer is downloaded and cached for `merge` if it isn't
+    /// cached yet.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the estimated size in bytes.
+    fn estimated_install_size<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<u64>> + Send + 'a>> {
+        Box::pin(async move {
+            let version_name = format!("{}-{}", mc_version, self.0);
+            let installer_path = temp_dir().join(format!("neoforge-{}.jar", version_name));
+            estimate_installer_size(
+                &INSTALLER_JAR_ENDPOINT.replace("{loader_version}", &self.0),
+                &installer_path,
+                client,
+            )
+            .await
+        })
+    }
+}
+
+/// Represents the versions of an artifact listed by the Maven API.
+#[derive(Deserialize)]
+struct MavenVersions {
+    versions: Vec<String>,
 }
 
 /// Downloads the installer for the NeoForge loader if it does not already exist.
@@ -204,18 +367,14 @@ fn merge_data(
         (
             "ROOT".to_string(),
             Data {
-                client: config.game_dir.to_string_lossy().into_owned(),
+                client: config.get_game_dir().to_string_lossy().into_owned(),
                 server: "".to_string(),
             },
         ),
         (
             "LIBRARY_DIR".to_string(),
             Data {
-                client: config
-                    .game_dir
-                    .join("libraries")
-                    .to_string_lossy()
-                    .into_owned(),
+                client: config.get_libraries_path().to_string_lossy().into_owned(),
                 server: "".to_string(),
             },
         ),
@@ -258,10 +417,7 @@ async fn process_data(
                 extract_specific_file(
                     installer_path,
                     file_path,
-                    &config
-                        .game_dir
-                        .join("libraries")
-                        .join(parse_lib_path(&path)?),
+                    &config.get_libraries_path().join(parse_lib_path(&path)?),
                 )?;
 
                 value.client = format!("[{}]", path);
@@ -272,9 +428,9 @@ async fn process_data(
 }
 
 fn merge_libraries(
-    config: &Config<impl Loader>,
     libraries: Vec<Library>,
     seen: &mut HashSet<String>,
+    warnings: &mut Vec<Warning>,
     skip_args: bool,
 ) -> Vec<vanilla::Library> {
     libraries
@@ -285,17 +441,17 @@ fn merge_libraries(
             }
 
             if let Some(url) = lib.url {
-                let path = parse_lib_path(&lib.name).ok()?;
+                let path = match parse_lib_path(&lib.name) {
+                    Ok(path) => path,
+                    Err(e) => {
+                        warnings.push(Warning::new(WarningCode::InvalidLibraryName, e.to_string()));
+                        return None;
+                    }
+                };
                 return Some(vanilla::Library {
                     downloads: Some(vanilla::LibraryDownloads {
                         artifact: Some(vanilla::File {
-                            path: Some(
-                                config
-                                    .get_libraries_path()
-                                    .join(&path)
-                                    .to_string_lossy()
-                                    .into_owned(),
-                            ),
+                            path: Some(path.clone()),
                             sha1: lib.sha1.unwrap_or_default(),
                             size: lib.size.unwrap_or_default(),
                             url: format!("{}/{}", url, path),
@@ -316,14 +472,11 @@ fn merge_libraries(
                         return Some(vanilla::Library {
                             downloads: Some(vanilla::LibraryDownloads {
                                 artifact: Some(vanilla::File {
-                                    path: Some(
-                                        config
-                                            .get_libraries_path()
-                                            .join(path)
-                                            .to_string_lossy()
-                                            .into_owned(),
-                                    ),
-                                    sha1: lib.sha1.unwrap_or_default(),
+                                    path: Some(path),
+                                    sha1: Some(artifact.sha1)
+                                        .filter(|sha1| !sha1.is_empty())
+                                        .or(lib.sha1)
+                                        .unwrap_or_default(),
                                     size: lib.size.unwrap_or_default(),
                                     url: artifact.url,
                                 }),
@@ -0,0 +1,401 @@
+use std::{
+    fs::{self, File},
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+
+use tokio::task::spawn_blocking;
+use zip::ZipArchive;
+
+use super::Loader;
+use crate::{
+    error::Error,
+    json::version::meta::{
+        custom::Processor,
+        vanilla::{self, Element, VersionMeta},
+    },
+    minecraft::{config::Config, emitter::Emitter, parse::parse_lib_path},
+    util::extract::{extract_specific_file, read_file_from_jar},
+};
+
+const LAUNCHWRAPPER: &str = "net.minecraft:launchwrapper:1.12";
+const LAUNCHWRAPPER_ENDPOINT: &str = "https://libraries.minecraft.net";
+const LAUNCH_MAIN_CLASS: &str = "net.minecraft.launchwrapper.Launch";
+const TWEAK_CLASS: &str = "optifine.OptiFineTweaker";
+const PATCHER_CLASS: &str = "optifine.Patcher";
+
+/// The `OptiFine` loader, installed from an installer downloaded by the
+/// user since OptiFine has no API to download it from.
+///
+/// The installer patches the vanilla client jar into the OptiFine library,
+/// which runs as a processor once the vanilla jar and Java are installed.
+/// The game is launched through launchwrapper with the OptiFine tweaker.
+pub struct OptiFine {
+    /// The OptiFine edition, e.g. `HD_U_I6`.
+    pub version: String,
+    /// The path to the OptiFine installer jar.
+    pub installer: PathBuf,
+}
+
+impl From<OptiFine> for Box<dyn Loader> {
+    fn from(value: OptiFine) -> Self {
+        Box::new(value)
+    }
+}
+
+impl OptiFine {
+    /// Creates an OptiFine loader.
+    ///
+    /// # Parameters
+    /// - `version`: The OptiFine edition, e.g. `HD_U_I6`.
+    /// - `installer`: The path to the OptiFine installer jar.
+    pub fn new(version: String, installer: PathBuf) -> Self {
+        Self { version, installer }
+    }
+
+    /// Copies the installer into the mods directory, which is how OptiFine
+    /// is installed alongside Forge.
+    ///
+    /// # Parameters
+    /// - `config`: The configuration of the Forge installation.
+    ///
+    /// # Returns
+    /// A result containing the path of the copied jar.
+    pub fn install_as_mod(&self, config: &Config<impl Loader>) -> crate::Result<PathBuf> {
+        let file_name = self
+            .installer
+            .file_name()
+            .ok_or_else(|| Error::NotFound("OptiFine installer".to_string()))?;
+        let mods_path = config.get_mods_path();
+        if !mods_path.is_dir() {
+            fs::create_dir_all(&mods_path)?;
+        }
+
+        let target = mods_path.join(file_name);
+        fs::copy(&self.installer, &target)?;
+        Ok(target)
+    }
+}
+
+impl Loader for OptiFine {
+    /// Merges OptiFine with the provided configuration and version metadata.
+    ///
+    /// # Parameters
+    /// - `config`: The configuration for the Minecraft installation.
+    /// - `meta`: The version metadata to be merged.
+    /// - `emitter`: An optional emitter, unused by OptiFine.
+    ///
+    /// # Returns
+    /// A future that resolves to the updated `VersionMeta`.
+    fn merge<'a>(
+        &'a self,
+        config: &'a Config<()>,
+        mut meta: VersionMeta,
+        _emitter: Option<&'a Emitter>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<VersionMeta>> + Send + 'a>> {
+        Box::pin(async move {
+            if !self.installer.is_file() {
+                return Err(Error::NotFound("OptiFine installer".to_string()));
+            }
+
+            let edition = format!("{}_{}", meta.id, self.version);
+            let libraries_path = config.get_libraries_path();
+
+            // The patcher runs as a processor from a copy of the installer
+            // next to the libraries.
+            let installer_name = format!("optifine:OptiFine-installer:{}", edition);
+            let installer_path = libraries_path.join(parse_lib_path(&installer_name)?);
+            let library_name = format!("optifine:OptiFine:{}", edition);
+            let library_path = parse_lib_path(&library_name)?;
+
+            let contents = {
+                let installer = self.installer.clone();
+                let installer_path = installer_path.clone();
+                let libraries_path = libraries_path.clone();
+                let library_path = library_path.clone();
+                spawn_blocking(move || {
+                    unpack_installer(&installer, &installer_path, &libraries_path, &library_path)
+                })
+                .await
+                .map_err(|e| Error::Fail(e.to_string()))??
+            };
+
+            meta.libraries
+                .push(local_library(library_name, library_path.clone()));
+            match contents.launchwrapper {
+                Some((name, path)) => meta.libraries.push(local_library(name, path)),
+                None => {
+                    let path = parse_lib_path(LAUNCHWRAPPER)?;
+                    let mut library = local_library(LAUNCHWRAPPER.to_string(), path.clone());
+                    if let Some(artifact) = library
+                        .downloads
+                        .as_mut()
+                        .and_then(|downloads| downloads.artifact.as_mut())
+                    {
+                        artifact.url = format!("{}/{}", LAUNCHWRAPPER_ENDPOINT, path);
+                    }
+                    meta.libraries.push(library);
+                }
+            }
+
+            // Set the main class and the tweaker for the launch
+            meta.main_class = LAUNCH_MAIN_CLASS.to_string();
+            match (&mut meta.arguments, &mut meta.minecraft_arguments) {
+                (Some(arguments), _) => {
+                    arguments
+                        .game
+                        .push(Element::String("--tweakClass".to_string()));
+                    arguments
+                        .game
+                        .push(Element::String(TWEAK_CLASS.to_string()));
+                }
+                (None, Some(minecraft_arguments)) => {
+                    minecraft_arguments.push_str(&format!(" --tweakClass {}", TWEAK_CLASS));
+                }
+                (None, None) => {
+                    meta.minecraft_arguments = Some(format!("--tweakClass {}", TWEAK_CLASS));
+                }
+            }
+
+            if contents.has_patcher {
+                let version_name = config
+                    .version_name
+                    .clone()
+                    .unwrap_or_else(|| format!("{}-{}", config.version, self.version));
+                let minecraft_jar = config
+                    .get_versions_path()
+                    .join(&version_name)
+                    .join(format!("{}.jar", version_name));
+
+                meta.processors
+                    .get_or_insert_with(Vec::new)
+                    .push(Processor {
+                        classpath: Vec::new(),
+                        args: vec![
+                            minecraft_jar.to_string_lossy().into_owned(),
+                            installer_path.to_string_lossy().into_owned(),
+                            libraries_path
+                                .join(&library_path)
+                                .to_string_lossy()
+                                .into_owned(),
+                        ],
+                        sides: Some(vec!["client".to_string()]),
+                        outputs: None,
+                        jar: installer_name,
+                        main_class: Some(PATCHER_CLASS.to_string()),
+                        success: false,
+                    });
+            }
+
+            Ok(meta)
+        })
+    }
+
+    /// Returns the OptiFine edition.
+    ///
+    /// # Returns
+    /// The version as a string.
+    fn get_version(&self) -> String {
+        self.version.to_string()
+    }
+}
+
+/// Creates a library that is placed in the libraries directory by the
+/// installation itself instead of being downloaded.
+///
+/// # Parameters
+/// - `name`: The Maven coordinate of the library.
+/// - `path`: The path of the library inside the libraries directory.
+///
+/// # Returns
+/// The library.
+fn local_library(name: String, path: String) -> vanilla::Library {
+    vanilla::Library {
+        downloads: Some(vanilla::LibraryDownloads {
+            artifact: Some(vanilla::File {
+                path: Some(path),
+                sha1: String::new(),
+                size: 0,
+                url: String::new(),
+            }),
+            classifiers: None,
+        }),
+        extract: None,
+        name,
+        rules: None,
+        natives: None,
+        skip_args: false,
+    }
+}
+
+/// What an OptiFine installer provides besides the OptiFine library.
+struct InstallerContents {
+    /// The name and path of the launchwrapper shipped by newer installers.
+    launchwrapper: Option<(String, String)>,
+    /// Whether the installer contains the patcher that builds the OptiFine
+    /// library from the vanilla client jar.
+    has_patcher: bool,
+}
+
+/// Copies an installer next to the libraries and extracts what it ships.
+///
+/// Installers without a patcher are the OptiFine library themselves, they
+/// are copied to the library path as well.
+///
+/// # Parameters
+/// - `installer`: The path to the installer provided by the user.
+/// - `installer_path`: The path the installer is copied to.
+/// - `libraries_path`: The path to the libraries directory.
+/// - `library_path`: The path of the OptiFine library inside the libraries
+///   directory.
+///
+/// # Returns
+/// A result containing what the installer provides.
+fn unpack_installer(
+    installer: &Path,
+    installer_path: &Path,
+    libraries_path: &Path,
+    library_path: &str,
+) -> crate::Result<InstallerContents> {
+    if let Some(parent) = installer_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(installer, installer_path)?;
+
+    // Newer installers ship their own launchwrapper, older versions use the
+    // one published by Mojang.
+    let launchwrapper = match read_file_from_jar(&installer_path, "launchwrapper-of.txt") {
+        Ok(version) => {
+            let version = version.trim();
+            let name = format!("optifine:launchwrapper-of:{}", version);
+            let path = parse_lib_path(&name)?;
+            extract_specific_file(
+                &installer_path,
+                &format!("launchwrapper-of-{}.jar", version),
+                &libraries_path.join(&path).as_path(),
+            )?;
+            Some((name, path))
+        }
+        Err(_) => None,
+    };
+
+    let mut archive = ZipArchive::new(File::open(installer_path)?)?;
+    let has_patcher = archive.by_name("optifine/Patcher.class").is_ok();
+    if !has_patcher {
+        let target = libraries_path.join(library_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(installer_path, target)?;
+    }
+
+    Ok(InstallerContents {
+        launchwrapper,
+        has_patcher,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Merges OptiFine `HD_U_M5` with a version JSON.
+    async fn merge(dir: &Path, files: &[(&str, &str)], version: serde_json::Value) -> VersionMeta {
+        let installer = dir.join("OptiFine_1.8.9_HD_U_M5.jar");
+        crate::test_util::write_zip(&installer, files);
+        let config = crate::test_util::config(&dir.join("game"), "1.8.9");
+        let meta = serde_json::from_value(version).unwrap();
+        OptiFine::new("HD_U_M5".to_string(), installer)
+            .merge(&config, meta, None)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn patcher_runs_as_processor() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = merge(
+            dir.path(),
+            &[
+                ("optifine/Patcher.class", ""),
+                ("launchwrapper-of.txt", "2.1\n"),
+                ("launchwrapper-of-2.1.jar", "launchwrapper"),
+            ],
+            crate::test_util::version_json("1.8.9"),
+        )
+        .await;
+
+        let libraries: Vec<&str> = meta.libraries.iter().map(|lib| lib.name.as_str()).collect();
+        assert_eq!(
+            libraries,
+            [
+                "optifine:OptiFine:1.8.9_HD_U_M5",
+                "optifine:launchwrapper-of:2.1"
+            ]
+        );
+        let libraries_path = dir.path().join("game").join("libraries");
+        assert_eq!(
+            fs::read_to_string(
+                libraries_path.join("optifine/launchwrapper-of/2.1/launchwrapper-of-2.1.jar")
+            )
+            .unwrap(),
+            "launchwrapper"
+        );
+        assert_eq!(meta.main_class, LAUNCH_MAIN_CLASS);
+        assert!(meta
+            .minecraft_arguments
+            .unwrap()
+            .ends_with(" --tweakClass optifine.OptiFineTweaker"));
+
+        // The patcher needs no installer data, so none is made up.
+        assert!(meta.data.is_none());
+        let processors = meta.processors.unwrap();
+        assert_eq!(processors.len(), 1);
+        assert_eq!(processors[0].main_class.as_deref(), Some(PATCHER_CLASS));
+        assert_eq!(
+            processors[0].jar,
+            "optifine:OptiFine-installer:1.8.9_HD_U_M5"
+        );
+        assert!(processors[0].args[2].ends_with("OptiFine-1.8.9_HD_U_M5.jar"));
+        assert!(!libraries_path
+            .join("optifine/OptiFine/1.8.9_HD_U_M5/OptiFine-1.8.9_HD_U_M5.jar")
+            .exists());
+    }
+
+    #[tokio::test]
+    async fn installer_without_patcher_is_the_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut version = crate::test_util::version_json("1.8.9");
+        version["minecraftArguments"] = serde_json::Value::Null;
+        version["arguments"] = serde_json::json!({ "game": [], "jvm": [] });
+        let meta = merge(dir.path(), &[("Config.class", "")], version).await;
+
+        let launchwrapper = meta
+            .libraries
+            .iter()
+            .find(|lib| lib.name == LAUNCHWRAPPER)
+            .and_then(|lib| lib.downloads.as_ref()?.artifact.as_ref())
+            .unwrap();
+        assert_eq!(
+            launchwrapper.url,
+            "https://libraries.minecraft.net/net/minecraft/launchwrapper/1.12/launchwrapper-1.12.jar"
+        );
+        let game: Vec<_> = meta
+            .arguments
+            .unwrap()
+            .game
+            .into_iter()
+            .map(|arg| match arg {
+                Element::String(arg) => arg,
+                _ => panic!("unexpected argument"),
+            })
+            .collect();
+        assert_eq!(game, ["--tweakClass", TWEAK_CLASS]);
+        assert!(meta.processors.is_none());
+        assert!(dir
+            .path()
+            .join("game/libraries/optifine/OptiFine/1.8.9_HD_U_M5/OptiFine-1.8.9_HD_U_M5.jar")
+            .is_file());
+    }
+}
@@ -1,15 +1,16 @@
 use std::{future::Future, pin::Pin};
 
-use super::Loader;
+use super::{
+    common::{check_meta_loaders, fetch_meta_loaders, merge_meta_profile, meta_profile_size},
+    CompatibilityStatus, Loader,
+};
 use crate::{
     error::Error,
-    http::fetch::fetch,
-    json::version::meta::{
-        custom::CustomMeta,
-        vanilla::{self, VersionMeta},
-    },
-    minecraft::{config::Config, emitter::Emitter, parse::parse_lib_path},
+    http::fetch::fetch_rate_limited,
+    json::version::meta::{custom::CustomMeta, vanilla::VersionMeta},
+    minecraft::{config::Config, emitter::Emitter},
 };
+use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
 const VERSION_META_ENDPOINT: &str = "https://meta.quiltmc.org/v3/";
@@ -58,27 +59,31 @@ impl Loader for Quilt {
     /// # Parameters
     /// - `config`: The configuration for the Minecraft installation.
     /// - `meta`: The version metadata to be merged.
-    /// - `_emitter`: An optional emitter for tracking events.
+    /// - `emitter`: An optional emitter for reporting skipped libraries.
     ///
     /// # Returns
     /// A future that resolves to the updated `VersionMeta`.
     fn merge<'a>(
         &'a self,
         config: &'a Config<()>,
-        mut meta: VersionMeta,
-        _emitter: Option<&'a Emitter>,
+        meta: VersionMeta,
+        emitter: Option<&'a Emitter>,
     ) -> Pin<Box<dyn Future<Output = crate::Result<VersionMeta>> + Send + 'a>> {
         Box::pin(async move {
             // Fetch the available Quilt loaders
-            let loaders: Vec<QuiltLoader> = fetch(
+            let loaders: Vec<QuiltLoader> = fetch_rate_limited(
                 format!("{}versions/loader", VERSION_META_ENDPOINT),
                 config.client.as_ref(),
+                config.rate_limit,
+                emitter,
             )
             .await?;
             // Fetch the available Quilt versions
-            let versions: Vec<Version> = fetch(
+            let versions: Vec<Version> = fetch_rate_limited(
                 format!("{}versions/game", VERSION_META_ENDPOINT),
                 config.client.as_ref(),
+                config.rate_limit,
+                emitter,
             )
             .await?;
 
@@ -86,72 +91,32 @@ impl Loader for Quilt {
             let loader = loaders
                 .into_iter()
                 .find(|v| v.version == self.0)
-                .ok_or_else(|| Error::UnknownVersion("Quilt Loader".into()))?;
+                .ok_or_else(|| Error::UnknownVersion {
+                    loader_type: "Quilt Loader".into(),
+                    version: self.0.clone(),
+                })?;
             // Find the Quilt version that matches the metadata
             let fabric = versions
                 .into_iter()
                 .find(|v| v.version == meta.id)
-                .ok_or_else(|| Error::UnknownVersion("Quilt".into()))?;
+                .ok_or_else(|| Error::UnknownVersion {
+                    loader_type: "Quilt".into(),
+                    version: meta.id.clone(),
+                })?;
 
             // Fetch the custom metadata for the loader
-            let version: CustomMeta = fetch(
+            let version: CustomMeta = fetch_rate_limited(
                 format!(
                     "{}versions/loader/{}/{}/profile/json",
                     VERSION_META_ENDPOINT, fabric.version, loader.version
                 ),
                 config.client.as_ref(),
+                config.rate_limit,
+                emitter,
             )
             .await?;
 
-            // Retain libraries that are not in the fetched version
-            meta.libraries.retain(|lib| {
-                version
-                    .libraries
-                    .iter()
-                    .all(|v_lib| v_lib.name.split(':').nth(1) != lib.name.split(':').nth(1))
-            });
-
-            // Extend the libraries with the new ones from the fetched version
-            meta.libraries.extend(
-                version
-                    .libraries
-                    .into_iter()
-                    .filter_map(|lib| {
-                        let path = parse_lib_path(&lib.name).ok()?;
-                        lib.url.map(|url| vanilla::Library {
-                            downloads: Some(vanilla::LibraryDownloads {
-                                artifact: Some(vanilla::File {
-                                    path: Some(path.clone()),
-                                    sha1: lib.sha1.unwrap_or_default(),
-                                    size: lib.size.unwrap_or_default(),
-                                    url: format!("{}/{}", url, path),
-                                }),
-                                classifiers: None,
-                            }),
-                            extract: None,
-                            name: lib.name.clone(),
-                            rules: None,
-                            natives: None,
-                            skip_args: false,
-                        })
-                    })
-                    .collect::<Vec<_>>(),
-            );
-
-            // Update the arguments for the Minecraft launch
-            if let Some(ref mut arguments) = meta.arguments {
-                if let Some(jvm) = version.arguments.jvm {
-                    arguments.jvm.extend(jvm);
-                }
-                if let Some(game) = version.arguments.game {
-                    arguments.game.extend(game);
-                }
-            }
-
-            // Set the main class for the Quilt version
-            meta.main_class = version.main_class;
-
-            Ok(meta)
+            Ok(merge_meta_profile(meta, version, emitter).await)
         })
     }
 
@@ -162,4 +127,66 @@ impl Loader for Quilt {
     fn get_version(&self) -> String {
         self.0.to_string()
     }
+
+    /// Checks whether the Quilt loader is available for a Minecraft version.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version to check against.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the compatibility status.
+    fn check_compatibility<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<CompatibilityStatus>> + Send + 'a>> {
+        Box::pin(check_meta_loaders(
+            VERSION_META_ENDPOINT,
+            "Quilt",
+            &self.0,
+            mc_version,
+            client,
+        ))
+    }
+
+    /// Gets the newest Quilt loader available for a Minecraft version.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the newest loader version.
+    fn latest_version<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<Option<String>>> + Send + 'a>> {
+        Box::pin(async move {
+            let versions = fetch_meta_loaders(VERSION_META_ENDPOINT, mc_version, client).await?;
+            Ok(versions.into_iter().next())
+        })
+    }
+
+    /// Estimates the size of the libraries of this Quilt loader.
+    ///
+    /// # Parameters
+    /// - `mc_version`: The Minecraft version.
+    /// - `client`: An optional HTTP client for making requests.
+    ///
+    /// # Returns
+    /// A future that resolves to the sum of the library sizes in bytes.
+    fn estimated_install_size<'a>(
+        &'a self,
+        mc_version: &'a str,
+        client: Option<&'a Client>,
+    ) -> Pin<Box<dyn Future<Output = crate::Result<u64>> + Send + 'a>> {
+        Box::pin(meta_profile_size(
+            VERSION_META_ENDPOINT,
+            &self.0,
+            mc_version,
+            client,
+        ))
+    }
 }
@@ -0,0 +1,306 @@
+/// A module for fetching the metadata of vanilla versions without installing
+/// them, and for resolving version JSONs that inherit from them.
+use std::{env::temp_dir, path::Path, time::Duration};
+
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::{
+    error::Error,
+    http::fetch::fetch_json_cached,
+    json::version::{manifest::VersionManifest, meta::vanilla::VersionMeta},
+    minecraft::{config::Config, inheritance, loader::Loader, VERSION_MANIFEST_ENDPOINT},
+    util::retry::RateLimit,
+};
+
+/// How long `fetch` reuses the manifest and the metadata it cached.
+const CACHE_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Fetches the metadata of a vanilla version from the version manifest.
+///
+/// The manifest and the metadata are cached in the temporary directory for
+/// a few minutes, so that looking up several versions fetches the manifest
+/// once.
+///
+/// # Parameters
+/// - `version_id`: The id of the version, e.g. `1.21.4`.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+/// A result containing the metadata of the version.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn run() -> lyceris::Result<()> {
+/// let meta = lyceris::minecraft::meta::fetch("1.21.4", None).await?;
+/// println!("1.21.4 requires Java {}", meta.required_java_major());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn fetch(version_id: &str, client: Option<&Client>) -> crate::Result<VersionMeta> {
+    fetch_cached(
+        VERSION_MANIFEST_ENDPOINT,
+        &temp_dir().join("lyceris-meta"),
+        version_id,
+        client,
+    )
+    .await
+}
+
+/// Fetches the metadata of a vanilla version like `fetch`, caching the
+/// responses in a directory.
+///
+/// # Parameters
+/// - `manifest_url`: The URL of the version manifest.
+/// - `cache_dir`: The directory the responses are cached in.
+/// - `version_id`: The id of the version.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+/// A result containing the metadata of the version.
+async fn fetch_cached(
+    manifest_url: &str,
+    cache_dir: &Path,
+    version_id: &str,
+    client: Option<&Client>,
+) -> crate::Result<VersionMeta> {
+    let manifest: VersionManifest = fetch_json_cached(
+        manifest_url,
+        &cache_dir.join("version_manifest_v2.json"),
+        CACHE_MAX_AGE,
+        client,
+        RateLimit::default(),
+        None,
+    )
+    .await?;
+    let version = manifest
+        .versions
+        .iter()
+        .find(|version| version.id == version_id)
+        .ok_or_else(|| Error::UnknownVersion {
+            loader_type: "Vanilla".to_string(),
+            version: version_id.to_string(),
+        })?;
+    fetch_json_cached(
+        &version.url,
+        &cache_dir.join(format!("{}.json", version_id)),
+        CACHE_MAX_AGE,
+        client,
+        RateLimit::default(),
+        None,
+    )
+    .await
+}
+
+/// Resolves a version JSON that inherits from another version through
+/// `inheritsFrom`, such as the ones written by MultiMC exports or manual Forge
+/// installs.
+///
+/// The parents are read from the versions directory of the configuration,
+/// missing ones are fetched and written there. The child is merged over its
+/// parent like the loaders do: its libraries come first, its arguments are
+/// appended and its other values, such as the main class, take precedence.
+///
+/// # Parameters
+/// - `meta`: The version JSON, which may lack the values of its parents.
+/// - `config`: The configuration whose versions directory holds the parents.
+/// - `client`: An optional HTTP client for fetching missing parents.
+///
+/// # Returns
+/// A result containing the complete version metadata.
+pub async fn resolve_inheritance<T: Loader>(
+    meta: Value,
+    config: &Config<T>,
+    client: Option<&Client>,
+) -> crate::Result<VersionMeta> {
+    inheritance::resolve(meta, &config.get_versions_path(), true, client).await
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::{
+        json::version::meta::vanilla::Element,
+        test_util::{config, version_json, write_version},
+    };
+
+    /// Gets the string arguments of a list of arguments.
+    fn strings(arguments: &[Element]) -> Vec<&str> {
+        arguments
+            .iter()
+            .filter_map(|argument| match argument {
+                Element::String(argument) => Some(argument.as_str()),
+                Element::Class(_) => None,
+            })
+            .collect()
+    }
+
+    /// Builds a library JSON without downloads.
+    fn library(name: &str) -> Value {
+        json!({ "name": name })
+    }
+
+    #[tokio::test]
+    async fn child_is_merged_over_its_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(dir.path(), "1.20.1");
+        let mut parent = version_json("1.20.1");
+        parent.as_object_mut().unwrap().remove("minecraftArguments");
+        parent["arguments"] = json!({
+            "game": ["--username", "${auth_player_name}"],
+            "jvm": ["-cp", "${classpath}"],
+        });
+        parent["libraries"] = json!([
+            library("org.ow2.asm:asm:9.3"),
+            library("com.mojang:brigadier:1.1.8"),
+        ]);
+        write_version(&config, "1.20.1", &parent).await;
+        let child = json!({
+            "id": "1.20.1-forge-47.3.0",
+            "inheritsFrom": "1.20.1",
+            "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+            "arguments": {
+                "game": ["--launchTarget", "forgeclient"],
+                "jvm": ["-DlibraryDirectory=${library_directory}"],
+            },
+            "libraries": [
+                library("org.ow2.asm:asm:9.7"),
+                library("net.minecraftforge:fmlloader:1.20.1-47.3.0"),
+            ],
+        });
+
+        let meta = resolve_inheritance(child, &config, None).await.unwrap();
+
+        assert_eq!(meta.id, "1.20.1-forge-47.3.0");
+        assert_eq!(
+            meta.main_class,
+            "cpw.mods.bootstraplauncher.BootstrapLauncher"
+        );
+        // The libraries of the child come first and replace the ones of the
+        // parent with the same group and artifact.
+        assert_eq!(
+            meta.libraries
+                .iter()
+                .map(|library| library.name.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "org.ow2.asm:asm:9.7",
+                "net.minecraftforge:fmlloader:1.20.1-47.3.0",
+                "com.mojang:brigadier:1.1.8",
+            ]
+        );
+        let arguments = meta.arguments.unwrap();
+        assert_eq!(
+            strings(&arguments.game),
+            [
+                "--username",
+                "${auth_player_name}",
+                "--launchTarget",
+                "forgeclient"
+            ]
+        );
+        assert_eq!(
+            strings(&arguments.jvm),
+            [
+                "-cp",
+                "${classpath}",
+                "-DlibraryDirectory=${library_directory}"
+            ]
+        );
+        // What the child doesn't have is inherited.
+        assert_eq!(meta.asset_index.id, "1.20.1");
+        assert_eq!(
+            meta.downloads.client.url,
+            "https://piston-data.mojang.com/client.jar"
+        );
+    }
+
+    #[tokio::test]
+    async fn chains_of_parents_are_resolved() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(dir.path(), "1.8.9");
+        write_version(&config, "1.8.9", &version_json("1.8.9")).await;
+        let forge = json!({
+            "id": "1.8.9-forge",
+            "inheritsFrom": "1.8.9",
+            "mainClass": "net.minecraft.launchwrapper.Launch",
+            "libraries": [library("net.minecraftforge:forge:1.8.9-11.15.1.2318")],
+        });
+        write_version(&config, "1.8.9-forge", &forge).await;
+        let pack = json!({
+            "id": "pack",
+            "inheritsFrom": "1.8.9-forge",
+            "libraries": [library("optifine:OptiFine:1.8.9_HD_U_M5")],
+        });
+
+        let meta = resolve_inheritance(pack, &config, None).await.unwrap();
+
+        assert_eq!(meta.id, "pack");
+        assert_eq!(meta.main_class, "net.minecraft.launchwrapper.Launch");
+        assert_eq!(meta.libraries.len(), 2);
+        assert_eq!(meta.libraries[0].name, "optifine:OptiFine:1.8.9_HD_U_M5");
+        assert_eq!(meta.assets, "1.8.9");
+    }
+
+    #[tokio::test]
+    async fn cyclic_parents_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(dir.path(), "a");
+        write_version(&config, "b", &json!({ "id": "b", "inheritsFrom": "a" })).await;
+        write_version(&config, "a", &json!({ "id": "a", "inheritsFrom": "b" })).await;
+
+        let result =
+            resolve_inheritance(json!({ "id": "a", "inheritsFrom": "b" }), &config, None).await;
+
+        assert!(matches!(result, Err(crate::Error::Parse(_))));
+    }
+
+    #[tokio::test]
+    async fn fetched_metadata_is_cached() {
+        let server = MockServer::start().await;
+        let mut meta = version_json("1.21.4");
+        meta["mainClass"] = json!("net.minecraft.client.main.Main");
+        meta["javaVersion"] = json!({ "component": "java-runtime-delta", "majorVersion": 21 });
+        Mock::given(method("GET"))
+            .and(path("/mc/game/version_manifest_v2.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "latest": { "release": "1.21.4", "snapshot": "1.21.4" },
+                "versions": [{
+                    "id": "1.21.4",
+                    "type": "release",
+                    "url": format!("{}/v1/packages/1.21.4.json", server.uri()),
+                    "time": "",
+                    "releaseTime": "",
+                }],
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/packages/1.21.4.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(meta))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_url = format!("{}/mc/game/version_manifest_v2.json", server.uri());
+        for _ in 0..2 {
+            let meta = fetch_cached(&manifest_url, dir.path(), "1.21.4", None)
+                .await
+                .unwrap();
+            assert_eq!(meta.required_java_major(), 21);
+        }
+        assert!(matches!(
+            fetch_cached(&manifest_url, dir.path(), "0.0.0", None).await,
+            Err(Error::UnknownVersion { .. })
+        ));
+    }
+}
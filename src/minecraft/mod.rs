@@ -1,9 +1,19 @@
+pub mod assets;
+pub mod inheritance;
+pub mod import;
 pub mod install;
 pub mod launch;
 pub mod loader;
+pub mod meta;
+pub mod options;
 pub mod parse;
+pub mod platform;
 pub mod emitter;
 pub mod config;
+pub mod console;
+pub mod storage;
+pub mod updates;
+pub mod versions;
 
 #[cfg(target_os = "windows")]
 pub const TARGET_OS: &str = "windows";
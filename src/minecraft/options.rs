@@ -0,0 +1,279 @@
+/// A module for reading and updating the option files of the game, such as
+/// `options.txt`, while keeping every option that is not touched.
+use std::{
+    path::Path,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use tokio::fs;
+
+use crate::json::version::meta::vanilla::VersionMeta;
+
+/// The options file of the game.
+pub const OPTIONS_FILE: &str = "options.txt";
+
+/// The options file of OptiFine's shaders.
+pub const OPTIFINE_SHADERS_FILE: &str = "optionsshaders.txt";
+
+/// The options file of Iris, relative to the game directory.
+pub const IRIS_PROPERTIES_FILE: &str = "config/iris.properties";
+
+/// The option holding the enabled resource packs.
+const RESOURCE_PACKS_KEY: &str = "resourcePacks";
+
+/// The option holding the selected shader pack, in both OptiFine and Iris.
+const SHADER_PACK_KEY: &str = "shaderPack";
+
+/// The release time of 17w43a, the first version that prefixes the resource
+/// packs in `options.txt` with `file/`.
+const PREFIXED_RESOURCE_PACKS_SINCE: u64 = 1508889600;
+
+/// The prefix of resource packs loaded from the `resourcepacks` directory.
+const FILE_PACK_PREFIX: &str = "file/";
+
+/// The built-in resource pack, listed first by versions using the prefix.
+const VANILLA_PACK: &str = "vanilla";
+
+/// An options file made of one `key<separator>value` pair per line.
+///
+/// The order of the lines, comments and lines that are not options are kept
+/// as they are when the file is saved.
+#[derive(Debug, Clone)]
+pub struct OptionsFile {
+    separator: char,
+    lines: Vec<String>,
+}
+
+impl OptionsFile {
+    /// Creates an empty options file.
+    ///
+    /// # Parameters
+    /// - `separator`: The character between keys and values, `:` for
+    ///   `options.txt` and `=` for properties files.
+    pub fn new(separator: char) -> Self {
+        Self {
+            separator,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Parses the content of an options file.
+    ///
+    /// # Parameters
+    /// - `content`: The content of the file.
+    /// - `separator`: The character between keys and values.
+    ///
+    /// # Returns
+    /// The parsed options file.
+    pub fn parse(content: &str, separator: char) -> Self {
+        Self {
+            separator,
+            lines: content.lines().map(str::to_string).collect(),
+        }
+    }
+
+    /// Reads an options file, which is empty if it doesn't exist yet.
+    ///
+    /// # Parameters
+    /// - `path`: The path to the file.
+    /// - `separator`: The character between keys and values.
+    ///
+    /// # Returns
+    /// A result containing the options file.
+    pub async fn read(path: &Path, separator: char) -> crate::Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::new(separator));
+        }
+        let content = fs::read_to_string(path).await?;
+        Ok(Self::parse(&content, separator))
+    }
+
+    /// Writes the options file, creating its parent directories.
+    ///
+    /// # Parameters
+    /// - `path`: The path to the file.
+    pub async fn write(&self, path: &Path) -> crate::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(path, self.to_string()).await?;
+        Ok(())
+    }
+
+    /// Gets the value of an option.
+    ///
+    /// # Parameters
+    /// - `key`: The key of the option.
+    ///
+    /// # Returns
+    /// The value, `None` if the option is not set.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.lines.iter().find_map(|line| self.value_of(line, key))
+    }
+
+    /// Sets the value of an option, replacing it in place if it is already
+    /// set and appending it otherwise.
+    ///
+    /// # Parameters
+    /// - `key`: The key of the option.
+    /// - `value`: The new value.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let entry = format!("{}{}{}", key, self.separator, value);
+        match self
+            .lines
+            .iter()
+            .position(|line| self.value_of(line, key).is_some())
+        {
+            Some(index) => self.lines[index] = entry,
+            None => self.lines.push(entry),
+        }
+    }
+
+    /// Gets the value of a line if it sets the given key.
+    fn value_of<'a>(&self, line: &'a str, key: &str) -> Option<&'a str> {
+        line.split_once(self.separator)
+            .filter(|(line_key, _)| line_key.trim() == key)
+            .map(|(_, value)| value)
+    }
+}
+
+impl std::fmt::Display for OptionsFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks whether a version lists the resource packs of the `resourcepacks`
+/// directory with the `file/` prefix, which started with 1.13.
+///
+/// # Parameters
+/// - `meta`: The version metadata.
+///
+/// # Returns
+/// Whether the resource packs are prefixed, `true` if the release time can't
+/// be parsed.
+pub fn uses_prefixed_resource_packs(meta: &VersionMeta) -> bool {
+    meta.release_datetime().map_or(true, |released| {
+        released >= UNIX_EPOCH + Duration::from_secs(PREFIXED_RESOURCE_PACKS_SINCE)
+    })
+}
+
+/// Adds resource packs to the enabled resource packs of `options.txt`,
+/// keeping the packs that are already enabled.
+///
+/// The packs are appended, which gives them priority over the packs the user
+/// enabled. Packs that are already enabled are left where they are.
+///
+/// # Parameters
+/// - `options`: The parsed `options.txt`.
+/// - `file_names`: The file names of the packs in the `resourcepacks`
+///   directory.
+/// - `prefixed`: Whether the version lists the packs with the `file/` prefix.
+///
+/// # Returns
+/// A result indicating success, `Error::Serde` if the current list is not a
+/// valid JSON array.
+pub fn enable_resource_packs(
+    options: &mut OptionsFile,
+    file_names: &[String],
+    prefixed: bool,
+) -> crate::Result<()> {
+    let mut packs: Vec<String> = match options.get(RESOURCE_PACKS_KEY) {
+        Some(value) if !value.trim().is_empty() => serde_json::from_str(value)?,
+        // Without the built-in pack the newer versions render no textures
+        _ if prefixed => vec![VANILLA_PACK.to_string()],
+        _ => Vec::new(),
+    };
+
+    for file_name in file_names {
+        let pack = if prefixed {
+            format!("{}{}", FILE_PACK_PREFIX, file_name)
+        } else {
+            file_name.clone()
+        };
+        if !packs.contains(&pack) {
+            packs.push(pack);
+        }
+    }
+
+    options.set(RESOURCE_PACKS_KEY, &serde_json::to_string(&packs)?);
+    Ok(())
+}
+
+/// Selects a shader pack in the options of OptiFine and Iris.
+///
+/// # Parameters
+/// - `game_path`: The working directory of the game.
+/// - `file_name`: The file name of the pack in the `shaderpacks` directory.
+///
+/// # Returns
+/// A result indicating success.
+pub async fn select_shader_pack(game_path: &Path, file_name: &str) -> crate::Result<()> {
+    let optifine_path = game_path.join(OPTIFINE_SHADERS_FILE);
+    let mut optifine = OptionsFile::read(&optifine_path, '=').await?;
+    optifine.set(SHADER_PACK_KEY, file_name);
+    optifine.write(&optifine_path).await?;
+
+    let iris_path = game_path.join(IRIS_PROPERTIES_FILE);
+    let mut iris = OptionsFile::read(&iris_path, '=').await?;
+    iris.set(SHADER_PACK_KEY, file_name);
+    iris.set("enableShaders", "true");
+    iris.write(&iris_path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enable(content: &str, file_names: &[&str], prefixed: bool) -> String {
+        let mut options = OptionsFile::parse(content, ':');
+        let file_names: Vec<String> = file_names.iter().map(|name| name.to_string()).collect();
+        enable_resource_packs(&mut options, &file_names, prefixed).unwrap();
+        options.to_string()
+    }
+
+    #[test]
+    fn packs_are_added_after_the_packs_of_the_user() {
+        let content = "fov:0.0\nresourcePacks:[\"vanilla\",\"file/user.zip\"]\nlang:en_us\n";
+        assert_eq!(
+            enable(content, &["server.zip"], true),
+            "fov:0.0\nresourcePacks:[\"vanilla\",\"file/user.zip\",\"file/server.zip\"]\nlang:en_us\n"
+        );
+    }
+
+    #[test]
+    fn enabled_packs_are_left_in_place() {
+        let content = "resourcePacks:[\"vanilla\",\"file/server.zip\",\"file/user.zip\"]\n";
+        assert_eq!(enable(content, &["server.zip"], true), content);
+    }
+
+    #[test]
+    fn older_versions_list_packs_without_prefix() {
+        assert_eq!(
+            enable("resourcePacks:[\"user.zip\"]\n", &["server.zip"], false),
+            "resourcePacks:[\"user.zip\",\"server.zip\"]\n"
+        );
+        assert_eq!(
+            enable("", &["server.zip"], false),
+            "resourcePacks:[\"server.zip\"]\n"
+        );
+    }
+
+    #[test]
+    fn newer_versions_keep_the_vanilla_pack() {
+        assert_eq!(
+            enable("fov:0.0\n", &["server.zip"], true),
+            "fov:0.0\nresourcePacks:[\"vanilla\",\"file/server.zip\"]\n"
+        );
+    }
+
+    #[test]
+    fn malformed_pack_list_is_an_error() {
+        let mut options = OptionsFile::parse("resourcePacks:[vanilla\n", ':');
+        assert!(enable_resource_packs(&mut options, &["server.zip".to_string()], true).is_err());
+        assert_eq!(options.to_string(), "resourcePacks:[vanilla\n");
+    }
+}
@@ -1,42 +1,67 @@
-use std::env::consts::OS;
+use crate::{
+    error::Error,
+    json::version::meta::vanilla::{Action, FeatureFlags, Os, Rule},
+};
 
-use crate::{error::Error, json::version::meta::vanilla::{Action, Name, Rule}};
-
-use super::TARGET_ARCH;
+use super::platform::Platform;
 
 /// Trait for parsing rules related to operating system and architecture.
 pub trait ParseRule {
-    /// Parses the rules and determines if the current environment is allowed.
+    /// Parses the rules and determines if the platform is allowed.
+    ///
+    /// Rules that depend on launcher features don't apply, as if every
+    /// feature was disabled.
+    ///
+    /// # Parameters
+    /// - `platform`: The platform the game directory is prepared for.
+    ///
+    /// # Returns
+    /// A boolean indicating whether the platform meets the rules.
+    fn parse_rule(&self, platform: &Platform) -> bool {
+        self.parse_rule_with_features(platform, &FeatureFlags::default())
+    }
+
+    /// Parses the rules and determines if the platform with the given
+    /// launcher features is allowed.
+    ///
+    /// # Parameters
+    /// - `platform`: The platform the game directory is prepared for.
+    /// - `features`: The launcher features, where missing ones are disabled.
     ///
     /// # Returns
-    /// A boolean indicating whether the current environment meets the rules.
-    fn parse_rule(&self) -> bool;
+    /// A boolean indicating whether the platform meets the rules.
+    fn parse_rule_with_features(&self, platform: &Platform, features: &FeatureFlags) -> bool;
 }
 
 impl ParseRule for [Rule] {
-    /// Parses the rules for an array of `Rule` and determines if the current environment is allowed.
+    /// Parses the rules for an array of `Rule` and determines if the
+    /// platform with the given launcher features is allowed.
+    ///
+    /// # Parameters
+    /// - `platform`: The platform the game directory is prepared for.
+    /// - `features`: The launcher features, where missing ones are disabled.
     ///
     /// # Returns
-    /// A boolean indicating whether the current environment meets the rules.
-    fn parse_rule(&self) -> bool {
-        let parsed_os: Name = match OS {
-            "linux" => Name::Linux,
-            "windows" => Name::Windows,
-            "macos" => Name::Osx,
-            _ => panic!("Unknown operating system!"),
-        };
+    /// A boolean indicating whether the platform meets the rules.
+    fn parse_rule_with_features(&self, platform: &Platform, features: &FeatureFlags) -> bool {
+        let parsed_os = platform.rule_os();
+        let parsed_arch = platform.rule_arch().to_string();
 
         if self.is_empty() {
             true
         } else {
             let mut should_push = false;
             for rule in self {
+                if !matches_features(rule, features) {
+                    continue;
+                }
                 if rule.action == Action::Disallow {
                     if let Some(os) = &rule.os {
-                        if os.name.is_some()
+                        if (os.name.is_some()
                             && os.name != Some(parsed_os.clone())
                             && os.arch.is_some()
-                            && os.arch != Some(TARGET_ARCH.to_string())
+                            && os.arch != Some(parsed_arch.clone()))
+                            || !matches_os_version(os, platform)
                         {
                             continue;
                         } else {
@@ -48,7 +73,8 @@ impl ParseRule for [Rule] {
                 } else if rule.action == Action::Allow {
                     if let Some(os) = &rule.os {
                         if (os.name.is_some() && os.name != Some(parsed_os.clone()))
-                            || (os.arch.is_some() && os.arch != Some(TARGET_ARCH.to_string()))
+                            || (os.arch.is_some() && os.arch != Some(parsed_arch.clone()))
+                            || !matches_os_version(os, platform)
                         {
                             continue;
                         } else {
@@ -67,107 +93,324 @@ impl ParseRule for [Rule] {
 }
 
 impl ParseRule for Option<Vec<Rule>> {
-    /// Parses the rules for an optional vector of `Rule` and determines if the current environment is allowed.
+    /// Parses the rules for an optional vector of `Rule` and determines if the
+    /// platform with the given launcher features is allowed.
+    ///
+    /// # Parameters
+    /// - `platform`: The platform the game directory is prepared for.
+    /// - `features`: The launcher features, where missing ones are disabled.
     ///
     /// # Returns
-    /// A boolean indicating whether the current environment meets the rules.
-    fn parse_rule(&self) -> bool {
+    /// A boolean indicating whether the platform meets the rules.
+    fn parse_rule_with_features(&self, platform: &Platform, features: &FeatureFlags) -> bool {
         match self {
-            Some(rules) => {
-                let parsed_os: Name = match OS {
-                    "linux" => Name::Linux,
-                    "windows" => Name::Windows,
-                    "macos" => Name::Osx,
-                    _ => panic!("Unknown operating system!"),
-                };
-
-                if rules.is_empty() {
-                    true
-                } else {
-                    let mut should_push = false;
-                    for rule in rules {
-                        if rule.action == Action::Disallow {
-                            if let Some(os) = &rule.os {
-                                if os.name.is_some()
-                                    && os.name != Some(parsed_os.clone())
-                                    && os.arch.is_some()
-                                    && os.arch != Some(TARGET_ARCH.to_string())
-                                {
-                                    continue;
-                                } else {
-                                    break;
-                                }
-                            } else {
-                                continue;
-                            }
-                        } else if rule.action == Action::Allow {
-                            if let Some(os) = &rule.os {
-                                if (os.name.is_some() && os.name != Some(parsed_os.clone()))
-                                    || (os.arch.is_some()
-                                        && os.arch != Some(TARGET_ARCH.to_string()))
-                                {
-                                    continue;
-                                } else {
-                                    should_push = true;
-                                    break;
-                                }
-                            } else {
-                                should_push = true;
-                                continue;
-                            }
-                        }
-                    }
-                    should_push
-                }
-            }
+            Some(rules) => rules.parse_rule_with_features(platform, features),
             None => true,
         }
     }
 }
 
+/// Checks whether the launcher features match the feature condition of a
+/// rule.
+///
+/// # Parameters
+/// - `rule`: The rule.
+/// - `features`: The launcher features, where missing ones are disabled.
+///
+/// # Returns
+/// `true` if the rule has no feature condition or if every feature it names
+/// has the required state.
+fn matches_features(rule: &Rule, features: &FeatureFlags) -> bool {
+    let Some(required) = &rule.features else {
+        return true;
+    };
+    required
+        .iter()
+        .all(|(name, required)| features.get(name).copied().unwrap_or_default() == *required)
+}
+
+/// Checks whether the version of the operating system matches the version
+/// condition of a rule.
+///
+/// # Parameters
+/// - `os`: The operating system condition of the rule.
+/// - `platform`: The platform, whose operating system version is compared.
+///
+/// # Returns
+/// `true` if the rule has no version condition or if it matches.
+fn matches_os_version(os: &Os, platform: &Platform) -> bool {
+    let Some(pattern) = &os.version else {
+        return true;
+    };
+    platform
+        .os_version
+        .as_deref()
+        .is_some_and(|version| matches_version_pattern(pattern, version))
+}
+
+/// Matches an OS version against the regular expression of a rule.
+#[cfg(feature = "regex")]
+fn matches_version_pattern(pattern: &str, version: &str) -> bool {
+    regex::Regex::new(pattern).is_ok_and(|regex| regex.is_match(version))
+}
+
+/// Matches an OS version against the regular expression of a rule.
+///
+/// Without the `regex` feature only literal prefixes such as `^10\.` are
+/// supported, any other pattern never matches.
+#[cfg(not(feature = "regex"))]
+fn matches_version_pattern(pattern: &str, version: &str) -> bool {
+    let Some(prefix) = pattern.strip_prefix('^') else {
+        return false;
+    };
+    if prefix
+        .replace("\\.", "")
+        .contains(|c: char| "\\^$.*+?()[]{}|".contains(c))
+    {
+        return false;
+    }
+    version.starts_with(&prefix.replace("\\.", "."))
+}
+
 /// Parses the library path from the given artifact string.
 ///
 /// # Parameters
-/// - `artifact`: The artifact string in the format "group:name:version[@classifier]".
+/// - `artifact`: The artifact string in the format "group:name:version[:classifier][@extension]".
 ///
 /// # Returns
 /// A `Result` containing the parsed library path as a string or an error if the format is invalid.
 pub fn parse_lib_path(artifact: &str) -> crate::Result<String> {
-    let name_items: Vec<&str> = artifact.split(':').collect();
-    if name_items.len() < 3 {
-        return Err(Error::Parse(format!("Invalid artifact format: {}", artifact)));
+    // The extension is always the part after the last `@`, versions and
+    // classifiers may contain dots but never an `@`.
+    let (coordinates, ext) = match artifact.rsplit_once('@') {
+        Some((coordinates, ext)) => (coordinates, ext),
+        None => (artifact, "jar"),
+    };
+
+    let name_items: Vec<&str> = coordinates.split(':').collect();
+    if !(3..=4).contains(&name_items.len()) {
+        return Err(Error::Parse(format!(
+            "Invalid artifact format, expected group:name:version[:classifier][@extension]: {}",
+            artifact
+        )));
+    }
+    if ext.is_empty() || name_items.iter().any(|item| item.is_empty()) {
+        return Err(Error::Parse(format!(
+            "Empty segment in artifact: {}",
+            artifact
+        )));
     }
 
     let package = name_items[0];
     let name = name_items[1];
-    let version_ext: Vec<&str> = name_items[2].split('@').collect();
-    let version = version_ext[0];
-    let ext = version_ext.get(1).unwrap_or(&"jar");
+    let version = name_items[2];
 
-    if name_items.len() == 3 {
-        Ok(format!(
-            "{}/{}/{}/{}-{}.{}",
+    match name_items.get(3) {
+        Some(classifier) => Ok(format!(
+            "{}/{}/{}/{}-{}-{}.{}",
             package.replace('.', "/"),
             name,
             version,
             name,
             version,
+            classifier,
             ext
-        ))
-    } else {
-        let data_ext: Vec<&str> = name_items[3].split('@').collect();
-        let data = data_ext[0];
-        let data_ext = data_ext.get(1).unwrap_or(&"jar");
-
-        Ok(format!(
-            "{}/{}/{}/{}-{}-{}.{}",
+        )),
+        None => Ok(format!(
+            "{}/{}/{}/{}-{}.{}",
             package.replace('.', "/"),
             name,
             version,
             name,
             version,
-            data,
-            data_ext
-        ))
+            ext
+        )),
     }
-}
\ No newline at end of file
+}
+
+/// Converts a library path back into its artifact string, the inverse of `parse_lib_path`.
+///
+/// # Parameters
+/// - `path`: The library path relative to the libraries directory,
+///   e.g. "net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-client.jar".
+///
+/// # Returns
+/// A `Result` containing the artifact string or an error if the path is not a Maven layout.
+pub fn lib_coordinates(path: &str) -> crate::Result<String> {
+    let invalid = || Error::Parse(format!("Invalid library path: {}", path));
+
+    let items: Vec<&str> = path
+        .split(['/', '\\'])
+        .filter(|item| !item.is_empty())
+        .collect();
+    if items.len() < 4 {
+        return Err(invalid());
+    }
+
+    let file = items[items.len() - 1];
+    let version = items[items.len() - 2];
+    let name = items[items.len() - 3];
+    let package = items[..items.len() - 3].join(".");
+
+    let (stem, ext) = file.rsplit_once('.').ok_or_else(invalid)?;
+    let rest = stem
+        .strip_prefix(&format!("{}-{}", name, version))
+        .ok_or_else(invalid)?;
+
+    let mut artifact = format!("{}:{}:{}", package, name, version);
+    if !rest.is_empty() {
+        let classifier = rest.strip_prefix('-').ok_or_else(invalid)?;
+        artifact.push(':');
+        artifact.push_str(classifier);
+    }
+    if ext != "jar" {
+        artifact.push('@');
+        artifact.push_str(ext);
+    }
+
+    Ok(artifact)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Coordinates and their paths, most of them taken from the install
+    /// profiles of Forge and NeoForge.
+    const LIBRARIES: &[(&str, &str)] = &[
+        (
+            "com.mojang:text2speech:1.10.3",
+            "com/mojang/text2speech/1.10.3/text2speech-1.10.3.jar",
+        ),
+        (
+            "net.minecraftforge:forge:1.20.1-47.2.0:client",
+            "net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-client.jar",
+        ),
+        (
+            "net.minecraftforge:forge:1.12.2-14.23.5.2860:clientdata@lzma",
+            "net/minecraftforge/forge/1.12.2-14.23.5.2860/forge-1.12.2-14.23.5.2860-clientdata.lzma",
+        ),
+        (
+            "net.minecraftforge:binarypatcher:1.1.1:fatjar",
+            "net/minecraftforge/binarypatcher/1.1.1/binarypatcher-1.1.1-fatjar.jar",
+        ),
+        (
+            "de.oceanlabs.mcp:mcp_config:1.20.1-20230612.114412@zip",
+            "de/oceanlabs/mcp/mcp_config/1.20.1-20230612.114412/mcp_config-1.20.1-20230612.114412.zip",
+        ),
+        (
+            "net.minecraft:client:1.20.1-20230612.114412:mappings@txt",
+            "net/minecraft/client/1.20.1-20230612.114412/client-1.20.1-20230612.114412-mappings.txt",
+        ),
+        (
+            "net.neoforged:neoforge:20.4.80-beta:universal",
+            "net/neoforged/neoforge/20.4.80-beta/neoforge-20.4.80-beta-universal.jar",
+        ),
+        (
+            "net.neoforged.installertools:installertools:2.1.2:fatjar",
+            "net/neoforged/installertools/installertools/2.1.2/installertools-2.1.2-fatjar.jar",
+        ),
+        (
+            "net.minecraft:client:1.20.4-20231207.154220:srg",
+            "net/minecraft/client/1.20.4-20231207.154220/client-1.20.4-20231207.154220-srg.jar",
+        ),
+        (
+            "org.lwjgl:lwjgl:3.3.1:natives-windows-x86_64",
+            "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-windows-x86_64.jar",
+        ),
+        (
+            "net.java.jinput:jinput-platform:2.0.5:natives-linux",
+            "net/java/jinput/jinput-platform/2.0.5/jinput-platform-2.0.5-natives-linux.jar",
+        ),
+        (
+            "cpw.mods:modlauncher:10.0.9-20230531.120100-1",
+            "cpw/mods/modlauncher/10.0.9-20230531.120100-1/modlauncher-10.0.9-20230531.120100-1.jar",
+        ),
+        (
+            "org.ow2.asm:asm-commons:9.7-SNAPSHOT",
+            "org/ow2/asm/asm-commons/9.7-SNAPSHOT/asm-commons-9.7-SNAPSHOT.jar",
+        ),
+        (
+            "org.jetbrains.kotlin:kotlin-stdlib.jdk8:1.9.0",
+            "org/jetbrains/kotlin/kotlin-stdlib.jdk8/1.9.0/kotlin-stdlib.jdk8-1.9.0.jar",
+        ),
+        (
+            "net.fabricmc:sponge-mixin:0.12.5+mixin.0.8.5",
+            "net/fabricmc/sponge-mixin/0.12.5+mixin.0.8.5/sponge-mixin-0.12.5+mixin.0.8.5.jar",
+        ),
+    ];
+
+    #[test]
+    fn parse_lib_path_table() {
+        for (artifact, path) in LIBRARIES {
+            assert_eq!(parse_lib_path(artifact).unwrap(), *path, "{}", artifact);
+        }
+    }
+
+    #[test]
+    fn lib_coordinates_table() {
+        for (artifact, path) in LIBRARIES {
+            assert_eq!(lib_coordinates(path).unwrap(), *artifact, "{}", path);
+            assert_eq!(
+                lib_coordinates(&path.replace('/', "\\")).unwrap(),
+                *artifact,
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn parse_lib_path_rejects_invalid_coordinates() {
+        for artifact in [
+            "",
+            "net.minecraftforge",
+            "net.minecraftforge:forge",
+            "net.minecraftforge:forge:1.20.1:client:extra",
+            ":forge:1.20.1",
+            "net.minecraftforge::1.20.1",
+            "net.minecraftforge:forge:",
+            "net.minecraftforge:forge:1.20.1:",
+            "net.minecraftforge:forge:1.20.1@",
+            "net.minecraftforge:forge:1.20.1:client@",
+        ] {
+            match parse_lib_path(artifact) {
+                Err(Error::Parse(message)) => assert!(message.contains(artifact), "{}", message),
+                other => panic!("{} parsed as {:?}", artifact, other),
+            }
+        }
+    }
+
+    #[test]
+    fn lib_coordinates_rejects_invalid_paths() {
+        for path in [
+            "",
+            "forge.jar",
+            "forge/1.20.1/forge-1.20.1.jar",
+            "net/minecraftforge/forge/1.20.1/forge-1.20.1",
+            "net/minecraftforge/forge/1.20.1/fmlcore-1.20.1.jar",
+            "net/minecraftforge/forge/1.20.1/forge-1.20.2.jar",
+            "net/minecraftforge/forge/1.20.1/forge-1.20.1client.jar",
+        ] {
+            assert!(
+                matches!(lib_coordinates(path), Err(Error::Parse(_))),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn rules_check_unknown_features() {
+        let rules: Vec<Rule> = serde_json::from_value(serde_json::json!([{
+            "action": "allow",
+            "features": { "has_made_up_feature": true }
+        }]))
+        .unwrap();
+        let platform = Platform::current().unwrap();
+
+        assert!(!rules.parse_rule(&platform));
+        let features = FeatureFlags::from([("has_made_up_feature".to_string(), true)]);
+        assert!(rules.parse_rule_with_features(&platform, &features));
+    }
+}
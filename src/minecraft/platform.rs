@@ -0,0 +1,211 @@
+/// A module describing the platform a game directory is prepared for, which
+/// decides the libraries, natives and Java runtime that get installed.
+///
+/// The platform is the host by default, it can be overridden to prepare a
+/// game directory for another operating system, e.g. a Windows instance on a
+/// Linux CI machine.
+use std::{
+    env::consts::{ARCH, OS},
+    process::Command,
+    sync::OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    json::version::meta::vanilla::{Classifiers, File, Name},
+};
+
+/// An operating system the game runs on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetOs {
+    Windows,
+    Linux,
+    Osx,
+}
+
+/// A processor architecture the game runs on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetArch {
+    X86,
+    X86_64,
+    Aarch64,
+}
+
+/// The operating system and architecture a game directory is prepared for.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Platform {
+    pub os: TargetOs,
+    pub arch: TargetArch,
+    /// The version of the operating system, compared against the version
+    /// conditions of library rules. Such conditions never match without it.
+    #[serde(default)]
+    pub os_version: Option<String>,
+}
+
+impl Platform {
+    /// Creates a platform without an operating system version.
+    ///
+    /// # Parameters
+    /// - `os`: The operating system.
+    /// - `arch`: The processor architecture.
+    pub fn new(os: TargetOs, arch: TargetArch) -> Self {
+        Self {
+            os,
+            arch,
+            os_version: None,
+        }
+    }
+
+    /// Gets the platform of the host.
+    ///
+    /// # Returns
+    /// A result containing the platform, `Error::UnsupportedOperatingSystem`
+    /// or `Error::UnsupportedArchitecture` if Minecraft isn't published for
+    /// the host.
+    pub fn current() -> crate::Result<Self> {
+        let os = match OS {
+            "windows" => TargetOs::Windows,
+            "linux" => TargetOs::Linux,
+            "macos" => TargetOs::Osx,
+            _ => return Err(Error::UnsupportedOperatingSystem(OS.to_string())),
+        };
+        let arch = match ARCH {
+            "x86" => TargetArch::X86,
+            "x86_64" => TargetArch::X86_64,
+            "aarch64" => TargetArch::Aarch64,
+            _ => return Err(Error::UnsupportedArchitecture),
+        };
+        Ok(Self {
+            os,
+            arch,
+            os_version: host_os_version().map(str::to_string),
+        })
+    }
+
+    /// Gets the name of the operating system in library rules.
+    ///
+    /// # Returns
+    /// The name.
+    pub fn rule_os(&self) -> Name {
+        match self.os {
+            TargetOs::Windows => Name::Windows,
+            TargetOs::Linux => Name::Linux,
+            TargetOs::Osx => Name::Osx,
+        }
+    }
+
+    /// Gets the name of the architecture in library rules.
+    ///
+    /// # Returns
+    /// The name.
+    pub fn rule_arch(&self) -> &'static str {
+        match self.arch {
+            TargetArch::X86 => "x86",
+            TargetArch::X86_64 => "x86_64",
+            TargetArch::Aarch64 => "aarch64",
+        }
+    }
+
+    /// Gets the native classifier of a library for this platform.
+    ///
+    /// # Parameters
+    /// - `classifiers`: The classifiers of the library.
+    ///
+    /// # Returns
+    /// The classifier, `None` if the library has no natives for this
+    /// platform.
+    pub fn natives<'a>(&self, classifiers: &'a Classifiers) -> Option<&'a File> {
+        match self.os {
+            TargetOs::Windows => classifiers.natives_windows.as_ref(),
+            TargetOs::Linux => classifiers.natives_linux.as_ref(),
+            TargetOs::Osx => classifiers.natives_macos.as_ref(),
+        }
+    }
+
+    /// Gets the key of this platform in Mojang's Java manifest.
+    ///
+    /// # Parameters
+    /// - `component`: The Java runtime component, the legacy runtime is only
+    ///   published for Intel Macs.
+    ///
+    /// # Returns
+    /// The key, e.g. `windows-x64` or `mac-os-arm64`.
+    pub fn java_manifest_key(&self, component: &str) -> String {
+        let os = match self.os {
+            TargetOs::Windows => "windows",
+            TargetOs::Linux => "linux",
+            TargetOs::Osx => "mac-os",
+        };
+        let arch = match (self.os, self.arch) {
+            (TargetOs::Linux, TargetArch::X86) => "i386",
+            (_, TargetArch::X86) => "x86",
+            (_, TargetArch::X86_64) => "x64",
+            (_, TargetArch::Aarch64) => "arm64",
+        };
+        match (self.os, self.arch) {
+            (TargetOs::Linux, TargetArch::X86_64 | TargetArch::Aarch64) => os.to_string(),
+            (TargetOs::Osx, TargetArch::Aarch64) if component != "jre-legacy" => {
+                format!("{}-{}", os, arch)
+            }
+            (TargetOs::Osx, _) => os.to_string(),
+            _ => format!("{}-{}", os, arch),
+        }
+    }
+}
+
+/// Gets the version of the host operating system, the same way Java reports
+/// it in the `os.version` property.
+///
+/// # Returns
+/// The version, or `None` if it couldn't be determined.
+fn host_os_version() -> Option<&'static str> {
+    static OS_VERSION: OnceLock<Option<String>> = OnceLock::new();
+    OS_VERSION
+        .get_or_init(|| {
+            let version = match OS {
+                "linux" => std::fs::read_to_string("/proc/sys/kernel/osrelease").ok()?,
+                "macos" => command_output("sw_vers", &["-productVersion"])?,
+                "windows" => windows_version()?,
+                _ => return None,
+            };
+            Some(version.trim().to_string())
+        })
+        .as_deref()
+}
+
+/// Gets the version of Windows as `<major>.<minor>`, e.g. `10.0`.
+///
+/// Unlike `GetVersionEx`, `RtlGetVersion` reports the real version regardless
+/// of the compatibility manifest of the process.
+#[cfg(windows)]
+fn windows_version() -> Option<String> {
+    use windows_sys::{
+        Wdk::System::SystemServices::RtlGetVersion,
+        Win32::System::SystemInformation::OSVERSIONINFOW,
+    };
+
+    let mut info = OSVERSIONINFOW {
+        dwOSVersionInfoSize: std::mem::size_of::<OSVERSIONINFOW>() as u32,
+        ..Default::default()
+    };
+    // SAFETY: the structure is initialized with its size, which is all
+    // `RtlGetVersion` reads from it.
+    let status = unsafe { RtlGetVersion(&mut info) };
+    (status == 0).then(|| format!("{}.{}", info.dwMajorVersion, info.dwMinorVersion))
+}
+
+#[cfg(not(windows))]
+fn windows_version() -> Option<String> {
+    None
+}
+
+/// Runs a command and returns its standard output.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,78 @@
+/// This module reports how much disk space an instance occupies, broken down
+/// by the directories the installer writes to.
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::{config::Config, loader::Loader};
+
+/// Represents the disk usage of an instance in bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct InstanceSize {
+    pub libraries: u64,
+    pub assets: u64,
+    pub natives: u64,
+    pub runtime: u64,
+    pub version_jar: u64,
+}
+
+impl InstanceSize {
+    /// Returns the sum of every category.
+    ///
+    /// # Returns
+    /// The total size in bytes.
+    pub fn total(&self) -> u64 {
+        self.libraries + self.assets + self.natives + self.runtime + self.version_jar
+    }
+}
+
+/// Calculates the disk usage of the instance described by the configuration.
+///
+/// Directories that do not exist yet are reported as zero bytes.
+///
+/// # Parameters
+/// - `config`: The configuration of the instance.
+///
+/// # Returns
+/// A result containing the size breakdown of the instance.
+pub fn instance_size<T: Loader>(config: &Config<T>) -> crate::Result<InstanceSize> {
+    let version_jar_path = config.get_version_jar_path();
+
+    Ok(InstanceSize {
+        libraries: dir_size(&config.get_libraries_path())?,
+        assets: dir_size(&config.get_assets_path())?,
+        natives: dir_size(&config.get_natives_path())?,
+        runtime: dir_size(&config.get_runtime_path())?,
+        version_jar: if version_jar_path.is_file() {
+            fs::metadata(&version_jar_path)?.len()
+        } else {
+            0
+        },
+    })
+}
+
+/// Recursively sums the size of every file inside the given directory.
+///
+/// # Parameters
+/// - `path`: The directory to walk.
+///
+/// # Returns
+/// A result containing the total size in bytes.
+fn dir_size(path: &Path) -> crate::Result<u64> {
+    if !path.is_dir() {
+        return Ok(0);
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+
+    Ok(size)
+}
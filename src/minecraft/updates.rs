@@ -0,0 +1,228 @@
+/// This module checks whether newer versions of Minecraft or of the loader
+/// of an instance are available.
+use std::cmp::Ordering;
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    http::fetch::fetch,
+    json::version::manifest::{Type, VersionManifest},
+};
+
+use super::{config::Config, loader::Loader, VERSION_MANIFEST_ENDPOINT};
+
+/// Represents the installed and the newest version of a component.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VersionUpdate {
+    pub current: String,
+    pub latest: String,
+    /// Whether `latest` is newer than `current`. An installed version newer
+    /// than the latest one, e.g. a beta loader, has no update.
+    #[serde(default)]
+    pub is_newer: bool,
+}
+
+impl VersionUpdate {
+    /// Compares an installed version with the newest one, see
+    /// `compare_versions`.
+    ///
+    /// # Parameters
+    /// - `current`: The installed version.
+    /// - `latest`: The newest version.
+    ///
+    /// # Returns
+    /// The update from `current` to `latest`.
+    pub fn new(current: String, latest: String) -> Self {
+        let is_newer = compare_versions(&current, &latest) == Ordering::Less;
+        Self {
+            current,
+            latest,
+            is_newer,
+        }
+    }
+
+    /// Checks whether the newest version is newer than the installed one.
+    ///
+    /// # Returns
+    /// `true` if an update is available.
+    pub fn is_available(&self) -> bool {
+        self.is_newer
+    }
+}
+
+/// Represents the available updates of an instance.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateInfo {
+    /// The newest Minecraft version of the same channel, release or snapshot.
+    pub minecraft: VersionUpdate,
+    /// The newest loader version for the configured Minecraft version, `None`
+    /// if there is no loader or if it can't be determined.
+    pub loader: Option<VersionUpdate>,
+}
+
+impl UpdateInfo {
+    /// Checks whether Minecraft or the loader can be updated.
+    ///
+    /// # Returns
+    /// `true` if any update is available.
+    pub fn any_available(&self) -> bool {
+        self.minecraft.is_available()
+            || self
+                .loader
+                .as_ref()
+                .is_some_and(VersionUpdate::is_available)
+    }
+}
+
+/// Checks whether newer versions of Minecraft or of the loader are available.
+///
+/// # Parameters
+/// - `config`: The configuration of the instance.
+/// - `client`: An optional HTTP client for making requests.
+///
+/// # Returns
+/// A result containing the installed and newest versions.
+pub async fn updates_available<T: Loader>(
+    config: &Config<T>,
+    client: Option<&Client>,
+) -> crate::Result<UpdateInfo> {
+    let manifest: VersionManifest = fetch(VERSION_MANIFEST_ENDPOINT, client).await?;
+
+    let release_time = |id: &str| {
+        manifest
+            .versions
+            .iter()
+            .find(|version| version.id == id)
+            .map(|version| version.release_time.clone())
+    };
+    let is_snapshot = manifest
+        .versions
+        .iter()
+        .find(|version| version.id == config.version)
+        .is_some_and(|version| matches!(version.r#type, Type::Snapshot));
+    let latest = if is_snapshot {
+        manifest.latest.snapshot.clone()
+    } else {
+        manifest.latest.release.clone()
+    };
+    // Snapshot ids can't be compared with release ids, the release times of
+    // the manifest can.
+    let minecraft = match (release_time(&config.version), release_time(&latest)) {
+        (Some(current_time), Some(latest_time)) => VersionUpdate {
+            current: config.version.clone(),
+            latest,
+            is_newer: current_time < latest_time,
+        },
+        _ => VersionUpdate::new(config.version.clone(), latest),
+    };
+
+    let loader = match &config.loader {
+        Some(loader) => loader
+            .latest_version(&config.version, client)
+            .await?
+            .map(|latest| VersionUpdate::new(loader.get_version(), latest)),
+        None => None,
+    };
+
+    Ok(UpdateInfo { minecraft, loader })
+}
+
+/// Compares two versions by their numeric and alphabetic parts, ignoring the
+/// separators, e.g. `0.16.9` < `0.16.10`.
+///
+/// A version extended by an alphabetic part is a pre-release of the shorter
+/// one, e.g. `21.4.1-beta` < `21.4.1` and `1.21-pre1` < `1.21` < `1.21.1`.
+///
+/// # Parameters
+/// - `a`: The first version.
+/// - `b`: The second version.
+///
+/// # Returns
+/// The ordering of `a` relative to `b`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (version_parts(a), version_parts(b));
+    for index in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(index), b.get(index)) {
+            (Some(VersionPart::Number(a)), Some(VersionPart::Number(b))) => a.cmp(b),
+            (Some(VersionPart::Text(a)), Some(VersionPart::Text(b))) => a.cmp(b),
+            (Some(VersionPart::Number(_)), Some(VersionPart::Text(_))) => Ordering::Greater,
+            (Some(VersionPart::Text(_)), Some(VersionPart::Number(_))) => Ordering::Less,
+            (Some(VersionPart::Number(_)), None) => Ordering::Greater,
+            (Some(VersionPart::Text(_)), None) => Ordering::Less,
+            (None, Some(VersionPart::Number(_))) => Ordering::Less,
+            (None, Some(VersionPart::Text(_))) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// A run of digits or of letters in a version.
+#[derive(Debug, PartialEq, Eq)]
+enum VersionPart<'a> {
+    Number(u64),
+    Text(&'a str),
+}
+
+/// Splits a version into its runs of digits and of letters.
+fn version_parts(version: &str) -> Vec<VersionPart<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = version;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+        rest = &rest[start..];
+        let is_digit = rest.starts_with(|c: char| c.is_ascii_digit());
+        let end = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() || c.is_ascii_digit() != is_digit)
+            .unwrap_or(rest.len());
+        let (part, remaining) = rest.split_at(end);
+        parts.push(if is_digit {
+            VersionPart::Number(part.parse().unwrap_or(u64::MAX))
+        } else {
+            VersionPart::Text(part)
+        });
+        rest = remaining;
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_compared_by_their_parts() {
+        let ordered = [
+            "0.15.11",
+            "0.16.9",
+            "0.16.10",
+            "1.20.6",
+            "1.21-pre1",
+            "1.21-rc1",
+            "1.21",
+            "1.21.1",
+            "21.4.1-beta",
+            "21.4.1",
+            "21.4.10-beta",
+        ];
+        for (index, a) in ordered.iter().enumerate() {
+            assert_eq!(compare_versions(a, a), Ordering::Equal);
+            for b in &ordered[index + 1..] {
+                assert_eq!(compare_versions(a, b), Ordering::Less, "{} < {}", a, b);
+                assert_eq!(compare_versions(b, a), Ordering::Greater, "{} > {}", b, a);
+            }
+        }
+        assert_eq!(compare_versions("24w14a", "24w14b"), Ordering::Less);
+        assert_eq!(compare_versions("1.21", "1_21"), Ordering::Equal);
+    }
+
+    #[test]
+    fn downgrades_are_not_updates() {
+        assert!(VersionUpdate::new("0.16.9".into(), "0.16.10".into()).is_available());
+        assert!(!VersionUpdate::new("0.16.10".into(), "0.16.10".into()).is_available());
+        assert!(!VersionUpdate::new("0.17.0-beta.1".into(), "0.16.10".into()).is_available());
+    }
+}
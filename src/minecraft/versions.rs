@@ -0,0 +1,168 @@
+/// This module enumerates the versions installed in a game directory.
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::read_dir;
+
+use crate::util::json::read_json;
+
+use super::{config::Config, loader::Loader};
+
+/// Represents the kind of an installed version.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum VersionKind {
+    Vanilla,
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+}
+
+/// Represents a version found in the versions directory.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstalledVersion {
+    /// The name of the version directory.
+    pub name: String,
+    /// The id declared in the version JSON.
+    pub id: String,
+    pub kind: VersionKind,
+    pub inherits_from: Option<String>,
+    /// The modification time of the version JSON in seconds since the Unix epoch.
+    pub installed_at: Option<u64>,
+}
+
+/// The subset of a version JSON needed to identify it.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VersionJson {
+    id: String,
+    inherits_from: Option<String>,
+    main_class: Option<String>,
+    #[serde(default)]
+    libraries: Vec<LibraryName>,
+}
+
+#[derive(Deserialize)]
+struct LibraryName {
+    name: String,
+}
+
+/// Lists the versions installed in the versions directory of a
+/// configuration.
+///
+/// Directories without a readable `<name>.json` are skipped.
+///
+/// # Parameters
+/// - `config`: The configuration whose versions directory is listed.
+///
+/// # Returns
+/// A result containing the installed versions.
+pub async fn installed_versions<T: Loader>(
+    config: &Config<T>,
+) -> crate::Result<Vec<InstalledVersion>> {
+    let versions_path = config.get_versions_path();
+    let mut versions = Vec::new();
+
+    if !versions_path.is_dir() {
+        return Ok(versions);
+    }
+
+    let mut entries = read_dir(&versions_path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if !entry.file_type().await?.is_dir() {
+            continue;
+        }
+
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let json_path = entry.path().join(format!("{}.json", name));
+        if !json_path.is_file() {
+            continue;
+        }
+
+        let json: VersionJson = match read_json(&json_path).await {
+            Ok(json) => json,
+            Err(_) => continue,
+        };
+
+        let installed_at = tokio::fs::metadata(&json_path)
+            .await
+            .ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs());
+
+        versions.push(InstalledVersion {
+            name,
+            kind: infer_kind(&json),
+            id: json.id,
+            inherits_from: json.inherits_from,
+            installed_at,
+        });
+    }
+
+    Ok(versions)
+}
+
+/// Infers the kind of a version from its main class and libraries.
+fn infer_kind(json: &VersionJson) -> VersionKind {
+    let has_library = |group: &str| json.libraries.iter().any(|lib| lib.name.starts_with(group));
+
+    match json.main_class.as_deref().unwrap_or_default() {
+        class if class.starts_with("org.quiltmc") => VersionKind::Quilt,
+        class if class.starts_with("net.fabricmc") => VersionKind::Fabric,
+        _ if has_library("net.neoforged") => VersionKind::NeoForge,
+        _ if has_library("net.minecraftforge") => VersionKind::Forge,
+        _ => VersionKind::Vanilla,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        test_util::{config, version_json, write_version},
+        util::json::write_json,
+    };
+
+    #[tokio::test]
+    async fn lists_the_overridden_versions_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let game_dir = dir.path().join("game");
+        let mut config = config(&game_dir, "1.8.9");
+        config.versions_dir = Some(dir.path().join("versions"));
+
+        write_version(&config, "1.8.9", &version_json("1.8.9")).await;
+        let fabric = json!({
+            "id": "fabric-loader-0.16.9-1.8.9",
+            "inheritsFrom": "1.8.9",
+            "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+        });
+        write_version(&config, "fabric-loader-0.16.9-1.8.9", &fabric).await;
+        // Versions left in the default directory are not listed.
+        write_json(
+            &game_dir.join("versions").join("1.7.10").join("1.7.10.json"),
+            &version_json("1.7.10"),
+        )
+        .await
+        .unwrap();
+
+        let mut versions = installed_versions(&config).await.unwrap();
+        versions.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].id, "1.8.9");
+        assert_eq!(versions[0].kind, VersionKind::Vanilla);
+        assert_eq!(versions[1].name, "fabric-loader-0.16.9-1.8.9");
+        assert_eq!(versions[1].kind, VersionKind::Fabric);
+        assert_eq!(versions[1].inherits_from.as_deref(), Some("1.8.9"));
+    }
+
+    #[tokio::test]
+    async fn missing_versions_directory_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = config(dir.path(), "1.8.9");
+        assert!(installed_versions(&config).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+//! Fixtures shared by the unit tests.
+use std::{fs::File, io::Write, path::Path};
+
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    http::mirror::MirrorConfig,
+    minecraft::config::{Config, ConfigBuilder},
+    util::json::write_json,
+};
+
+/// Builds the configuration of an offline player in the given game
+/// directory.
+pub fn config(game_dir: &Path, version: &str) -> Config<()> {
+    ConfigBuilder::offline(game_dir, version.to_string(), "Player".to_string()).build()
+}
+
+/// Builds a minimal vanilla version JSON.
+pub fn version_json(id: &str) -> Value {
+    let file = |url: &str| json!({ "size": 0, "url": url });
+    json!({
+        "assetIndex": {
+            "id": id,
+            "sha1": "",
+            "size": 0,
+            "totalSize": 0,
+            "url": format!("https://piston-meta.mojang.com/{}.json", id),
+        },
+        "assets": id,
+        "downloads": {
+            "client": file("https://piston-data.mojang.com/client.jar"),
+            "server": file("https://piston-data.mojang.com/server.jar"),
+        },
+        "id": id,
+        "libraries": [],
+        "mainClass": "net.minecraft.client.main.Main",
+        "minecraftArguments": "--username ${auth_player_name} --version ${version_name}",
+        "releaseTime": "2015-12-03T09:24:39+00:00",
+        "time": "2015-12-03T09:24:39+00:00",
+        "type": "release",
+    })
+}
+
+/// Writes a version JSON to the versions directory of the configuration.
+pub async fn write_version(config: &Config<()>, id: &str, json: &Value) {
+    let path = config
+        .get_versions_path()
+        .join(id)
+        .join(format!("{}.json", id));
+    write_json(&path, json).await.unwrap();
+}
+
+/// Writes the asset index of the version of the configuration.
+pub async fn write_asset_index(config: &Config<()>, id: &str, objects: Value) {
+    let path = config.get_indexes_path().join(format!("{}.json", id));
+    write_json(&path, &json!({ "objects": objects }))
+        .await
+        .unwrap();
+}
+
+/// Writes a ZIP archive, e.g. a jar, containing the given files.
+pub fn write_zip(path: &Path, files: &[(&str, &str)]) {
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    for (name, contents) in files {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+/// The Java executable served by `mojang_server`, which appends its
+/// arguments to `<executable>.log` and exits.
+pub const FAKE_JAVA: &str = "#!/bin/sh\necho \"$@\" >> \"$0.log\"\n";
+
+/// Starts a server standing in for the official Mojang endpoints, serving
+/// everything the installation of `version_json(id)` fetches on Linux.
+///
+/// It is meant to be configured as the only mirror, see `mirror`.
+pub async fn mojang_server(id: &str) -> MockServer {
+    let server = MockServer::start().await;
+    let official = |path: &str| format!("https://piston-data.mojang.com{}", path);
+    let file = |path: &str, contents: &str| {
+        json!({
+            "sha1": format!("{:x}", Sha1::digest(contents)),
+            "size": contents.len(),
+            "url": official(path),
+        })
+    };
+
+    let mut version = version_json(id);
+    version["downloads"]["client"] = file("/client.jar", "client");
+    version["assetIndex"]["url"] = json!(format!(
+        "https://piston-meta.mojang.com/indexes/{}.json",
+        id
+    ));
+    let responses = [
+        (
+            "/mc/game/version_manifest_v2.json".to_string(),
+            json!({
+                "latest": { "release": id, "snapshot": id },
+                "versions": [{
+                    "id": id,
+                    "type": "release",
+                    "url": format!("https://piston-meta.mojang.com/versions/{}.json", id),
+                    "time": "2015-12-03T09:24:39+00:00",
+                    "releaseTime": "2015-12-03T09:24:39+00:00",
+                }],
+            })
+            .to_string(),
+        ),
+        (format!("/versions/{}.json", id), version.to_string()),
+        (
+            format!("/indexes/{}.json", id),
+            json!({ "objects": {} }).to_string(),
+        ),
+        (
+            "/v1/products/java-runtime/2ec0cc96c44e5a76b9c8b7c39df7210883d12871/all.json"
+                .to_string(),
+            json!({
+                "linux": {
+                    "jre-legacy": [{
+                        "availability": { "group": 1, "progress": 100 },
+                        "manifest": file("/java/manifest.json", ""),
+                        "version": { "name": "8u202", "released": "2019-01-15T00:00:00+00:00" },
+                    }],
+                },
+            })
+            .to_string(),
+        ),
+        (
+            "/java/manifest.json".to_string(),
+            json!({
+                "files": {
+                    "bin": { "type": "directory" },
+                    "bin/java": {
+                        "type": "file",
+                        "executable": true,
+                        "downloads": { "raw": file("/java/bin/java", FAKE_JAVA) },
+                    },
+                    "lib": { "type": "directory" },
+                    "lib/rt.jar": {
+                        "type": "file",
+                        "executable": false,
+                        "downloads": { "raw": file("/java/lib/rt.jar", "rt") },
+                    },
+                },
+            })
+            .to_string(),
+        ),
+        ("/java/bin/java".to_string(), FAKE_JAVA.to_string()),
+        ("/java/lib/rt.jar".to_string(), "rt".to_string()),
+        ("/client.jar".to_string(), "client".to_string()),
+    ];
+    for (path, body) in responses {
+        Mock::given(matchers::path(path))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&server)
+            .await;
+    }
+    server
+}
+
+/// Builds a mirror pointing to a server, e.g. one of `mojang_server`.
+pub fn mirror(server: &MockServer) -> MirrorConfig {
+    MirrorConfig {
+        base_url: server.uri(),
+        priority: 0,
+    }
+}
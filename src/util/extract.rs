@@ -8,13 +8,15 @@ use zip::read::ZipArchive;
 /// - `output_dir`: The directory where the files should be extracted.
 ///
 /// # Returns
-/// A result indicating success or failure of the extraction operation.
-pub fn extract_file<P: AsRef<Path>>(zip_path: &P, output_dir: &P) -> crate::Result<()> {
+/// A result containing the paths of the extracted files, relative to the
+/// output directory.
+pub fn extract_file<P: AsRef<Path>>(zip_path: &P, output_dir: &P) -> crate::Result<Vec<PathBuf>> {
     let file = File::open(zip_path)?;
 
     create_dir_all(output_dir)?;
 
     let mut archive = ZipArchive::new(file)?;
+    let mut extracted = Vec::with_capacity(archive.len());
 
     for i in 0..archive.len() {
         let mut file = archive.by_index(i)?;
@@ -24,12 +26,13 @@ pub fn extract_file<P: AsRef<Path>>(zip_path: &P, output_dir: &P) -> crate::Resu
             let directory_path = &output_dir.as_ref().join(file_path);
             std::fs::create_dir_all(directory_path)?;
         } else {
-            let mut file_buffer = File::create(output_dir.as_ref().join(file_path))?;
+            let mut file_buffer = File::create(output_dir.as_ref().join(&file_path))?;
             std::io::copy(&mut file, &mut file_buffer)?;
+            extracted.push(file_path);
         }
     }
 
-    Ok(())
+    Ok(extracted)
 }
 
 /// Extracts a specific file from a ZIP archive.
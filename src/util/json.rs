@@ -1,9 +1,15 @@
 use serde::{de::DeserializeOwned, Serialize};
-use std::path::Path;
+use std::{
+    fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
 use tokio::{
-    fs::{create_dir_all, File},
+    fs::{create_dir_all, remove_file, rename, File},
     io::{AsyncReadExt, AsyncWriteExt},
+    task::spawn_blocking,
 };
+use uuid::Uuid;
 
 /// Reads a JSON file from the specified path and deserializes it into the specified type.
 ///
@@ -19,6 +25,40 @@ pub async fn read_json<T: DeserializeOwned>(path: &Path) -> crate::Result<T> {
     Ok(serde_json::from_str(&contents)?)
 }
 
+/// Reads a JSON file from the specified path and deserializes it while it is
+/// being read, without holding the text of the file in memory.
+///
+/// Meant for large files such as asset indexes that deserialize into typed
+/// structs, reading into a `serde_json::Value` saves nothing over
+/// `read_json`. The deserialization runs on the blocking thread pool.
+///
+/// # Parameters
+/// - `path`: The path to the JSON file to read.
+///
+/// # Returns
+/// A result containing the deserialized data on success, or an error if the file could not be read or parsed.
+pub async fn read_json_streaming<T: DeserializeOwned + Send + 'static>(
+    path: &Path,
+) -> crate::Result<T> {
+    let path = path.to_path_buf();
+    spawn_blocking(move || {
+        let reader = BufReader::new(fs::File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    })
+    .await
+    .map_err(|e| crate::Error::Fail(e.to_string()))?
+}
+
+/// Represents how a JSON file is formatted when written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonFormat {
+    /// Everything on a single line.
+    #[default]
+    Compact,
+    /// Indented over multiple lines for readability.
+    Pretty,
+}
+
 /// Writes the specified data to a JSON file at the given path.
 ///
 /// # Parameters
@@ -28,13 +68,62 @@ pub async fn read_json<T: DeserializeOwned>(path: &Path) -> crate::Result<T> {
 /// # Returns
 /// A result indicating success or failure of the write operation.
 pub async fn write_json<T: Serialize>(path: &Path, data: &T) -> crate::Result<()> {
-    let json_string = serde_json::to_string(data)?;
+    write_json_format(path, data, JsonFormat::Compact).await
+}
+
+/// Writes the specified data to an indented JSON file at the given path.
+///
+/// # Parameters
+/// - `path`: The path where the JSON file should be written.
+/// - `data`: The data to serialize and write to the file.
+///
+/// # Returns
+/// A result indicating success or failure of the write operation.
+pub async fn write_json_pretty<T: Serialize>(path: &Path, data: &T) -> crate::Result<()> {
+    write_json_format(path, data, JsonFormat::Pretty).await
+}
+
+/// Writes the specified data to a JSON file at the given path in the given format.
+///
+/// The file is replaced atomically, an interrupted write leaves the previous
+/// file in place.
+///
+/// # Parameters
+/// - `path`: The path where the JSON file should be written.
+/// - `data`: The data to serialize and write to the file.
+/// - `format`: The format of the written JSON.
+///
+/// # Returns
+/// A result indicating success or failure of the write operation.
+pub async fn write_json_format<T: Serialize>(
+    path: &Path,
+    data: &T,
+    format: JsonFormat,
+) -> crate::Result<()> {
+    let json_string = match format {
+        JsonFormat::Compact => serde_json::to_string(data)?,
+        JsonFormat::Pretty => serde_json::to_string_pretty(data)?,
+    };
     if let Some(parent) = path.parent() {
         if !parent.is_dir() {
             create_dir_all(parent).await?;
         }
     }
-    let mut file = File::create(path).await?;
-    file.write_all(json_string.as_bytes()).await?;
-    Ok(())
+
+    // The file is written next to the target and renamed over it, so a crash
+    // leaves either the old or the new file but never a truncated one.
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(format!(".{}.tmp", Uuid::new_v4().simple()));
+    let temp_path = PathBuf::from(temp_path);
+    let result = async {
+        let mut file = File::create(&temp_path).await?;
+        file.write_all(json_string.as_bytes()).await?;
+        file.sync_all().await?;
+        rename(&temp_path, path).await
+    }
+    .await;
+    if result.is_err() {
+        let _ = remove_file(&temp_path).await;
+    }
+    Ok(result?)
 }
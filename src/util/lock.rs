@@ -0,0 +1,138 @@
+/// A module for locking directories between concurrent installations.
+///
+/// The lock is a file created next to the locked directory, which works
+/// across processes as well as within one.
+use std::{
+    fs::{self, OpenOptions},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use tokio::{task::JoinHandle, time::sleep};
+
+/// How long to wait before trying to acquire a held lock again.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How old a lock file has to be to be considered left behind by a process
+/// that crashed.
+const STALE_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// How often a held lock file is touched, so that it never looks stale while
+/// its holder is alive.
+#[cfg(not(test))]
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+#[cfg(test)]
+const REFRESH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A held lock on a directory, released when dropped.
+pub struct DirLock {
+    path: PathBuf,
+    heartbeat: JoinHandle<()>,
+}
+
+impl DirLock {
+    /// Acquires the lock of a directory, waiting until no one else holds it.
+    ///
+    /// # Parameters
+    /// - `dir`: The directory to lock.
+    ///
+    /// # Returns
+    /// A result containing the held lock.
+    pub async fn acquire(dir: &Path) -> crate::Result<Self> {
+        let mut path = dir.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+
+        if let Some(parent) = path.parent() {
+            if !parent.is_dir() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => {
+                    let heartbeat = tokio::spawn(refresh(path.clone()));
+                    return Ok(Self { path, heartbeat });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    if is_stale(&path) {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    sleep(POLL_INTERVAL).await;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        self.heartbeat.abort();
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Keeps touching a held lock file until the lock is released.
+async fn refresh(path: PathBuf) {
+    loop {
+        sleep(REFRESH_INTERVAL).await;
+        let _ = OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+    }
+}
+
+/// Checks whether a lock file was left behind.
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > STALE_AFTER)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, time::Instant};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn lock_is_held_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let locked = dir.path().join("runtime");
+        let lock = DirLock::acquire(&locked).await.unwrap();
+
+        let started = Instant::now();
+        let waiting = tokio::spawn({
+            let locked = locked.clone();
+            async move { DirLock::acquire(&locked).await.map(|_| started.elapsed()) }
+        });
+        sleep(Duration::from_millis(600)).await;
+        drop(lock);
+        assert!(waiting.await.unwrap().unwrap() >= Duration::from_millis(600));
+        assert!(!dir.path().join("runtime.lock").exists());
+    }
+
+    #[tokio::test]
+    async fn held_lock_never_becomes_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = DirLock::acquire(&dir.path().join("runtime")).await.unwrap();
+        let old = SystemTime::now() - STALE_AFTER * 2;
+        File::options()
+            .write(true)
+            .open(&lock.path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        assert!(is_stale(&lock.path));
+
+        sleep(REFRESH_INTERVAL * 2).await;
+        assert!(!is_stale(&lock.path));
+    }
+}
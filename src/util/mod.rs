@@ -3,4 +3,6 @@ pub mod extract; // Functions for extracting files from ZIP archives
 pub mod hash; // Functions for calculating hashes
 pub mod json; // Functions for reading and writing JSON files
 pub mod retry; // Functions for retrying operations
-pub mod base64; // Functions for base64 encoding and decoding
\ No newline at end of file
+pub mod base64; // Functions for base64 encoding and decoding
+pub mod time; // Functions for parsing timestamps
+pub mod lock; // Functions for locking directories between installations
\ No newline at end of file
@@ -1,7 +1,19 @@
 /// A module for utility functions, including retry logic.
 ///
-/// This module provides functions to retry asynchronous operations with specified delays.
-use std::time::Duration;
+/// This module provides functions to retry asynchronous operations with specified delays,
+/// and a circuit breaker to stop retrying hosts that keep failing.
+use std::{
+    collections::HashMap,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    minecraft::emitter::{Emit, Emitter, Warning, WarningCode},
+};
 
 /// Retries a given asynchronous operation a specified number of times with a delay.
 ///
@@ -45,3 +57,239 @@ pub async fn retry<A, B: std::future::Future<Output = A>>(
         tokio::time::sleep(delay).await;
     }
 }
+
+/// Limits how long requests wait for servers that ask to retry later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// The longest a single `Retry-After` is waited for.
+    pub max_wait: Duration,
+    /// The longest all the waits of a single request may add up to.
+    pub max_total: Duration,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            max_wait: Duration::from_secs(60),
+            max_total: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// How many times `retry_rate_limited` runs an operation again, so that a
+/// server answering with a `Retry-After` of zero isn't asked endlessly.
+const MAX_RATE_LIMITED_RETRIES: u32 = 10;
+
+/// Runs an operation again whenever it fails with `Error::RateLimited`,
+/// after waiting as long as the server asked to.
+///
+/// These waits do not count as attempts of `retry`, they are only bounded by
+/// `rate_limit` and `MAX_RATE_LIMITED_RETRIES`.
+///
+/// # Parameters
+/// - `f`: The operation to run.
+/// - `rate_limit`: How long the operation may wait.
+/// - `emitter`: An optional emitter, warned about every wait.
+///
+/// # Returns
+/// The result of the operation, `Error::RateLimited` once waiting any longer
+/// would exceed `rate_limit.max_total` or the operation was rate limited
+/// `MAX_RATE_LIMITED_RETRIES` times in a row.
+pub async fn retry_rate_limited<T, F: Future<Output = crate::Result<T>>>(
+    f: impl Fn() -> F,
+    rate_limit: RateLimit,
+    emitter: Option<&Emitter>,
+) -> crate::Result<T> {
+    let mut waited = Duration::ZERO;
+    let mut retries = 0;
+    loop {
+        match f().await {
+            Err(Error::RateLimited { url, retry_after })
+                if waited < rate_limit.max_total && retries < MAX_RATE_LIMITED_RETRIES =>
+            {
+                let wait = retry_after
+                    .min(rate_limit.max_wait)
+                    .min(rate_limit.max_total - waited);
+                emitter
+                    .warn(Warning::new(
+                        WarningCode::RateLimited,
+                        format!("{} is rate limited, waiting {:?}", url, wait),
+                    ))
+                    .await;
+                tokio::time::sleep(wait).await;
+                waited += wait;
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// The state of the circuit of a host, as returned by `CircuitBreaker::check`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests to the host are allowed.
+    Closed,
+    /// The host failed too often, requests to it fail immediately.
+    Open,
+    /// The circuit was open and its timeout elapsed, a request is allowed
+    /// again and a single failure opens the circuit once more.
+    Reset,
+}
+
+/// The failures of a single host.
+#[derive(Debug, Default)]
+struct HostState {
+    failures: u32,
+    opened_at: Option<Instant>,
+}
+
+/// Tracks consecutive failures per host and stops sending requests to a host
+/// once it failed `threshold` times in a row, until `reset_timeout` elapsed.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    reset_timeout: Duration,
+    hosts: HashMap<String, HostState>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(5, Duration::from_secs(30))
+    }
+}
+
+impl CircuitBreaker {
+    /// Creates a circuit breaker.
+    ///
+    /// # Parameters
+    /// - `threshold`: The number of consecutive failures that open the circuit.
+    /// - `reset_timeout`: How long the circuit stays open.
+    pub fn new(threshold: u32, reset_timeout: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            reset_timeout,
+            hosts: HashMap::new(),
+        }
+    }
+
+    /// Checks whether a request to a host is allowed.
+    ///
+    /// # Parameters
+    /// - `host`: The host of the request.
+    ///
+    /// # Returns
+    /// The state of the circuit of the host.
+    pub fn check(&mut self, host: &str) -> CircuitState {
+        let Some(state) = self.hosts.get_mut(host) else {
+            return CircuitState::Closed;
+        };
+        match state.opened_at {
+            Some(opened_at) if opened_at.elapsed() < self.reset_timeout => CircuitState::Open,
+            Some(_) => {
+                state.opened_at = None;
+                state.failures = self.threshold - 1;
+                CircuitState::Reset
+            }
+            None => CircuitState::Closed,
+        }
+    }
+
+    /// Records a successful request, closing the circuit of the host.
+    ///
+    /// # Parameters
+    /// - `host`: The host of the request.
+    pub fn record_success(&mut self, host: &str) {
+        self.hosts.remove(host);
+    }
+
+    /// Records a failed request.
+    ///
+    /// # Parameters
+    /// - `host`: The host of the request.
+    ///
+    /// # Returns
+    /// Whether this failure opened the circuit of the host.
+    pub fn record_failure(&mut self, host: &str) -> bool {
+        let state = self.hosts.entry(host.to_string()).or_default();
+        if state.opened_at.is_some() {
+            return false;
+        }
+        state.failures += 1;
+        if state.failures >= self.threshold {
+            state.opened_at = Some(Instant::now());
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOST: &str = "piston-data.mojang.com";
+
+    #[test]
+    fn circuit_opens_after_the_threshold() {
+        let mut breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+
+        assert!(!breaker.record_failure(HOST));
+        assert!(!breaker.record_failure(HOST));
+        assert_eq!(breaker.check(HOST), CircuitState::Closed);
+        assert!(breaker.record_failure(HOST));
+        assert_eq!(breaker.check(HOST), CircuitState::Open);
+        // Failures while open don't open it again.
+        assert!(!breaker.record_failure(HOST));
+        // Other hosts are unaffected.
+        assert_eq!(
+            breaker.check("libraries.minecraft.net"),
+            CircuitState::Closed
+        );
+    }
+
+    #[test]
+    fn success_closes_the_circuit() {
+        let mut breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        breaker.record_failure(HOST);
+        breaker.record_success(HOST);
+        // The failures are counted again from zero.
+        assert!(!breaker.record_failure(HOST));
+        assert_eq!(breaker.check(HOST), CircuitState::Closed);
+
+        breaker.record_failure(HOST);
+        assert_eq!(breaker.check(HOST), CircuitState::Open);
+        breaker.record_success(HOST);
+        assert_eq!(breaker.check(HOST), CircuitState::Closed);
+    }
+
+    #[test]
+    fn circuit_resets_after_the_timeout() {
+        let mut breaker = CircuitBreaker::new(3, Duration::from_millis(50));
+        for _ in 0..3 {
+            breaker.record_failure(HOST);
+        }
+        assert_eq!(breaker.check(HOST), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(60));
+
+        assert_eq!(breaker.check(HOST), CircuitState::Reset);
+        // The request allowed by the reset succeeds.
+        breaker.record_success(HOST);
+        assert_eq!(breaker.check(HOST), CircuitState::Closed);
+    }
+
+    #[test]
+    fn single_failure_after_a_reset_opens_the_circuit() {
+        let mut breaker = CircuitBreaker::new(3, Duration::from_millis(50));
+        for _ in 0..3 {
+            breaker.record_failure(HOST);
+        }
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(breaker.check(HOST), CircuitState::Reset);
+
+        assert!(breaker.record_failure(HOST));
+        assert_eq!(breaker.check(HOST), CircuitState::Open);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::error::Error;
+
+/// Parses an ISO-8601 timestamp into seconds since the Unix epoch.
+///
+/// Fractional seconds are ignored, `Z` and `+hh:mm`/`-hh:mm` offsets are supported.
+///
+/// # Parameters
+/// - `timestamp`: The timestamp to parse, e.g. `2024-05-01T10:00:00.1234567Z`.
+///
+/// # Returns
+/// A result containing the Unix timestamp in seconds.
+pub fn parse_iso8601(timestamp: &str) -> crate::Result<u64> {
+    let invalid = || Error::Parse(format!("Invalid timestamp: {}", timestamp));
+
+    let (date, time) = timestamp.split_once('T').ok_or_else(invalid)?;
+
+    let mut date_parts = date.split('-').map(|part| part.parse::<i64>());
+    let (year, month, day) = match (date_parts.next(), date_parts.next(), date_parts.next()) {
+        (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) => (year, month, day),
+        _ => return Err(invalid()),
+    };
+
+    let (clock, offset) = if let Some(clock) = time.strip_suffix('Z') {
+        (clock, 0)
+    } else if let Some(index) = time.rfind(['+', '-']) {
+        let (clock, offset) = time.split_at(index);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = offset[1..].split_once(':').ok_or_else(invalid)?;
+        let hours: i64 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+        (clock, sign * (hours * 3600 + minutes * 60))
+    } else {
+        (time, 0)
+    };
+
+    let clock = clock.split('.').next().unwrap_or(clock);
+    let mut clock_parts = clock.split(':').map(|part| part.parse::<i64>());
+    let (hour, minute, second) = match (clock_parts.next(), clock_parts.next(), clock_parts.next()) {
+        (Some(Ok(hour)), Some(Ok(minute)), Some(Ok(second))) => (hour, minute, second),
+        _ => return Err(invalid()),
+    };
+
+    // Days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+    u64::try_from(seconds).map_err(|_| invalid())
+}
@@ -0,0 +1,68 @@
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use lyceris::{
+    minecraft::emitter::{EventVariant, ListenerId, TypedEmitter},
+    EventEnum,
+};
+
+#[derive(EventEnum)]
+enum AppEvent {
+    Started,
+    Message(String),
+    Progress(u64, u64),
+}
+
+/// Registers a listener that records the payloads it receives.
+async fn record<V>(emitter: &TypedEmitter<AppEvent>) -> (ListenerId, Arc<Mutex<Vec<V::Payload>>>)
+where
+    V: EventVariant<Event = AppEvent>,
+    V::Payload: Send + 'static,
+{
+    let payloads = Arc::new(Mutex::new(Vec::new()));
+    let id = emitter
+        .on(PhantomData::<V>, {
+            let payloads = payloads.clone();
+            move |payload| payloads.lock().unwrap().push(payload)
+        })
+        .await;
+    (id, payloads)
+}
+
+#[tokio::test]
+async fn events_reach_the_listeners_of_their_variant() {
+    let emitter = TypedEmitter::new();
+    let (_, started) = record::<AppEventStarted>(&emitter).await;
+    let (_, messages) = record::<AppEventMessage>(&emitter).await;
+    let (_, progress) = record::<AppEventProgress>(&emitter).await;
+
+    emitter.emit(AppEvent::Started).await;
+    emitter.emit(AppEvent::Message("hello".to_string())).await;
+    emitter.emit(AppEvent::Progress(1, 10)).await;
+    emitter.emit(AppEvent::Progress(2, 10)).await;
+
+    assert_eq!(*started.lock().unwrap(), [()]);
+    assert_eq!(*messages.lock().unwrap(), ["hello"]);
+    assert_eq!(*progress.lock().unwrap(), [(1, 10), (2, 10)]);
+}
+
+#[tokio::test]
+async fn removed_listener_is_not_called() {
+    let emitter = TypedEmitter::new();
+    let (id, removed) = record::<AppEventMessage>(&emitter).await;
+    let (_, kept) = record::<AppEventMessage>(&emitter).await;
+
+    assert!(emitter.off(id).await);
+    assert!(!emitter.off(id).await);
+    emitter.emit(AppEvent::Message("hello".to_string())).await;
+
+    assert!(removed.lock().unwrap().is_empty());
+    assert_eq!(*kept.lock().unwrap(), ["hello"]);
+}
+
+#[test]
+fn named_fields_are_rejected() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
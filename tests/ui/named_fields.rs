@@ -0,0 +1,8 @@
+use lyceris::EventEnum;
+
+#[derive(EventEnum)]
+enum AppEvent {
+    Progress { current: u64, total: u64 },
+}
+
+fn main() {}